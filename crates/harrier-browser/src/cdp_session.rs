@@ -9,7 +9,9 @@ use chromiumoxide::cdp::browser_protocol::network::{
 };
 use chromiumoxide::cdp::browser_protocol::page::{EventLoadEventFired, NavigateParams};
 use futures::StreamExt;
-use std::collections::HashMap;
+use harrier_core::filter::{CaptureScope, ScopeDecision};
+use harrier_core::har::HarJournal;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::oneshot;
 
 /// Manages Chrome DevTools Protocol session
@@ -174,8 +176,16 @@ impl CdpSession {
     /// Returns a tuple of (shutdown_sender, capture_receiver) where:
     /// - shutdown_sender: Send () to stop capturing and get results
     /// - capture_receiver: Receives the NetworkCapture when shutdown is triggered
+    ///
+    /// With a journal, each request is appended to it once its body has been fetched,
+    /// and the returned capture holds only requests that never finished loading.
+    ///
+    /// Requests outside `scope` are never added to the capture, or are added without
+    /// fetching their response body when the scope keeps their headers.
     pub async fn capture_traffic(
         &self,
+        journal: Option<HarJournal>,
+        scope: CaptureScope,
     ) -> Result<(oneshot::Sender<()>, oneshot::Receiver<NetworkCapture>)> {
         tracing::info!(
             "CDP session: connecting to Chrome on port {}",
@@ -229,6 +239,8 @@ impl CdpSession {
         let page_clone = page.clone();
         tokio::spawn(async move {
             let mut capture = NetworkCapture::new();
+            // Out-of-scope requests kept without their bodies
+            let mut headers_only = HashSet::new();
 
             loop {
                 tokio::select! {
@@ -240,6 +252,16 @@ impl CdpSession {
                     Some(event) = request_events.next() => {
                         tracing::debug!("Request: {} {}", event.request.method, event.request.url);
                         let request_id = event.request_id.inner().to_string();
+                        match scope.decide(&event.request.url) {
+                            ScopeDecision::Record => {}
+                            ScopeDecision::HeadersOnly => {
+                                headers_only.insert(request_id.clone());
+                            }
+                            ScopeDecision::Ignore => {
+                                tracing::trace!("Not recording out-of-scope request: {}", event.request.url);
+                                continue;
+                            }
+                        }
                         capture.add_request(
                             request_id.clone(),
                             event.request.method.clone(),
@@ -271,39 +293,56 @@ impl CdpSession {
                     Some(event) = loading_finished_events.next() => {
                         tracing::debug!("Loading finished: {}", event.request_id.inner());
                         let request_id = event.request_id.inner().to_string();
+                        if !capture.contains(&request_id) {
+                            continue;
+                        }
                         capture.mark_completed(&request_id, event.encoded_data_length as i64);
 
-                        // Fetch response body
-                        let params = GetResponseBodyParams::new(event.request_id.clone());
-                        if let Ok(body_result) = page_clone.execute(params).await {
-                            let body = body_result.body.clone();
-                            let base64_encoded = body_result.base64_encoded;
-
-                            // Truncate body if needed
-                            let original_size = body.len();
-                            let (truncated_body, was_truncated) = if original_size > MAX_RESPONSE_BODY_SIZE {
-                                let truncated = if base64_encoded {
-                                    truncate_base64(&body, MAX_RESPONSE_BODY_SIZE)
+                        // Fetch response body, unless the request is out of scope
+                        if headers_only.remove(&request_id) {
+                            capture.set_comment(&request_id, "Out of scope: bodies not recorded".to_string());
+                        } else {
+                            let params = GetResponseBodyParams::new(event.request_id.clone());
+                            if let Ok(body_result) = page_clone.execute(params).await {
+                                let body = body_result.body.clone();
+                                let base64_encoded = body_result.base64_encoded;
+
+                                // Truncate body if needed
+                                let original_size = body.len();
+                                let (truncated_body, was_truncated) = if original_size > MAX_RESPONSE_BODY_SIZE {
+                                    let truncated = if base64_encoded {
+                                        truncate_base64(&body, MAX_RESPONSE_BODY_SIZE)
+                                    } else {
+                                        truncate_utf8(&body, MAX_RESPONSE_BODY_SIZE)
+                                    };
+                                    (truncated, true)
                                 } else {
-                                    truncate_utf8(&body, MAX_RESPONSE_BODY_SIZE)
+                                    (body, false)
                                 };
-                                (truncated, true)
-                            } else {
-                                (body, false)
-                            };
 
-                            // Update response with body
-                            capture.set_response_body(
-                                &request_id,
-                                truncated_body,
-                                base64_encoded,
-                                was_truncated,
-                                if was_truncated {
-                                    Some(original_size as i64)
-                                } else {
-                                    None
-                                },
-                            );
+                                // Update response with body
+                                capture.set_response_body(
+                                    &request_id,
+                                    truncated_body,
+                                    base64_encoded,
+                                    was_truncated,
+                                    if was_truncated {
+                                        Some(original_size as i64)
+                                    } else {
+                                        None
+                                    },
+                                );
+                            }
+                        }
+
+                        // Request is complete: move it out of memory and onto disk
+                        if let Some(journal) = &journal
+                            && let Some(entry) = capture.entry(&request_id)
+                        {
+                            match journal.append(&entry) {
+                                Ok(()) => capture.remove_request(&request_id),
+                                Err(e) => tracing::warn!("Failed to journal entry, keeping it in memory: {}", e),
+                            }
                         }
                     }
                 }
@@ -18,6 +18,8 @@ pub struct NetworkRequest {
     pub response: Option<NetworkResponse>,
     pub completed: bool,
     pub encoded_data_length: i64,
+    /// HAR comment for the entry
+    pub comment: Option<String>,
 }
 
 impl NetworkRequest {
@@ -33,6 +35,7 @@ impl NetworkRequest {
             response: None,
             completed: false,
             encoded_data_length: 0,
+            comment: None,
         }
     }
 
@@ -110,6 +113,13 @@ impl NetworkCapture {
         }
     }
 
+    /// Attach a HAR comment to a request
+    pub fn set_comment(&mut self, request_id: &str, comment: String) {
+        if let Some(req) = self.requests.get_mut(request_id) {
+            req.comment = Some(comment);
+        }
+    }
+
     /// Mark request as completed
     pub fn mark_completed(&mut self, request_id: &str, encoded_data_length: i64) {
         if let Some(req) = self.requests.get_mut(request_id) {
@@ -137,6 +147,23 @@ impl NetworkCapture {
         }
     }
 
+    /// Convert one request to a HAR entry
+    pub fn entry(&self, request_id: &str) -> Option<Entry> {
+        self.requests
+            .get(request_id)
+            .map(|net_req| self.network_request_to_entry(net_req))
+    }
+
+    /// Whether a request is being tracked
+    pub fn contains(&self, request_id: &str) -> bool {
+        self.requests.contains_key(request_id)
+    }
+
+    /// Stop tracking a request
+    pub fn remove_request(&mut self, request_id: &str) {
+        self.requests.remove(request_id);
+    }
+
     /// Get all captured requests
     pub fn requests(&self) -> Vec<NetworkRequest> {
         self.requests.values().cloned().collect()
@@ -281,7 +308,8 @@ impl NetworkCapture {
             },
             server_ip_address: None,
             connection: None,
-            comment: None,
+            comment: net_req.comment.clone(),
+            web_socket_messages: None,
         }
     }
 
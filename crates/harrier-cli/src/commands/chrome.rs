@@ -1,7 +1,11 @@
 use anyhow::Result;
 use harrier_browser::{CdpSession, ChromeFinder, ChromeLauncher, ProfileManager};
+use harrier_core::filter::CaptureScope;
+use harrier_core::har::{Creator, HarJournal, Rotation};
 use std::path::{Path, PathBuf};
 
+use super::pipeline::Pipeline;
+
 /// Kill a process by PID (cross-platform)
 fn kill_process_by_pid(pid: u32) {
     #[cfg(unix)]
@@ -20,15 +24,32 @@ fn kill_process_by_pid(pid: u32) {
     }
 }
 
+/// How Chrome is started
+pub struct LaunchOptions {
+    /// Override Chrome binary location
+    pub chrome_path: Option<PathBuf>,
+    /// Starting URL to navigate to
+    pub url: Option<String>,
+    /// Named persistent profile
+    pub profile: Option<String>,
+    /// Use a temporary profile
+    pub temp: bool,
+}
+
 pub fn execute(
     output: &Path,
-    hosts: Vec<String>,
-    scan: bool,
-    chrome_path: Option<PathBuf>,
-    url: Option<String>,
-    profile: Option<String>,
-    temp: bool,
+    scope: CaptureScope,
+    pipeline: &Pipeline,
+    rotate: Option<Rotation>,
+    launch: LaunchOptions,
 ) -> Result<()> {
+    let LaunchOptions {
+        chrome_path,
+        url,
+        profile,
+        temp,
+    } = launch;
+
     // Create tokio runtime for async operations
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -104,13 +125,24 @@ pub fn execute(
         println!("What would you like to do?");
         println!("  s) Stop capturing and save HAR (Chrome continues)");
         println!("  k) Kill Chrome and save HAR");
-        println!("  a) Abort everything - kill Chrome, no HAR, no scan");
+        println!("  a) Abort everything - kill Chrome, no HAR, no pipeline");
         println!();
         println!("Press a key when ready, or close Chrome naturally...");
 
         // Step 6: Start capture
+        // Finished requests are journaled as they complete so a crash does not lose the session
+        let journal = HarJournal::create(
+            output,
+            Creator {
+                name: "Harrier".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                comment: None,
+            },
+            rotate,
+        )?;
+
         // Start CDP capture (returns shutdown channel and result receiver)
-        let (shutdown_tx, capture_rx) = cdp_session.capture_traffic().await?;
+        let (shutdown_tx, capture_rx) = cdp_session.capture_traffic(Some(journal.clone()), scope).await?;
 
         // Step 7: Wait for Chrome to exit or user input
         use console::Term;
@@ -219,6 +251,7 @@ pub fn execute(
                 if let Some(task) = wait_task.take() {
                     let _ = task.await; // Wait for termination but ignore result
                 }
+                journal.discard()?;
                 println!("❌ Aborted - no HAR saved");
                 return Ok(());
             }
@@ -236,30 +269,23 @@ pub fn execute(
         };
 
         // Step 7: Process captured traffic
+        // Requests still in memory never finished loading; keep them too
+        for entry in network_capture.to_har().log.entries {
+            journal.append(&entry)?;
+        }
+        println!("📊 Captured {} HTTP requests", journal.entry_count());
 
-        let request_count = network_capture.count();
-        println!("📊 Captured {} HTTP requests", request_count);
-
-        // Step 8: Convert to HAR
-        let mut har = network_capture.to_har();
-
-        // Step 9: Apply host filters if specified
-        if !hosts.is_empty() {
-            println!("🔍 Filtering to hosts: {}", hosts.join(", "));
-            har = apply_host_filter(har, hosts)?;
-            println!("📝 Filtered to {} requests", har.log.entries.len());
+        // Step 8: Write HAR files
+        let files = journal.finish()?;
+        if files.is_empty() {
+            println!("⚠️  No traffic captured, HAR file not generated");
         }
 
-        // Step 10: Write HAR file
-        let har_json = serde_json::to_string_pretty(&har)?;
-        std::fs::write(output, har_json)?;
-        println!("✅ HAR file written to: {}", output.display());
+        for file in &files {
+            println!("✅ HAR file written to: {}", file.display());
 
-        // Step 11: Run hawk scan if requested
-        if scan {
-            println!("🦅 Running StackHawk scan...");
-            run_hawk_scan(output)?;
-            println!("✅ Scan complete");
+            // Step 9: Run post-capture pipeline
+            pipeline.run(file)?;
         }
 
         Ok(())
@@ -270,48 +296,3 @@ pub fn execute(
 
     result
 }
-
-/// Apply host filtering to HAR file
-fn apply_host_filter(
-    har: harrier_core::har::Har,
-    host_patterns: Vec<String>,
-) -> Result<harrier_core::har::Har> {
-    use harrier_core::filter::FilterCriteria;
-
-    let criteria = FilterCriteria::new().with_hosts(host_patterns)?;
-
-    harrier_core::filter::filter_har(&har, &criteria)
-        .map_err(|e| anyhow::anyhow!("Filter failed: {}", e))
-}
-
-/// Run StackHawk scan with HAR file
-fn run_hawk_scan(har_path: &Path) -> Result<()> {
-    use std::process::Command;
-
-    // Check if hawk binary exists
-    if which::which("hawk").is_err() {
-        return Err(anyhow::anyhow!(
-            "hawk command not found. Install StackHawk CLI or omit --scan flag."
-        ));
-    }
-
-    // Check for stackhawk.yml
-    if !std::path::Path::new("stackhawk.yml").exists() {
-        println!("⚠️  No stackhawk.yml found, running scan with defaults");
-    }
-
-    // Run hawk scan
-    let output = Command::new("hawk").arg("scan").arg(har_path).output()?;
-
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "hawk scan failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-
-    // Print hawk output
-    println!("{}", String::from_utf8_lossy(&output.stdout));
-
-    Ok(())
-}
@@ -0,0 +1,549 @@
+//! HawkScan configuration generated from HAR captures.
+//!
+//! `harrier hawk init --har capture.har` writes a complete `stackhawk.yml` for each
+//! first-party application in the capture: host and environment, spider seeds, OpenAPI or
+//! GraphQL settings, authentication derived from the detected login flow, and excluded paths.
+//!
+//! # Examples
+//!
+//! ```bash
+//! # Write stackhawk.yml (or stackhawk-<host>[-<port>].yml per application) to the current directory
+//! harrier hawk init --har capture.har
+//!
+//! # Pick the StackHawk application and environment
+//! harrier hawk init --har capture.har --app-id 3f1c... --env Staging -o config/
+//! ```
+
+use anyhow::{Context, Result, bail};
+use harrier_core::har::{Entry, Har, HarReader};
+use harrier_detectors::yaml::quote;
+use harrier_detectors::{
+    AccessMatrixBuilder, AppType, AuthAnalysis, AuthAnalyzer, AuthEventType, LoginFormat,
+    LoginParam, LoginRequestExtractor, SessionType, TestPath, TokenExtraction,
+};
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+use super::stats::{analyze_hosts, get_root_domain};
+
+/// Most spider seed paths written per application
+const MAX_SEED_PATHS: usize = 25;
+
+/// `stackhawk.yml` contents for one application
+#[derive(Debug, Clone, Default)]
+pub struct StackHawkConfig {
+    pub domain: String,
+    pub host: String, // Origin, e.g. https://app.example.com
+    pub env: String,
+    pub application_id: Option<String>,
+    pub anti_csrf_param: Option<String>,
+    pub exclude_paths: Vec<String>,
+    pub openapi_path: Option<String>,
+    pub graphql_path: Option<String>,
+    pub rest_api: bool, // REST traffic seen but no spec captured
+    pub authentication: Option<AuthenticationConfig>,
+    pub seed_paths: Vec<String>,
+    pub har_file: String,
+}
+
+/// `app.authentication` settings
+#[derive(Debug, Clone)]
+pub struct AuthenticationConfig {
+    pub logged_in_indicator: Option<String>,
+    pub logged_out_indicator: Option<String>,
+    pub login: LoginConfig,
+    pub authorization: Authorization,
+    pub test_path: Option<TestPath>,
+}
+
+/// `usernamePassword` login settings
+#[derive(Debug, Clone)]
+pub struct LoginConfig {
+    pub login_type: LoginType,
+    pub login_path: String,
+    pub login_page_path: Option<String>,
+    pub username_field: String,
+    pub password_field: String,
+    pub other_params: Vec<LoginParam>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginType {
+    Form,
+    Json,
+}
+
+impl LoginType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LoginType::Form => "FORM",
+            LoginType::Json => "JSON",
+        }
+    }
+}
+
+/// How the scanner presents the session after logging in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Authorization {
+    Cookie { names: Vec<String> },
+    Token { extraction: TokenExtraction },
+}
+
+/// Write a stackhawk.yml for each first-party application in a HAR file
+pub fn init(
+    har_path: &Path,
+    output_dir: &Path,
+    env: &str,
+    app_id: Option<&str>,
+    force: bool,
+) -> Result<()> {
+    tracing::info!(
+        "Generating HawkScan configuration from: {}",
+        har_path.display()
+    );
+
+    let har = HarReader::from_file(har_path)?;
+    let configs = generate(&har, &har_path.display().to_string(), env, app_id)?;
+    if configs.is_empty() {
+        bail!(
+            "No first-party application traffic found in {}",
+            har_path.display()
+        );
+    }
+
+    let names = file_names(&configs)?;
+    let paths: Vec<_> = names.iter().map(|n| output_dir.join(n)).collect();
+    if !force && let Some(existing) = paths.iter().find(|p| p.exists()) {
+        bail!(
+            "{} already exists (use --force to overwrite)",
+            existing.display()
+        );
+    }
+
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    for (config, path) in configs.iter().zip(&paths) {
+        fs::write(path, config.to_yaml())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("✓ Wrote {} for {}", path.display(), config.host);
+    }
+
+    Ok(())
+}
+
+/// Build one config per first-party application host
+pub fn generate(
+    har: &Har,
+    har_file: &str,
+    env: &str,
+    app_id: Option<&str>,
+) -> Result<Vec<StackHawkConfig>> {
+    let analysis = AuthAnalyzer::analyze(har)?;
+    let hosts = analyze_hosts(har);
+    let Some(first) = hosts.first() else {
+        return Ok(Vec::new());
+    };
+    let first_party = get_root_domain(&first.domain);
+
+    let mut configs = Vec::new();
+    for host in &hosts {
+        if get_root_domain(&host.domain) != first_party {
+            continue;
+        }
+        let default_port = if host.protocol == "https" { 443 } else { 80 };
+        let origin = if host.port == default_port {
+            format!("{}://{}", host.protocol, host.domain)
+        } else {
+            format!("{}://{}:{}", host.protocol, host.domain, host.port)
+        };
+
+        let entries: Vec<(usize, &Entry)> = har
+            .log
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| {
+                LoginRequestExtractor::origin_of(&e.request.url).as_deref() == Some(origin.as_str())
+            })
+            .collect();
+        // Hosts that only served assets are not applications
+        if entries
+            .iter()
+            .all(|(_, e)| AccessMatrixBuilder::is_static_resource(e))
+        {
+            continue;
+        }
+
+        let rest_api = host.api_types.iter().any(|t| {
+            matches!(
+                t.api_type,
+                AppType::Rest | AppType::RestJson | AppType::RestXml
+            )
+        });
+        let openapi_path = entries
+            .iter()
+            .find(|(_, e)| {
+                is_success(e)
+                    && is_openapi_document(&LoginRequestExtractor::path_of(&e.request.url))
+            })
+            .map(|(_, e)| LoginRequestExtractor::path_of(&e.request.url));
+        let graphql_path = host
+            .api_types
+            .iter()
+            .any(|t| t.api_type == AppType::GraphQL)
+            .then(|| {
+                entries
+                    .iter()
+                    .find(|(_, e)| is_graphql_request(e))
+                    .map(|(_, e)| LoginRequestExtractor::path_of(&e.request.url))
+            })
+            .flatten();
+
+        let exclude_paths = unique(
+            analysis
+                .events
+                .iter()
+                .filter(|e| e.event_type == AuthEventType::Logout)
+                .filter(|e| entries.iter().any(|(idx, _)| *idx == e.entry_index))
+                .map(|e| {
+                    LoginRequestExtractor::path_of(&har.log.entries[e.entry_index].request.url)
+                }),
+        );
+        let authentication = authentication_for(&analysis, &entries, &exclude_paths);
+        let anti_csrf_param = analysis
+            .login_requests
+            .iter()
+            .find(|l| entries.iter().any(|(idx, _)| *idx == l.entry_index))
+            .and_then(|l| l.csrf_field.clone());
+
+        let login_paths: Vec<&str> = authentication
+            .iter()
+            .flat_map(|a| [Some(&a.login.login_path), a.login.login_page_path.as_ref()])
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let mut seed_paths = unique(
+            entries
+                .iter()
+                .filter(|(_, e)| e.request.method == "GET" && is_success(e))
+                .filter(|(_, e)| e.response.content.mime_type.contains("text/html"))
+                .map(|(_, e)| LoginRequestExtractor::path_of(&e.request.url))
+                .filter(|p| !exclude_paths.contains(p) && !login_paths.contains(&p.as_str())),
+        );
+        seed_paths.truncate(MAX_SEED_PATHS);
+
+        configs.push(StackHawkConfig {
+            domain: host.domain.clone(),
+            host: origin,
+            env: env.to_string(),
+            application_id: app_id.map(str::to_string),
+            anti_csrf_param,
+            exclude_paths,
+            rest_api: rest_api && openapi_path.is_none(),
+            openapi_path,
+            graphql_path,
+            authentication,
+            seed_paths,
+            har_file: har_file.to_string(),
+        });
+    }
+
+    Ok(configs)
+}
+
+/// `stackhawk.yml` for a single application, `stackhawk-<host>.yml` for several
+///
+/// The port is added for hosts off their scheme's default port or sharing a hostname with
+/// another application, and the scheme too when hostname and port are both shared.
+pub fn file_names(configs: &[StackHawkConfig]) -> Result<Vec<String>> {
+    if configs.len() == 1 {
+        return Ok(vec!["stackhawk.yml".to_string()]);
+    }
+
+    let origins = configs
+        .iter()
+        .map(|c| {
+            let url = Url::parse(&c.host)
+                .with_context(|| format!("Invalid application host {}", c.host))?;
+            let port = url.port_or_known_default().unwrap_or_default();
+            Ok((
+                url.scheme().to_string(),
+                c.domain.as_str(),
+                port,
+                url.port().is_some(),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut names: Vec<String> = Vec::new();
+    for (scheme, domain, port, explicit_port) in &origins {
+        let shared_domain = origins.iter().filter(|o| o.1 == *domain).count() > 1;
+        let shared_port = origins
+            .iter()
+            .filter(|o| o.1 == *domain && o.2 == *port)
+            .count()
+            > 1;
+        let name = match (shared_port, shared_domain || *explicit_port) {
+            (true, _) => format!("stackhawk-{}-{}-{}.yml", scheme, domain, port),
+            (false, true) => format!("stackhawk-{}-{}.yml", domain, port),
+            (false, false) => format!("stackhawk-{}.yml", domain),
+        };
+        if names.contains(&name) {
+            bail!("Two applications would both be written to {}", name);
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+impl StackHawkConfig {
+    /// Render as a stackhawk.yml document
+    pub fn to_yaml(&self) -> String {
+        let mut out = format!("# Generated by harrier from {}\n", self.har_file);
+        out.push_str("app:\n");
+        out.push_str(&format!(
+            "  applicationId: {}\n",
+            quote(self.application_id.as_deref().unwrap_or("${APP_ID}"))
+        ));
+        out.push_str(&format!(
+            "  env: {}\n",
+            quote(&format!("${{APP_ENV:{}}}", self.env))
+        ));
+        out.push_str(&format!(
+            "  host: {}\n",
+            quote(&format!("${{APP_HOST:{}}}", self.host))
+        ));
+        if let Some(ref param) = self.anti_csrf_param {
+            out.push_str(&format!("  antiCsrfParam: {}\n", quote(param)));
+        }
+
+        if !self.exclude_paths.is_empty() {
+            out.push_str("  excludePaths:\n");
+            for path in &self.exclude_paths {
+                out.push_str(&format!("    - {}\n", quote(path)));
+            }
+        }
+
+        if let Some(ref path) = self.openapi_path {
+            out.push_str("  openApiConf:\n");
+            out.push_str(&format!("    path: {}\n", quote(path)));
+        } else if self.rest_api {
+            out.push_str("  # REST API traffic seen but no OpenAPI document was captured:\n");
+            out.push_str("  # add openApiConf with the spec path or filePath for full coverage\n");
+        }
+
+        if let Some(ref path) = self.graphql_path {
+            out.push_str("  graphqlConf:\n");
+            out.push_str("    enabled: true\n");
+            out.push_str(&format!("    schemaPath: {}\n", quote(path)));
+            out.push_str("    operation: ALL\n");
+            out.push_str("    requestMethod: POST\n");
+        }
+
+        if let Some(ref auth) = self.authentication {
+            out.push_str("  authentication:\n");
+            if let Some(ref indicator) = auth.logged_in_indicator {
+                out.push_str(&format!("    loggedInIndicator: {}\n", quote(indicator)));
+            }
+            if let Some(ref indicator) = auth.logged_out_indicator {
+                out.push_str(&format!("    loggedOutIndicator: {}\n", quote(indicator)));
+            }
+
+            let login = &auth.login;
+            out.push_str("    usernamePassword:\n");
+            out.push_str(&format!("      type: {}\n", login.login_type.as_str()));
+            out.push_str(&format!("      loginPath: {}\n", quote(&login.login_path)));
+            if let Some(ref path) = login.login_page_path {
+                out.push_str(&format!("      loginPagePath: {}\n", quote(path)));
+            }
+            out.push_str(&format!(
+                "      usernameField: {}\n",
+                quote(&login.username_field)
+            ));
+            out.push_str(&format!(
+                "      passwordField: {}\n",
+                quote(&login.password_field)
+            ));
+            out.push_str("      scanUsername: \"${SCAN_USERNAME}\"\n");
+            out.push_str("      scanPassword: \"${SCAN_PASSWORD}\"\n");
+            if !login.other_params.is_empty() {
+                out.push_str("      otherParams:\n");
+                for param in &login.other_params {
+                    if !param.is_static() {
+                        out.push_str(&format!(
+                            "        # Captured value not written: set {}\n",
+                            param.env_var()
+                        ));
+                    }
+                    out.push_str(&format!("        - name: {}\n", quote(&param.name)));
+                    out.push_str(&format!(
+                        "          val: {}\n",
+                        quote(&param.config_value())
+                    ));
+                }
+            }
+
+            match auth.authorization {
+                Authorization::Cookie { ref names } => {
+                    out.push_str("    cookieAuthorization:\n");
+                    out.push_str("      cookieNames:\n");
+                    for name in names {
+                        out.push_str(&format!("        - {}\n", quote(name)));
+                    }
+                }
+                Authorization::Token { ref extraction } => {
+                    let (kind, value) = match extraction {
+                        TokenExtraction::BodyPath { path } => ("TOKEN_PATH", path),
+                        TokenExtraction::Header { name } => ("HEADER_NAME", name),
+                    };
+                    out.push_str("    tokenAuthorization:\n");
+                    out.push_str("      type: HEADER\n");
+                    out.push_str("      value: Authorization\n");
+                    out.push_str("      tokenType: Bearer\n");
+                    out.push_str("    tokenExtraction:\n");
+                    out.push_str(&format!("      type: {}\n", kind));
+                    out.push_str(&format!("      value: {}\n", quote(value)));
+                }
+            }
+
+            if let Some(ref test_path) = auth.test_path {
+                out.push_str("    testPath:\n");
+                out.push_str(&format!("      path: {}\n", quote(&test_path.path)));
+                out.push_str(&format!("      success: {}\n", quote(&test_path.success)));
+                if let Some(ref fail) = test_path.fail {
+                    out.push_str(&format!("      fail: {}\n", quote(fail)));
+                }
+                out.push_str(&format!("      requestMethod: {}\n", test_path.method));
+            }
+        }
+
+        out.push_str("hawk:\n");
+        out.push_str("  spider:\n");
+        out.push_str("    base: true\n");
+        if !self.seed_paths.is_empty() {
+            out.push_str("    seedPaths:\n");
+            for path in &self.seed_paths {
+                out.push_str(&format!("      - {}\n", quote(path)));
+            }
+        }
+        out.push_str("    har:\n");
+        out.push_str("      file:\n");
+        out.push_str("        paths:\n");
+        out.push_str(&format!("          - {}\n", quote(&self.har_file)));
+
+        out
+    }
+}
+
+/// Login, session and indicator settings from the login request on this host
+fn authentication_for(
+    analysis: &AuthAnalysis,
+    entries: &[(usize, &Entry)],
+    logout_paths: &[String],
+) -> Option<AuthenticationConfig> {
+    let on_host = |idx: usize| entries.iter().any(|(i, _)| *i == idx);
+
+    let request = analysis
+        .login_requests
+        .iter()
+        .find(|l| on_host(l.entry_index))?;
+    let login_page_path = request.login_page_path.clone();
+    let login = LoginConfig {
+        login_type: match request.format {
+            LoginFormat::Form => LoginType::Form,
+            LoginFormat::Json => LoginType::Json,
+        },
+        login_path: request.login_path.clone(),
+        login_page_path: login_page_path.clone(),
+        username_field: request
+            .username_field
+            .clone()
+            .unwrap_or_else(|| "username".to_string()),
+        password_field: request
+            .password_field
+            .clone()
+            .unwrap_or_else(|| "password".to_string()),
+        other_params: request.other_params.clone(),
+    };
+
+    let cookie_names = unique(
+        analysis
+            .sessions
+            .iter()
+            .filter_map(|s| match s.session_type {
+                SessionType::Cookie { ref name } if s.entry_indices.iter().any(|&i| on_host(i)) => {
+                    Some(name.clone())
+                }
+                _ => None,
+            }),
+    );
+    let authorization = match request.token_extraction {
+        Some(ref extraction) => Authorization::Token {
+            extraction: extraction.clone(),
+        },
+        _ if !cookie_names.is_empty() => Authorization::Cookie {
+            names: cookie_names,
+        },
+        _ => return None,
+    };
+
+    // A link to logout only appears once logged in; being sent back to login means logged out
+    let logged_in_indicator = logout_paths
+        .first()
+        .map(|path| format!(".*{}.*", regex::escape(path)));
+    let logged_out_indicator = match login_page_path {
+        Some(ref path) => Some(format!(".*Location:.*{}.*", regex::escape(path))),
+        None => entries
+            .iter()
+            .any(|(i, e)| {
+                e.response.status == 401
+                    && analysis.events.iter().any(|ev| {
+                        ev.entry_index == *i && ev.event_type == AuthEventType::SessionExpired
+                    })
+            })
+            .then(|| "HTTP.*401.*".to_string()),
+    };
+
+    Some(AuthenticationConfig {
+        logged_in_indicator,
+        logged_out_indicator,
+        login,
+        authorization,
+        test_path: request.test_path.clone(),
+    })
+}
+
+fn is_openapi_document(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    let file = lower.rsplit('/').next().unwrap_or_default();
+    lower.ends_with("/api-docs")
+        || ["openapi", "swagger"].iter().any(|name| {
+            file.strip_prefix(name)
+                .is_some_and(|ext| ext.is_empty() || [".json", ".yaml", ".yml"].contains(&ext))
+        })
+}
+
+fn is_graphql_request(entry: &Entry) -> bool {
+    LoginRequestExtractor::path_of(&entry.request.url).contains("graphql")
+        || entry
+            .request
+            .post_data
+            .as_ref()
+            .and_then(|p| p.text.as_deref())
+            .is_some_and(|t| t.trim_start().starts_with("{\"query\""))
+}
+
+fn is_success(entry: &Entry) -> bool {
+    (200..300).contains(&entry.response.status)
+}
+
+fn unique(items: impl Iterator<Item = String>) -> Vec<String> {
+    let mut seen = Vec::new();
+    for item in items {
+        if !seen.contains(&item) {
+            seen.push(item);
+        }
+    }
+    seen
+}
@@ -2,7 +2,11 @@ pub mod chrome;
 pub mod completion;
 pub mod discover;
 pub mod filter;
+pub mod hawk;
+pub mod pipeline;
 pub mod profile;
 pub mod proxy;
+pub mod recover;
+pub mod scope;
 pub mod security;
 pub mod stats;
@@ -0,0 +1,556 @@
+//! Post-capture hook pipeline.
+//!
+//! `chrome` and `proxy` run a pipeline on the HAR file once it is written. The pipeline is a
+//! TOML file of chained steps: `filter` and `redact` rewrite the HAR in place, `validate` gates
+//! the remaining steps, and `command` runs any external program with `{har}` replaced by the
+//! HAR path. Each step decides what a failure means through `on_failure`.
+//!
+//! # Examples
+//!
+//! ```toml
+//! [[step]]
+//! type = "filter"
+//! hosts = ["*.example.com"]
+//!
+//! [[step]]
+//! type = "redact"
+//! headers = ["Authorization", "Cookie", "Set-Cookie"]
+//! body_fields = ["password", "access_token"]
+//!
+//! [[step]]
+//! type = "validate"
+//! min_entries = 10
+//! on_failure = "stop"
+//!
+//! [[step]]
+//! name = "hawkscan"
+//! type = "command"
+//! run = "hawk scan {har}"
+//! timeout = 1800
+//! success_codes = [0, 42]
+//! ```
+
+use anyhow::{Context, Result, bail};
+use harrier_core::filter::FilterCriteria;
+use harrier_core::har::{Har, HarReader, HarWriter};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use url::Url;
+use wait_timeout::ChildExt;
+
+/// Placeholder replaced by the HAR path in command arguments
+const HAR_PLACEHOLDER: &str = "{har}";
+
+/// Steps run after capture, in order
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Pipeline {
+    #[serde(default, rename = "step")]
+    pub steps: Vec<Step>,
+}
+
+/// One pipeline step and how to treat its failure
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub on_failure: OnFailure,
+    #[serde(flatten)]
+    pub action: StepAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Keep entries matching all criteria (same semantics as `harrier filter`)
+    Filter {
+        #[serde(default)]
+        hosts: Vec<String>,
+        status: Option<String>,
+        method: Option<String>,
+        content_type: Option<String>,
+    },
+    /// Replace secrets with a placeholder
+    Redact {
+        #[serde(default = "default_redact_headers")]
+        headers: Vec<String>,
+        #[serde(default = "default_true")]
+        cookies: bool,
+        #[serde(default)]
+        query_params: Vec<String>,
+        #[serde(default = "default_redact_body_fields")]
+        body_fields: Vec<String>,
+        #[serde(default = "default_replacement")]
+        replacement: String,
+    },
+    /// Fail unless the HAR is well-formed and large enough to be useful
+    Validate {
+        #[serde(default = "default_min_entries")]
+        min_entries: usize,
+        max_size_mb: Option<u64>,
+    },
+    /// Run an external program
+    Command {
+        run: String,
+        #[serde(default = "default_timeout")]
+        timeout: u64, // Seconds
+        #[serde(default = "default_success_codes")]
+        success_codes: Vec<i32>,
+    },
+}
+
+/// What a failed step does to the rest of the pipeline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnFailure {
+    /// Skip remaining steps and report an error
+    #[default]
+    Abort,
+    /// Warn and run the next step
+    Continue,
+    /// Skip remaining steps without an error
+    Stop,
+}
+
+fn default_redact_headers() -> Vec<String> {
+    [
+        "Authorization",
+        "Proxy-Authorization",
+        "Cookie",
+        "Set-Cookie",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect()
+}
+
+fn default_redact_body_fields() -> Vec<String> {
+    vec!["password".to_string()]
+}
+
+fn default_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_entries() -> usize {
+    1
+}
+
+fn default_timeout() -> u64 {
+    600
+}
+
+fn default_success_codes() -> Vec<i32> {
+    vec![0]
+}
+
+impl Pipeline {
+    /// Load a pipeline from a TOML file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read pipeline {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid pipeline {}", path.display()))
+    }
+
+    /// Parse a pipeline from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let pipeline: Pipeline = toml::from_str(content)?;
+        for step in &pipeline.steps {
+            if let StepAction::Command { ref run, .. } = step.action {
+                let args = shell_words::split(run)
+                    .with_context(|| format!("Cannot parse command '{}'", run))?;
+                if args.is_empty() {
+                    bail!("Command step has an empty 'run'");
+                }
+            }
+        }
+        Ok(pipeline)
+    }
+
+    /// Pipeline for a capture command: the `--pipeline` file, then HawkScan if `scan` is set
+    pub fn for_capture(path: Option<&Path>, scan: bool) -> Result<Self> {
+        let mut pipeline = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        if scan {
+            if which::which("hawk").is_err() {
+                bail!("hawk command not found. Install StackHawk CLI or omit --scan flag.");
+            }
+            pipeline.steps.push(Self::hawk_scan_step());
+        }
+        Ok(pipeline)
+    }
+
+    /// Command step that runs `hawk scan` on the capture
+    pub fn hawk_scan_step() -> Step {
+        Step {
+            name: Some("hawkscan".to_string()),
+            on_failure: OnFailure::Abort,
+            action: StepAction::Command {
+                run: format!("hawk scan {}", HAR_PLACEHOLDER),
+                timeout: 3600,
+                success_codes: default_success_codes(),
+            },
+        }
+    }
+
+    /// Run each step against the HAR file at `har_path`
+    pub fn run(&self, har_path: &Path) -> Result<()> {
+        if self.steps.is_empty() {
+            return Ok(());
+        }
+
+        println!(
+            "🔗 Running post-capture pipeline ({} steps)",
+            self.steps.len()
+        );
+        let mut har = HarReader::from_file(har_path)?;
+        let mut dirty = false; // In-memory HAR differs from the file
+
+        for (i, step) in self.steps.iter().enumerate() {
+            let label = format!("[{}/{}] {}", i + 1, self.steps.len(), step.label());
+            if matches!(step.action, StepAction::Command { .. }) && dirty {
+                HarWriter::to_file(&har, har_path)?;
+                dirty = false;
+            }
+
+            match step.execute(&mut har, har_path) {
+                Ok(StepOutcome { summary, modified }) => {
+                    dirty |= modified;
+                    println!("✅ {}: {}", label, summary);
+                }
+                Err(e) => match step.on_failure {
+                    OnFailure::Abort => {
+                        if dirty {
+                            HarWriter::to_file(&har, har_path)?;
+                        }
+                        return Err(e.context(format!("Pipeline step {} failed", label)));
+                    }
+                    OnFailure::Continue => {
+                        eprintln!("⚠️  {} failed: {:#} (continuing)", label, e);
+                    }
+                    OnFailure::Stop => {
+                        eprintln!("⏹️  {} failed: {:#} (skipping remaining steps)", label, e);
+                        break;
+                    }
+                },
+            }
+        }
+
+        if dirty {
+            HarWriter::to_file(&har, har_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of a successful step
+struct StepOutcome {
+    summary: String,
+    modified: bool,
+}
+
+impl Step {
+    fn label(&self) -> String {
+        let kind = match self.action {
+            StepAction::Filter { .. } => "filter",
+            StepAction::Redact { .. } => "redact",
+            StepAction::Validate { .. } => "validate",
+            StepAction::Command { .. } => "command",
+        };
+        match self.name {
+            Some(ref name) => format!("{} ({})", name, kind),
+            None => kind.to_string(),
+        }
+    }
+
+    fn execute(&self, har: &mut Har, har_path: &Path) -> Result<StepOutcome> {
+        match self.action {
+            StepAction::Filter {
+                ref hosts,
+                ref status,
+                ref method,
+                ref content_type,
+            } => {
+                let mut criteria = FilterCriteria::new().with_hosts(hosts.clone())?;
+                if let Some(status) = status {
+                    criteria = criteria.with_status(status.clone())?;
+                }
+                if let Some(method) = method {
+                    criteria = criteria.with_method(method.clone());
+                }
+                if let Some(content_type) = content_type {
+                    criteria = criteria.with_content_type(content_type.clone());
+                }
+
+                let before = har.log.entries.len();
+                *har = harrier_core::filter::filter_har(har, &criteria)?;
+                Ok(StepOutcome {
+                    summary: format!("kept {} of {} entries", har.log.entries.len(), before),
+                    modified: true,
+                })
+            }
+            StepAction::Redact {
+                ref headers,
+                cookies,
+                ref query_params,
+                ref body_fields,
+                ref replacement,
+            } => {
+                let redactor = Redactor {
+                    headers,
+                    cookies,
+                    query_params,
+                    body_fields,
+                    replacement,
+                };
+                let count = redactor.redact(har);
+                Ok(StepOutcome {
+                    summary: format!("redacted {} values", count),
+                    modified: count > 0,
+                })
+            }
+            StepAction::Validate {
+                min_entries,
+                max_size_mb,
+            } => {
+                HarReader::validate(har)?;
+                let entries = har.log.entries.len();
+                if entries < min_entries {
+                    bail!(
+                        "{} entries captured, at least {} required",
+                        entries,
+                        min_entries
+                    );
+                }
+                if let Some(max) = max_size_mb {
+                    let size = HarWriter::to_string(har)?.len() as u64;
+                    if size > max * 1024 * 1024 {
+                        bail!(
+                            "HAR is {:.1} MB, limit is {} MB",
+                            size as f64 / (1024.0 * 1024.0),
+                            max
+                        );
+                    }
+                }
+                Ok(StepOutcome {
+                    summary: format!("{} entries", entries),
+                    modified: false,
+                })
+            }
+            StepAction::Command {
+                ref run,
+                timeout,
+                ref success_codes,
+            } => {
+                let code = run_command(run, har_path, Duration::from_secs(timeout))?;
+                if !success_codes.contains(&code) {
+                    bail!("'{}' exited with code {}", run, code);
+                }
+                Ok(StepOutcome {
+                    summary: format!("exited with code {}", code),
+                    modified: false,
+                })
+            }
+        }
+    }
+}
+
+/// Run `run` with the HAR path substituted, returning its exit code
+fn run_command(run: &str, har_path: &Path, timeout: Duration) -> Result<i32> {
+    let har = har_path.display().to_string();
+    let args: Vec<String> = shell_words::split(run)?
+        .into_iter()
+        .map(|arg| arg.replace(HAR_PLACEHOLDER, &har))
+        .collect();
+    let Some((program, args)) = args.split_first() else {
+        bail!("Command step has an empty 'run'");
+    };
+    let program: PathBuf =
+        which::which(program).with_context(|| format!("{} not found on PATH", program))?;
+
+    let mut child = Command::new(&program)
+        .args(args)
+        .stdin(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program.display()))?;
+
+    match child.wait_timeout(timeout)? {
+        Some(status) => match status.code() {
+            Some(code) => Ok(code),
+            None => bail!("'{}' was terminated by a signal", run),
+        },
+        None => {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("'{}' timed out after {}s", run, timeout.as_secs())
+        }
+    }
+}
+
+/// Replaces configured secrets throughout a HAR
+struct Redactor<'a> {
+    headers: &'a [String],
+    cookies: bool,
+    query_params: &'a [String],
+    body_fields: &'a [String],
+    replacement: &'a str,
+}
+
+impl Redactor<'_> {
+    /// Redact in place, returning the number of values replaced
+    fn redact(&self, har: &mut Har) -> usize {
+        let mut count = 0;
+
+        for entry in &mut har.log.entries {
+            let request = &mut entry.request;
+            let response = &mut entry.response;
+
+            for header in request
+                .headers
+                .iter_mut()
+                .chain(response.headers.iter_mut())
+            {
+                if self
+                    .headers
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(&header.name))
+                {
+                    count += self.replace(&mut header.value);
+                }
+            }
+
+            if self.cookies {
+                for cookie in request
+                    .cookies
+                    .iter_mut()
+                    .chain(response.cookies.iter_mut())
+                {
+                    count += self.replace(&mut cookie.value);
+                }
+            }
+
+            if !self.query_params.is_empty() {
+                for param in &mut request.query_string {
+                    if self.query_params.contains(&param.name) {
+                        count += self.replace(&mut param.value);
+                    }
+                }
+                count += self.redact_url(&mut request.url);
+            }
+
+            if !self.body_fields.is_empty() {
+                if let Some(ref mut post_data) = request.post_data {
+                    for param in post_data.params.iter_mut().flatten() {
+                        if self.body_fields.contains(&param.name)
+                            && let Some(ref mut value) = param.value
+                        {
+                            count += self.replace(value);
+                        }
+                    }
+                    if let Some(ref mut text) = post_data.text {
+                        count += self.redact_body(text, &post_data.mime_type);
+                    }
+                }
+                if let Some(ref mut text) = response.content.text {
+                    count += self.redact_body(text, &response.content.mime_type);
+                }
+            }
+        }
+
+        count
+    }
+
+    fn replace(&self, value: &mut String) -> usize {
+        if value.is_empty() || value == self.replacement {
+            return 0;
+        }
+        *value = self.replacement.to_string();
+        1
+    }
+
+    fn redact_url(&self, url: &mut String) -> usize {
+        let Ok(mut parsed) = Url::parse(url) else {
+            return 0;
+        };
+        let mut count = 0;
+        let pairs: Vec<(String, String)> = parsed
+            .query_pairs()
+            .map(|(name, value)| {
+                if self.query_params.iter().any(|p| *p == name) && value != self.replacement {
+                    count += 1;
+                    (name.into_owned(), self.replacement.to_string())
+                } else {
+                    (name.into_owned(), value.into_owned())
+                }
+            })
+            .collect();
+        if count > 0 {
+            parsed.query_pairs_mut().clear().extend_pairs(pairs);
+            *url = parsed.to_string();
+        }
+        count
+    }
+
+    /// Redact fields of a JSON or form-encoded body
+    fn redact_body(&self, text: &mut String, mime_type: &str) -> usize {
+        if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(text) {
+            let count = self.redact_json(&mut json);
+            if count > 0 {
+                *text = json.to_string();
+            }
+            return count;
+        }
+
+        if !mime_type.contains("x-www-form-urlencoded") {
+            return 0;
+        }
+        let mut count = 0;
+        let pairs: Vec<(String, String)> = url::form_urlencoded::parse(text.as_bytes())
+            .map(|(name, value)| {
+                if self.body_fields.iter().any(|f| *f == name) && value != self.replacement {
+                    count += 1;
+                    (name.into_owned(), self.replacement.to_string())
+                } else {
+                    (name.into_owned(), value.into_owned())
+                }
+            })
+            .collect();
+        if count > 0 {
+            *text = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(pairs)
+                .finish();
+        }
+        count
+    }
+
+    fn redact_json(&self, value: &mut serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::Object(object) => object
+                .iter_mut()
+                .map(|(key, child)| {
+                    if self.body_fields.contains(key) && !child.is_object() && !child.is_array() {
+                        if child.as_str() == Some(self.replacement) {
+                            0
+                        } else {
+                            *child = serde_json::Value::String(self.replacement.to_string());
+                            1
+                        }
+                    } else {
+                        self.redact_json(child)
+                    }
+                })
+                .sum(),
+            serde_json::Value::Array(items) => {
+                items.iter_mut().map(|item| self.redact_json(item)).sum()
+            }
+            _ => 0,
+        }
+    }
+}
@@ -1,13 +1,53 @@
 use anyhow::Result;
-use harrier_proxy::{CertificateAuthority, ProxyServer};
+use harrier_core::filter::CaptureScope;
+use harrier_core::har::{Creator, HarJournal, Rotation};
+use harrier_proxy::{
+    CertificateAuthority, ProxyAuth, ProxyServer, RewriteRules, TlsPassthrough, UpstreamProxy,
+};
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 
+use super::pipeline::Pipeline;
+
+/// What the proxy records and where
+pub struct ProxyOptions {
+    /// Largest body recorded, in bytes
+    pub max_body_size: usize,
+    /// When to start a new HAR file
+    pub rotate: Option<Rotation>,
+    /// Hosts to record
+    pub scope: CaptureScope,
+    /// Proxy to reach origin servers through
+    pub upstream: Option<UpstreamProxy>,
+    /// Addresses to listen on; `127.0.0.1` when empty
+    pub listen: Vec<SocketAddr>,
+    /// Credentials clients must present
+    pub auth: Option<ProxyAuth>,
+    /// Match-and-replace rules for traffic
+    pub rules: Option<RewriteRules>,
+    /// Hosts tunnelled without decryption
+    pub passthrough: TlsPassthrough,
+}
+
 pub fn execute(
     port: u16,
     output: &Path,
     cert_path: Option<&Path>,
     key_path: Option<&Path>,
+    options: ProxyOptions,
+    pipeline: &Pipeline,
 ) -> Result<()> {
+    let ProxyOptions {
+        max_body_size,
+        rotate,
+        scope,
+        upstream,
+        listen,
+        auth,
+        rules,
+        passthrough,
+    } = options;
+
     tracing::info!("Starting Harrier MITM proxy on port {}", port);
 
     // Load or generate CA certificate
@@ -19,11 +59,31 @@ pub fn execute(
         CertificateAuthority::load_or_generate()?
     };
 
+    // Entries are journaled as they complete so a crash does not lose the session
+    let journal = HarJournal::create(output, creator(), rotate)?;
+
     println!("📝 Output will be written to: {}", output.display());
+    if let Some(path) = journal.journal_path() {
+        println!("   Journaling entries to: {}", path.display());
+    }
     println!();
 
     // Create and start proxy server
-    let server = ProxyServer::new(port, ca);
+    let mut server = ProxyServer::new(port, ca)
+        .with_max_body_size(max_body_size)
+        .with_scope(scope)
+        .with_listen_addrs(listen)
+        .with_passthrough(passthrough)
+        .with_journal(journal.clone());
+    if let Some(upstream) = upstream {
+        server = server.with_upstream(upstream);
+    }
+    if let Some(auth) = auth {
+        server = server.with_auth(auth);
+    }
+    if let Some(rules) = rules {
+        server = server.with_rules(rules);
+    }
 
     // Run the proxy (this blocks until Ctrl+C)
     let runtime = tokio::runtime::Runtime::new()?;
@@ -32,92 +92,145 @@ pub fn execute(
     println!();
     println!("🛑 Proxy stopped");
 
-    // Get captured entries
-    let entries = runtime.block_on(async { handler.entries().lock().await.clone() });
-
-    println!("📊 Captured {} HTTP transactions", entries.len());
-
-    // Generate HAR file
-    if !entries.is_empty() {
-        use serde_json::json;
-        use std::fs;
-
-        // Convert entries to HAR format
-        let har_entries: Vec<serde_json::Value> = entries
-            .iter()
-            .map(|entry| {
-                let duration = entry
-                    .completed_at
-                    .duration_since(entry.started_at)
-                    .unwrap_or_default();
-
-                json!({
-                    "startedDateTime": format!("{:?}", entry.started_at),
-                    "time": duration.as_millis() as i64,
-                    "request": {
-                        "method": entry.method,
-                        "url": entry.url,
-                        "httpVersion": "HTTP/1.1",
-                        "headers": entry.request_headers.iter().map(|(k, v)| {
-                            json!({
-                                "name": k,
-                                "value": v
-                            })
-                        }).collect::<Vec<_>>(),
-                        "queryString": [],
-                        "cookies": [],
-                        "headersSize": -1,
-                        "bodySize": -1
-                    },
-                    "response": {
-                        "status": entry.response_status,
-                        "statusText": "OK",
-                        "httpVersion": "HTTP/1.1",
-                        "headers": entry.response_headers.iter().map(|(k, v)| {
-                            json!({
-                                "name": k,
-                                "value": v
-                            })
-                        }).collect::<Vec<_>>(),
-                        "cookies": [],
-                        "content": {
-                            "size": -1,
-                            "mimeType": "application/octet-stream"
-                        },
-                        "redirectURL": "",
-                        "headersSize": -1,
-                        "bodySize": -1
-                    },
-                    "cache": {},
-                    "timings": {
-                        "send": 0,
-                        "wait": duration.as_millis() as i64,
-                        "receive": 0
-                    }
-                })
-            })
-            .collect();
-
-        // Create HAR structure
-        let har = json!({
-            "log": {
-                "version": "1.2",
-                "creator": {
-                    "name": "Harrier",
-                    "version": env!("CARGO_PKG_VERSION")
-                },
-                "entries": har_entries
-            }
-        });
+    // Entries the journal could not take were kept in memory; try them once more
+    for entry in handler.to_har().log.entries {
+        journal.append(&entry)?;
+    }
 
-        // Write to file
-        let har_json = serde_json::to_string_pretty(&har)?;
-        fs::write(output, har_json)?;
+    println!("📊 Captured {} HTTP transactions", journal.entry_count());
 
-        println!("✅ HAR file written to: {}", output.display());
-    } else {
+    // Generate HAR files
+    let files = journal.finish()?;
+    if files.is_empty() {
         println!("⚠️  No traffic captured, HAR file not generated");
     }
+    for file in &files {
+        println!("✅ HAR file written to: {}", file.display());
+        pipeline.run(file)?;
+    }
 
     Ok(())
 }
+
+/// Listen addresses from `--bind`, each an IP (listening on `port`) or `IP:PORT`
+///
+/// Without `--bind` this is `127.0.0.1:port`.
+pub fn listen_addrs(binds: &[String], port: u16) -> Result<Vec<SocketAddr>> {
+    if binds.is_empty() {
+        return Ok(vec![SocketAddr::from(([127, 0, 0, 1], port))]);
+    }
+    binds
+        .iter()
+        .map(|bind| {
+            let bind = bind.trim();
+            if let Ok(addr) = bind.parse::<SocketAddr>() {
+                return Ok(addr);
+            }
+            // Also accept a bracketed IPv6 address without a port
+            let ip = bind.trim_start_matches('[').trim_end_matches(']');
+            ip.parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, port))
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid --bind address '{}' (use an IP such as 0.0.0.0, or IP:PORT)",
+                        bind
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Load rewrite rules and mocks from `--rules`
+pub fn load_rules(path: &Path) -> Result<RewriteRules> {
+    let rules = RewriteRules::from_file(path)?;
+    let (rewrites, mocks) = (rules.len(), rules.mock_count());
+    if rules.is_empty() {
+        println!("⚠️  No rules in {}", path.display());
+    }
+    if rewrites > 0 {
+        println!(
+            "✏️  Rewriting traffic with {} rules from {}",
+            rewrites,
+            path.display()
+        );
+    }
+    if mocks > 0 {
+        println!(
+            "🎭 Mocking responses with {} mocks from {}",
+            mocks,
+            path.display()
+        );
+    }
+    Ok(rules)
+}
+
+/// TLS passthrough from `--no-intercept`, detecting pinned hosts unless `detect` is off
+///
+/// Hosts may be repeated or comma-separated.
+pub fn passthrough(hosts: Vec<String>, detect: bool) -> Result<TlsPassthrough> {
+    let hosts = super::scope::split_patterns(hosts);
+    if !hosts.is_empty() {
+        println!("🔓 Not intercepting TLS for: {}", hosts.join(", "));
+    }
+    let passthrough = TlsPassthrough::new(&hosts)?;
+    Ok(if detect {
+        passthrough
+    } else {
+        passthrough.without_detection()
+    })
+}
+
+/// Upstream proxy from `--upstream-proxy`, or else from `HTTP(S)_PROXY`/`ALL_PROXY`
+///
+/// `no_proxy` entries may be repeated or comma-separated and are added to `NO_PROXY`.
+/// `listen` is every address this proxy listens on, so a proxy URL looping back to any
+/// of them is refused.
+pub fn upstream_proxy(
+    url: Option<&str>,
+    no_proxy: &[String],
+    listen: &[SocketAddr],
+) -> Result<Option<UpstreamProxy>> {
+    let no_proxy = no_proxy.join(",");
+
+    if let Some(url) = url {
+        let upstream = UpstreamProxy::new(url, &no_proxy)?;
+        if upstream.points_to(listen) {
+            anyhow::bail!("--upstream-proxy {} points at this proxy's own port", url);
+        }
+        print_upstream(&upstream, "--upstream-proxy");
+        return Ok(Some(upstream));
+    }
+
+    // Unusable values are skipped, since other tools may read these variables too
+    let Some(upstream) = UpstreamProxy::from_env(&no_proxy) else {
+        return Ok(None);
+    };
+    // Shells set up to send traffic through Harrier would otherwise loop back into it
+    if upstream.points_to(listen) {
+        eprintln!("⚠️  Ignoring HTTP(S)_PROXY: it points at this proxy's own port");
+        return Ok(None);
+    }
+    print_upstream(&upstream, "environment");
+    Ok(Some(upstream))
+}
+
+fn print_upstream(upstream: &UpstreamProxy, source: &str) {
+    let targets: Vec<String> = upstream
+        .targets()
+        .iter()
+        .map(|uri| uri.to_string().trim_end_matches('/').to_string())
+        .collect();
+    println!(
+        "🔗 Chaining through upstream proxy: {} (from {})",
+        targets.join(", "),
+        source
+    );
+}
+
+fn creator() -> Creator {
+    Creator {
+        name: "Harrier".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        comment: None,
+    }
+}
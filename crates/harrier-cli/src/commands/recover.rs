@@ -0,0 +1,30 @@
+use anyhow::Result;
+use harrier_core::har::{HarJournal, HarWriter};
+use std::path::Path;
+
+pub fn execute(journal: &Path, output: Option<&Path>, keep_journal: bool) -> Result<()> {
+    tracing::debug!("Recovering HAR from journal: {}", journal.display());
+
+    let recovered = HarJournal::recover(journal)?;
+    let output = output.map_or_else(|| HarJournal::har_path_for(journal), Path::to_path_buf);
+
+    HarWriter::to_file_atomic(&recovered.har, &output)?;
+
+    println!(
+        "✅ Recovered {} entries to: {}",
+        recovered.har.log.entries.len(),
+        output.display()
+    );
+    if recovered.skipped_lines > 0 {
+        println!(
+            "⚠️  Skipped {} unreadable journal line(s), most likely cut short by the crash",
+            recovered.skipped_lines
+        );
+    }
+
+    if !keep_journal {
+        std::fs::remove_file(journal)?;
+    }
+
+    Ok(())
+}
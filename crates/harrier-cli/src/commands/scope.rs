@@ -0,0 +1,50 @@
+use anyhow::Result;
+use harrier_core::filter::{CaptureScope, OutOfScope};
+
+/// Build the live capture scope from `--hosts`, `--ignore-hosts` and related flags
+///
+/// Host lists accept repeated flags and comma-separated values.
+pub fn build(
+    hosts: Vec<String>,
+    ignore_hosts: Vec<String>,
+    out_of_scope: OutOfScope,
+    record_telemetry: bool,
+) -> Result<CaptureScope> {
+    let mut scope = CaptureScope::new()
+        .with_hosts(split_patterns(hosts))?
+        .with_ignored_hosts(split_patterns(ignore_hosts))?
+        .with_out_of_scope(out_of_scope);
+
+    if !record_telemetry {
+        scope = scope.with_telemetry_ignored();
+    }
+
+    Ok(scope)
+}
+
+/// Print what the capture will record
+pub fn describe(hosts: &[String], out_of_scope: OutOfScope, record_telemetry: bool) {
+    let hosts = split_patterns(hosts.to_vec());
+    if !hosts.is_empty() {
+        let others = match out_of_scope {
+            OutOfScope::Drop => "not recorded",
+            OutOfScope::HeadersOnly => "headers only",
+        };
+        println!(
+            "🔍 Recording hosts: {} (others: {})",
+            hosts.join(", "),
+            others
+        );
+    }
+    if !record_telemetry {
+        println!("   Ignoring known telemetry hosts (--record-telemetry to keep them)");
+    }
+}
+
+pub fn split_patterns(patterns: Vec<String>) -> Vec<String> {
+    patterns
+        .iter()
+        .flat_map(|p| p.split(',').map(|s| s.trim().to_string()))
+        .filter(|p| !p.is_empty())
+        .collect()
+}
@@ -1,11 +1,92 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{Context, Result};
+use harrier_core::har::HarReader;
+use harrier_detectors::{
+    AccessCell, AccessMatrix, AggregatedFinding, AuthAnalysis, AuthAnalyzer, AuthSummaryGenerator,
+    InvalidationStatus, JwtVerifier, SecurityFindingsSummary, VerificationStatus,
+};
+use std::path::{Path, PathBuf};
+
+/// Key material used to verify captured JWT signatures
+#[derive(Debug, Default, Clone)]
+pub struct JwtKeyOptions {
+    pub jwks: Vec<PathBuf>,
+    pub keys: Vec<PathBuf>,
+    pub secret_files: Vec<PathBuf>,
+    pub wordlist: Option<PathBuf>,
+}
+
+/// Build a JWT verifier from JWKS files, PEM public keys and secret files
+pub fn build_verifier(options: &JwtKeyOptions) -> Result<JwtVerifier> {
+    let mut verifier = JwtVerifier::new();
+
+    for path in &options.jwks {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read JWKS file {}", path.display()))?;
+        let count = verifier.add_jwks(&json, &path.display().to_string())?;
+        tracing::debug!("Loaded {} key(s) from {}", count, path.display());
+    }
+
+    for path in &options.keys {
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read key file {}", path.display()))?;
+        let count = verifier.add_pem(&pem, &path.display().to_string())?;
+        tracing::debug!("Loaded {} key(s) from {}", count, path.display());
+    }
+
+    for path in &options.secret_files {
+        let secret = std::fs::read(path)
+            .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+        // Editors usually leave a trailing newline that is not part of the secret
+        let secret = secret
+            .strip_suffix(b"\r\n")
+            .or_else(|| secret.strip_suffix(b"\n"))
+            .unwrap_or(&secret);
+        verifier.add_secret(secret, &path.display().to_string());
+    }
+
+    Ok(verifier)
+}
+
+/// Load candidate HMAC secrets, one per line
+pub fn load_wordlist(path: &Path) -> Result<Vec<String>> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read wordlist {}", path.display()))?;
+    // Wordlists such as rockyou are not valid UTF-8 throughout
+    let text = String::from_utf8_lossy(&bytes);
+    let words: Vec<String> = text
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    tracing::debug!(
+        "Loaded {} candidate secrets from {}",
+        words.len(),
+        path.display()
+    );
+    Ok(words)
+}
+
+/// Run authentication and JWT analysis for the security report
+pub fn analyze(file: &Path, jwt_keys: &JwtKeyOptions) -> Result<AuthAnalysis> {
+    let har = HarReader::from_file(file)?;
+    let verifier = build_verifier(jwt_keys)?;
+    let wordlist = match jwt_keys.wordlist {
+        Some(ref path) => load_wordlist(path)?,
+        None => Vec::new(),
+    };
+    Ok(AuthAnalyzer::analyze_with_jwt_checks(
+        &har, &verifier, &wordlist,
+    )?)
+}
 
 pub fn execute(
     file: &Path,
     check_auth: bool,
     find_sensitive: bool,
     insecure_only: bool,
+    access_matrix: bool,
+    jwt_keys: &JwtKeyOptions,
     format: &str,
 ) -> Result<()> {
     tracing::info!(
@@ -13,13 +94,540 @@ pub fn execute(
         file.display()
     );
 
-    // TODO: Implement security command
-    println!("Security command - Coming soon!");
-    println!("  File: {}", file.display());
-    println!("  Check auth: {}", check_auth);
-    println!("  Find sensitive data: {}", find_sensitive);
-    println!("  Insecure only: {}", insecure_only);
-    println!("  Format: {}", format);
+    let analysis = analyze(file, jwt_keys)?;
+
+    if access_matrix {
+        let matrix = &analysis.access_matrix;
+        match format {
+            "json" => println!("{}", serde_json::to_string_pretty(matrix)?),
+            "table" => print!("{}", access_matrix_csv(matrix)),
+            _ => output_access_matrix(matrix),
+        }
+        return Ok(());
+    }
+
+    let findings = select_findings(
+        AuthSummaryGenerator::aggregate_security_findings(&analysis),
+        check_auth,
+        find_sensitive,
+        insecure_only,
+    );
+
+    match format {
+        "json" => output_json(&analysis, &findings)?,
+        _ => output_pretty(&analysis, &findings),
+    }
+
+    Ok(())
+}
+
+/// Narrow findings to the requested categories
+fn select_findings(
+    mut findings: SecurityFindingsSummary,
+    check_auth: bool,
+    find_sensitive: bool,
+    insecure_only: bool,
+) -> SecurityFindingsSummary {
+    let keep = |finding: &AggregatedFinding| {
+        let sensitive = finding.finding_type.starts_with("Token Exposure");
+        match (check_auth, find_sensitive) {
+            (true, false) => !sensitive,
+            (false, true) => sensitive,
+            _ => true,
+        }
+    };
+
+    findings.critical.retain(keep);
+    findings.warnings.retain(keep);
+    if insecure_only {
+        findings.info.clear();
+    } else {
+        findings.info.retain(keep);
+    }
+
+    findings
+}
+
+/// Access matrix as CSV: one row per endpoint, one column per identity
+pub fn access_matrix_csv(matrix: &AccessMatrix) -> String {
+    let quote = |value: &str| {
+        if value.contains([',', '"']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+
+    let mut csv = String::from("Host,Endpoint");
+    for identity in &matrix.identities {
+        csv.push(',');
+        csv.push_str(&quote(&identity.label));
+    }
+    csv.push('\n');
+
+    for endpoint in &matrix.endpoints {
+        csv.push_str(&quote(&endpoint.host));
+        csv.push(',');
+        csv.push_str(&quote(&endpoint.endpoint));
+        for identity in &matrix.identities {
+            csv.push(',');
+            csv.push_str(&cell_statuses(endpoint.cell(&identity.label)));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+fn cell_statuses(cell: Option<&AccessCell>) -> String {
+    match cell {
+        Some(cell) => join_statuses(&cell.statuses),
+        None => "-".to_string(),
+    }
+}
+
+fn join_statuses(statuses: &[i64]) -> String {
+    statuses
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn output_access_matrix(matrix: &AccessMatrix) {
+    use console::style;
+
+    println!("\n{}", style("Access Matrix").bold().cyan());
+    println!("{}", style("=============").cyan());
+
+    if matrix.endpoints.is_empty() {
+        println!("\n  No requests to map");
+        return;
+    }
+
+    println!("\n{}", style("Identities:").bold());
+    for (i, identity) in matrix.identities.iter().enumerate() {
+        let roles = if identity.roles.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", identity.roles.join(", "))
+        };
+        let label = format!("{}{}", identity.label, roles);
+        println!(
+            "  #{:<3} {} ({} request{})",
+            i + 1,
+            if identity.privileged {
+                style(label).magenta()
+            } else {
+                style(label)
+            },
+            identity.request_count,
+            if identity.request_count > 1 { "s" } else { "" }
+        );
+    }
 
+    let rows: Vec<String> = matrix
+        .endpoints
+        .iter()
+        .map(|e| format!("{} {}", e.host, e.endpoint))
+        .collect();
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let columns: Vec<String> = (1..=matrix.identities.len())
+        .map(|i| format!("#{}", i))
+        .collect();
+    let column_width = matrix
+        .endpoints
+        .iter()
+        .flat_map(|e| e.cells.iter().map(|c| cell_statuses(Some(c)).len()))
+        .chain(columns.iter().map(|c| c.len()))
+        .max()
+        .unwrap_or(3);
+
+    print!("\n  {:<width$}", "", width = width);
+    for column in &columns {
+        print!("  {:<column_width$}", style(column).bold());
+    }
+    println!();
+    for (endpoint, row) in matrix.endpoints.iter().zip(&rows) {
+        print!("  {:<width$}", row, width = width);
+        for identity in &matrix.identities {
+            let cell = endpoint.cell(&identity.label);
+            let text = cell_statuses(cell);
+            let styled = match cell {
+                Some(c) if c.allowed() => style(text).green(),
+                Some(_) => style(text).red(),
+                None => style(text).dim(),
+            };
+            print!("  {:<column_width$}", styled);
+        }
+        println!();
+    }
+
+    if !matrix.issues.is_empty() {
+        println!("\n{}", style("Flagged:").yellow().bold());
+        for issue in &matrix.issues {
+            println!("  {} (#{})", issue.message, issue.entry_index);
+        }
+    }
+}
+
+fn output_json(analysis: &AuthAnalysis, findings: &SecurityFindingsSummary) -> Result<()> {
+    use serde_json::json;
+
+    let output = json!({
+        "findings": findings,
+        "jwt_verification": analysis.jwt_verification,
+        "oauth": {
+            "authorization_requests": analysis.oauth.authorization_requests,
+            "provider_configs": analysis.oauth.provider_configs,
+        },
+        "saml_flows": analysis.saml_flows,
+        "session_lifecycle": analysis.session_lifecycle,
+        "idor_candidates": analysis.idor.candidates,
+        "unauthenticated": analysis.unauthenticated,
+        "csrf": {
+            "endpoints": analysis.csrf.endpoints,
+            "tokens": analysis.csrf.tokens,
+        },
+    });
+
+    println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
+
+fn output_pretty(analysis: &AuthAnalysis, findings: &SecurityFindingsSummary) {
+    use console::style;
+
+    println!("\n{}", style("Security Analysis Report").bold().cyan());
+    println!("{}", style("========================").cyan());
+
+    let sections = [
+        ("Critical", &findings.critical),
+        ("Warning", &findings.warnings),
+        ("Info", &findings.info),
+    ];
+
+    if sections.iter().all(|(_, list)| list.is_empty()) {
+        println!("\n  No security findings");
+    }
+
+    for (label, list) in sections {
+        if list.is_empty() {
+            continue;
+        }
+        let heading = format!("{} {}:", list.len(), label);
+        let heading = match label {
+            "Critical" => style(heading).red().bold(),
+            "Warning" => style(heading).yellow().bold(),
+            _ => style(heading).blue(),
+        };
+        println!("\n{}", heading);
+        for finding in list {
+            println!(
+                "  {} ({} occurrence{})",
+                finding.message,
+                finding.count,
+                if finding.count > 1 { "s" } else { "" }
+            );
+        }
+    }
+
+    let oauth = &analysis.oauth;
+    if !oauth.authorization_requests.is_empty() {
+        println!("\n{}", style("OAuth Authorization Requests").bold());
+        println!("{}", style("────────────────────────────").dim());
+        for request in &oauth.authorization_requests {
+            let check = |present: bool| {
+                if present {
+                    style("✓").green()
+                } else {
+                    style("✗").red()
+                }
+            };
+            println!(
+                "  #{} client {} response_type={}",
+                request.entry_index,
+                request.client_id.as_deref().unwrap_or("?"),
+                request.response_type.as_deref().unwrap_or("?")
+            );
+            println!(
+                "      state {}  nonce {}  PKCE {}",
+                check(request.state.is_some()),
+                check(request.nonce.is_some()),
+                match request.code_challenge_method {
+                    Some(ref method) if method == "S256" => style(method.clone()).green(),
+                    Some(ref method) => style(method.clone()).yellow(),
+                    None => style("none".to_string()).red(),
+                }
+            );
+            if let Some(token_idx) = request.token_entry {
+                println!("      Code exchanged at #{}", token_idx);
+            }
+        }
+    }
+
+    for config in &oauth.provider_configs {
+        println!(
+            "\n{} {}",
+            style("OIDC Provider Configuration:").bold(),
+            config.issuer.as_deref().unwrap_or(&config.source_url)
+        );
+        for (label, values) in [
+            ("Grants", &config.grant_types_supported),
+            ("Response types", &config.response_types_supported),
+            ("PKCE methods", &config.code_challenge_methods_supported),
+            (
+                "ID token algs",
+                &config.id_token_signing_alg_values_supported,
+            ),
+            ("Client auth", &config.token_endpoint_auth_methods_supported),
+        ] {
+            if !values.is_empty() {
+                println!("  {:<15} {}", format!("{}:", label), values.join(", "));
+            }
+        }
+    }
+
+    let lifecycle = &analysis.session_lifecycle;
+    if !lifecycle.login_rotations.is_empty() || !lifecycle.logout_checks.is_empty() {
+        println!("\n{}", style("Session Lifecycle").bold());
+        println!("{}", style("─────────────────").dim());
+        for rotation in &lifecycle.login_rotations {
+            println!(
+                "  #{} login{}: {} {} → {} {}",
+                rotation.login_entry,
+                rotation
+                    .user
+                    .as_deref()
+                    .map(|u| format!(" ({})", u))
+                    .unwrap_or_default(),
+                rotation.cookie_name,
+                rotation.pre_login,
+                rotation.post_login,
+                if rotation.rotated {
+                    style("rotated").green()
+                } else {
+                    style("not rotated").red()
+                }
+            );
+        }
+        for check in &lifecycle.logout_checks {
+            let status = match check.status {
+                InvalidationStatus::Invalidated => style(check.status.as_str()).green(),
+                InvalidationStatus::StillValid => style(check.status.as_str()).red(),
+                InvalidationStatus::NotTested => style(check.status.as_str()).dim(),
+            };
+            println!(
+                "  #{} logout: {} {}",
+                check.logout_entry, check.identifier, status
+            );
+        }
+    }
+
+    if !analysis.idor.candidates.is_empty() {
+        println!("\n{}", style("IDOR / BOLA Candidates").bold());
+        println!("{}", style("──────────────────────").dim());
+        for candidate in analysis.idor.candidates.iter().take(10) {
+            println!(
+                "  [{}] {} {} ({} {} {})",
+                candidate.score,
+                candidate.host,
+                candidate.endpoint,
+                candidate.location.as_str(),
+                candidate.id_kind.as_str(),
+                candidate.parameter
+            );
+            println!("      {}", style(candidate.reasons.join("; ")).dim());
+        }
+        if analysis.idor.candidates.len() > 10 {
+            println!(
+                "  ... and {} more (use --format json for the full list)",
+                analysis.idor.candidates.len() - 10
+            );
+        }
+    }
+
+    let inventory = &analysis.unauthenticated;
+    let hosts = inventory.hosts();
+    if !hosts.is_empty() {
+        println!(
+            "\n{}",
+            style("Endpoints Requested Without Credentials").bold()
+        );
+        println!("{}", style("───────────────────────────────────────").dim());
+        for host in hosts {
+            println!("  {}", host);
+            for endpoint in inventory.open_endpoints.iter().filter(|e| e.host == host) {
+                println!(
+                    "    {} {} ({})",
+                    style("open  ").yellow(),
+                    endpoint.endpoint,
+                    join_statuses(&endpoint.statuses)
+                );
+            }
+            for endpoint in inventory.denied_endpoints.iter().filter(|e| e.host == host) {
+                println!(
+                    "    {} {} ({})",
+                    style("denied").green(),
+                    endpoint.endpoint,
+                    join_statuses(&endpoint.statuses)
+                );
+            }
+        }
+    }
+
+    if !analysis.csrf.endpoints.is_empty() {
+        println!("\n{}", style("CSRF Protection").bold());
+        println!("{}", style("───────────────").dim());
+        for endpoint in &analysis.csrf.endpoints {
+            let marker = if endpoint.is_protected() {
+                style("✓").green()
+            } else {
+                style("✗").red()
+            };
+            let protections: Vec<&str> = endpoint.protections.iter().map(|p| p.as_str()).collect();
+            println!(
+                "  {} {} {} ({} request{})",
+                marker,
+                endpoint.host,
+                endpoint.endpoint,
+                endpoint.request_count,
+                if endpoint.request_count > 1 { "s" } else { "" }
+            );
+            if !protections.is_empty() {
+                println!("      {}", style(protections.join(", ")).dim());
+            }
+        }
+    }
+
+    for flow in analysis
+        .saml_flows
+        .iter()
+        .filter(|f| !f.messages.is_empty())
+    {
+        println!(
+            "\n{} {}",
+            style("SAML Flow:").bold(),
+            flow.flow_type.as_str()
+        );
+        if let Some(ref idp) = flow.idp_entity_id {
+            println!("  IdP: {}", idp);
+        }
+        if let Some(ref sp) = flow.sp_entity_id {
+            println!("  SP:  {}", sp);
+        }
+        for message in &flow.messages {
+            println!(
+                "  #{} {} ({}, {})",
+                message.entry_index,
+                message.kind.as_str(),
+                message.binding.as_str(),
+                if message.signed {
+                    style("signed").green()
+                } else {
+                    style("unsigned").red()
+                }
+            );
+            for assertion in &message.assertions {
+                println!(
+                    "      Subject: {}{}",
+                    assertion.name_id.as_deref().unwrap_or("?"),
+                    assertion
+                        .name_id_format
+                        .as_deref()
+                        .and_then(|f| f.rsplit(':').next())
+                        .map(|f| format!(" ({})", f))
+                        .unwrap_or_default()
+                );
+                if !assertion.audiences.is_empty() {
+                    println!("      Audience: {}", assertion.audiences.join(", "));
+                }
+                if let Some(ref until) = assertion.not_on_or_after {
+                    println!(
+                        "      Valid: {} → {}",
+                        assertion.not_before.as_deref().unwrap_or("?"),
+                        until
+                    );
+                }
+                for attribute in &assertion.attributes {
+                    println!(
+                        "      {}: {}",
+                        attribute
+                            .friendly_name
+                            .as_deref()
+                            .unwrap_or(&attribute.name),
+                        attribute.values.join(", ")
+                    );
+                }
+            }
+            if message.encrypted_assertions > 0 {
+                println!(
+                    "      {} encrypted assertion(s)",
+                    message.encrypted_assertions
+                );
+            }
+        }
+    }
+
+    let report = &analysis.jwt_verification;
+    if !report.results.is_empty() {
+        println!("\n{}", style("JWT Signature Verification").bold());
+        println!("{}", style("──────────────────────────").dim());
+        for result in &report.results {
+            let status = match result.status {
+                VerificationStatus::Verified => style(result.status.as_str()).green(),
+                VerificationStatus::InvalidSignature | VerificationStatus::Unsigned => {
+                    style(result.status.as_str()).red()
+                }
+                _ => style(result.status.as_str()).dim(),
+            };
+            println!(
+                "  [{}] {} ({})",
+                status,
+                result.token_preview,
+                result.alg.as_deref().unwrap_or("no alg")
+            );
+            if let Some(ref source) = result.key_source {
+                println!("      Key: {}", source);
+            }
+            if !result.accepted_entries.is_empty() {
+                println!(
+                    "      Accepted by server: {}",
+                    result
+                        .accepted_entries
+                        .iter()
+                        .map(|e| format!("#{}", e))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+    }
+
+    if !report.exposed_keys.is_empty() {
+        println!("\n{}", style("Public Keys Found in Capture:").bold());
+        for key in &report.exposed_keys {
+            println!(
+                "  {} key{} from {}",
+                key.key_type,
+                key.kid
+                    .as_ref()
+                    .map(|kid| format!(" (kid {})", kid))
+                    .unwrap_or_default(),
+                key.source
+            );
+        }
+    }
+
+    if !report.uncaptured_jwks_uris.is_empty() {
+        println!("\n{}", style("JWKS Not Captured:").bold());
+        for uri in &report.uncaptured_jwks_uris {
+            println!("  {}", uri);
+        }
+        println!(
+            "  {}",
+            style("Download the key set and pass it with --jwks to verify these tokens").dim()
+        );
+    }
+
+    println!();
+}
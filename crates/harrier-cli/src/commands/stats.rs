@@ -52,7 +52,7 @@ pub struct HostStats {
 /// Extract root domain from a domain string using the Public Suffix List
 /// Examples: api.example.com -> example.com, www.example.com -> example.com, api.co.uk -> example.co.uk
 /// This properly handles public suffixes like .co.uk, .com.au, etc.
-fn get_root_domain(domain: &str) -> String {
+pub(crate) fn get_root_domain(domain: &str) -> String {
     // Use the psl crate to get the registrable domain (eTLD+1)
     // This handles public suffixes correctly
     match psl::domain(domain.as_bytes()) {
@@ -516,22 +516,103 @@ fn output_pretty(
                 println!("{}", style("──────────────────").dim());
                 for (i, token) in auth_analysis.jwt_tokens.iter().enumerate() {
                     println!("\n{}. {}", i + 1, token.raw_token);
+                    println!("   Format:     {}", token.format.as_str());
                     if let Some(ref alg) = token.header.alg {
                         println!("   Algorithm:  {}", alg);
                     }
+                    if let Some(ref enc) = token.header.enc {
+                        println!("   Encryption: {}", enc);
+                    }
                     if let Some(ref iss) = token.claims.iss {
                         println!("   Issuer:     {}", iss);
                     }
                     if let Some(ref sub) = token.claims.sub {
                         println!("   Subject:    {}", sub);
                     }
+                    if !token.claims.aud.is_empty() {
+                        println!("   Audience:   {}", token.claims.aud.join(", "));
+                    }
+                    if let Some(ref azp) = token.claims.azp {
+                        println!("   Client:     {}", azp);
+                    }
+                    if !token.claims.scope.is_empty() {
+                        println!("   Scope:      {}", token.claims.scope.join(" "));
+                    }
+                    if !token.claims.roles.is_empty() {
+                        println!("   Roles:      {}", token.claims.roles.join(", "));
+                    }
+                    if let Some(ref cnf) = token.claims.cnf {
+                        let binding = if cnf.jkt.is_some() {
+                            "DPoP key"
+                        } else if cnf.x5t_s256.is_some() {
+                            "mTLS certificate"
+                        } else {
+                            "proof-of-possession key"
+                        };
+                        println!("   Bound to:   {}", binding);
+                    }
                     if let Some(exp) = token.claims.exp {
                         println!("   Expires:    {} (Unix timestamp)", exp);
                     }
+                    for inner in &token.nested {
+                        println!(
+                            "   Nested:     {} {}",
+                            inner.format.as_str(),
+                            inner.raw_token
+                        );
+                    }
                     println!("   Usage:      {} requests", token.usage_count);
                 }
             }
 
+            // Show where tokens came from and where they were used
+            if !auth_analysis.token_lineage.is_empty() {
+                println!("\n{}", style("Token Lineage").bold());
+                println!("{}", style("─────────────").dim());
+                for (i, lineage) in auth_analysis.token_lineage.iter().enumerate() {
+                    println!(
+                        "\n{}. {} {}",
+                        i + 1,
+                        lineage.kind.as_str(),
+                        lineage.token_preview
+                    );
+                    match lineage.issued_by {
+                        Some(ref issuance) => println!(
+                            "   Issued by:  #{} {} ({}{})",
+                            issuance.entry_index,
+                            issuance.url,
+                            issuance.flow_type.as_str(),
+                            issuance
+                                .grant_type
+                                .as_ref()
+                                .map(|g| format!(", grant_type={}", g))
+                                .unwrap_or_default()
+                        ),
+                        None => println!("   Issued by:  not captured"),
+                    }
+                    if let Some(ref iss) = lineage.issuer {
+                        println!("   Issuer:     {}", iss);
+                    }
+                    if let Some(lifetime) = lineage.lifetime_seconds {
+                        println!("   Lifetime:   {}s", lifetime);
+                    }
+                    if let Some(ref previous) = lineage.replaces {
+                        println!("   Replaces:   {}", previous);
+                    }
+                    if let Some(ref next) = lineage.replaced_by {
+                        println!("   Rotated to: {}", next);
+                    }
+                    for usage in &lineage.used_by {
+                        println!(
+                            "   Used by:    {} ({} request{})",
+                            usage.endpoint,
+                            usage.request_count,
+                            if usage.request_count > 1 { "s" } else { "" }
+                        );
+                    }
+                }
+            }
+
             // Show authentication events
             if !auth_analysis.events.is_empty() {
                 println!("\n{}", style("Authentication Events").bold());
@@ -19,10 +19,23 @@ fn test_chrome_command_help() {
         ))
         .stdout(predicate::str::contains("--output"))
         .stdout(predicate::str::contains("--hosts"))
+        .stdout(predicate::str::contains("--ignore-hosts"))
+        .stdout(predicate::str::contains("--out-of-scope"))
         .stdout(predicate::str::contains("--scan"))
+        .stdout(predicate::str::contains("--pipeline"))
         .stdout(predicate::str::contains("--profile"));
 }
 
+#[test]
+fn test_chrome_rejects_unknown_out_of_scope_mode() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("chrome").arg("--out-of-scope").arg("keep");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown out-of-scope mode 'keep'"));
+}
+
 #[test]
 fn test_chrome_command_without_chrome() {
     // This test will fail if Chrome is actually installed
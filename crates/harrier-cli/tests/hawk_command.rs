@@ -0,0 +1,206 @@
+use harrier_cli::commands::hawk::{
+    Authorization, LoginType, StackHawkConfig, file_names, generate, init,
+};
+use harrier_core::har::HarReader;
+use harrier_detectors::LoginParam;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Helper to get path to test fixtures
+fn fixture_path(filename: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join(filename)
+}
+
+/// Test that each first-party application host gets its own config
+#[test]
+fn test_hawk_init_one_config_per_application() {
+    // Arrange
+    let har = HarReader::from_file(&fixture_path("hawk-init.har")).unwrap();
+
+    // Act
+    let configs = generate(&har, "capture.har", "Staging", None).unwrap();
+
+    // Assert - the asset-only CDN and third-party analytics hosts are skipped
+    let hosts: Vec<&str> = configs.iter().map(|c| c.host.as_str()).collect();
+    assert_eq!(
+        hosts,
+        vec!["https://app.example.com", "https://api.example.com"]
+    );
+    assert_eq!(
+        file_names(&configs).unwrap(),
+        vec![
+            "stackhawk-app.example.com.yml",
+            "stackhawk-api.example.com.yml"
+        ]
+    );
+    assert_eq!(file_names(&configs[..1]).unwrap(), vec!["stackhawk.yml"]);
+}
+
+/// Test that applications sharing a hostname get distinct file names
+#[test]
+fn test_hawk_init_file_names_for_shared_hostname() {
+    // Arrange
+    let config = |host: &str| StackHawkConfig {
+        domain: "localhost".to_string(),
+        host: host.to_string(),
+        ..Default::default()
+    };
+    let ports = [
+        config("http://localhost:3000"),
+        config("http://localhost:8080"),
+    ];
+    let schemes = [
+        config("http://localhost:8080"),
+        config("https://localhost:8080"),
+        config("https://localhost"),
+    ];
+
+    // Act & Assert
+    assert_eq!(
+        file_names(&ports).unwrap(),
+        vec![
+            "stackhawk-localhost-3000.yml",
+            "stackhawk-localhost-8080.yml"
+        ]
+    );
+    assert_eq!(
+        file_names(&schemes).unwrap(),
+        vec![
+            "stackhawk-http-localhost-8080.yml",
+            "stackhawk-https-localhost-8080.yml",
+            "stackhawk-localhost-443.yml"
+        ]
+    );
+}
+
+/// Test that the login flow, session cookie and logout become authentication settings
+#[test]
+fn test_hawk_init_form_login_authentication() {
+    // Arrange
+    let har = HarReader::from_file(&fixture_path("hawk-init.har")).unwrap();
+
+    // Act
+    let configs = generate(&har, "capture.har", "Staging", Some("app-123")).unwrap();
+
+    // Assert
+    let app = &configs[0];
+    assert_eq!(app.exclude_paths, vec!["/logout"]);
+    assert_eq!(app.seed_paths, vec!["/", "/dashboard", "/settings"]);
+
+    let auth = app.authentication.as_ref().unwrap();
+    assert_eq!(auth.login.login_type, LoginType::Form);
+    assert_eq!(auth.login.login_path, "/login");
+    assert_eq!(auth.login.login_page_path.as_deref(), Some("/login"));
+    assert_eq!(auth.login.username_field, "username");
+    assert_eq!(auth.login.password_field, "password");
+    assert_eq!(app.anti_csrf_param.as_deref(), Some("csrf_token"));
+    assert_eq!(
+        auth.authorization,
+        Authorization::Cookie {
+            names: vec!["sessionid".to_string()]
+        }
+    );
+    assert_eq!(
+        auth.logged_out_indicator.as_deref(),
+        Some(".*Location:.*/login.*")
+    );
+
+    let yaml = app.to_yaml();
+    assert!(yaml.contains("  applicationId: \"app-123\"\n"));
+    assert!(yaml.contains("  env: \"${APP_ENV:Staging}\"\n"));
+    assert!(yaml.contains("  host: \"${APP_HOST:https://app.example.com}\"\n"));
+    assert!(yaml.contains("      type: FORM\n"));
+    assert!(yaml.contains("      cookieNames:\n        - \"sessionid\"\n"));
+    assert!(yaml.contains("  antiCsrfParam: \"csrf_token\"\n"));
+    assert!(yaml.contains(
+        "    testPath:\n      path: \"/dashboard\"\n      success: \"HTTP.*200.*\"\n      fail: \".*Location:.*/login.*\"\n      requestMethod: GET\n"
+    ));
+    assert!(yaml.contains("        paths:\n          - \"capture.har\"\n"));
+}
+
+/// Test that captured API documents become OpenAPI and GraphQL settings
+#[test]
+fn test_hawk_init_api_configuration() {
+    // Arrange
+    let har = HarReader::from_file(&fixture_path("hawk-init.har")).unwrap();
+
+    // Act
+    let configs = generate(&har, "capture.har", "Development", None).unwrap();
+
+    // Assert
+    let api = &configs[1];
+    assert_eq!(api.openapi_path.as_deref(), Some("/v3/api-docs"));
+    assert_eq!(api.graphql_path.as_deref(), Some("/graphql"));
+    assert!(api.authentication.is_none());
+
+    let yaml = api.to_yaml();
+    assert!(yaml.contains("  applicationId: \"${APP_ID}\"\n"));
+    assert!(yaml.contains("  openApiConf:\n    path: \"/v3/api-docs\"\n"));
+    assert!(yaml.contains("  graphqlConf:\n    enabled: true\n    schemaPath: \"/graphql\"\n"));
+}
+
+/// Test that init writes the files and refuses to overwrite them without --force
+#[test]
+fn test_hawk_init_writes_files() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let har = fixture_path("hawk-init.har");
+
+    // Act
+    let first = init(&har, temp_dir.path(), "Development", None, false);
+    let second = init(&har, temp_dir.path(), "Development", None, false);
+    let forced = init(&har, temp_dir.path(), "Development", None, true);
+
+    // Assert
+    assert!(first.is_ok());
+    assert!(
+        temp_dir
+            .path()
+            .join("stackhawk-app.example.com.yml")
+            .exists()
+    );
+    assert!(
+        temp_dir
+            .path()
+            .join("stackhawk-api.example.com.yml")
+            .exists()
+    );
+    assert!(second.unwrap_err().to_string().contains("--force"));
+    assert!(forced.is_ok());
+}
+
+/// Test that secret-looking login fields are written as placeholders, not captured values
+#[test]
+fn test_hawk_init_login_params_use_placeholders_for_secrets() {
+    // Arrange
+    let har = HarReader::from_file(&fixture_path("hawk-init.har")).unwrap();
+    let mut configs = generate(&har, "capture.har", "Staging", None).unwrap();
+    let auth = configs[0].authentication.as_mut().unwrap();
+    auth.login.other_params = vec![
+        LoginParam {
+            name: "locale".to_string(),
+            value: "en-US".to_string(),
+        },
+        LoginParam {
+            name: "confirm_password".to_string(),
+            value: "hunter2".to_string(),
+        },
+    ];
+
+    // Act
+    let yaml = configs[0].to_yaml();
+
+    // Assert
+    assert!(yaml.contains("        - name: \"locale\"\n          val: \"en-US\"\n"));
+    assert!(yaml.contains(
+        "        # Captured value not written: set LOGIN_CONFIRM_PASSWORD\n        - name: \"confirm_password\"\n          val: \"${LOGIN_CONFIRM_PASSWORD}\"\n"
+    ));
+    assert!(!yaml.contains("hunter2"));
+}
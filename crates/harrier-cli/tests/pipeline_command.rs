@@ -0,0 +1,236 @@
+use harrier_cli::commands::pipeline::{OnFailure, Pipeline, StepAction};
+use harrier_core::har::HarReader;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+/// Helper to get path to test fixtures
+fn fixture_path(filename: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join(filename)
+}
+
+/// Copy a fixture into a temp dir so steps can rewrite it
+fn capture_copy(temp_dir: &TempDir, filename: &str) -> PathBuf {
+    let path = temp_dir.path().join(filename);
+    std::fs::copy(fixture_path(filename), &path).unwrap();
+    path
+}
+
+/// Test that omitted step settings fall back to their defaults
+#[test]
+fn test_pipeline_parse_defaults() {
+    // Act
+    let pipeline = Pipeline::parse(
+        r#"
+        [[step]]
+        type = "redact"
+
+        [[step]]
+        type = "command"
+        run = "uploader --file {har}"
+        on_failure = "continue"
+        "#,
+    )
+    .unwrap();
+
+    // Assert
+    assert_eq!(pipeline.steps.len(), 2);
+    assert_eq!(pipeline.steps[0].on_failure, OnFailure::Abort);
+    match pipeline.steps[0].action {
+        StepAction::Redact {
+            ref headers,
+            cookies,
+            ref body_fields,
+            ref replacement,
+            ..
+        } => {
+            assert!(headers.contains(&"Authorization".to_string()));
+            assert!(cookies);
+            assert_eq!(body_fields, &vec!["password".to_string()]);
+            assert_eq!(replacement, "[REDACTED]");
+        }
+        _ => panic!("expected a redact step"),
+    }
+    assert_eq!(pipeline.steps[1].on_failure, OnFailure::Continue);
+    match pipeline.steps[1].action {
+        StepAction::Command {
+            timeout,
+            ref success_codes,
+            ..
+        } => {
+            assert_eq!(timeout, 600);
+            assert_eq!(success_codes, &vec![0]);
+        }
+        _ => panic!("expected a command step"),
+    }
+}
+
+/// Test that unknown step types and unparseable commands are rejected up front
+#[test]
+fn test_pipeline_parse_errors() {
+    assert!(Pipeline::parse("[[step]]\ntype = \"upload\"\n").is_err());
+    assert!(Pipeline::parse("[[step]]\ntype = \"command\"\nrun = \"\"\n").is_err());
+    assert!(Pipeline::parse("[[step]]\ntype = \"command\"\nrun = \"echo 'open\"\n").is_err());
+}
+
+/// Test that filter and redact steps rewrite the HAR in place
+#[test]
+fn test_pipeline_filter_and_redact() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let har_path = capture_copy(&temp_dir, "hawk-init.har");
+    let pipeline = Pipeline::parse(
+        r#"
+        [[step]]
+        type = "filter"
+        hosts = ["app.example.com"]
+
+        [[step]]
+        type = "redact"
+        body_fields = ["password", "csrf_token"]
+        "#,
+    )
+    .unwrap();
+
+    // Act
+    pipeline.run(&har_path).unwrap();
+
+    // Assert
+    let har = HarReader::from_file(&har_path).unwrap();
+    assert!(
+        har.log
+            .entries
+            .iter()
+            .all(|e| e.request.url.starts_with("https://app.example.com/"))
+    );
+
+    let login = har
+        .log
+        .entries
+        .iter()
+        .find(|e| e.request.method == "POST")
+        .unwrap();
+    let body = login.request.post_data.as_ref().unwrap().text.as_deref();
+    assert_eq!(
+        body,
+        Some("username=alice%40example.com&password=%5BREDACTED%5D&csrf_token=%5BREDACTED%5D")
+    );
+    assert!(
+        login
+            .response
+            .cookies
+            .iter()
+            .all(|c| c.value == "[REDACTED]")
+    );
+    assert!(har.log.entries.iter().all(|e| {
+        e.request
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("cookie"))
+            .all(|h| h.value == "[REDACTED]")
+    }));
+}
+
+/// Test that a failed validate gate with on_failure = "stop" skips later steps without an error
+#[cfg(unix)]
+#[test]
+fn test_pipeline_validate_gate_stops() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let har_path = capture_copy(&temp_dir, "hawk-init.har");
+    let marker = temp_dir.path().join("uploaded");
+    let pipeline = Pipeline::parse(&format!(
+        r#"
+        [[step]]
+        type = "validate"
+        min_entries = 1000
+        on_failure = "stop"
+
+        [[step]]
+        type = "command"
+        run = "touch {}"
+        "#,
+        marker.display()
+    ))
+    .unwrap();
+
+    // Act
+    let result = pipeline.run(&har_path);
+
+    // Assert
+    assert!(result.is_ok());
+    assert!(!marker.exists());
+}
+
+/// Test that command steps see the HAR path and honor their success codes
+#[cfg(unix)]
+#[test]
+fn test_pipeline_command_exit_codes() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let har_path = capture_copy(&temp_dir, "hawk-init.har");
+    let copy = temp_dir.path().join("copy.har");
+    let pipeline = |codes: &str, on_failure: &str| {
+        Pipeline::parse(&format!(
+            r#"
+            [[step]]
+            type = "command"
+            run = "sh -c 'cp \"$0\" {}; exit 3' {{har}}"
+            success_codes = {}
+            on_failure = "{}"
+            "#,
+            copy.display(),
+            codes,
+            on_failure
+        ))
+        .unwrap()
+    };
+
+    // Act
+    let accepted = pipeline("[0, 3]", "abort").run(&har_path);
+    let rejected = pipeline("[0]", "abort").run(&har_path);
+    let continued = pipeline("[0]", "continue").run(&har_path);
+
+    // Assert
+    assert!(accepted.is_ok());
+    assert!(copy.exists());
+    let err = format!("{:#}", rejected.unwrap_err());
+    assert!(err.contains("[1/1] command"));
+    assert!(err.contains("exited with code 3"));
+    assert!(continued.is_ok());
+}
+
+/// Test that a command running past its timeout is killed and fails the step
+#[cfg(unix)]
+#[test]
+fn test_pipeline_command_timeout() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let har_path = capture_copy(&temp_dir, "hawk-init.har");
+    let pipeline = Pipeline::parse(
+        r#"
+        [[step]]
+        name = "slow-upload"
+        type = "command"
+        run = "sleep 10"
+        timeout = 1
+        "#,
+    )
+    .unwrap();
+
+    // Act
+    let started = std::time::Instant::now();
+    let result = pipeline.run(&har_path);
+
+    // Assert
+    let err = format!("{:#}", result.unwrap_err());
+    assert!(err.contains("slow-upload (command)"));
+    assert!(err.contains("timed out after 1s"));
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
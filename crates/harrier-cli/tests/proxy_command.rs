@@ -0,0 +1,179 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::PathBuf;
+
+#[allow(deprecated)]
+fn get_harrier_bin() -> PathBuf {
+    assert_cmd::cargo::cargo_bin("harrier")
+}
+
+#[test]
+fn test_proxy_command_help() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("--upstream-proxy"))
+        .stdout(predicate::str::contains("--no-proxy"))
+        .stdout(predicate::str::contains("--bind"))
+        .stdout(predicate::str::contains("--proxy-auth"))
+        .stdout(predicate::str::contains("--rules"))
+        .stdout(predicate::str::contains("--no-intercept"))
+        .stdout(predicate::str::contains("--no-auto-passthrough"))
+        .stdout(predicate::str::contains("--hosts"));
+}
+
+#[test]
+fn test_proxy_rejects_unsupported_upstream_scheme() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--upstream-proxy")
+        .arg("socks5://proxy.corp:1080");
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Unsupported upstream proxy scheme 'socks5'",
+    ));
+}
+
+#[test]
+fn test_proxy_rejects_upstream_pointing_at_itself() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--port")
+        .arg("18080")
+        .arg("--upstream-proxy")
+        .arg("http://127.0.0.1:18080");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("points at this proxy's own port"));
+}
+
+#[test]
+fn test_proxy_rejects_upstream_pointing_at_bound_port() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--port")
+        .arg("18080")
+        .arg("--bind")
+        .arg("127.0.0.1:18080")
+        .arg("--bind")
+        .arg("127.0.0.1:18081")
+        .arg("--upstream-proxy")
+        .arg("http://localhost:18081");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("points at this proxy's own port"));
+}
+
+#[test]
+fn test_proxy_ignores_unsupported_proxy_from_environment() {
+    let mut cmd = Command::new(get_harrier_bin());
+    // Malformed credentials stop the command once the upstream proxy has been chosen
+    cmd.arg("proxy")
+        .arg("--proxy-auth")
+        .arg("alice")
+        .env_remove("HTTP_PROXY")
+        .env_remove("http_proxy")
+        .env_remove("HTTPS_PROXY")
+        .env_remove("https_proxy")
+        .env_remove("all_proxy")
+        .env("ALL_PROXY", "socks5://127.0.0.1:1080");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Ignoring ALL_PROXY"))
+        .stderr(predicate::str::contains(
+            "Unsupported upstream proxy scheme 'socks5'",
+        ))
+        .stderr(predicate::str::contains(
+            "Proxy credentials must be in the form user:password",
+        ));
+}
+
+#[test]
+fn test_proxy_rejects_invalid_bind_address() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--bind").arg("my-laptop.local");
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Invalid --bind address 'my-laptop.local'",
+    ));
+}
+
+#[test]
+fn test_proxy_rejects_oversized_max_body_size() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--max-body-size")
+        .arg(usize::MAX.to_string());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "--max-body-size {} MB is too large",
+            usize::MAX
+        )));
+}
+
+#[test]
+fn test_proxy_rejects_malformed_credentials() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--proxy-auth").arg("alice");
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Proxy credentials must be in the form user:password",
+    ));
+}
+
+#[test]
+fn test_proxy_rejects_invalid_rules() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let rules = temp_dir.path().join("rules.toml");
+    std::fs::write(
+        &rules,
+        "[[rule]]\nname = \"Broken\"\npath = \"(unclosed\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--rules").arg(&rules);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid rewrite rules"))
+        .stderr(predicate::str::contains("Rule 'Broken': invalid path"));
+}
+
+#[test]
+fn test_proxy_rejects_mock_with_missing_file() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let rules = temp_dir.path().join("rules.toml");
+    std::fs::write(
+        &rules,
+        "[[mock]]\nname = \"Users\"\nfile = \"users.json\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--rules").arg(&rules);
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Mock 'Users': file"))
+        .stderr(predicate::str::contains("users.json not found"));
+}
+
+#[test]
+fn test_proxy_rejects_invalid_passthrough_host() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--no-intercept")
+        .arg("*.apple.com,*[bad");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid passthrough host '*[bad'"));
+}
@@ -0,0 +1,142 @@
+use assert_cmd::Command;
+use harrier_core::har::{Creator, HarJournal, HarReader};
+use predicates::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+#[allow(deprecated)]
+fn get_harrier_bin() -> PathBuf {
+    assert_cmd::cargo::cargo_bin("harrier")
+}
+
+/// Helper to get path to test fixtures
+fn fixture_path(filename: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join(filename)
+}
+
+/// Journal the fixture's entries, then stop without finishing as a crash would,
+/// leaving half of one more entry behind
+fn crashed_capture(output: &Path) -> (PathBuf, usize) {
+    let har = HarReader::from_file(&fixture_path("sample.har")).unwrap();
+    let creator = Creator {
+        name: "Harrier".to_string(),
+        version: "test".to_string(),
+        comment: None,
+    };
+
+    let journal = HarJournal::create(output, creator, None).unwrap();
+    for entry in &har.log.entries {
+        journal.append(entry).unwrap();
+    }
+    let journal_path = journal.journal_path().unwrap();
+    drop(journal);
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&journal_path)
+        .unwrap();
+    file.write_all(b"{\"startedDateTime\": \"2026-").unwrap();
+
+    (journal_path, har.log.entries.len())
+}
+
+#[test]
+fn test_recover_rebuilds_har_from_journal() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let output = temp_dir.path().join("captured.har");
+    let (journal_path, count) = crashed_capture(&output);
+
+    // Act
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("recover").arg(&journal_path);
+
+    // Assert
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Recovered {} entries",
+            count
+        )))
+        .stdout(predicate::str::contains(
+            "Skipped 1 unreadable journal line",
+        ));
+
+    let har = HarReader::from_file(&output).unwrap();
+    assert_eq!(har.log.entries.len(), count);
+    HarReader::validate(&har).unwrap();
+    assert!(!journal_path.exists());
+}
+
+#[test]
+fn test_recover_to_custom_output_keeping_journal() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let (journal_path, count) = crashed_capture(&temp_dir.path().join("captured.har"));
+    let output = temp_dir.path().join("rescued.har");
+
+    // Act
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("recover")
+        .arg(&journal_path)
+        .arg("--output")
+        .arg(&output)
+        .arg("--keep-journal");
+
+    // Assert
+    cmd.assert().success();
+    assert_eq!(
+        HarReader::from_file(&output).unwrap().log.entries.len(),
+        count
+    );
+    assert!(journal_path.exists());
+}
+
+#[test]
+fn test_recover_rejects_non_journal() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("recover").arg(fixture_path("sample.har"));
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a Harrier journal"));
+}
+
+#[test]
+fn test_capture_refuses_to_overwrite_unfinished_journal() {
+    // Arrange
+    let temp_dir = TempDir::new().unwrap();
+    let output = temp_dir.path().join("captured.har");
+    crashed_capture(&output);
+
+    // Act
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy")
+        .arg("--port")
+        .arg("0")
+        .arg("--output")
+        .arg(&output);
+
+    // Assert
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("harrier recover"));
+}
+
+#[test]
+fn test_rotate_rejects_unknown_unit() {
+    let mut cmd = Command::new(get_harrier_bin());
+    cmd.arg("proxy").arg("--rotate").arg("10parsecs");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown unit"));
+}
@@ -0,0 +1,963 @@
+use harrier_cli::commands::security::{JwtKeyOptions, access_matrix_csv, analyze};
+use harrier_detectors::{
+    AuthFlowType, AuthMethod, AuthSummaryGenerator, CsrfIssueType, CsrfProtection, FlowRole,
+    InvalidationStatus, IssuedTokenKind, JwtIssueType, LoginFormat, LoginParam, OAuthIssueType,
+    ObjectIdKind, ObjectIdLocation, SamlBinding, SamlFlowType, SamlMessageKind, SessionIssueType,
+    Severity, TokenExtraction, TokenFormat, VerificationStatus,
+};
+use std::path::PathBuf;
+
+/// Helper to get path to test fixtures
+fn fixture_path(filename: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("tests")
+        .join("fixtures")
+        .join(filename)
+}
+
+/// Test that a token signed with the supplied secret verifies
+#[test]
+fn test_security_verifies_jwt_with_secret_file() {
+    // Arrange
+    let options = JwtKeyOptions {
+        secret_files: vec![fixture_path("jwt-secret.txt")],
+        ..Default::default()
+    };
+
+    // Act
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &options).unwrap();
+
+    // Assert
+    let verified: Vec<_> = analysis
+        .jwt_verification
+        .results
+        .iter()
+        .filter(|r| r.status == VerificationStatus::Verified)
+        .collect();
+    assert_eq!(verified.len(), 1);
+    assert_eq!(verified[0].alg.as_deref(), Some("HS256"));
+    assert_eq!(verified[0].accepted_entries, vec![2]);
+}
+
+/// Test that an alg:none token answered with 2xx is reported as critical
+#[test]
+fn test_security_flags_unsigned_token_accepted() {
+    // Act
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let issue = analysis
+        .jwt_issues
+        .iter()
+        .find(|i| i.issue_type == JwtIssueType::UnsignedTokenAccepted)
+        .expect("unsigned token accepted by the server should be flagged");
+    assert_eq!(issue.severity, Severity::Critical);
+
+    // JWT findings carry sample entries like every other analysis
+    let entry = issue.entry_index.expect("issue should name its entry");
+    let findings = AuthSummaryGenerator::aggregate_security_findings(&analysis);
+    let finding = findings
+        .critical
+        .iter()
+        .find(|f| f.message.starts_with("JWT Unsigned token accepted"))
+        .unwrap();
+    assert_eq!(finding.sample_entries, vec![entry]);
+}
+
+/// Test that a jwks_uri from a captured discovery document is reported when not captured
+#[test]
+fn test_security_reports_uncaptured_jwks_uri() {
+    // Act
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    assert_eq!(
+        analysis.jwt_verification.uncaptured_jwks_uris,
+        vec!["https://auth.example.com/.well-known/jwks.json".to_string()]
+    );
+}
+
+/// Test that a missing key file is reported as an error
+#[test]
+fn test_security_missing_key_file_errors() {
+    let options = JwtKeyOptions {
+        jwks: vec![fixture_path("does-not-exist.json")],
+        ..Default::default()
+    };
+
+    assert!(analyze(&fixture_path("jwt-auth.har"), &options).is_err());
+}
+
+/// Test that a token signed with a secret from the wordlist is reported as critical
+#[test]
+fn test_security_recovers_weak_secret_from_wordlist() {
+    // Without a wordlist the fixture secret is not in the built-in list
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &JwtKeyOptions::default()).unwrap();
+    assert!(
+        !analysis
+            .jwt_issues
+            .iter()
+            .any(|i| i.issue_type == JwtIssueType::WeakSecret)
+    );
+
+    // Arrange
+    let options = JwtKeyOptions {
+        wordlist: Some(fixture_path("jwt-secret.txt")),
+        ..Default::default()
+    };
+
+    // Act
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &options).unwrap();
+
+    // Assert
+    let issue = analysis
+        .jwt_issues
+        .iter()
+        .find(|i| i.issue_type == JwtIssueType::WeakSecret)
+        .expect("secret from the wordlist should be recovered");
+    assert_eq!(issue.severity, Severity::Critical);
+    assert!(!issue.message.contains("harrier-test-secret"));
+}
+
+/// Test that tokens are linked to the token exchange that issued them and to their rotation
+#[test]
+fn test_security_traces_token_lineage_through_refresh() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("oauth-refresh.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let lineage = &analysis.token_lineage;
+    let first_access = lineage
+        .iter()
+        .find(|l| {
+            l.kind == IssuedTokenKind::Access && l.issued_by.as_ref().unwrap().entry_index == 2
+        })
+        .expect("access token from the code exchange");
+    assert_eq!(
+        first_access.issuer.as_deref(),
+        Some("https://auth.example.com")
+    );
+    assert_eq!(first_access.lifetime_seconds, Some(300));
+    assert_eq!(
+        first_access
+            .used_by
+            .iter()
+            .map(|u| u.endpoint.as_str())
+            .collect::<Vec<_>>(),
+        vec!["GET /v1/orders", "GET /v1/orders/17"]
+    );
+
+    let renewed = lineage
+        .iter()
+        .find(|l| {
+            l.kind == IssuedTokenKind::Access && l.issued_by.as_ref().unwrap().entry_index == 5
+        })
+        .expect("access token from the refresh grant");
+    let issuance = renewed.issued_by.as_ref().unwrap();
+    assert_eq!(issuance.grant_type.as_deref(), Some("refresh_token"));
+    assert_eq!(renewed.replaces.as_ref(), Some(&first_access.token_preview));
+    assert_eq!(
+        first_access.replaced_by.as_ref(),
+        Some(&renewed.token_preview)
+    );
+
+    let first_refresh = lineage
+        .iter()
+        .find(|l| l.token_preview == "rt-1-8xLOxBtZp8")
+        .expect("refresh token from the code exchange");
+    assert_eq!(
+        first_refresh.replaced_by.as_deref(),
+        Some("rt-2-tGzv3JOkF0")
+    );
+    assert_eq!(first_refresh.used_by[0].entry_indices, vec![5]);
+}
+
+/// Test that array audiences, scopes and encrypted tokens are parsed
+#[test]
+fn test_security_parses_full_claim_model_and_jwe() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("oauth-refresh.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let access = analysis
+        .jwt_tokens
+        .iter()
+        .find(|t| t.claims.aud.len() == 2)
+        .expect("token with an array audience should be parsed");
+    assert_eq!(
+        access.claims.scope,
+        vec!["openid", "profile", "orders.read"]
+    );
+    assert_eq!(access.claims.roles, vec!["admin", "user"]);
+    assert_eq!(access.claims.azp.as_deref(), Some("web-app"));
+
+    let jwe = analysis
+        .jwt_tokens
+        .iter()
+        .find(|t| t.format == TokenFormat::Jwe)
+        .expect("5-part JWE bearer token should be recognized");
+    assert_eq!(jwe.header.enc.as_deref(), Some("A256GCM"));
+    assert_eq!(jwe.entry_indices, vec![7]);
+}
+
+/// Test that a well-formed code flow with PKCE, state and nonce raises no OAuth issues
+#[test]
+fn test_security_accepts_secure_oauth_code_flow() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("oauth-refresh.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let request = &analysis.oauth.authorization_requests[0];
+    assert_eq!(request.code_challenge_method.as_deref(), Some("S256"));
+    assert_eq!(request.callback_entry, Some(1));
+    assert_eq!(request.token_entry, Some(2));
+    assert!(analysis.oauth.issues.is_empty());
+}
+
+/// Test that insecure OAuth parameters and provider settings are reported
+#[test]
+fn test_security_flags_insecure_oauth_flows() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("oauth-insecure.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let issues = &analysis.oauth.issues;
+    let severity_of = |issue_type: OAuthIssueType| {
+        issues
+            .iter()
+            .filter(|i| i.issue_type == issue_type)
+            .map(|i| i.severity.clone())
+            .max_by_key(|s| match s {
+                Severity::Info => 0,
+                Severity::Warning => 1,
+                Severity::Critical => 2,
+            })
+    };
+
+    assert_eq!(
+        severity_of(OAuthIssueType::ImplicitFlow),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::MissingState),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::StateMismatch),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::TokenInFragment),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::TokenInReferer),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::PlainPkce),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::WeakPkceVerifier),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::PkceNotEnforced),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::RedirectUriMismatch),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity_of(OAuthIssueType::WeakProviderConfig),
+        Some(Severity::Critical)
+    );
+
+    let config = &analysis.oauth.provider_configs[0];
+    assert_eq!(config.issuer.as_deref(), Some("http://idp.example.com"));
+    assert!(
+        config
+            .grant_types_supported
+            .contains(&"password".to_string())
+    );
+}
+
+/// Test that SAML messages are decoded from both bindings and attached to their flow
+#[test]
+fn test_security_decodes_saml_messages() {
+    // Act
+    let analysis = analyze(&fixture_path("saml-sso.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let flow = analysis
+        .saml_flows
+        .iter()
+        .find(|f| f.flow_type == SamlFlowType::SpInitiated)
+        .expect("SP-initiated flow");
+    assert_eq!(
+        flow.idp_entity_id.as_deref(),
+        Some("https://idp.example.org/metadata")
+    );
+    assert_eq!(
+        flow.sp_entity_id.as_deref(),
+        Some("https://sp.example.com/metadata")
+    );
+
+    let request = &flow.messages[0];
+    assert_eq!(request.kind, SamlMessageKind::AuthnRequest);
+    assert_eq!(request.binding, SamlBinding::Redirect);
+    assert_eq!(request.id.as_deref(), Some("_req-7c1d2f"));
+
+    let response = &flow.messages[1];
+    assert_eq!(response.kind, SamlMessageKind::Response);
+    assert_eq!(response.binding, SamlBinding::Post);
+    let assertion = &response.assertions[0];
+    assert_eq!(assertion.name_id.as_deref(), Some("alice@example.com"));
+    assert!(assertion.audiences.is_empty());
+    assert_eq!(assertion.attributes.len(), 2);
+    assert_eq!(assertion.attributes[1].values, vec!["admins", "staff"]);
+}
+
+/// Test that insecure SAML responses and assertions are flagged
+#[test]
+fn test_security_flags_insecure_saml_assertions() {
+    // Act
+    let analysis = analyze(&fixture_path("saml-sso.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let severity_of = |needle: &str| {
+        analysis
+            .saml_issues
+            .iter()
+            .find(|i| i.message.contains(needle))
+            .map(|i| i.severity.clone())
+    };
+    assert_eq!(severity_of("both unsigned"), Some(Severity::Critical));
+    assert_eq!(
+        severity_of("Response sent over unencrypted HTTP"),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity_of("addressed to an unencrypted HTTP endpoint"),
+        Some(Severity::Critical)
+    );
+    assert_eq!(
+        severity_of("no AudienceRestriction"),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of("valid for 1440 minutes"),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        severity_of("InResponseTo '_req-other'"),
+        Some(Severity::Warning)
+    );
+    assert_eq!(
+        analysis
+            .saml_issues
+            .iter()
+            .filter(|i| i.message == "SAML Response sent over unencrypted HTTP")
+            .count(),
+        1
+    );
+}
+
+/// Test that a session cookie surviving login is reported as fixation
+#[test]
+fn test_security_detects_session_fixation() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("session-lifecycle.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let rotations = &analysis.session_lifecycle.login_rotations;
+    assert_eq!(rotations.len(), 2);
+    assert_eq!(rotations[0].login_entry, 1);
+    assert_eq!(rotations[0].user.as_deref(), Some("alice"));
+    assert!(!rotations[0].rotated);
+    assert_eq!(rotations[1].login_entry, 3);
+    assert!(rotations[1].rotated);
+
+    let fixation: Vec<_> = analysis
+        .session_lifecycle
+        .issues
+        .iter()
+        .filter(|i| i.issue_type == SessionIssueType::SessionFixation)
+        .collect();
+    assert_eq!(fixation.len(), 1);
+    assert_eq!(fixation[0].severity, Severity::Critical);
+    assert_eq!(fixation[0].entry_index, 1);
+}
+
+/// Test that identifiers reused after logout are classified by the server's answer
+#[test]
+fn test_security_checks_logout_invalidation() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("session-lifecycle.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let checks = &analysis.session_lifecycle.logout_checks;
+    let session = checks
+        .iter()
+        .find(|c| c.identifier.starts_with("sessionid="))
+        .unwrap();
+    assert_eq!(session.logout_entry, 6);
+    assert_eq!(session.status, InvalidationStatus::StillValid);
+    assert_eq!(session.reused_entries, vec![7]);
+
+    let token = checks
+        .iter()
+        .find(|c| c.identifier.starts_with("auth_token="))
+        .unwrap();
+    assert_eq!(token.status, InvalidationStatus::Invalidated);
+    assert!(token.reused_entries.is_empty());
+
+    assert!(analysis.session_lifecycle.issues.iter().any(|i| {
+        i.issue_type == SessionIssueType::NotInvalidatedOnLogout && i.entry_index == 7
+    }));
+}
+
+/// Test that overlapping sessions established for the same user are reported
+#[test]
+fn test_security_reports_concurrent_sessions() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("session-lifecycle.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let concurrent = &analysis.session_lifecycle.concurrent_sessions;
+    let sessionid = concurrent
+        .iter()
+        .find(|c| c.cookie_name == "sessionid")
+        .unwrap();
+    assert_eq!(sessionid.user, "alice");
+    assert_eq!(sessionid.entry_indices, vec![1, 3]);
+    assert_eq!(sessionid.identifiers.len(), 2);
+}
+
+/// Test that CSRF defenses are recognized per endpoint and unprotected endpoints reported
+#[test]
+fn test_security_checks_csrf_protection() {
+    // Act
+    let analysis = analyze(&fixture_path("csrf.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let endpoint = |name: &str| {
+        analysis
+            .csrf
+            .endpoints
+            .iter()
+            .find(|e| e.endpoint == name)
+            .unwrap()
+    };
+
+    let transfer = endpoint("POST /transfer");
+    assert_eq!(transfer.request_count, 3);
+    assert_eq!(transfer.token_name.as_deref(), Some("X-XSRF-TOKEN"));
+    assert!(
+        transfer
+            .protections
+            .contains(&CsrfProtection::DoubleSubmitCookie)
+    );
+    assert!(transfer.is_protected());
+
+    assert!(
+        endpoint("POST /settings")
+            .protections
+            .contains(&CsrfProtection::SameSiteCookie)
+    );
+    assert!(!endpoint("POST /profile").is_protected());
+    assert!(!endpoint("DELETE /api/cards/7").is_protected());
+
+    // Bearer-authenticated requests are not CSRF-able
+    assert!(
+        !analysis
+            .csrf
+            .endpoints
+            .iter()
+            .any(|e| e.endpoint == "POST /api/export")
+    );
+
+    let missing: Vec<usize> = analysis
+        .csrf
+        .issues
+        .iter()
+        .filter(|i| i.issue_type == CsrfIssueType::MissingProtection)
+        .map(|i| i.entry_index)
+        .collect();
+    assert_eq!(missing, vec![3, 4]);
+}
+
+/// Test that a CSRF token shared by two sessions and never rotated is reported
+#[test]
+fn test_security_detects_csrf_token_reuse() {
+    // Act
+    let analysis = analyze(&fixture_path("csrf.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    assert_eq!(analysis.csrf.tokens.len(), 1);
+    let token = &analysis.csrf.tokens[0];
+    assert_eq!(token.request_count, 3);
+    assert_eq!(token.session_count, 2);
+
+    let issue_types: Vec<&CsrfIssueType> =
+        analysis.csrf.issues.iter().map(|i| &i.issue_type).collect();
+    assert!(issue_types.contains(&&CsrfIssueType::StaticToken));
+    assert!(issue_types.contains(&&CsrfIssueType::TokenReusedAcrossSessions));
+}
+
+/// Test that object-ID parameters are ranked as IDOR candidates
+#[test]
+fn test_security_ranks_idor_candidates() {
+    // Act
+    let analysis = analyze(&fixture_path("idor.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let candidates = &analysis.idor.candidates;
+    assert_eq!(candidates.len(), 3);
+
+    let orders = &candidates[0];
+    assert_eq!(orders.endpoint, "GET /api/v1/orders/{id}");
+    assert_eq!(orders.location, ObjectIdLocation::Path);
+    assert_eq!(orders.id_kind, ObjectIdKind::Numeric);
+    assert_eq!(orders.identities, vec!["sub:alice", "sub:bob"]);
+    assert_eq!(orders.object_ids, vec!["1001", "1002", "1003"]);
+    assert_eq!(orders.shared_ids, vec!["1002"]);
+    assert!(orders.sequential);
+    // The 403 response is not counted as an access
+    assert!(!orders.entry_indices.contains(&4));
+
+    let invoices = candidates
+        .iter()
+        .find(|c| c.parameter == "invoice_id")
+        .unwrap();
+    assert_eq!(invoices.location, ObjectIdLocation::Query);
+    assert_eq!(invoices.id_kind, ObjectIdKind::Slug);
+    assert_eq!(invoices.max_ids_per_identity, 2);
+    assert!(!invoices.guessable);
+
+    let profile = candidates.iter().find(|c| c.parameter == "userId").unwrap();
+    assert_eq!(profile.location, ObjectIdLocation::Body);
+    assert!(profile.guessable);
+
+    // A single random UUID is not a lead
+    assert!(!candidates.iter().any(|c| c.id_kind == ObjectIdKind::Uuid));
+}
+
+/// Test that the access matrix records status codes per identity and templated endpoint
+#[test]
+fn test_security_builds_access_matrix() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("access-matrix.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let matrix = &analysis.access_matrix;
+    let labels: Vec<&str> = matrix.identities.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(
+        labels,
+        vec!["sub:admin-1", "sub:alice", "sub:bob", "anonymous"]
+    );
+    assert!(matrix.identities[0].privileged);
+    assert!(!matrix.identities[1].privileged);
+
+    // Static assets are left out
+    assert_eq!(matrix.endpoints.len(), 5);
+    let admin_users = matrix
+        .endpoints
+        .iter()
+        .find(|e| e.endpoint == "GET /api/admin/users")
+        .unwrap();
+    assert_eq!(admin_users.cell("sub:alice").unwrap().statuses, vec![403]);
+    assert!(admin_users.cell("sub:bob").is_none());
+
+    let csv = access_matrix_csv(matrix);
+    assert!(csv.starts_with("Host,Endpoint,sub:admin-1,sub:alice,sub:bob,anonymous\n"));
+    assert!(csv.contains("app.example.com,DELETE /api/admin/users/{id},204,-,204,-\n"));
+}
+
+/// Test that admin-only endpoints reached by other identities are flagged
+#[test]
+fn test_security_flags_admin_endpoints_reached_by_others() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("access-matrix.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let issues = &analysis.access_matrix.issues;
+    assert_eq!(issues.len(), 2);
+
+    assert_eq!(issues[0].endpoint, "DELETE /api/admin/users/{id}");
+    assert_eq!(issues[0].identity, "sub:bob");
+    assert_eq!(issues[0].severity, Severity::Warning);
+    assert_eq!(issues[0].entry_index, 6);
+
+    assert_eq!(issues[1].endpoint, "GET /api/admin/audit-log");
+    assert_eq!(issues[1].identity, "anonymous");
+    assert_eq!(issues[1].severity, Severity::Critical);
+
+    // Endpoints shared by every user, or denied to the non-admin, are not flagged
+    assert!(
+        !issues
+            .iter()
+            .any(|i| i.endpoint == "GET /api/me" || i.endpoint == "GET /api/admin/users")
+    );
+}
+
+/// Test that endpoints requested without credentials are split into open and denied
+#[test]
+fn test_security_lists_unauthenticated_endpoints() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("unauthenticated.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let inventory = &analysis.unauthenticated;
+    assert_eq!(
+        inventory.hosts(),
+        vec!["api.example.com", "files.example.com"]
+    );
+
+    let open: Vec<(&str, &str)> = inventory
+        .open_endpoints
+        .iter()
+        .map(|e| (e.host.as_str(), e.endpoint.as_str()))
+        .collect();
+    assert_eq!(
+        open,
+        vec![
+            ("api.example.com", "GET /api/health"),
+            ("api.example.com", "GET /api/products/{id}"),
+            ("files.example.com", "GET /v1/files/{id}"),
+        ]
+    );
+
+    // A non-auth cookie is not a credential; HTML pages and Digest-authenticated requests are left out
+    let denied: Vec<(&str, &[i64])> = inventory
+        .denied_endpoints
+        .iter()
+        .map(|e| (e.endpoint.as_str(), e.statuses.as_slice()))
+        .collect();
+    assert_eq!(
+        denied,
+        vec![
+            ("GET /api/orders", &[401][..]),
+            ("POST /api/cart", &[403][..])
+        ]
+    );
+}
+
+/// Test that endpoints answering the same with and without credentials are flagged
+#[test]
+fn test_security_flags_missing_auth_enforcement() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("unauthenticated.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    let issues = &analysis.unauthenticated.issues;
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].endpoint, "GET /api/products/{id}");
+    assert_eq!(issues[0].status, 200);
+    assert_eq!(issues[0].entry_index, 1);
+    assert_eq!(issues[0].authenticated_entry, 2);
+    assert_eq!(issues[1].host, "files.example.com");
+    assert_eq!(issues[1].authenticated_entry, 8);
+
+    // /api/orders rejected the anonymous request, so it is not flagged
+    assert!(!issues.iter().any(|i| i.endpoint == "GET /api/orders"));
+
+    let findings = AuthSummaryGenerator::aggregate_security_findings(&analysis);
+    assert!(findings.warnings.iter().any(|f| {
+        f.message
+            .starts_with("Missing Auth Enforcement - GET /api/products/{id}")
+    }));
+}
+
+/// Test that Digest, NTLM, SigV4, HMAC signing and DPoP are detected as distinct methods
+#[test]
+fn test_security_detects_extended_auth_schemes() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("extended-auth.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    for method in [
+        AuthMethod::Ntlm,
+        AuthMethod::Digest,
+        AuthMethod::AwsSigV4,
+        AuthMethod::Hmac("X-Signature".to_string()),
+        AuthMethod::Dpop,
+    ] {
+        assert!(analysis.methods.contains(&method), "missing {:?}", method);
+    }
+    // X-Amz-Date and X-Timestamp are not reported as custom auth headers
+    assert!(
+        !analysis
+            .methods
+            .iter()
+            .any(|m| matches!(m, AuthMethod::Custom(_)))
+    );
+
+    let messages: Vec<&str> = analysis
+        .security_notes
+        .iter()
+        .map(|n| n.message.as_str())
+        .collect();
+    assert!(
+        messages
+            .iter()
+            .any(|m| m.starts_with("Digest challenge without qop"))
+    );
+    assert!(messages.iter().any(|m| m.starts_with("DPoP proof reused")));
+    assert!(!messages.iter().any(|m| m.contains("fell back to NTLM")));
+}
+
+/// Test that 401 challenges are followed through the client's retries
+#[test]
+fn test_security_reconstructs_challenge_handshakes() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("extended-auth.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+
+    // Assert
+    // The server offered Negotiate and NTLM; the client answered with NTLM
+    let ntlm = analysis
+        .flows
+        .iter()
+        .find(|f| f.flow_type == AuthFlowType::Ntlm)
+        .unwrap();
+    let steps: Vec<(usize, &FlowRole, i64)> = ntlm
+        .steps
+        .iter()
+        .map(|s| (s.entry_index, &s.role, s.status))
+        .collect();
+    assert_eq!(
+        steps,
+        vec![
+            (0, &FlowRole::Challenge, 401),
+            (1, &FlowRole::ChallengeResponse, 401),
+            (2, &FlowRole::ChallengeResponse, 200),
+        ]
+    );
+    assert_eq!(ntlm.steps[1].description, "NTLM negotiate message (type 1)");
+    assert_eq!(
+        ntlm.steps[2].description,
+        "NTLM authenticate message (type 3)"
+    );
+
+    let digest = analysis
+        .flows
+        .iter()
+        .find(|f| f.flow_type == AuthFlowType::HttpDigest)
+        .unwrap();
+    assert_eq!(digest.steps.len(), 2);
+    assert_eq!(
+        digest.steps[0].description,
+        "Digest challenge (realm \"legacy-admin\", qop none, algorithm MD5)"
+    );
+    assert_eq!(
+        digest.steps[1].description,
+        "Digest response for user \"ops\""
+    );
+
+    assert!(
+        !analysis
+            .flows
+            .iter()
+            .any(|f| f.flow_type == AuthFlowType::Negotiate)
+    );
+}
+
+/// Test that HawkScan guidance covers challenge/response schemes
+#[test]
+fn test_security_hawkscan_config_for_ntlm() {
+    // Act
+    let analysis = analyze(
+        &fixture_path("extended-auth.har"),
+        &JwtKeyOptions::default(),
+    )
+    .unwrap();
+    let summary = AuthSummaryGenerator::generate_summary(&analysis).unwrap();
+
+    // Assert
+    assert_eq!(summary.primary_method.method_type, "NTLM");
+    assert_eq!(summary.session_mechanism.mechanism_type, "Connection-Based");
+    // No NTLM handler ships, so the script and credentials are placeholders
+    let config = &summary.hawkscan_config;
+    assert_eq!(config.auth_type, "scriptAuthn");
+    assert!(
+        config
+            .config_snippet
+            .contains("    name: \"${AUTH_SCRIPT}\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("      username: \"${AUTH_USERNAME}\"\n")
+    );
+    assert!(!config.config_snippet.contains("httpAuthn"));
+    assert!(config.notes[0].contains("No NTLM authentication script ships"));
+}
+
+/// Test that a form login is described from the submitted fields
+#[test]
+fn test_security_describes_form_login_request() {
+    // Act
+    let analysis = analyze(&fixture_path("hawk-init.har"), &JwtKeyOptions::default()).unwrap();
+
+    // Assert
+    let login = analysis.login_requests.first().unwrap();
+    assert_eq!(login.format, LoginFormat::Form);
+    assert_eq!(login.host, "https://app.example.com");
+    assert_eq!(login.login_path, "/login");
+    assert_eq!(login.username_field.as_deref(), Some("username"));
+    assert_eq!(login.password_field.as_deref(), Some("password"));
+    assert_eq!(login.csrf_field.as_deref(), Some("csrf_token"));
+    assert!(login.other_params.is_empty());
+    assert_eq!(login.logout_paths, vec!["/logout"]);
+
+    let test_path = login.test_path.as_ref().unwrap();
+    assert_eq!(test_path.path, "/dashboard");
+    assert_eq!(test_path.success, "HTTP.*200.*");
+    assert_eq!(test_path.fail.as_deref(), Some(".*Location:.*/login.*"));
+}
+
+/// Test that HawkScan guidance replays a JSON login and extracts its token
+#[test]
+fn test_security_hawkscan_config_for_json_login() {
+    // Act
+    let analysis = analyze(&fixture_path("jwt-auth.har"), &JwtKeyOptions::default()).unwrap();
+    let summary = AuthSummaryGenerator::generate_summary(&analysis).unwrap();
+
+    // Assert
+    let login = analysis.login_requests.first().unwrap();
+    assert_eq!(
+        login.token_extraction,
+        Some(TokenExtraction::BodyPath {
+            path: "access_token".to_string()
+        })
+    );
+
+    let config = &summary.hawkscan_config;
+    assert_eq!(config.auth_type, "usernamePassword");
+    assert!(config.config_snippet.contains("    type: JSON\n"));
+    assert!(
+        config
+            .config_snippet
+            .contains("    loginPath: \"/api/login\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("    usernameField: \"username\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("  tokenExtraction:\n    type: TOKEN_PATH\n    value: \"access_token\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("  testPath:\n    path: \"/api/me\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("    fail: \"HTTP.*401.*\"\n")
+    );
+}
+
+/// Test that captured login values are quoted and secret-looking ones are not written
+#[test]
+fn test_security_hawkscan_login_params_quoted_and_redacted() {
+    // Arrange
+    let mut analysis = analyze(&fixture_path("hawk-init.har"), &JwtKeyOptions::default()).unwrap();
+    let login = &mut analysis.login_requests[0];
+    login.login_path = "/login: \"admin\" #1".to_string();
+    login.other_params = vec![
+        LoginParam {
+            name: "remember_me".to_string(),
+            value: "on".to_string(),
+        },
+        LoginParam {
+            name: "otp_code".to_string(),
+            value: "493021".to_string(),
+        },
+    ];
+
+    // Act
+    let summary = AuthSummaryGenerator::generate_summary(&analysis).unwrap();
+
+    // Assert
+    let config = &summary.hawkscan_config;
+    assert!(
+        config
+            .config_snippet
+            .contains("    loginPath: \"/login: \\\"admin\\\" #1\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("      - name: \"remember_me\"\n        val: \"on\"\n")
+    );
+    assert!(
+        config
+            .config_snippet
+            .contains("      - name: \"otp_code\"\n        val: \"${LOGIN_OTP_CODE}\"\n")
+    );
+    assert!(!config.config_snippet.contains("493021"));
+    assert!(config.notes.iter().any(|n| n.contains("LOGIN_OTP_CODE")));
+}
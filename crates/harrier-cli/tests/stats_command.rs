@@ -258,6 +258,7 @@ fn create_entry(url: &str) -> Entry {
         server_ip_address: None,
         connection: None,
         comment: None,
+        web_socket_messages: None,
     }
 }
 
@@ -19,6 +19,12 @@ pub enum Error {
 
     #[error("Invalid pattern: {0}")]
     InvalidPattern(String),
+
+    #[error("Invalid rotation: {0}")]
+    InvalidRotation(String),
+
+    #[error("Journal error: {0}")]
+    Journal(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
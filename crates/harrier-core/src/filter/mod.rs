@@ -1,6 +1,8 @@
 mod host_matcher;
+mod scope;
 
 pub use host_matcher::HostPattern;
+pub use scope::{CaptureScope, OutOfScope, ScopeDecision, TELEMETRY_HOSTS};
 
 use crate::har::{Entry, Har};
 use url::Url;
@@ -9,7 +11,7 @@ use url::Url;
 ///
 /// All filter conditions are combined with AND logic - an entry must match
 /// ALL specified criteria to be included in the filtered output.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct FilterCriteria {
     /// Host patterns to match (any pattern matching = pass)
     pub hosts: Vec<HostPattern>,
@@ -95,8 +97,13 @@ impl FilterCriteria {
             }
         };
 
-        // Entry passes if ANY host pattern matches (OR logic within hosts)
-        self.hosts.iter().any(|pattern| pattern.matches(hostname))
+        self.matches_hostname(hostname)
+    }
+
+    /// Check if a hostname matches the host criteria
+    pub fn matches_hostname(&self, hostname: &str) -> bool {
+        // Passes if there are no host filters or ANY host pattern matches (OR logic within hosts)
+        self.hosts.is_empty() || self.hosts.iter().any(|pattern| pattern.matches(hostname))
     }
 
     /// Check if entry matches status criteria
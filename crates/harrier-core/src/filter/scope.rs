@@ -0,0 +1,228 @@
+use super::{FilterCriteria, HostPattern};
+use crate::{Error, Result};
+use std::str::FromStr;
+use url::Url;
+
+/// Analytics, telemetry and browser-update hosts ignored during capture by default
+pub const TELEMETRY_HOSTS: &[&str] = &[
+    "*.google-analytics.com",
+    "*.analytics.google.com",
+    "*.googletagmanager.com",
+    "*.doubleclick.net",
+    "*.googlesyndication.com",
+    "*.segment.io",
+    "*.segment.com",
+    "*.mixpanel.com",
+    "*.amplitude.com",
+    "*.hotjar.com",
+    "*.hotjar.io",
+    "*.fullstory.com",
+    "*.clarity.ms",
+    "*.sentry.io",
+    "*.nr-data.net",
+    "*.newrelic.com",
+    "*.datadoghq.com",
+    "*.browser-intake-datadoghq.com",
+    "*.facebook.net",
+    "*.optimizely.com",
+    "*.launchdarkly.com",
+    "update.googleapis.com",
+    "safebrowsing.googleapis.com",
+    "optimizationguide-pa.googleapis.com",
+    "content-autofill.googleapis.com",
+    "clients2.google.com",
+    "*.gvt1.com",
+];
+
+/// What capture does with traffic outside the host scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfScope {
+    /// Forward it without recording anything
+    #[default]
+    Drop,
+    /// Record method, URL, status and headers, but no bodies
+    HeadersOnly,
+}
+
+impl FromStr for OutOfScope {
+    type Err = Error;
+
+    /// Parse `drop` or `headers`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "drop" => Ok(OutOfScope::Drop),
+            "headers" | "headers-only" => Ok(OutOfScope::HeadersOnly),
+            other => Err(Error::InvalidPattern(format!(
+                "Unknown out-of-scope mode '{}' (use drop or headers)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How a capture should record one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeDecision {
+    /// Record the full request and response
+    Record,
+    /// Record headers but not bodies
+    HeadersOnly,
+    /// Forward without recording
+    Ignore,
+}
+
+/// Host scope applied while traffic is captured, before anything is kept in memory
+///
+/// Hosts matching the ignore list are never recorded. Other hosts are recorded in full
+/// when they match the scope's host patterns (or there are none), and handled by
+/// [`OutOfScope`] otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureScope {
+    /// In-scope hosts; only the host criteria are used
+    criteria: FilterCriteria,
+    /// Hosts never recorded, whatever the scope
+    ignore: Vec<HostPattern>,
+    out_of_scope: OutOfScope,
+}
+
+impl CaptureScope {
+    /// Scope recording every host
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit recording to hosts matching any of `patterns`
+    pub fn with_hosts(mut self, patterns: Vec<String>) -> Result<Self> {
+        self.criteria = self.criteria.with_hosts(patterns)?;
+        Ok(self)
+    }
+
+    /// Never record hosts matching any of `patterns`
+    pub fn with_ignored_hosts(mut self, patterns: Vec<String>) -> Result<Self> {
+        for pattern in patterns {
+            self.ignore.push(HostPattern::parse(&pattern)?);
+        }
+        Ok(self)
+    }
+
+    /// Never record the built-in [`TELEMETRY_HOSTS`]
+    pub fn with_telemetry_ignored(self) -> Self {
+        let patterns = TELEMETRY_HOSTS.iter().map(|h| h.to_string()).collect();
+        self.with_ignored_hosts(patterns)
+            .expect("built-in telemetry patterns are valid")
+    }
+
+    /// Set what happens to traffic outside the host patterns
+    pub fn with_out_of_scope(mut self, out_of_scope: OutOfScope) -> Self {
+        self.out_of_scope = out_of_scope;
+        self
+    }
+
+    /// Whether host patterns limit what is recorded
+    pub fn has_hosts(&self) -> bool {
+        !self.criteria.hosts.is_empty()
+    }
+
+    /// Decide how to record a request to `url`
+    ///
+    /// URLs without a host (e.g. `data:`) are in scope only when there are no host patterns.
+    pub fn decide(&self, url: &str) -> ScopeDecision {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string));
+
+        match host {
+            Some(host) if self.ignore.iter().any(|pattern| pattern.matches(&host)) => {
+                ScopeDecision::Ignore
+            }
+            Some(host) if self.criteria.matches_hostname(&host) => ScopeDecision::Record,
+            None if !self.has_hosts() => ScopeDecision::Record,
+            _ => match self.out_of_scope {
+                OutOfScope::Drop => ScopeDecision::Ignore,
+                OutOfScope::HeadersOnly => ScopeDecision::HeadersOnly,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_scope_records_everything() {
+        let scope = CaptureScope::new();
+        assert_eq!(
+            scope.decide("https://api.example.com/"),
+            ScopeDecision::Record
+        );
+        assert_eq!(
+            scope.decide("data:image/png;base64,AA"),
+            ScopeDecision::Record
+        );
+    }
+
+    #[test]
+    fn test_hosts_limit_recording() {
+        let scope = CaptureScope::new()
+            .with_hosts(vec!["*.example.com".to_string()])
+            .unwrap();
+        assert_eq!(
+            scope.decide("https://api.example.com/v1"),
+            ScopeDecision::Record
+        );
+        assert_eq!(
+            scope.decide("https://cdn.other.net/app.js"),
+            ScopeDecision::Ignore
+        );
+        assert_eq!(
+            scope.decide("data:image/png;base64,AA"),
+            ScopeDecision::Ignore
+        );
+    }
+
+    #[test]
+    fn test_out_of_scope_headers_only() {
+        let scope = CaptureScope::new()
+            .with_hosts(vec!["api.example.com".to_string()])
+            .unwrap()
+            .with_out_of_scope(OutOfScope::HeadersOnly);
+        assert_eq!(
+            scope.decide("https://cdn.other.net/app.js"),
+            ScopeDecision::HeadersOnly
+        );
+    }
+
+    #[test]
+    fn test_ignore_list_wins_over_scope() {
+        let scope = CaptureScope::new()
+            .with_hosts(vec!["*.example.com".to_string()])
+            .unwrap()
+            .with_ignored_hosts(vec!["metrics.example.com".to_string()])
+            .unwrap()
+            .with_telemetry_ignored()
+            .with_out_of_scope(OutOfScope::HeadersOnly);
+        assert_eq!(
+            scope.decide("https://metrics.example.com/collect"),
+            ScopeDecision::Ignore
+        );
+        assert_eq!(
+            scope.decide("https://www.google-analytics.com/g/collect"),
+            ScopeDecision::Ignore
+        );
+        assert_eq!(
+            scope.decide("https://app.example.com/"),
+            ScopeDecision::Record
+        );
+    }
+
+    #[test]
+    fn test_parse_out_of_scope() {
+        assert_eq!("drop".parse::<OutOfScope>().unwrap(), OutOfScope::Drop);
+        assert_eq!(
+            "Headers".parse::<OutOfScope>().unwrap(),
+            OutOfScope::HeadersOnly
+        );
+        assert!("keep".parse::<OutOfScope>().is_err());
+    }
+}
@@ -0,0 +1,616 @@
+use super::types::{Creator, Entry, Har, Log};
+use super::writer::HarWriter;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Extension appended to a HAR path for its journal (`capture.har.journal`)
+pub const JOURNAL_EXTENSION: &str = "journal";
+
+/// Journal format version written in the header line
+const JOURNAL_FORMAT: u32 = 1;
+
+/// When a capture starts a new HAR file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// After this many entries
+    Entries(usize),
+    /// Once the journal reaches this many bytes
+    Bytes(u64),
+    /// Once the file has been open this long (checked as entries arrive)
+    Time(Duration),
+}
+
+impl FromStr for Rotation {
+    type Err = Error;
+
+    /// Parse `5000` or `5000entries`, `100MB` (B, KB, MB, GB), or `30m` (s, m, h)
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: u64 = number
+            .parse()
+            .map_err(|_| Error::InvalidRotation(format!("'{}' does not start with a number", s)))?;
+        if number == 0 {
+            return Err(Error::InvalidRotation(
+                "limit must be above zero".to_string(),
+            ));
+        }
+
+        let too_large = || Error::InvalidRotation(format!("'{}' is too large", s));
+        let scaled = |factor: u64| number.checked_mul(factor).ok_or_else(too_large);
+
+        let rotation = match unit.trim().to_ascii_lowercase().as_str() {
+            "" | "entries" => Rotation::Entries(usize::try_from(number).map_err(|_| too_large())?),
+            "b" => Rotation::Bytes(number),
+            "kb" => Rotation::Bytes(scaled(1024)?),
+            "mb" => Rotation::Bytes(scaled(1024 * 1024)?),
+            "gb" => Rotation::Bytes(scaled(1024 * 1024 * 1024)?),
+            "s" => Rotation::Time(Duration::from_secs(number)),
+            "m" => Rotation::Time(Duration::from_secs(scaled(60)?)),
+            "h" => Rotation::Time(Duration::from_secs(scaled(3600)?)),
+            other => {
+                return Err(Error::InvalidRotation(format!(
+                    "unknown unit '{}' (use entries, B, KB, MB, GB, s, m or h)",
+                    other
+                )));
+            }
+        };
+        Ok(rotation)
+    }
+}
+
+/// First line of a journal
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalHeader {
+    harrier_journal: u32,
+    version: String,
+    creator: Creator,
+}
+
+/// HAR rebuilt from a journal
+#[derive(Debug)]
+pub struct Recovered {
+    pub har: Har,
+    /// Lines that could not be parsed, usually one cut short by a crash
+    pub skipped_lines: usize,
+}
+
+/// Crash-safe HAR writer for long-running captures
+///
+/// Each completed entry is appended as one JSON line to a `.journal` sidecar next to the
+/// HAR file, so a crash loses at most the entry being written. [`HarJournal::finish`]
+/// turns the journal into a HAR file; after a crash, [`HarJournal::recover`] does the
+/// same from whatever reached the disk.
+///
+/// Clones share the same journal, so one can be handed to each capture task. Rotated
+/// files are written on a separate thread, so appending never waits for a HAR file.
+#[derive(Clone)]
+pub struct HarJournal {
+    state: Arc<Mutex<JournalState>>,
+}
+
+struct JournalState {
+    output: PathBuf,
+    creator: Creator,
+    rotation: Option<Rotation>,
+    /// File being written, `None` once finished
+    current: Option<Part>,
+    /// Number of the current file, starting at 1
+    part: usize,
+    /// HAR files already finalized
+    written: Vec<PathBuf>,
+    /// Rotated files still being finalized, in rotation order
+    rotated: Vec<JoinHandle<Result<PathBuf>>>,
+    total_entries: usize,
+}
+
+/// One HAR file and its journal
+struct Part {
+    har_path: PathBuf,
+    journal_path: PathBuf,
+    file: File,
+    entries: usize,
+    bytes: u64,
+    opened: Instant,
+}
+
+impl HarJournal {
+    /// Start a journal for `output`
+    ///
+    /// With rotation, files are numbered: `capture.har` becomes `capture-001.har`,
+    /// `capture-002.har` and so on.
+    pub fn create(output: &Path, creator: Creator, rotation: Option<Rotation>) -> Result<Self> {
+        let mut state = JournalState {
+            output: output.to_path_buf(),
+            creator,
+            rotation,
+            current: None,
+            part: 1,
+            written: Vec::new(),
+            rotated: Vec::new(),
+            total_entries: 0,
+        };
+        state.current = Some(state.open_part()?);
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(state)),
+        })
+    }
+
+    /// Append a completed entry, starting a new file first if the current one is full
+    pub fn append(&self, entry: &Entry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut state = self.lock();
+        let Some(part) = state.current.as_mut() else {
+            return Err(Error::Journal("journal already finished".to_string()));
+        };
+        // A single write per entry, so a crash cuts at most this line short
+        part.file.write_all(&line)?;
+        part.entries += 1;
+        part.bytes += line.len() as u64;
+        state.total_entries += 1;
+
+        if state.rotation_due() {
+            let closed = state.rotate()?;
+            drop(state);
+
+            // The closed file is re-read and written out without holding the journal
+            let har_path = closed.har_path.clone();
+            let handle = thread::Builder::new()
+                .name("har-rotate".to_string())
+                .spawn(move || {
+                    let path = closed.finalize()?;
+                    tracing::info!("Rotated capture, wrote {}", path.display());
+                    Ok(path)
+                })
+                .map_err(|e| {
+                    Error::Journal(format!("cannot write {}: {}", har_path.display(), e))
+                })?;
+            self.lock().rotated.push(handle);
+        }
+        Ok(())
+    }
+
+    /// Entries appended so far, across all files
+    pub fn entry_count(&self) -> usize {
+        self.lock().total_entries
+    }
+
+    /// Journal currently being written
+    pub fn journal_path(&self) -> Option<PathBuf> {
+        self.lock()
+            .current
+            .as_ref()
+            .map(|part| part.journal_path.clone())
+    }
+
+    /// Write the last HAR file and return every file written
+    ///
+    /// A last file with no entries is discarded rather than written empty.
+    pub fn finish(&self) -> Result<Vec<PathBuf>> {
+        let current = self.lock().current.take();
+        self.wait_for_rotated()?;
+        if let Some(part) = current {
+            if part.entries > 0 {
+                let path = part.finalize()?;
+                self.lock().written.push(path);
+            } else {
+                drop(part.file);
+                fs::remove_file(&part.journal_path)?;
+            }
+        }
+        Ok(self.lock().written.clone())
+    }
+
+    /// Stop journaling and delete the journal of the current file
+    ///
+    /// Files already written by rotation are kept.
+    pub fn discard(&self) -> Result<()> {
+        let current = self.lock().current.take();
+        if let Some(part) = current {
+            drop(part.file);
+            fs::remove_file(&part.journal_path)?;
+        }
+        self.wait_for_rotated()
+    }
+
+    /// Wait for rotated files to be written, returning the first failure
+    fn wait_for_rotated(&self) -> Result<()> {
+        let rotated = std::mem::take(&mut self.lock().rotated);
+        let mut result = Ok(());
+        for handle in rotated {
+            let finalized = handle.join().unwrap_or_else(|_| {
+                Err(Error::Journal(
+                    "writing a rotated file panicked".to_string(),
+                ))
+            });
+            match finalized {
+                Ok(path) => self.lock().written.push(path),
+                Err(e) if result.is_ok() => result = Err(e),
+                Err(e) => tracing::warn!("Failed to write a rotated file: {}", e),
+            }
+        }
+        result
+    }
+
+    /// Rebuild a HAR from a journal, skipping lines that do not parse
+    pub fn recover(journal_path: &Path) -> Result<Recovered> {
+        let reader = BufReader::new(File::open(journal_path)?);
+        let mut lines = reader.lines();
+
+        let header: JournalHeader = match lines.next() {
+            Some(line) => serde_json::from_str(&line?).map_err(|_| {
+                Error::Journal(format!(
+                    "{} is not a Harrier journal",
+                    journal_path.display()
+                ))
+            })?,
+            None => {
+                return Err(Error::Journal(format!(
+                    "{} is empty",
+                    journal_path.display()
+                )));
+            }
+        };
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut skipped_lines = 0;
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable journal line: {}", e);
+                    skipped_lines += 1;
+                }
+            }
+        }
+        // Entries are journaled as they complete; HAR lists them in start order
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+
+        Ok(Recovered {
+            har: Har {
+                log: Log {
+                    version: header.version,
+                    creator: header.creator,
+                    browser: None,
+                    pages: None,
+                    entries,
+                    comment: None,
+                },
+            },
+            skipped_lines,
+        })
+    }
+
+    /// HAR path a journal finalizes to (`capture.har.journal` -> `capture.har`)
+    pub fn har_path_for(journal_path: &Path) -> PathBuf {
+        if journal_path
+            .extension()
+            .is_some_and(|ext| ext == JOURNAL_EXTENSION)
+        {
+            journal_path.with_extension("")
+        } else {
+            journal_path.with_extension("har")
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JournalState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl JournalState {
+    fn har_path(&self) -> PathBuf {
+        if self.rotation.is_none() {
+            return self.output.clone();
+        }
+        let stem = self
+            .output
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "capture".to_string());
+        let extension = self
+            .output
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "har".to_string());
+        self.output
+            .with_file_name(format!("{}-{:03}.{}", stem, self.part, extension))
+    }
+
+    fn open_part(&self) -> Result<Part> {
+        let har_path = self.har_path();
+        let journal_path = journal_path(&har_path);
+
+        // Never overwrite the entries of a capture that did not finish
+        if journal_path.exists()
+            && HarJournal::recover(&journal_path).map_or(true, |r| {
+                !r.har.log.entries.is_empty() || r.skipped_lines > 0
+            })
+        {
+            return Err(Error::Journal(format!(
+                "unfinished journal {} already exists; run `harrier recover {}` or remove it",
+                journal_path.display(),
+                journal_path.display()
+            )));
+        }
+        let mut file = File::create(&journal_path)?;
+
+        let header = JournalHeader {
+            harrier_journal: JOURNAL_FORMAT,
+            version: "1.2".to_string(),
+            creator: self.creator.clone(),
+        };
+        let mut line = serde_json::to_vec(&header)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+
+        tracing::debug!("Journaling entries to {}", journal_path.display());
+
+        Ok(Part {
+            har_path,
+            journal_path,
+            file,
+            entries: 0,
+            bytes: line.len() as u64,
+            opened: Instant::now(),
+        })
+    }
+
+    fn rotation_due(&self) -> bool {
+        let (Some(rotation), Some(part)) = (self.rotation, self.current.as_ref()) else {
+            return false;
+        };
+        match rotation {
+            Rotation::Entries(limit) => part.entries >= limit,
+            Rotation::Bytes(limit) => part.bytes >= limit,
+            Rotation::Time(limit) => part.opened.elapsed() >= limit,
+        }
+    }
+
+    /// Start the next file, returning the one it replaces for finalizing
+    fn rotate(&mut self) -> Result<Part> {
+        self.part += 1;
+        let next = self.open_part()?;
+        self.current
+            .replace(next)
+            .ok_or_else(|| Error::Journal("journal already finished".to_string()))
+    }
+}
+
+impl Part {
+    /// Write the HAR file from the journal, then remove the journal
+    fn finalize(self) -> Result<PathBuf> {
+        self.file.sync_all()?;
+        drop(self.file);
+
+        let recovered = HarJournal::recover(&self.journal_path)?;
+        HarWriter::to_file_atomic(&recovered.har, &self.har_path)?;
+        fs::remove_file(&self.journal_path)?;
+        Ok(self.har_path)
+    }
+}
+
+/// `capture.har` -> `capture.har.journal`
+fn journal_path(har_path: &Path) -> PathBuf {
+    let mut path = har_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(JOURNAL_EXTENSION);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::har::{Cache, Content, HarReader, Request, Response, Timings};
+    use std::fs::OpenOptions;
+
+    fn creator() -> Creator {
+        Creator {
+            name: "Harrier".to_string(),
+            version: "test".to_string(),
+            comment: None,
+        }
+    }
+
+    fn entry(started: &str, url: &str) -> Entry {
+        Entry {
+            page_ref: None,
+            started_date_time: started.to_string(),
+            time: 1.0,
+            request: Request {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                http_version: "HTTP/1.1".to_string(),
+                cookies: vec![],
+                headers: vec![],
+                query_string: vec![],
+                post_data: None,
+                headers_size: -1,
+                body_size: 0,
+                comment: None,
+            },
+            response: Response {
+                status: 200,
+                status_text: "OK".to_string(),
+                http_version: "HTTP/1.1".to_string(),
+                cookies: vec![],
+                headers: vec![],
+                content: Content {
+                    size: 0,
+                    compression: None,
+                    mime_type: "text/plain".to_string(),
+                    text: None,
+                    encoding: None,
+                    comment: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: 0,
+                comment: None,
+            },
+            cache: Cache {
+                before_request: None,
+                after_request: None,
+                comment: None,
+            },
+            timings: Timings {
+                blocked: None,
+                dns: None,
+                connect: None,
+                send: 0.0,
+                wait: 1.0,
+                receive: 0.0,
+                ssl: None,
+                comment: None,
+            },
+            server_ip_address: None,
+            connection: None,
+            comment: None,
+            web_socket_messages: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_rotation() {
+        assert_eq!("500".parse::<Rotation>().unwrap(), Rotation::Entries(500));
+        assert_eq!(
+            "500entries".parse::<Rotation>().unwrap(),
+            Rotation::Entries(500)
+        );
+        assert_eq!(
+            "100MB".parse::<Rotation>().unwrap(),
+            Rotation::Bytes(100 * 1024 * 1024)
+        );
+        assert_eq!(
+            "30m".parse::<Rotation>().unwrap(),
+            Rotation::Time(Duration::from_secs(1800))
+        );
+        assert!("0".parse::<Rotation>().is_err());
+        assert!("10 parsecs".parse::<Rotation>().is_err());
+        assert!("MB".parse::<Rotation>().is_err());
+        assert!(
+            "18446744073709551615GB"
+                .parse::<Rotation>()
+                .unwrap_err()
+                .to_string()
+                .contains("too large")
+        );
+        assert!("18446744073709551615h".parse::<Rotation>().is_err());
+    }
+
+    #[test]
+    fn test_finish_writes_sorted_har() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("capture.har");
+
+        let journal = HarJournal::create(&output, creator(), None).unwrap();
+        journal
+            .append(&entry("2026-01-01T00:00:02.000Z", "https://a.io/second"))
+            .unwrap();
+        journal
+            .append(&entry("2026-01-01T00:00:01.000Z", "https://a.io/first"))
+            .unwrap();
+        assert!(dir.path().join("capture.har.journal").exists());
+
+        assert_eq!(journal.finish().unwrap(), vec![output.clone()]);
+        assert!(!dir.path().join("capture.har.journal").exists());
+
+        let har = HarReader::from_file(&output).unwrap();
+        assert_eq!(har.log.entries[0].request.url, "https://a.io/first");
+        assert_eq!(har.log.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_recover_skips_torn_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("capture.har");
+
+        let journal = HarJournal::create(&output, creator(), None).unwrap();
+        journal
+            .append(&entry("2026-01-01T00:00:01.000Z", "https://a.io/kept"))
+            .unwrap();
+        let journal_path = journal.journal_path().unwrap();
+        // Simulate a crash part way through the next write
+        drop(journal);
+        let mut file = OpenOptions::new().append(true).open(&journal_path).unwrap();
+        file.write_all(b"{\"startedDateTime\":\"2026-01-01T00:")
+            .unwrap();
+
+        let recovered = HarJournal::recover(&journal_path).unwrap();
+        assert_eq!(recovered.har.log.entries.len(), 1);
+        assert_eq!(recovered.skipped_lines, 1);
+        assert_eq!(recovered.har.log.creator.name, "Harrier");
+        assert_eq!(HarJournal::har_path_for(&journal_path), output);
+    }
+
+    #[test]
+    fn test_rotation_by_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("capture.har");
+
+        let journal = HarJournal::create(&output, creator(), Some(Rotation::Entries(2))).unwrap();
+        for i in 0..5 {
+            journal
+                .append(&entry(
+                    &format!("2026-01-01T00:00:0{}.000Z", i),
+                    &format!("https://a.io/{}", i),
+                ))
+                .unwrap();
+        }
+        let files = journal.finish().unwrap();
+
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["capture-001.har", "capture-002.har", "capture-003.har"]
+        );
+        let last = HarReader::from_file(&files[2]).unwrap();
+        assert_eq!(last.log.entries.len(), 1);
+        assert_eq!(journal.entry_count(), 5);
+    }
+
+    #[test]
+    fn test_existing_journal_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("capture.har");
+
+        drop(HarJournal::create(&output, creator(), None).unwrap());
+        // A journal with no entries yet is safe to start over
+        let second = HarJournal::create(&output, creator(), None).unwrap();
+
+        second
+            .append(&entry("2026-01-01T00:00:01.000Z", "https://a.io/"))
+            .unwrap();
+        let err = HarJournal::create(&output, creator(), None).err().unwrap();
+        assert!(err.to_string().contains("harrier recover"));
+    }
+
+    #[test]
+    fn test_empty_capture_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("capture.har");
+
+        let journal = HarJournal::create(&output, creator(), None).unwrap();
+        assert!(journal.finish().unwrap().is_empty());
+        assert!(!output.exists());
+        assert!(!dir.path().join("capture.har.journal").exists());
+    }
+}
@@ -1,7 +1,9 @@
+mod journal;
 mod reader;
 mod types;
 mod writer;
 
+pub use journal::{HarJournal, JOURNAL_EXTENSION, Recovered, Rotation};
 pub use reader::HarReader;
 pub use types::*;
 pub use writer::HarWriter;
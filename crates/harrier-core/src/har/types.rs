@@ -71,6 +71,27 @@ pub struct Entry {
     pub connection: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    /// Frames sent over a WebSocket opened by this request (Chrome extension field)
+    #[serde(
+        rename = "_webSocketMessages",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub web_socket_messages: Option<Vec<WebSocketMessage>>,
+}
+
+/// WebSocket frame, as Chrome DevTools exports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketMessage {
+    /// `send` (client to server) or `receive` (server to client)
+    #[serde(rename = "type")]
+    pub message_type: String,
+    /// Seconds since the Unix epoch
+    pub time: f64,
+    /// WebSocket opcode: 1 text, 2 binary, 8 close, 9 ping, 10 pong
+    pub opcode: u8,
+    /// Text payload, or base64 for other frames
+    pub data: String,
 }
 
 /// HTTP request
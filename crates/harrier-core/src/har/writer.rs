@@ -2,7 +2,7 @@ use super::types::Har;
 use crate::Result;
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct HarWriter;
 
@@ -24,6 +24,18 @@ impl HarWriter {
         Ok(())
     }
 
+    /// Write a HAR file through a temporary file, so a crash never leaves it half-written
+    pub fn to_file_atomic(har: &Har, path: &Path) -> Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        Self::to_file(har, &tmp)?;
+        std::fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
     /// Convert a HAR structure to a JSON string
     pub fn to_string(har: &Har) -> Result<String> {
         tracing::debug!("Converting HAR to string");
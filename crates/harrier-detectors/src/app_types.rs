@@ -298,7 +298,12 @@ impl AppTypeDetector {
             return true;
         }
 
-        false
+        // graphql-ws / subscriptions-transport-ws messages over a WebSocket
+        Self::websocket_text_frames(entry).any(|frame| {
+            frame.contains("\"connection_init\"")
+                || ((frame.contains("\"subscribe\"") || frame.contains("\"start\""))
+                    && frame.contains("\"query\""))
+        })
     }
 
     fn is_soap(entry: &Entry) -> bool {
@@ -319,6 +324,11 @@ impl AppTypeDetector {
     }
 
     fn is_websocket(entry: &Entry) -> bool {
+        // Frames recorded by the proxy or Chrome
+        if entry.web_socket_messages.is_some() {
+            return true;
+        }
+
         // Check for upgrade headers
         for header in &entry.request.headers {
             if header.name.to_lowercase() == "upgrade"
@@ -341,7 +351,18 @@ impl AppTypeDetector {
             return false;
         }
 
-        false
+        // MCP over a WebSocket: JSON-RPC messages using MCP methods
+        Self::websocket_text_frames(entry).any(|frame| {
+            serde_json::from_str::<serde_json::Value>(frame).is_ok_and(|message| {
+                message["jsonrpc"] == "2.0"
+                    && message["method"].as_str().is_some_and(|method| {
+                        method == "initialize"
+                            || ["tools/", "resources/", "prompts/", "notifications/"]
+                                .iter()
+                                .any(|prefix| method.starts_with(prefix))
+                    })
+            })
+        })
     }
 
     fn is_jsonrpc(entry: &Entry) -> bool {
@@ -352,6 +373,13 @@ impl AppTypeDetector {
             return true;
         }
 
+        // Check WebSocket messages for JSON-RPC 2.0 structure
+        if Self::websocket_text_frames(entry).any(|frame| {
+            frame.contains("\"jsonrpc\":\"2.0\"") || frame.contains("\"jsonrpc\": \"2.0\"")
+        }) {
+            return true;
+        }
+
         // Check request body for JSON-RPC 2.0 structure
         if let Some(ref post_data) = entry.request.post_data
             && let Some(ref text) = post_data.text
@@ -417,7 +445,9 @@ impl AppTypeDetector {
             return true;
         }
 
-        false
+        // Engine.IO open packet or Socket.IO event frames
+        Self::websocket_text_frames(entry)
+            .any(|frame| frame.starts_with("0{\"sid\"") || frame.starts_with("42["))
     }
 
     fn is_sockjs(entry: &Entry) -> bool {
@@ -550,6 +580,16 @@ impl AppTypeDetector {
         html_count <= 2 && api_count > 5 && js_count > 0
     }
 
+    /// Text frames of the WebSocket this entry opened
+    fn websocket_text_frames(entry: &Entry) -> impl Iterator<Item = &str> {
+        entry
+            .web_socket_messages
+            .iter()
+            .flatten()
+            .filter(|message| message.opcode == 1)
+            .map(|message| message.data.as_str())
+    }
+
     fn get_content_type(headers: &[Header]) -> Option<String> {
         headers
             .iter()
@@ -561,7 +601,9 @@ impl AppTypeDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use harrier_core::har::{Cache, Content, PostData, Request, Response, Timings};
+    use harrier_core::har::{
+        Cache, Content, PostData, Request, Response, Timings, WebSocketMessage,
+    };
 
     fn create_test_entry(
         url: &str,
@@ -647,6 +689,7 @@ mod tests {
             server_ip_address: None,
             connection: None,
             comment: None,
+            web_socket_messages: None,
         }
     }
 
@@ -754,6 +797,50 @@ mod tests {
         assert!(!AppTypeDetector::is_socketio(&entry));
     }
 
+    fn websocket_entry(url: &str, frames: &[&str]) -> Entry {
+        let mut entry = create_test_entry(url, "GET", None, None, None, None);
+        entry.response.status = 101;
+        entry.web_socket_messages = Some(
+            frames
+                .iter()
+                .map(|frame| WebSocketMessage {
+                    message_type: "send".to_string(),
+                    time: 1_700_000_000.0,
+                    opcode: 1,
+                    data: frame.to_string(),
+                })
+                .collect(),
+        );
+        entry
+    }
+
+    #[test]
+    fn test_detect_from_websocket_frames() {
+        let socketio = websocket_entry(
+            "wss://chat.example.com/live",
+            &["40", "42[\"message\",\"hi\"]"],
+        );
+        assert!(AppTypeDetector::is_websocket(&socketio));
+        assert!(AppTypeDetector::is_socketio(&socketio));
+
+        let graphql = websocket_entry(
+            "wss://api.example.com/subscriptions",
+            &[
+                r#"{"type":"connection_init","payload":{}}"#,
+                r#"{"id":"1","type":"subscribe","payload":{"query":"subscription { orders { id } }"}}"#,
+            ],
+        );
+        assert!(AppTypeDetector::is_graphql(&graphql));
+        assert!(!AppTypeDetector::is_socketio(&graphql));
+
+        let mcp = websocket_entry(
+            "wss://agent.example.com/ws",
+            &[r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#],
+        );
+        assert!(AppTypeDetector::is_mcp(&mcp));
+        assert!(AppTypeDetector::is_jsonrpc(&mcp));
+    }
+
     #[test]
     fn test_detect_for_host_multiple_types() {
         let entries = [
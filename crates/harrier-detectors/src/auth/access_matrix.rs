@@ -0,0 +1,245 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::identity::{IdentityKind, IdentityResolver};
+use super::idor::IdorAnalyzer;
+use super::security::Severity;
+
+/// Which identities reached which templated endpoints, and with what status codes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessMatrix {
+    pub identities: Vec<MatrixIdentity>,
+    pub endpoints: Vec<MatrixEndpoint>,
+    pub issues: Vec<AccessControlIssue>,
+}
+
+/// A column of the matrix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixIdentity {
+    pub label: String,
+    pub kind: Option<IdentityKind>, // None for requests without credentials
+    pub roles: Vec<String>,
+    pub privileged: bool,
+    pub request_count: usize,
+}
+
+/// A row of the matrix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixEndpoint {
+    pub endpoint: String, // "METHOD /path/{id}"
+    pub host: String,
+    pub cells: Vec<AccessCell>,
+}
+
+impl MatrixEndpoint {
+    pub fn cell(&self, identity: &str) -> Option<&AccessCell> {
+        self.cells.iter().find(|c| c.identity == identity)
+    }
+}
+
+/// Responses one identity got from one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessCell {
+    pub identity: String,
+    pub statuses: Vec<i64>, // Distinct, ascending
+    pub request_count: usize,
+    pub entry_indices: Vec<usize>,
+}
+
+impl AccessCell {
+    pub fn allowed(&self) -> bool {
+        self.statuses.iter().any(|s| (200..300).contains(s))
+    }
+}
+
+/// Endpoint used by admins that a lower-privileged identity also reached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessControlIssue {
+    pub severity: Severity,
+    pub endpoint: String,
+    pub identity: String,
+    pub message: String,
+    pub entry_index: usize,
+}
+
+/// Column label for requests without credentials
+const ANONYMOUS: &str = "anonymous";
+
+pub struct AccessMatrixBuilder;
+
+impl AccessMatrixBuilder {
+    /// Build the identity x endpoint matrix and flag admin-only endpoints reached by others
+    pub fn build(har: &Har) -> AccessMatrix {
+        let mut matrix = AccessMatrix::default();
+        let mut rows: HashMap<(String, String), usize> = HashMap::new();
+
+        for (idx, entry) in har.log.entries.iter().enumerate() {
+            if Self::is_static_resource(entry) {
+                continue;
+            }
+            let Ok(url) = url::Url::parse(&entry.request.url) else {
+                continue;
+            };
+
+            let identity = IdentityResolver::resolve(entry);
+            let label = identity
+                .as_ref()
+                .map(|i| i.label.clone())
+                .unwrap_or_else(|| ANONYMOUS.to_string());
+            match matrix.identities.iter_mut().find(|i| i.label == label) {
+                Some(existing) => existing.request_count += 1,
+                None => {
+                    let roles = identity
+                        .as_ref()
+                        .map(|i| i.roles.clone())
+                        .unwrap_or_default();
+                    matrix.identities.push(MatrixIdentity {
+                        label: label.clone(),
+                        kind: identity.as_ref().map(|i| i.kind),
+                        privileged: roles.iter().any(|r| Self::is_privileged_role(r)),
+                        roles,
+                        request_count: 1,
+                    });
+                }
+            }
+
+            let (template, _) = IdorAnalyzer::template_path(url.path());
+            let key = (
+                url.host_str().unwrap_or_default().to_string(),
+                format!("{} {}", entry.request.method, template),
+            );
+            let row = *rows.entry(key.clone()).or_insert_with(|| {
+                matrix.endpoints.push(MatrixEndpoint {
+                    endpoint: key.1.clone(),
+                    host: key.0.clone(),
+                    cells: Vec::new(),
+                });
+                matrix.endpoints.len() - 1
+            });
+
+            let cells = &mut matrix.endpoints[row].cells;
+            let cell = match cells.iter().position(|c| c.identity == label) {
+                Some(pos) => &mut cells[pos],
+                None => {
+                    cells.push(AccessCell {
+                        identity: label,
+                        statuses: Vec::new(),
+                        request_count: 0,
+                        entry_indices: Vec::new(),
+                    });
+                    cells.last_mut().unwrap()
+                }
+            };
+            cell.request_count += 1;
+            cell.entry_indices.push(idx);
+            if let Err(pos) = cell.statuses.binary_search(&entry.response.status) {
+                cell.statuses.insert(pos, entry.response.status);
+            }
+        }
+
+        matrix.issues = Self::find_privilege_gaps(&matrix, &har.log.entries);
+        matrix
+    }
+
+    /// Endpoints that privileged identities used, reached with a 2xx by a non-privileged one
+    fn find_privilege_gaps(matrix: &AccessMatrix, entries: &[Entry]) -> Vec<AccessControlIssue> {
+        let privileged: Vec<&str> = matrix
+            .identities
+            .iter()
+            .filter(|i| i.privileged)
+            .map(|i| i.label.as_str())
+            .collect();
+        if privileged.is_empty() {
+            return Vec::new();
+        }
+
+        let mut issues = Vec::new();
+        for endpoint in &matrix.endpoints {
+            let allowed: Vec<&AccessCell> = endpoint.cells.iter().filter(|c| c.allowed()).collect();
+            let admin_uses = allowed
+                .iter()
+                .filter(|c| privileged.contains(&c.identity.as_str()))
+                .count();
+            let others: Vec<&AccessCell> = allowed
+                .iter()
+                .filter(|c| !privileged.contains(&c.identity.as_str()))
+                .copied()
+                .collect();
+            // Apart from this one identity, only admins got through
+            if admin_uses == 0 || others.len() != 1 {
+                continue;
+            }
+
+            let cell = others[0];
+            let anonymous = cell.identity == ANONYMOUS;
+            let entry_index = cell
+                .entry_indices
+                .iter()
+                .copied()
+                .find(|&i| (200..300).contains(&entries[i].response.status))
+                .unwrap_or(cell.entry_indices[0]);
+            issues.push(AccessControlIssue {
+                severity: if anonymous {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                },
+                endpoint: endpoint.endpoint.clone(),
+                identity: cell.identity.clone(),
+                message: format!(
+                    "{} otherwise used only by admin identities was reached by {}",
+                    endpoint.endpoint,
+                    if anonymous {
+                        "an unauthenticated request".to_string()
+                    } else {
+                        format!("non-admin identity {}", cell.identity)
+                    }
+                ),
+                entry_index,
+            });
+        }
+        issues
+    }
+
+    fn is_privileged_role(role: &str) -> bool {
+        let lower = role.to_lowercase();
+        lower.contains("admin") || lower.contains("superuser") || lower == "root"
+    }
+
+    /// Images, fonts, stylesheets and scripts say nothing about access control
+    pub fn is_static_resource(entry: &Entry) -> bool {
+        let mime = entry.response.content.mime_type.to_lowercase();
+        mime.starts_with("image/")
+            || mime.starts_with("font/")
+            || mime.starts_with("text/css")
+            || mime.contains("javascript")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_privileged_role() {
+        assert!(AccessMatrixBuilder::is_privileged_role("admin"));
+        assert!(AccessMatrixBuilder::is_privileged_role("ROLE_ADMIN"));
+        assert!(AccessMatrixBuilder::is_privileged_role(
+            "tenant-administrator"
+        ));
+        assert!(!AccessMatrixBuilder::is_privileged_role("user"));
+        assert!(!AccessMatrixBuilder::is_privileged_role("rooted-device"));
+    }
+
+    #[test]
+    fn test_access_cell_allowed() {
+        let cell = AccessCell {
+            identity: "sub:alice".to_string(),
+            statuses: vec![403],
+            request_count: 1,
+            entry_indices: vec![0],
+        };
+        assert!(!cell.allowed());
+    }
+}
@@ -2,14 +2,23 @@ use crate::Result;
 use harrier_core::har::Har;
 use serde::{Deserialize, Serialize};
 
+use super::access_matrix::{AccessMatrix, AccessMatrixBuilder};
 use super::advanced_security::{AdvancedSecurityAnalysis, AdvancedSecurityAnalyzer};
+use super::csrf::{CsrfAnalysis, CsrfAnalyzer};
 use super::events::{AuthEvent, EventDetector};
 use super::flows::{AuthFlow, FlowDetector};
+use super::idor::{IdorAnalysis, IdorAnalyzer};
 use super::jwt::{JwtAnalyzer, JwtSecurityIssue, JwtToken};
+use super::jwt_verification::{JwtVerificationReport, JwtVerifier};
+use super::lineage::{TokenLineage, TokenLineageTracker};
+use super::login::{LoginRequest, LoginRequestExtractor};
 use super::methods::{AuthDetector, AuthMethod};
+use super::oauth::{OAuthAnalysis, OAuthAnalyzer};
 use super::saml::{SamlDetector, SamlFlow, SamlSecurityIssue};
 use super::security::{SecurityAnalyzer, SecurityNote};
+use super::session_lifecycle::{SessionLifecycleAnalysis, SessionLifecycleAnalyzer};
 use super::sessions::{AuthSession, SessionTracker};
+use super::unauthenticated::{UnauthenticatedAnalyzer, UnauthenticatedInventory};
 
 /// Complete authentication analysis result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,11 +27,20 @@ pub struct AuthAnalysis {
     pub sessions: Vec<AuthSession>,
     pub flows: Vec<AuthFlow>,
     pub events: Vec<AuthEvent>,
+    pub login_requests: Vec<LoginRequest>,
     pub security_notes: Vec<SecurityNote>,
+    pub session_lifecycle: SessionLifecycleAnalysis,
+    pub csrf: CsrfAnalysis,
+    pub idor: IdorAnalysis,
+    pub access_matrix: AccessMatrix,
+    pub unauthenticated: UnauthenticatedInventory,
 
     // Phase 3: Advanced features
     pub jwt_tokens: Vec<JwtToken>,
     pub jwt_issues: Vec<JwtSecurityIssue>,
+    pub jwt_verification: JwtVerificationReport,
+    pub token_lineage: Vec<TokenLineage>,
+    pub oauth: OAuthAnalysis,
     pub saml_flows: Vec<SamlFlow>,
     pub saml_issues: Vec<SamlSecurityIssue>,
     pub advanced_security: AdvancedSecurityAnalysis,
@@ -33,6 +51,16 @@ pub struct AuthAnalyzer;
 impl AuthAnalyzer {
     /// Perform comprehensive authentication analysis on a HAR file
     pub fn analyze(har: &Har) -> Result<AuthAnalysis> {
+        Self::analyze_with_jwt_checks(har, &JwtVerifier::new(), &[])
+    }
+
+    /// Perform authentication analysis, verifying JWT signatures with the given keys
+    /// and trying HMAC-signed tokens against common secrets plus `jwt_wordlist`
+    pub fn analyze_with_jwt_checks(
+        har: &Har,
+        verifier: &JwtVerifier,
+        jwt_wordlist: &[String],
+    ) -> Result<AuthAnalysis> {
         tracing::debug!("Starting authentication analysis");
 
         // 1. Detect authentication methods (existing functionality)
@@ -51,21 +79,96 @@ impl AuthAnalyzer {
         let events = EventDetector::detect_events(har)?;
         tracing::debug!("Detected {} authentication events", events.len());
 
-        // 5. Analyze security
+        // 5. Describe the login requests of form and JSON login flows
+        let login_requests = LoginRequestExtractor::extract(har, &flows, &events);
+        tracing::debug!("Described {} login requests", login_requests.len());
+
+        // 6. Analyze security
         let security_notes = SecurityAnalyzer::analyze(har, &methods, &sessions)?;
         tracing::debug!("Generated {} security notes", security_notes.len());
 
+        // 7. Check session rotation at login and invalidation at logout
+        let session_lifecycle = SessionLifecycleAnalyzer::analyze(har, &events, &sessions);
+        tracing::debug!(
+            "Checked {} login rotations and {} logout invalidations ({} issues)",
+            session_lifecycle.login_rotations.len(),
+            session_lifecycle.logout_checks.len(),
+            session_lifecycle.issues.len()
+        );
+
+        // 8. Check CSRF defenses on cookie-authenticated state changes
+        let csrf = CsrfAnalyzer::analyze(har);
+        tracing::debug!(
+            "Checked CSRF protection on {} endpoints ({} issues)",
+            csrf.endpoints.len(),
+            csrf.issues.len()
+        );
+
+        // 9. Rank object-level authorization (IDOR/BOLA) test candidates
+        let idor = IdorAnalyzer::analyze(har);
+        tracing::debug!("Found {} IDOR candidates", idor.candidates.len());
+
+        // 10. Map which identities reached which endpoints
+        let access_matrix = AccessMatrixBuilder::build(har);
+        tracing::debug!(
+            "Built access matrix of {} identities x {} endpoints ({} issues)",
+            access_matrix.identities.len(),
+            access_matrix.endpoints.len(),
+            access_matrix.issues.len()
+        );
+
+        // 11. Inventory endpoints reached without credentials
+        let unauthenticated = UnauthenticatedAnalyzer::analyze(har);
+        tracing::debug!(
+            "Found {} endpoints open and {} denied without credentials ({} issues)",
+            unauthenticated.open_endpoints.len(),
+            unauthenticated.denied_endpoints.len(),
+            unauthenticated.issues.len()
+        );
+
         // Phase 3: Advanced features
 
-        // 6. Analyze JWT tokens
-        let (jwt_tokens, jwt_issues) = JwtAnalyzer::analyze(har)?;
+        // 12. Analyze JWT tokens
+        let (jwt_tokens, mut jwt_issues) = JwtAnalyzer::analyze(har)?;
         tracing::debug!(
             "Found {} JWT tokens with {} security issues",
             jwt_tokens.len(),
             jwt_issues.len()
         );
 
-        // 7. Detect SAML flows
+        // 13. Verify JWT signatures
+        let (jwt_verification, verification_issues) = verifier.verify(har, &jwt_tokens);
+        tracing::debug!(
+            "Verified {} JWT tokens against {} configured and {} exposed keys",
+            jwt_verification.results.len(),
+            verifier.keys().len(),
+            jwt_verification.exposed_keys.len()
+        );
+        jwt_issues.extend(verification_issues);
+
+        // 14. Look for HMAC secrets that can be guessed
+        let weak_secret_issues = JwtAnalyzer::find_weak_secrets(&jwt_tokens, jwt_wordlist);
+        tracing::debug!(
+            "Recovered {} weak JWT secrets using {} wordlist entries",
+            weak_secret_issues.len(),
+            jwt_wordlist.len()
+        );
+        jwt_issues.extend(weak_secret_issues);
+
+        // 15. Trace tokens from the token exchange that issued them
+        let token_lineage = TokenLineageTracker::track(har, &jwt_tokens, &flows);
+        tracing::debug!("Traced lineage for {} tokens", token_lineage.len());
+
+        // 16. Check OAuth 2.0 / OIDC parameters
+        let oauth = OAuthAnalyzer::analyze(har)?;
+        tracing::debug!(
+            "Checked {} OAuth authorization requests and {} provider configurations ({} issues)",
+            oauth.authorization_requests.len(),
+            oauth.provider_configs.len(),
+            oauth.issues.len()
+        );
+
+        // 17. Detect SAML flows
         let (saml_flows, saml_issues) = SamlDetector::detect_flows(har)?;
         tracing::debug!(
             "Detected {} SAML flows with {} security issues",
@@ -73,7 +176,7 @@ impl AuthAnalyzer {
             saml_issues.len()
         );
 
-        // 8. Advanced security analysis
+        // 18. Advanced security analysis
         let advanced_security = AdvancedSecurityAnalyzer::analyze(har)?;
         tracing::debug!(
             "Advanced security: {} token exposures, {} CORS issues, {} CSP findings, {} refresh patterns",
@@ -88,9 +191,18 @@ impl AuthAnalyzer {
             sessions,
             flows,
             events,
+            login_requests,
             security_notes,
+            session_lifecycle,
+            csrf,
+            idor,
+            access_matrix,
+            unauthenticated,
             jwt_tokens,
             jwt_issues,
+            jwt_verification,
+            token_lineage,
+            oauth,
             saml_flows,
             saml_issues,
             advanced_security,
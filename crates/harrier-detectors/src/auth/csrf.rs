@@ -0,0 +1,479 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::security::Severity;
+use super::sessions::SessionTracker;
+
+/// CSRF defenses observed on cookie-authenticated, state-changing requests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsrfAnalysis {
+    pub endpoints: Vec<CsrfEndpoint>,
+    pub tokens: Vec<CsrfTokenObservation>,
+    pub issues: Vec<CsrfSecurityIssue>,
+}
+
+/// A state-changing endpoint and the protections present on every captured request to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfEndpoint {
+    pub endpoint: String, // "METHOD /path"
+    pub host: String,
+    pub request_count: usize,
+    pub entry_indices: Vec<usize>,
+    pub session_cookies: Vec<String>,
+    pub token_name: Option<String>, // Form field or header carrying the synchronizer token
+    pub protections: Vec<CsrfProtection>,
+}
+
+impl CsrfEndpoint {
+    /// Protections that stop a cross-site request on their own (Origin/Referer only help
+    /// if the server checks them, which a capture cannot show)
+    pub fn is_protected(&self) -> bool {
+        self.protections.iter().any(|p| {
+            matches!(
+                p,
+                CsrfProtection::SynchronizerToken
+                    | CsrfProtection::DoubleSubmitCookie
+                    | CsrfProtection::SameSiteCookie
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsrfProtection {
+    SynchronizerToken,
+    DoubleSubmitCookie,
+    SameSiteCookie,
+    OriginHeader,
+    RefererHeader,
+}
+
+impl CsrfProtection {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CsrfProtection::SynchronizerToken => "Synchronizer token",
+            CsrfProtection::DoubleSubmitCookie => "Double-submit cookie",
+            CsrfProtection::SameSiteCookie => "SameSite session cookie",
+            CsrfProtection::OriginHeader => "Origin header",
+            CsrfProtection::RefererHeader => "Referer header",
+        }
+    }
+}
+
+/// One CSRF token value and where it was presented
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfTokenObservation {
+    pub name: String,
+    pub token_preview: String,
+    pub request_count: usize,
+    pub session_count: usize, // Distinct session cookie values it was sent with
+    pub entry_indices: Vec<usize>,
+}
+
+/// CSRF security issues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsrfSecurityIssue {
+    pub severity: Severity,
+    pub issue_type: CsrfIssueType,
+    pub message: String,
+    pub entry_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CsrfIssueType {
+    MissingProtection,
+    StaticToken,
+    TokenReusedAcrossSessions,
+}
+
+impl CsrfIssueType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            CsrfIssueType::MissingProtection => "Missing protection",
+            CsrfIssueType::StaticToken => "Static token",
+            CsrfIssueType::TokenReusedAcrossSessions => "Token reused across sessions",
+        }
+    }
+}
+
+/// Headers commonly used to carry a synchronizer token
+const CSRF_HEADERS: [&str; 6] = [
+    "x-csrf-token",
+    "x-xsrf-token",
+    "x-csrftoken",
+    "csrf-token",
+    "x-csrf",
+    "x-xsrf",
+];
+
+/// Form fields used by frameworks whose names don't mention csrf/xsrf
+const CSRF_FIELDS: [&str; 4] = [
+    "authenticity_token",
+    "_token",
+    "__requestverificationtoken",
+    "anti_forgery_token",
+];
+
+/// A token has to be sent this many times before it counts as never changing
+const STATIC_TOKEN_MIN_REQUESTS: usize = 3;
+
+/// (host, "METHOD /path")
+type EndpointKey = (String, String);
+
+/// Synchronizer token found on a request
+struct PresentedToken {
+    name: String,
+    value: String,
+}
+
+pub struct CsrfAnalyzer;
+
+impl CsrfAnalyzer {
+    /// Check state-changing, cookie-authenticated requests for CSRF defenses
+    pub fn analyze(har: &Har) -> CsrfAnalysis {
+        let entries = &har.log.entries;
+        let mut analysis = CsrfAnalysis::default();
+
+        let same_site = Self::same_site_attributes(entries);
+
+        // Requests and their protections per endpoint, in first-seen order
+        let mut order: Vec<EndpointKey> = Vec::new();
+        let mut grouped: HashMap<EndpointKey, Vec<(usize, Vec<CsrfProtection>)>> = HashMap::new();
+        let mut session_cookie_names: HashMap<EndpointKey, Vec<String>> = HashMap::new();
+        let mut token_names: HashMap<EndpointKey, String> = HashMap::new();
+        // Distinct tokens with the requests and session identities that presented them
+        let mut tokens: Vec<(PresentedToken, Vec<usize>, HashSet<String>)> = Vec::new();
+
+        for (idx, entry) in entries.iter().enumerate() {
+            if !Self::is_state_changing(&entry.request.method) {
+                continue;
+            }
+            let session_cookies: Vec<(&str, &str)> = entry
+                .request
+                .cookies
+                .iter()
+                .filter(|c| SessionTracker::is_auth_cookie(&c.name) && !Self::is_csrf_name(&c.name))
+                .map(|c| (c.name.as_str(), c.value.as_str()))
+                .collect();
+            let has_authorization = entry
+                .request
+                .headers
+                .iter()
+                .any(|h| h.name.eq_ignore_ascii_case("authorization"));
+            if session_cookies.is_empty() || has_authorization {
+                continue;
+            }
+
+            let mut protections = Vec::new();
+            let token = Self::presented_token(entry);
+            if let Some(ref token) = token {
+                protections.push(CsrfProtection::SynchronizerToken);
+                if entry
+                    .request
+                    .cookies
+                    .iter()
+                    .any(|c| Self::decode(&c.value) == Self::decode(&token.value))
+                {
+                    protections.push(CsrfProtection::DoubleSubmitCookie);
+                }
+            }
+            let all_same_site = session_cookies.iter().all(|(name, _)| {
+                Self::same_site_before(&same_site, name, idx).is_some_and(|v| {
+                    v.eq_ignore_ascii_case("lax") || v.eq_ignore_ascii_case("strict")
+                })
+            });
+            if all_same_site {
+                protections.push(CsrfProtection::SameSiteCookie);
+            }
+            if Self::has_header(entry, "origin") {
+                protections.push(CsrfProtection::OriginHeader);
+            }
+            if Self::has_header(entry, "referer") {
+                protections.push(CsrfProtection::RefererHeader);
+            }
+
+            let key = Self::endpoint_key(entry);
+            if !grouped.contains_key(&key) {
+                order.push(key.clone());
+            }
+            grouped
+                .entry(key.clone())
+                .or_default()
+                .push((idx, protections));
+            let names = session_cookie_names.entry(key.clone()).or_default();
+            for (name, _) in &session_cookies {
+                if !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+
+            if let Some(token) = token {
+                token_names.entry(key).or_insert_with(|| token.name.clone());
+
+                let mut identity: Vec<String> = session_cookies
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                identity.sort();
+                let identity = identity.join("; ");
+
+                match tokens
+                    .iter_mut()
+                    .find(|(t, _, _)| t.name == token.name && t.value == token.value)
+                {
+                    Some((_, indices, sessions)) => {
+                        indices.push(idx);
+                        sessions.insert(identity);
+                    }
+                    None => tokens.push((token, vec![idx], HashSet::from([identity]))),
+                }
+            }
+        }
+
+        for key in order {
+            let requests = &grouped[&key];
+            // Only protections present on every request count for the endpoint
+            let protections: Vec<CsrfProtection> = requests[0]
+                .1
+                .iter()
+                .copied()
+                .filter(|p| requests.iter().all(|(_, ps)| ps.contains(p)))
+                .collect();
+
+            let endpoint = CsrfEndpoint {
+                endpoint: key.1.clone(),
+                host: key.0.clone(),
+                request_count: requests.len(),
+                entry_indices: requests.iter().map(|(idx, _)| *idx).collect(),
+                session_cookies: session_cookie_names.remove(&key).unwrap_or_default(),
+                token_name: token_names.remove(&key),
+                protections,
+            };
+
+            if !endpoint.is_protected() {
+                let headers = endpoint.protections.contains(&CsrfProtection::OriginHeader)
+                    || endpoint
+                        .protections
+                        .contains(&CsrfProtection::RefererHeader);
+                analysis.issues.push(CsrfSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: CsrfIssueType::MissingProtection,
+                    message: format!(
+                        "{} has no CSRF token, double-submit cookie or SameSite session cookie{}",
+                        endpoint.endpoint,
+                        if headers {
+                            " (only Origin/Referer available for server-side checks)"
+                        } else {
+                            ""
+                        }
+                    ),
+                    entry_index: endpoint.entry_indices[0],
+                });
+            }
+
+            analysis.endpoints.push(endpoint);
+        }
+
+        // Token rotation: per-name distinct values and sessions sharing a value
+        for (token, indices, sessions) in &tokens {
+            let preview = SessionTracker::truncate_value(&token.value);
+            let distinct_values = tokens
+                .iter()
+                .filter(|(t, _, _)| t.name == token.name)
+                .count();
+
+            if distinct_values == 1 && indices.len() >= STATIC_TOKEN_MIN_REQUESTS {
+                analysis.issues.push(CsrfSecurityIssue {
+                    severity: Severity::Info,
+                    issue_type: CsrfIssueType::StaticToken,
+                    message: format!(
+                        "CSRF token '{}' never changes across {} requests",
+                        token.name,
+                        indices.len()
+                    ),
+                    entry_index: indices[0],
+                });
+            }
+            if sessions.len() > 1 {
+                analysis.issues.push(CsrfSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: CsrfIssueType::TokenReusedAcrossSessions,
+                    message: format!(
+                        "CSRF token '{}' accepted with {} different sessions (not bound to the session)",
+                        token.name,
+                        sessions.len()
+                    ),
+                    entry_index: indices[0],
+                });
+            }
+
+            analysis.tokens.push(CsrfTokenObservation {
+                name: token.name.clone(),
+                token_preview: preview,
+                request_count: indices.len(),
+                session_count: sessions.len(),
+                entry_indices: indices.clone(),
+            });
+        }
+
+        analysis
+    }
+
+    fn is_state_changing(method: &str) -> bool {
+        matches!(
+            method.to_uppercase().as_str(),
+            "POST" | "PUT" | "PATCH" | "DELETE"
+        )
+    }
+
+    fn is_csrf_name(name: &str) -> bool {
+        let lower = name.to_lowercase();
+        lower.contains("csrf") || lower.contains("xsrf") || CSRF_FIELDS.contains(&lower.as_str())
+    }
+
+    /// Synchronizer token sent in a header, form field or JSON body field
+    fn presented_token(entry: &Entry) -> Option<PresentedToken> {
+        if let Some(header) = entry
+            .request
+            .headers
+            .iter()
+            .find(|h| CSRF_HEADERS.contains(&h.name.to_lowercase().as_str()))
+            .filter(|h| !h.value.is_empty())
+        {
+            return Some(PresentedToken {
+                name: header.name.clone(),
+                value: header.value.clone(),
+            });
+        }
+
+        let text = entry.request.post_data.as_ref()?.text.as_ref()?;
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_str(text) {
+            return map.iter().find_map(|(key, value)| {
+                value
+                    .as_str()
+                    .filter(|v| Self::is_csrf_name(key) && !v.is_empty())
+                    .map(|v| PresentedToken {
+                        name: key.clone(),
+                        value: v.to_string(),
+                    })
+            });
+        }
+
+        url::form_urlencoded::parse(text.as_bytes())
+            .find(|(key, value)| Self::is_csrf_name(key) && !value.is_empty())
+            .map(|(key, value)| PresentedToken {
+                name: key.into_owned(),
+                value: value.into_owned(),
+            })
+    }
+
+    /// SameSite attribute of each Set-Cookie, with the entry that set it
+    fn same_site_attributes(entries: &[Entry]) -> Vec<(usize, String, Option<String>)> {
+        let mut attributes = Vec::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            for header in &entry.response.headers {
+                if !header.name.eq_ignore_ascii_case("set-cookie") {
+                    continue;
+                }
+                let mut parts = header.value.split(';');
+                let Some(name) = parts
+                    .next()
+                    .and_then(|pair| pair.split_once('='))
+                    .map(|(name, _)| name.trim().to_string())
+                else {
+                    continue;
+                };
+                let same_site = parts.find_map(|attr| {
+                    let (key, value) = attr.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("samesite")
+                        .then(|| value.trim().to_string())
+                });
+                attributes.push((idx, name, same_site));
+            }
+        }
+        attributes
+    }
+
+    /// SameSite value from the latest Set-Cookie for `name` before entry `idx`
+    fn same_site_before<'a>(
+        attributes: &'a [(usize, String, Option<String>)],
+        name: &str,
+        idx: usize,
+    ) -> Option<&'a str> {
+        attributes
+            .iter()
+            .rev()
+            .find(|(set_at, cookie, _)| *set_at < idx && cookie == name)
+            .and_then(|(_, _, same_site)| same_site.as_deref())
+    }
+
+    fn has_header(entry: &Entry, name: &str) -> bool {
+        entry
+            .request
+            .headers
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case(name) && !h.value.is_empty())
+    }
+
+    fn endpoint_key(entry: &Entry) -> EndpointKey {
+        match url::Url::parse(&entry.request.url) {
+            Ok(url) => (
+                url.host_str().unwrap_or_default().to_string(),
+                format!("{} {}", entry.request.method, url.path()),
+            ),
+            Err(_) => (
+                String::new(),
+                format!("{} {}", entry.request.method, entry.request.url),
+            ),
+        }
+    }
+
+    fn decode(value: &str) -> String {
+        url::form_urlencoded::parse(format!("v={}", value).as_bytes())
+            .next()
+            .map(|(_, v)| v.into_owned())
+            .unwrap_or_else(|| value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_csrf_name() {
+        assert!(CsrfAnalyzer::is_csrf_name("csrf_token"));
+        assert!(CsrfAnalyzer::is_csrf_name("XSRF-TOKEN"));
+        assert!(CsrfAnalyzer::is_csrf_name("csrfmiddlewaretoken"));
+        assert!(CsrfAnalyzer::is_csrf_name("authenticity_token"));
+        assert!(!CsrfAnalyzer::is_csrf_name("sessionid"));
+        assert!(!CsrfAnalyzer::is_csrf_name("auth_token"));
+    }
+
+    #[test]
+    fn test_same_site_before() {
+        let attributes = vec![
+            (0, "sid".to_string(), None),
+            (2, "sid".to_string(), Some("Lax".to_string())),
+        ];
+        assert_eq!(CsrfAnalyzer::same_site_before(&attributes, "sid", 1), None);
+        assert_eq!(
+            CsrfAnalyzer::same_site_before(&attributes, "sid", 3),
+            Some("Lax")
+        );
+        assert_eq!(
+            CsrfAnalyzer::same_site_before(&attributes, "other", 3),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(CsrfAnalyzer::decode("abc%3D%3D"), "abc==");
+        assert_eq!(CsrfAnalyzer::decode("plain"), "plain");
+    }
+}
@@ -522,6 +522,7 @@ mod tests {
             server_ip_address: None,
             connection: None,
             comment: None,
+            web_socket_messages: None,
         }
     }
 }
@@ -2,6 +2,9 @@ use crate::Result;
 use chrono::{DateTime, Utc};
 use harrier_core::har::{Entry, Har};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::methods::AuthDetector;
 
 /// Represents a detected authentication flow with its sequence
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,8 +23,12 @@ pub enum AuthFlowType {
     OAuth2AuthorizationCode { pkce: bool },
     OAuth2ClientCredentials,
     OAuth2Implicit,
+    OAuth2RefreshToken,
     FormBased,
     JsonApi,
+    HttpDigest,
+    Ntlm,
+    Negotiate,
     Unknown,
 }
 
@@ -34,8 +41,12 @@ impl AuthFlowType {
             AuthFlowType::OAuth2AuthorizationCode { pkce: false } => "OAuth 2.0 Authorization Code",
             AuthFlowType::OAuth2ClientCredentials => "OAuth 2.0 Client Credentials",
             AuthFlowType::OAuth2Implicit => "OAuth 2.0 Implicit",
+            AuthFlowType::OAuth2RefreshToken => "OAuth 2.0 Refresh Token",
             AuthFlowType::FormBased => "Form-based Login",
             AuthFlowType::JsonApi => "JSON API Authentication",
+            AuthFlowType::HttpDigest => "HTTP Digest Handshake",
+            AuthFlowType::Ntlm => "NTLM Handshake",
+            AuthFlowType::Negotiate => "Negotiate (Kerberos) Handshake",
             AuthFlowType::Unknown => "Unknown",
         }
     }
@@ -64,6 +75,8 @@ pub enum FlowRole {
     TokenExchange,
     TokenResponse,
     FirstAuthenticatedRequest,
+    Challenge,         // 401 with WWW-Authenticate
+    ChallengeResponse, // Retry carrying the scheme's Authorization header
 }
 
 pub struct FlowDetector;
@@ -77,6 +90,7 @@ impl FlowDetector {
         flows.extend(Self::detect_oauth2_authorization_code(har)?);
         flows.extend(Self::detect_oauth2_client_credentials(har)?);
         flows.extend(Self::detect_oauth2_implicit(har)?);
+        flows.extend(Self::detect_oauth2_refresh_token(har)?);
 
         // Detect form-based login flows
         flows.extend(Self::detect_form_login(har)?);
@@ -84,6 +98,9 @@ impl FlowDetector {
         // Detect JSON API authentication flows
         flows.extend(Self::detect_json_api_auth(har)?);
 
+        // Detect Digest / NTLM / Negotiate challenge handshakes
+        flows.extend(Self::detect_challenge_handshakes(har)?);
+
         // Sort by start time
         flows.sort_by(|a, b| a.start_time.cmp(&b.start_time));
 
@@ -257,6 +274,55 @@ impl FlowDetector {
         Ok(flows)
     }
 
+    /// Detect OAuth 2.0 Refresh Token grants (token renewal and rotation)
+    fn detect_oauth2_refresh_token(har: &Har) -> Result<Vec<AuthFlow>> {
+        let mut flows = Vec::new();
+        let entries = &har.log.entries;
+
+        for (i, entry) in entries.iter().enumerate() {
+            if Self::is_refresh_token_request(entry) {
+                let mut steps = vec![FlowStep {
+                    entry_index: i,
+                    timestamp: entry.started_date_time.clone(),
+                    role: FlowRole::TokenExchange,
+                    method: entry.request.method.clone(),
+                    url: Self::truncate_url(&entry.request.url),
+                    status: entry.response.status,
+                    description: "Refresh token grant".to_string(),
+                }];
+
+                // Look for first request using the renewed token
+                if let Some((auth_idx, auth_entry)) =
+                    Self::find_first_bearer_request(entries, i + 1)
+                {
+                    steps.push(FlowStep {
+                        entry_index: auth_idx,
+                        timestamp: auth_entry.started_date_time.clone(),
+                        role: FlowRole::FirstAuthenticatedRequest,
+                        method: auth_entry.request.method.clone(),
+                        url: Self::truncate_url(&auth_entry.request.url),
+                        status: auth_entry.response.status,
+                        description: "First authenticated request".to_string(),
+                    });
+                }
+
+                let start_time = steps.first().unwrap().timestamp.clone();
+                let end_time = steps.last().map(|s| s.timestamp.clone());
+                let duration_ms = Self::calculate_duration(&start_time, end_time.as_deref());
+
+                flows.push(AuthFlow {
+                    flow_type: AuthFlowType::OAuth2RefreshToken,
+                    start_time,
+                    end_time,
+                    duration_ms,
+                    steps,
+                });
+            }
+        }
+
+        Ok(flows)
+    }
+
     /// Detect form-based login flows
     fn detect_form_login(har: &Har) -> Result<Vec<AuthFlow>> {
         let mut flows = Vec::new();
@@ -391,6 +457,171 @@ impl FlowDetector {
         Ok(flows)
     }
 
+    /// Reconstruct 401 challenge → retry handshakes for Digest, NTLM and Negotiate
+    fn detect_challenge_handshakes(har: &Har) -> Result<Vec<AuthFlow>> {
+        let mut flows = Vec::new();
+        let entries = &har.log.entries;
+        let mut consumed = HashSet::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            if entry.response.status != 401 || consumed.contains(&i) {
+                continue;
+            }
+            let offered = Self::offered_challenges(entry);
+            if offered.is_empty() {
+                continue;
+            }
+            // Servers may offer several schemes; follow the one the client answers with
+            let (flow_type, challenge) = entries
+                .iter()
+                .skip(i + 1)
+                .take(10)
+                .filter(|e| e.request.url == entry.request.url)
+                .find_map(|e| {
+                    offered
+                        .iter()
+                        .find(|(t, _)| Self::challenge_credentials(e, t).is_some())
+                })
+                .unwrap_or(&offered[0])
+                .clone();
+
+            let mut steps = vec![FlowStep {
+                entry_index: i,
+                timestamp: entry.started_date_time.clone(),
+                role: FlowRole::Challenge,
+                method: entry.request.method.clone(),
+                url: Self::truncate_url(&entry.request.url),
+                status: entry.response.status,
+                description: Self::describe_challenge(&flow_type, challenge),
+            }];
+
+            // Follow retries to the same URL until the server stops challenging
+            let mut last = i;
+            for (j, retry) in entries.iter().enumerate().skip(i + 1) {
+                if j - last > 10 {
+                    break;
+                }
+                if retry.request.url != entry.request.url {
+                    continue;
+                }
+                let Some(credentials) = Self::challenge_credentials(retry, &flow_type) else {
+                    continue;
+                };
+
+                consumed.insert(j);
+                last = j;
+                steps.push(FlowStep {
+                    entry_index: j,
+                    timestamp: retry.started_date_time.clone(),
+                    role: FlowRole::ChallengeResponse,
+                    method: retry.request.method.clone(),
+                    url: Self::truncate_url(&retry.request.url),
+                    status: retry.response.status,
+                    description: Self::describe_challenge_response(&flow_type, credentials),
+                });
+
+                // NTLM and Negotiate continue with a server token; anything else ends the handshake
+                let continues = retry.response.status == 401
+                    && flow_type != AuthFlowType::HttpDigest
+                    && Self::offered_challenges(retry)
+                        .iter()
+                        .any(|(t, token)| *t == flow_type && !token.is_empty());
+                if !continues {
+                    break;
+                }
+            }
+
+            let start_time = entry.started_date_time.clone();
+            let end_time = steps.last().map(|s| s.timestamp.clone());
+            let duration_ms = Self::calculate_duration(&start_time, end_time.as_deref());
+            flows.push(AuthFlow {
+                flow_type,
+                start_time,
+                end_time,
+                duration_ms,
+                steps,
+            });
+        }
+
+        Ok(flows)
+    }
+
+    /// WWW-Authenticate challenges for handshake schemes, with their parameters or token
+    fn offered_challenges(entry: &Entry) -> Vec<(AuthFlowType, &str)> {
+        entry
+            .response
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("www-authenticate"))
+            .filter_map(|h| {
+                let (scheme, rest) = AuthDetector::split_scheme(&h.value);
+                Self::handshake_type(scheme).map(|t| (t, rest))
+            })
+            .collect()
+    }
+
+    /// Credentials a retry sends for the handshake's scheme
+    fn challenge_credentials<'a>(entry: &'a Entry, flow_type: &AuthFlowType) -> Option<&'a str> {
+        entry
+            .request
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("authorization"))
+            .find_map(|h| {
+                let (scheme, rest) = AuthDetector::split_scheme(&h.value);
+                (Self::handshake_type(scheme).as_ref() == Some(flow_type)).then_some(rest)
+            })
+    }
+
+    fn handshake_type(scheme: &str) -> Option<AuthFlowType> {
+        match scheme.to_lowercase().as_str() {
+            "digest" => Some(AuthFlowType::HttpDigest),
+            "ntlm" => Some(AuthFlowType::Ntlm),
+            "negotiate" => Some(AuthFlowType::Negotiate),
+            _ => None,
+        }
+    }
+
+    fn describe_challenge(flow_type: &AuthFlowType, challenge: &str) -> String {
+        match flow_type {
+            AuthFlowType::HttpDigest => {
+                let params = AuthDetector::auth_params(challenge);
+                format!(
+                    "Digest challenge (realm \"{}\", qop {}, algorithm {})",
+                    params.get("realm").map(String::as_str).unwrap_or(""),
+                    params.get("qop").map(String::as_str).unwrap_or("none"),
+                    params.get("algorithm").map(String::as_str).unwrap_or("MD5")
+                )
+            }
+            AuthFlowType::Ntlm => "NTLM challenge".to_string(),
+            _ => "Negotiate challenge".to_string(),
+        }
+    }
+
+    fn describe_challenge_response(flow_type: &AuthFlowType, credentials: &str) -> String {
+        match flow_type {
+            AuthFlowType::HttpDigest => {
+                let params = AuthDetector::auth_params(credentials);
+                format!(
+                    "Digest response for user \"{}\"",
+                    params.get("username").map(String::as_str).unwrap_or("")
+                )
+            }
+            AuthFlowType::Ntlm => match AuthDetector::ntlm_message_type(credentials) {
+                Some(1) => "NTLM negotiate message (type 1)".to_string(),
+                Some(3) => "NTLM authenticate message (type 3)".to_string(),
+                _ => "NTLM message".to_string(),
+            },
+            _ => {
+                if AuthDetector::ntlm_message_type(credentials).is_some() {
+                    "Negotiate token (NTLM fallback)".to_string()
+                } else {
+                    "Negotiate token (Kerberos)".to_string()
+                }
+            }
+        }
+    }
+
     // Helper methods for pattern detection
 
     fn is_oauth_authorize_request(entry: &Entry) -> bool {
@@ -457,6 +688,17 @@ impl FlowDetector {
         false
     }
 
+    fn is_refresh_token_request(entry: &Entry) -> bool {
+        if entry.request.method == "POST"
+            && entry.request.url.contains("/token")
+            && let Some(ref post_data) = entry.request.post_data
+            && let Some(ref text) = post_data.text
+        {
+            return text.contains("grant_type=refresh_token");
+        }
+        false
+    }
+
     fn is_oauth_implicit_request(entry: &Entry) -> bool {
         entry.request.url.contains("/authorize")
             && entry.request.url.contains("response_type=token")
@@ -657,6 +899,22 @@ mod tests {
         assert_eq!(duration5, 3000.0);
     }
 
+    #[test]
+    fn test_is_refresh_token_request() {
+        use harrier_core::har::PostData;
+
+        let mut entry = create_test_entry("https://auth.example.com/oauth/token");
+        entry.request.method = "POST".to_string();
+        entry.request.post_data = Some(PostData {
+            mime_type: "application/x-www-form-urlencoded".to_string(),
+            params: None,
+            text: Some("grant_type=refresh_token&refresh_token=abc".to_string()),
+            comment: None,
+        });
+        assert!(FlowDetector::is_refresh_token_request(&entry));
+        assert!(!FlowDetector::is_client_credentials_request(&entry));
+    }
+
     fn create_test_entry(url: &str) -> Entry {
         use harrier_core::har::*;
         Entry {
@@ -712,6 +970,7 @@ mod tests {
             server_ip_address: None,
             connection: None,
             comment: None,
+            web_socket_messages: None,
         }
     }
 }
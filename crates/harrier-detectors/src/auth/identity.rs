@@ -0,0 +1,115 @@
+use harrier_core::har::Entry;
+use serde::{Deserialize, Serialize};
+
+use super::jwt::JwtAnalyzer;
+use super::sessions::SessionTracker;
+
+/// Who made a request, as far as its credentials tell
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestIdentity {
+    pub kind: IdentityKind,
+    pub label: String, // e.g. "sub:alice", "sid=3f2a9c...", "X-API-Key: #9a1f03c2"
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityKind {
+    JwtSubject,
+    SessionCookie,
+    BearerToken,
+    ApiKey,
+}
+
+impl IdentityKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IdentityKind::JwtSubject => "JWT subject",
+            IdentityKind::SessionCookie => "Session cookie",
+            IdentityKind::BearerToken => "Bearer token",
+            IdentityKind::ApiKey => "API key",
+        }
+    }
+}
+
+pub struct IdentityResolver;
+
+impl IdentityResolver {
+    /// Identity behind a request: JWT `sub` first, then session cookie, opaque bearer token or API key
+    pub fn resolve(entry: &Entry) -> Option<RequestIdentity> {
+        let bearer = entry
+            .request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("authorization"))
+            .and_then(|h| JwtAnalyzer::extract_bearer_token(&h.value))
+            .filter(|token| !token.is_empty());
+
+        if let Some(token) = bearer
+            && JwtAnalyzer::is_jwt(token)
+            && let Ok(jwt) = JwtAnalyzer::parse_jwt(token, &entry.started_date_time)
+            && let Some(sub) = jwt.claims.sub
+        {
+            return Some(RequestIdentity {
+                kind: IdentityKind::JwtSubject,
+                label: format!("sub:{}", sub),
+                roles: jwt.claims.roles,
+            });
+        }
+
+        let mut session_cookies: Vec<String> = entry
+            .request
+            .cookies
+            .iter()
+            .filter(|c| SessionTracker::is_auth_cookie(&c.name) && !c.value.is_empty())
+            .map(|c| format!("{}={}", c.name, SessionTracker::truncate_value(&c.value)))
+            .collect();
+        if !session_cookies.is_empty() {
+            session_cookies.sort();
+            return Some(RequestIdentity {
+                kind: IdentityKind::SessionCookie,
+                label: session_cookies.join("; "),
+                roles: Vec::new(),
+            });
+        }
+
+        if let Some(token) = bearer {
+            return Some(RequestIdentity {
+                kind: IdentityKind::BearerToken,
+                label: format!("Bearer {}", SessionTracker::truncate_value(token)),
+                roles: Vec::new(),
+            });
+        }
+
+        entry
+            .request
+            .headers
+            .iter()
+            .find(|h| SessionTracker::is_api_key_header(&h.name) && !h.value.is_empty())
+            .map(|h| RequestIdentity {
+                kind: IdentityKind::ApiKey,
+                label: format!("{}: #{}", h.name, Self::fingerprint(&h.value)),
+                roles: Vec::new(),
+            })
+    }
+
+    /// Short SHA-256 fingerprint, so API keys can be told apart without being shown
+    fn fingerprint(value: &str) -> String {
+        let digest = aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, value.as_bytes());
+        digest.as_ref()[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        // SHA-256("abc") = ba7816bf...
+        assert_eq!(IdentityResolver::fingerprint("abc"), "ba7816bf");
+    }
+}
@@ -0,0 +1,462 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use super::identity::IdentityResolver;
+
+/// Endpoints worth testing for broken object-level authorization, highest score first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdorAnalysis {
+    pub candidates: Vec<IdorCandidate>,
+}
+
+/// An object identifier parameter and how it was accessed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdorCandidate {
+    pub endpoint: String, // "METHOD /path/{id}"
+    pub host: String,
+    pub parameter: String, // "{id}" for path segments, otherwise the query/body field name
+    pub location: ObjectIdLocation,
+    pub id_kind: ObjectIdKind,
+    pub object_ids: Vec<String>, // Distinct values, capped at MAX_LISTED_IDS
+    pub identities: Vec<String>,
+    pub max_ids_per_identity: usize,
+    pub shared_ids: Vec<String>, // Values reached by more than one identity
+    pub sequential: bool,
+    pub guessable: bool,
+    pub score: u32,
+    pub reasons: Vec<String>,
+    pub entry_indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectIdLocation {
+    Path,
+    Query,
+    Body,
+}
+
+impl ObjectIdLocation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ObjectIdLocation::Path => "path",
+            ObjectIdLocation::Query => "query",
+            ObjectIdLocation::Body => "body",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectIdKind {
+    Numeric,
+    Uuid,
+    Hex,
+    Slug,
+}
+
+impl ObjectIdKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ObjectIdKind::Numeric => "numeric",
+            ObjectIdKind::Uuid => "UUID",
+            ObjectIdKind::Hex => "hex",
+            ObjectIdKind::Slug => "slug",
+        }
+    }
+}
+
+/// Object identifier found in a request
+#[derive(Debug, Clone)]
+pub(crate) struct ObjectIdRef {
+    pub parameter: String,
+    pub location: ObjectIdLocation,
+    pub kind: ObjectIdKind,
+    pub value: String,
+}
+
+/// Largest gap between sorted numeric IDs that still counts as sequential
+const SEQUENTIAL_MAX_GAP: u64 = 10;
+
+const MAX_LISTED_IDS: usize = 20;
+
+/// Nesting depth searched for ID fields in JSON bodies
+const MAX_BODY_DEPTH: usize = 4;
+
+/// Accesses of one parameter: identity label -> object IDs, plus the entries involved
+#[derive(Default)]
+struct ParameterAccess {
+    kind: Option<ObjectIdKind>,
+    by_identity: HashMap<String, BTreeSet<String>>,
+    entry_indices: Vec<usize>,
+}
+
+/// (host, "METHOD /template", parameter, location)
+type ParameterKey = (String, String, String, ObjectIdLocation);
+
+pub struct IdorAnalyzer;
+
+impl IdorAnalyzer {
+    /// Rank object-ID parameters by how promising they are for manual BOLA testing
+    pub fn analyze(har: &Har) -> IdorAnalysis {
+        let mut order: Vec<ParameterKey> = Vec::new();
+        let mut accesses: HashMap<ParameterKey, ParameterAccess> = HashMap::new();
+
+        for (idx, entry) in har.log.entries.iter().enumerate() {
+            // Only objects the server actually returned or changed
+            if !(200..300).contains(&entry.response.status) {
+                continue;
+            }
+            let Ok(url) = url::Url::parse(&entry.request.url) else {
+                continue;
+            };
+            let (template, ids) = Self::extract_ids(entry, &url);
+            if ids.is_empty() {
+                continue;
+            }
+
+            let identity = IdentityResolver::resolve(entry)
+                .map(|i| i.label)
+                .unwrap_or_else(|| "anonymous".to_string());
+            let endpoint = format!("{} {}", entry.request.method, template);
+            let host = url.host_str().unwrap_or_default().to_string();
+
+            for id in ids {
+                let key = (host.clone(), endpoint.clone(), id.parameter, id.location);
+                let access = accesses.entry(key.clone()).or_insert_with(|| {
+                    order.push(key);
+                    ParameterAccess::default()
+                });
+                access.kind.get_or_insert(id.kind);
+                access
+                    .by_identity
+                    .entry(identity.clone())
+                    .or_default()
+                    .insert(id.value);
+                if access.entry_indices.last() != Some(&idx) {
+                    access.entry_indices.push(idx);
+                }
+            }
+        }
+
+        let mut candidates: Vec<IdorCandidate> = order
+            .into_iter()
+            .filter_map(|key| {
+                let access = accesses.remove(&key)?;
+                Self::build_candidate(key, access)
+            })
+            .collect();
+        // Stable sort keeps capture order among equal scores
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
+
+        IdorAnalysis { candidates }
+    }
+
+    fn build_candidate(key: ParameterKey, access: ParameterAccess) -> Option<IdorCandidate> {
+        let (host, endpoint, parameter, location) = key;
+        let kind = access.kind?;
+
+        let mut all_ids: BTreeSet<&String> = BTreeSet::new();
+        let mut owners: HashMap<&String, usize> = HashMap::new();
+        for ids in access.by_identity.values() {
+            for id in ids {
+                all_ids.insert(id);
+                *owners.entry(id).or_default() += 1;
+            }
+        }
+        let max_ids_per_identity = access
+            .by_identity
+            .values()
+            .map(|ids| ids.len())
+            .max()
+            .unwrap_or(0);
+        let mut shared_ids: Vec<String> = owners
+            .iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(id, _)| id.to_string())
+            .collect();
+        shared_ids.sort();
+
+        let sequential = kind == ObjectIdKind::Numeric && Self::is_sequential(&all_ids);
+        let guessable = all_ids.iter().all(|id| Self::is_guessable(kind, id));
+
+        if max_ids_per_identity < 2 && !sequential && !guessable {
+            return None;
+        }
+
+        let mut score = 0;
+        let mut reasons = Vec::new();
+        if !shared_ids.is_empty() {
+            score += 4;
+            reasons.push(format!(
+                "{} object ID(s) reached by more than one identity",
+                shared_ids.len()
+            ));
+        }
+        if max_ids_per_identity >= 2 {
+            score += 3;
+            reasons.push(format!(
+                "one identity accessed {} different objects",
+                max_ids_per_identity
+            ));
+        }
+        if sequential {
+            score += 3;
+            reasons.push("IDs are sequential".to_string());
+        } else if guessable {
+            score += 2;
+            reasons.push(format!("{} IDs are guessable", kind.as_str()));
+        }
+        if !endpoint.starts_with("GET ") && !endpoint.starts_with("HEAD ") {
+            score += 1;
+            reasons.push("state-changing method".to_string());
+        }
+
+        let mut identities: Vec<String> = access.by_identity.keys().cloned().collect();
+        identities.sort();
+
+        Some(IdorCandidate {
+            endpoint,
+            host,
+            parameter,
+            location,
+            id_kind: kind,
+            object_ids: all_ids
+                .iter()
+                .take(MAX_LISTED_IDS)
+                .map(|id| id.to_string())
+                .collect(),
+            identities,
+            max_ids_per_identity,
+            shared_ids,
+            sequential,
+            guessable,
+            score,
+            reasons,
+            entry_indices: access.entry_indices,
+        })
+    }
+
+    /// Templated path and every object ID found in the path, query string and JSON body
+    pub(crate) fn extract_ids(entry: &Entry, url: &url::Url) -> (String, Vec<ObjectIdRef>) {
+        let (template, mut ids) = Self::template_path(url.path());
+
+        for (name, value) in url.query_pairs() {
+            if let Some(kind) = Self::classify(&value) {
+                ids.push(ObjectIdRef {
+                    parameter: name.into_owned(),
+                    location: ObjectIdLocation::Query,
+                    kind,
+                    value: value.into_owned(),
+                });
+            }
+        }
+
+        if let Some(text) = entry
+            .request
+            .post_data
+            .as_ref()
+            .and_then(|p| p.text.as_ref())
+            && let Ok(body) = serde_json::from_str::<serde_json::Value>(text)
+        {
+            Self::collect_body_ids(&body, "", 0, &mut ids);
+        }
+
+        (template, ids)
+    }
+
+    /// Replace object-ID path segments with `{id}`, `{id2}`, ...
+    pub(crate) fn template_path(path: &str) -> (String, Vec<ObjectIdRef>) {
+        let mut ids = Vec::new();
+        let segments: Vec<String> = path
+            .split('/')
+            .map(|segment| match Self::classify(segment) {
+                Some(kind) => {
+                    let parameter = if ids.is_empty() {
+                        "{id}".to_string()
+                    } else {
+                        format!("{{id{}}}", ids.len() + 1)
+                    };
+                    ids.push(ObjectIdRef {
+                        parameter: parameter.clone(),
+                        location: ObjectIdLocation::Path,
+                        kind,
+                        value: segment.to_string(),
+                    });
+                    parameter
+                }
+                None => segment.to_string(),
+            })
+            .collect();
+        (segments.join("/"), ids)
+    }
+
+    fn collect_body_ids(
+        value: &serde_json::Value,
+        prefix: &str,
+        depth: usize,
+        ids: &mut Vec<ObjectIdRef>,
+    ) {
+        if depth > MAX_BODY_DEPTH {
+            return;
+        }
+        let serde_json::Value::Object(map) = value else {
+            return;
+        };
+        for (key, value) in map {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            let id_value = match value {
+                serde_json::Value::String(s) if Self::is_id_field(key) => Some(s.clone()),
+                serde_json::Value::Number(n) if Self::is_id_field(key) => Some(n.to_string()),
+                serde_json::Value::Object(_) => {
+                    Self::collect_body_ids(value, &path, depth + 1, ids);
+                    None
+                }
+                _ => None,
+            };
+            if let Some(id_value) = id_value
+                && let Some(kind) = Self::classify(&id_value)
+            {
+                ids.push(ObjectIdRef {
+                    parameter: path,
+                    location: ObjectIdLocation::Body,
+                    kind,
+                    value: id_value,
+                });
+            }
+        }
+    }
+
+    /// `id`, `order_id`, `orderId`, `accountID`
+    fn is_id_field(key: &str) -> bool {
+        key.eq_ignore_ascii_case("id")
+            || key.to_lowercase().ends_with("_id")
+            || key.ends_with("Id")
+            || key.ends_with("ID")
+    }
+
+    /// Classify a path segment or parameter value as an object identifier
+    pub(crate) fn classify(value: &str) -> Option<ObjectIdKind> {
+        if value.is_empty() || value.len() > 64 {
+            return None;
+        }
+        if value.len() <= 19 && value.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(ObjectIdKind::Numeric);
+        }
+        if Self::is_uuid(value) {
+            return Some(ObjectIdKind::Uuid);
+        }
+        let has_digit = value.bytes().any(|b| b.is_ascii_digit());
+        let has_alpha = value.bytes().any(|b| b.is_ascii_alphabetic());
+        if value.len() >= 16 && value.bytes().all(|b| b.is_ascii_hexdigit()) && has_digit {
+            return Some(ObjectIdKind::Hex);
+        }
+
+        // Slugs mix letters and digits ("inv-2024-0042"); versions and file names do not count
+        let is_version = value.len() > 1
+            && value.starts_with(['v', 'V'])
+            && value[1..].bytes().all(|b| b.is_ascii_digit());
+        let slug_chars = value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if has_digit
+            && has_alpha
+            && slug_chars
+            && !is_version
+            && (value.contains(['-', '_']) || value.len() >= 8)
+            && value.len() >= 4
+        {
+            return Some(ObjectIdKind::Slug);
+        }
+        None
+    }
+
+    fn is_uuid(value: &str) -> bool {
+        let parts: Vec<&str> = value.split('-').collect();
+        parts.len() == 5
+            && parts
+                .iter()
+                .zip([8, 4, 4, 4, 12])
+                .all(|(p, len)| p.len() == len && p.bytes().all(|b| b.is_ascii_hexdigit()))
+    }
+
+    /// Numeric IDs, time-based (v1) UUIDs and MongoDB ObjectIds (timestamp prefix)
+    fn is_guessable(kind: ObjectIdKind, value: &str) -> bool {
+        match kind {
+            ObjectIdKind::Numeric => true,
+            ObjectIdKind::Uuid => value.as_bytes().get(14) == Some(&b'1'),
+            ObjectIdKind::Hex => value.len() == 24,
+            ObjectIdKind::Slug => false,
+        }
+    }
+
+    fn is_sequential(ids: &BTreeSet<&String>) -> bool {
+        let mut numbers: Vec<u64> = ids.iter().filter_map(|id| id.parse().ok()).collect();
+        if numbers.len() < 2 {
+            return false;
+        }
+        numbers.sort_unstable();
+        numbers
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] <= SEQUENTIAL_MAX_GAP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(IdorAnalyzer::classify("1042"), Some(ObjectIdKind::Numeric));
+        assert_eq!(
+            IdorAnalyzer::classify("3f2b8c1e-7d4a-4c2b-9e1f-0a6b5c4d3e2f"),
+            Some(ObjectIdKind::Uuid)
+        );
+        assert_eq!(
+            IdorAnalyzer::classify("65a4f1c2e8b9d70012345678"),
+            Some(ObjectIdKind::Hex)
+        );
+        assert_eq!(
+            IdorAnalyzer::classify("inv-2024-0042"),
+            Some(ObjectIdKind::Slug)
+        );
+        assert_eq!(IdorAnalyzer::classify("orders"), None);
+        assert_eq!(IdorAnalyzer::classify("v2"), None);
+        assert_eq!(IdorAnalyzer::classify("oauth2"), None);
+        assert_eq!(IdorAnalyzer::classify("app.3f2a1b.js"), None);
+    }
+
+    #[test]
+    fn test_template_path() {
+        let (template, ids) = IdorAnalyzer::template_path("/api/v1/users/42/orders/1001");
+        assert_eq!(template, "/api/v1/users/{id}/orders/{id2}");
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[1].value, "1001");
+    }
+
+    #[test]
+    fn test_is_sequential() {
+        let (a, b, c) = ("1001".to_string(), "1002".to_string(), "1005".to_string());
+        assert!(IdorAnalyzer::is_sequential(&BTreeSet::from([&a, &b, &c])));
+        let far = "9000".to_string();
+        assert!(!IdorAnalyzer::is_sequential(&BTreeSet::from([&a, &far])));
+    }
+
+    #[test]
+    fn test_is_guessable_uuid_version() {
+        assert!(IdorAnalyzer::is_guessable(
+            ObjectIdKind::Uuid,
+            "6ba7b810-9dad-11d1-80b4-00c04fd430c8"
+        ));
+        assert!(!IdorAnalyzer::is_guessable(
+            ObjectIdKind::Uuid,
+            "3f2b8c1e-7d4a-4c2b-9e1f-0a6b5c4d3e2f"
+        ));
+    }
+}
@@ -2,11 +2,101 @@ use crate::Result;
 use harrier_core::har::Har;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Secrets that show up in tutorials, framework defaults and sample configs
+const COMMON_JWT_SECRETS: &[&str] = &[
+    "",
+    "secret",
+    "Secret",
+    "SECRET",
+    "secretkey",
+    "secret_key",
+    "secret-key",
+    "SECRET_KEY",
+    "secret123",
+    "s3cr3t",
+    "mysecret",
+    "my_secret",
+    "my-secret",
+    "mysecretkey",
+    "supersecret",
+    "super-secret",
+    "super_secret_key",
+    "topsecret",
+    "your-256-bit-secret",
+    "your-384-bit-secret",
+    "your-512-bit-secret",
+    "your_jwt_secret",
+    "your-secret-key",
+    "your_secret_key",
+    "jwt",
+    "jwtsecret",
+    "jwt_secret",
+    "jwt-secret",
+    "JWT_SECRET",
+    "jwtkey",
+    "jwt_key",
+    "jwt-key",
+    "token",
+    "tokensecret",
+    "auth",
+    "authsecret",
+    "app_secret",
+    "appsecret",
+    "api_secret",
+    "apisecret",
+    "hmac_secret",
+    "hmacsecret",
+    "signing_key",
+    "key",
+    "private",
+    "privatekey",
+    "password",
+    "Password",
+    "password1",
+    "password123",
+    "passw0rd",
+    "admin",
+    "administrator",
+    "root",
+    "test",
+    "testing",
+    "test123",
+    "dev",
+    "development",
+    "prod",
+    "production",
+    "staging",
+    "default",
+    "example",
+    "demo",
+    "sample",
+    "changeme",
+    "changeit",
+    "change_me",
+    "letmein",
+    "welcome",
+    "qwerty",
+    "hello",
+    "helloworld",
+    "abc123",
+    "123456",
+    "12345678",
+    "1234567890",
+    "keyboard cat",
+    "shhhhh",
+    "shhhhhhared-secret",
+    "gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr9C",
+    "null",
+    "undefined",
+];
 
 /// Represents a parsed JWT token with its components
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtToken {
     pub raw_token: String,
+    pub format: TokenFormat,
     pub header: JwtHeader,
     pub claims: JwtClaims,
     pub signature_present: bool,
@@ -14,6 +104,34 @@ pub struct JwtToken {
     pub last_seen: String,
     pub usage_count: usize,
     pub entry_indices: Vec<usize>,
+    /// Tokens carried inside this one (`cty: JWT` payloads and token-valued claims)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nested: Vec<JwtToken>,
+    /// Why a `cty: JWT` payload could not be read as a token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nested_error: Option<String>,
+    /// Full encoded token, kept for signature checks but never serialized
+    #[serde(skip)]
+    pub encoded: String,
+}
+
+/// Compact serialization of the token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenFormat {
+    /// Signed token (header.payload.signature)
+    Jws,
+    /// Encrypted token (header.key.iv.ciphertext.tag) - claims are not readable
+    Jwe,
+}
+
+impl TokenFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TokenFormat::Jws => "JWS",
+            TokenFormat::Jwe => "JWE",
+        }
+    }
 }
 
 /// JWT header information
@@ -22,27 +140,59 @@ pub struct JwtHeader {
     pub alg: Option<String>,
     pub typ: Option<String>,
     pub kid: Option<String>,
+    pub cty: Option<String>, // Content type ("JWT" for nested tokens)
+    pub enc: Option<String>, // Content encryption (JWE only)
     #[serde(flatten)]
     pub other: HashMap<String, serde_json::Value>,
 }
 
 /// JWT claims (payload)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct JwtClaims {
     // Standard claims
     pub iss: Option<String>, // Issuer
     pub sub: Option<String>, // Subject
-    pub aud: Option<String>, // Audience
+    #[serde(default, deserialize_with = "deserialize_string_list")]
+    pub aud: Vec<String>, // Audience (a single string or an array)
     pub exp: Option<i64>,    // Expiration time
     pub nbf: Option<i64>,    // Not before
     pub iat: Option<i64>,    // Issued at
     pub jti: Option<String>, // JWT ID
 
+    // OAuth 2.0 / OIDC claims
+    pub azp: Option<String>, // Authorized party
+    #[serde(default)]
+    pub scope: Vec<String>, // From `scope` (space separated) and `scp`
+    #[serde(default)]
+    pub roles: Vec<String>, // From `roles`, `role`, `realm_access.roles` and `cognito:groups`
+    pub cnf: Option<ConfirmationClaim>, // Proof-of-possession binding
+
     // Additional claims
     #[serde(flatten)]
     pub other: HashMap<String, serde_json::Value>,
 }
 
+/// Proof-of-possession confirmation (`cnf`) claim
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfirmationClaim {
+    /// DPoP public key thumbprint
+    pub jkt: Option<String>,
+    /// mTLS client certificate thumbprint
+    #[serde(rename = "x5t#S256")]
+    pub x5t_s256: Option<String>,
+    pub kid: Option<String>,
+    pub jwk: Option<serde_json::Value>,
+}
+
+/// Accept claims that may be a single string or an array of strings
+fn deserialize_string_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(JwtAnalyzer::string_list(value.as_ref(), false))
+}
+
 /// Security issues found in JWT tokens
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtSecurityIssue {
@@ -50,6 +200,9 @@ pub struct JwtSecurityIssue {
     pub issue_type: JwtIssueType,
     pub message: String,
     pub token_preview: String,
+    /// First entry the token was seen at, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_index: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -62,6 +215,30 @@ pub enum JwtIssueType {
     TokenInUrl,
     LongLivedToken,
     MissingSignature,
+    InvalidSignature,
+    UnsignedTokenAccepted,
+    AlgorithmConfusion,
+    WeakSecret,
+    MalformedNestedToken,
+}
+
+impl JwtIssueType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            JwtIssueType::WeakAlgorithm => "Weak algorithm",
+            JwtIssueType::NoAlgorithm => "No algorithm",
+            JwtIssueType::Expired => "Expired token",
+            JwtIssueType::MissingExpiration => "Missing expiration",
+            JwtIssueType::TokenInUrl => "Token in URL",
+            JwtIssueType::LongLivedToken => "Long-lived token",
+            JwtIssueType::MissingSignature => "Missing signature",
+            JwtIssueType::InvalidSignature => "Invalid signature",
+            JwtIssueType::UnsignedTokenAccepted => "Unsigned token accepted",
+            JwtIssueType::AlgorithmConfusion => "Algorithm confusion",
+            JwtIssueType::WeakSecret => "Weak secret",
+            JwtIssueType::MalformedNestedToken => "Malformed nested token",
+        }
+    }
 }
 
 pub struct JwtAnalyzer;
@@ -77,7 +254,7 @@ impl JwtAnalyzer {
             for header in &entry.request.headers {
                 if header.name.to_lowercase() == "authorization"
                     && let Some(token) = Self::extract_bearer_token(&header.value)
-                    && Self::is_jwt(token)
+                    && (Self::is_jwt(token) || Self::is_jwe(token))
                 {
                     Self::process_jwt_token(
                         token,
@@ -114,23 +291,133 @@ impl JwtAnalyzer {
                     issue_type: JwtIssueType::TokenInUrl,
                     message: format!("JWT token found in URL at entry {}", idx),
                     token_preview: Self::truncate_url(&entry.request.url),
+                    entry_index: Some(idx),
                 });
             }
         }
 
         // Convert map to vector
-        let tokens: Vec<JwtToken> = tokens_map
-            .into_iter()
-            .map(|(_, (mut token, indices))| {
+        let mut tokens: Vec<JwtToken> = tokens_map
+            .into_values()
+            .map(|(mut token, indices)| {
                 token.entry_indices = indices;
                 token.usage_count = token.entry_indices.len();
                 token
             })
             .collect();
+        tokens.sort_by_key(|t| t.entry_indices.first().copied());
 
         Ok((tokens, security_issues))
     }
 
+    /// Try HS256/384/512 tokens against common secrets and an optional wordlist
+    pub fn find_weak_secrets(tokens: &[JwtToken], wordlist: &[String]) -> Vec<JwtSecurityIssue> {
+        let candidates: Vec<&str> = COMMON_JWT_SECRETS
+            .iter()
+            .copied()
+            .chain(wordlist.iter().map(String::as_str))
+            .collect();
+        let mut issues = Vec::new();
+
+        for token in tokens {
+            let Some(alg) = token.header.alg.as_deref() else {
+                continue;
+            };
+            if !matches!(alg, "HS256" | "HS384" | "HS512") {
+                continue;
+            }
+            let Some((signing_input, encoded_signature)) = token.encoded.rsplit_once('.') else {
+                continue;
+            };
+            let Ok(signature) = Self::base64url_decode_bytes(encoded_signature) else {
+                continue;
+            };
+            if signature.is_empty() {
+                continue;
+            }
+
+            if let Some(secret) =
+                Self::crack_secret(alg, signing_input.as_bytes(), &signature, &candidates)
+            {
+                let origin = if COMMON_JWT_SECRETS.contains(&secret) {
+                    "common secrets list"
+                } else {
+                    "wordlist"
+                };
+                issues.push(JwtSecurityIssue {
+                    severity: super::security::Severity::Critical,
+                    issue_type: JwtIssueType::WeakSecret,
+                    message: format!(
+                        "{} token is signed with a guessable secret '{}' (found in {}) - anyone can forge tokens",
+                        alg,
+                        Self::mask_secret(secret),
+                        origin
+                    ),
+                    token_preview: token.raw_token.clone(),
+                    entry_index: token.entry_indices.first().copied(),
+                });
+            }
+        }
+
+        issues
+    }
+
+    /// Split the candidates across worker threads and stop as soon as one matches
+    fn crack_secret<'a>(
+        alg: &str,
+        signing_input: &[u8],
+        signature: &[u8],
+        candidates: &[&'a str],
+    ) -> Option<&'a str> {
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(candidates.len().max(1));
+        let chunk_size = candidates.len().div_ceil(workers).max(1);
+        let found = AtomicBool::new(false);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let found = &found;
+                    scope.spawn(move || {
+                        for &candidate in chunk {
+                            if found.load(Ordering::Relaxed) {
+                                return None;
+                            }
+                            if super::jwt_verification::JwtVerifier::verify_hmac(
+                                alg,
+                                candidate.as_bytes(),
+                                signing_input,
+                                signature,
+                            ) {
+                                found.store(true, Ordering::Relaxed);
+                                return Some(candidate);
+                            }
+                        }
+                        None
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().ok().flatten())
+                .next()
+        })
+    }
+
+    /// Keep only enough of a recovered secret to recognise it in a report
+    fn mask_secret(secret: &str) -> String {
+        let chars: Vec<char> = secret.chars().collect();
+        match chars.len() {
+            0 => "<empty>".to_string(),
+            1..=4 => "*".repeat(chars.len()),
+            len => format!("{}{}{}", chars[0], "*".repeat(len - 2), chars[len - 1]),
+        }
+    }
+
     fn process_jwt_token(
         token: &str,
         entry_idx: usize,
@@ -139,12 +426,8 @@ impl JwtAnalyzer {
         tokens_map: &mut HashMap<String, (JwtToken, Vec<usize>)>,
         security_issues: &mut Vec<JwtSecurityIssue>,
     ) {
-        // Use first 20 chars as key to group same tokens
-        let token_key = if token.len() > 20 {
-            token[..20].to_string()
-        } else {
-            token.to_string()
-        };
+        // Group by the full token - a shared prefix is usually just the header
+        let token_key = token.to_string();
 
         if let Some((existing_token, indices)) = tokens_map.get_mut(&token_key) {
             // Update existing token
@@ -155,31 +438,64 @@ impl JwtAnalyzer {
             if let Ok(parsed_token) = Self::parse_jwt(token, timestamp) {
                 // Analyze security
                 let issues = Self::analyze_token_security(&parsed_token, in_url);
-                security_issues.extend(issues);
+                security_issues.extend(issues.into_iter().map(|issue| JwtSecurityIssue {
+                    entry_index: Some(entry_idx),
+                    ..issue
+                }));
 
                 tokens_map.insert(token_key, (parsed_token, vec![entry_idx]));
             }
         }
     }
 
-    fn parse_jwt(token: &str, timestamp: &str) -> Result<JwtToken> {
+    pub(crate) fn parse_jwt(token: &str, timestamp: &str) -> Result<JwtToken> {
         let parts: Vec<&str> = token.split('.').collect();
 
-        if parts.len() != 3 {
-            return Err(crate::Error::Parse("Invalid JWT format".to_string()));
+        match parts.len() {
+            3 => {}
+            5 => return Self::parse_jwe(&parts, token, timestamp),
+            _ => return Err(crate::Error::Parse("Invalid JWT format".to_string())),
         }
 
         // Parse header
         let header = Self::parse_jwt_header(parts[0])?;
 
-        // Parse claims
-        let claims = Self::parse_jwt_claims(parts[1])?;
+        // Nested JWT: the payload is itself a compact token rather than a claims set
+        let mut nested = Vec::new();
+        let mut nested_error = None;
+        let claims = if header
+            .cty
+            .as_deref()
+            .is_some_and(|cty| cty.eq_ignore_ascii_case("JWT"))
+        {
+            // A broken inner token is reported on the outer one rather than dropping both
+            match Self::base64_decode(parts[1])
+                .and_then(|inner| Self::parse_jwt(inner.trim(), timestamp))
+            {
+                Ok(inner) => nested.push(inner),
+                Err(e) => nested_error = Some(e.to_string()),
+            }
+            JwtClaims::default()
+        } else {
+            Self::parse_jwt_claims(parts[1])?
+        };
+
+        // Tokens embedded as claim values (e.g. an ID token or subject token)
+        for value in claims.other.values() {
+            if let Some(inner) = value.as_str()
+                && (Self::is_jwt(inner) || Self::is_jwe(inner))
+                && let Ok(inner_token) = Self::parse_jwt(inner, timestamp)
+            {
+                nested.push(inner_token);
+            }
+        }
 
         // Check signature
         let signature_present = !parts[2].is_empty();
 
         Ok(JwtToken {
             raw_token: Self::truncate_token(token),
+            format: TokenFormat::Jws,
             header,
             claims,
             signature_present,
@@ -187,6 +503,35 @@ impl JwtAnalyzer {
             last_seen: timestamp.to_string(),
             usage_count: 1,
             entry_indices: vec![],
+            nested,
+            nested_error,
+            encoded: token.to_string(),
+        })
+    }
+
+    /// Parse an encrypted token - only the protected header can be read
+    fn parse_jwe(parts: &[&str], token: &str, timestamp: &str) -> Result<JwtToken> {
+        let header = Self::parse_jwt_header(parts[0])?;
+        if header.enc.is_none() {
+            return Err(crate::Error::Parse(
+                "JWE header missing 'enc' parameter".to_string(),
+            ));
+        }
+
+        Ok(JwtToken {
+            raw_token: Self::truncate_token(token),
+            format: TokenFormat::Jwe,
+            header,
+            claims: JwtClaims::default(),
+            // The authentication tag protects the ciphertext
+            signature_present: !parts[4].is_empty(),
+            first_seen: timestamp.to_string(),
+            last_seen: timestamp.to_string(),
+            usage_count: 1,
+            entry_indices: vec![],
+            nested: vec![],
+            nested_error: None,
+            encoded: token.to_string(),
         })
     }
 
@@ -203,9 +548,11 @@ impl JwtAnalyzer {
             alg: obj.get("alg").and_then(|v| v.as_str()).map(String::from),
             typ: obj.get("typ").and_then(|v| v.as_str()).map(String::from),
             kid: obj.get("kid").and_then(|v| v.as_str()).map(String::from),
+            cty: obj.get("cty").and_then(|v| v.as_str()).map(String::from),
+            enc: obj.get("enc").and_then(|v| v.as_str()).map(String::from),
             other: obj
                 .iter()
-                .filter(|(k, _)| !["alg", "typ", "kid"].contains(&k.as_str()))
+                .filter(|(k, _)| !["alg", "typ", "kid", "cty", "enc"].contains(&k.as_str()))
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
         })
@@ -220,29 +567,85 @@ impl JwtAnalyzer {
             .as_object()
             .ok_or_else(|| crate::Error::Parse("JWT claims is not an object".to_string()))?;
 
+        let mut scope = Self::string_list(obj.get("scope"), true);
+        for scp in Self::string_list(obj.get("scp"), true) {
+            if !scope.contains(&scp) {
+                scope.push(scp);
+            }
+        }
+
+        let mut roles = Vec::new();
+        for value in [
+            obj.get("roles"),
+            obj.get("role"),
+            obj.get("realm_access").and_then(|v| v.get("roles")),
+            obj.get("cognito:groups"),
+        ] {
+            for role in Self::string_list(value, false) {
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        }
+
         Ok(JwtClaims {
             iss: obj.get("iss").and_then(|v| v.as_str()).map(String::from),
             sub: obj.get("sub").and_then(|v| v.as_str()).map(String::from),
-            aud: obj.get("aud").and_then(|v| v.as_str()).map(String::from),
+            aud: Self::string_list(obj.get("aud"), false),
             exp: obj.get("exp").and_then(|v| v.as_i64()),
             nbf: obj.get("nbf").and_then(|v| v.as_i64()),
             iat: obj.get("iat").and_then(|v| v.as_i64()),
             jti: obj.get("jti").and_then(|v| v.as_str()).map(String::from),
+            azp: obj.get("azp").and_then(|v| v.as_str()).map(String::from),
+            scope,
+            roles,
+            cnf: obj
+                .get("cnf")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
             other: obj
                 .iter()
                 .filter(|(k, _)| {
-                    !["iss", "sub", "aud", "exp", "nbf", "iat", "jti"].contains(&k.as_str())
+                    ![
+                        "iss", "sub", "aud", "exp", "nbf", "iat", "jti", "azp", "scope", "scp",
+                        "roles", "role", "cnf",
+                    ]
+                    .contains(&k.as_str())
                 })
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
         })
     }
 
+    /// Read a claim that may be a string or an array of strings
+    fn string_list(value: Option<&serde_json::Value>, split_whitespace: bool) -> Vec<String> {
+        match value {
+            Some(serde_json::Value::String(s)) if split_whitespace => {
+                s.split_whitespace().map(String::from).collect()
+            }
+            Some(serde_json::Value::String(s)) => vec![s.clone()],
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
     fn base64_decode(encoded: &str) -> Result<String> {
+        let decoded_bytes = Self::base64url_decode_bytes(encoded)?;
+
+        String::from_utf8(decoded_bytes)
+            .map_err(|e| crate::Error::Parse(format!("UTF-8 decode failed: {}", e)))
+    }
+
+    /// Decode a base64url segment (signatures, JWK parameters) to raw bytes
+    pub(crate) fn base64url_decode_bytes(encoded: &str) -> Result<Vec<u8>> {
         use base64::engine::Engine;
         use base64::engine::general_purpose::STANDARD;
 
         // JWT uses base64url encoding (no padding)
+        let encoded = encoded.trim_end_matches('=');
         let padded = match encoded.len() % 4 {
             2 => format!("{}==", encoded),
             3 => format!("{}=", encoded),
@@ -252,12 +655,9 @@ impl JwtAnalyzer {
         // Replace URL-safe characters
         let standard = padded.replace('-', "+").replace('_', "/");
 
-        let decoded_bytes = STANDARD
+        STANDARD
             .decode(&standard)
-            .map_err(|e| crate::Error::Parse(format!("Base64 decode failed: {}", e)))?;
-
-        String::from_utf8(decoded_bytes)
-            .map_err(|e| crate::Error::Parse(format!("UTF-8 decode failed: {}", e)))
+            .map_err(|e| crate::Error::Parse(format!("Base64 decode failed: {}", e)))
     }
 
     fn analyze_token_security(token: &JwtToken, in_url: bool) -> Vec<JwtSecurityIssue> {
@@ -272,6 +672,7 @@ impl JwtAnalyzer {
                     issue_type: JwtIssueType::NoAlgorithm,
                     message: "JWT using 'none' algorithm (no signature verification)".to_string(),
                     token_preview: token.raw_token.clone(),
+                    entry_index: token.entry_indices.first().copied(),
                 });
             } else if alg_lower == "hs256" || alg_lower == "hs384" || alg_lower == "hs512" {
                 issues.push(JwtSecurityIssue {
@@ -279,6 +680,7 @@ impl JwtAnalyzer {
                     issue_type: JwtIssueType::WeakAlgorithm,
                     message: format!("JWT using symmetric algorithm {} (shared secret)", alg),
                     token_preview: token.raw_token.clone(),
+                    entry_index: token.entry_indices.first().copied(),
                 });
             }
         }
@@ -295,14 +697,30 @@ impl JwtAnalyzer {
                     issue_type: JwtIssueType::LongLivedToken,
                     message: format!("JWT has long lifetime: {} hours", lifetime / 3600),
                     token_preview: token.raw_token.clone(),
+                    entry_index: token.entry_indices.first().copied(),
                 });
             }
-        } else if token.claims.exp.is_none() {
+        } else if token.claims.exp.is_none() && token.format == TokenFormat::Jws {
+            // JWE claims are encrypted, so a missing `exp` cannot be assessed
             issues.push(JwtSecurityIssue {
                 severity: super::security::Severity::Warning,
                 issue_type: JwtIssueType::MissingExpiration,
                 message: "JWT missing expiration claim (exp)".to_string(),
                 token_preview: token.raw_token.clone(),
+                entry_index: token.entry_indices.first().copied(),
+            });
+        }
+
+        if let Some(ref error) = token.nested_error {
+            issues.push(JwtSecurityIssue {
+                severity: super::security::Severity::Warning,
+                issue_type: JwtIssueType::MalformedNestedToken,
+                message: format!(
+                    "JWT declares a nested token (cty: JWT) that could not be parsed: {}",
+                    error
+                ),
+                token_preview: token.raw_token.clone(),
+                entry_index: token.entry_indices.first().copied(),
             });
         }
 
@@ -313,6 +731,7 @@ impl JwtAnalyzer {
                 issue_type: JwtIssueType::MissingSignature,
                 message: "JWT missing signature component".to_string(),
                 token_preview: token.raw_token.clone(),
+                entry_index: token.entry_indices.first().copied(),
             });
         }
 
@@ -323,6 +742,7 @@ impl JwtAnalyzer {
                 issue_type: JwtIssueType::TokenInUrl,
                 message: "JWT token transmitted in URL (visible in logs)".to_string(),
                 token_preview: token.raw_token.clone(),
+                entry_index: token.entry_indices.first().copied(),
             });
         }
 
@@ -354,7 +774,7 @@ impl JwtAnalyzer {
                     && let Some(quote_end) = after_colon[quote_start + 1..].find('"')
                 {
                     let token = &after_colon[quote_start + 1..quote_start + 1 + quote_end];
-                    if Self::is_jwt(token) {
+                    if Self::is_jwt(token) || Self::is_jwe(token) {
                         Self::process_jwt_token(
                             token,
                             entry_idx,
@@ -369,16 +789,28 @@ impl JwtAnalyzer {
         }
     }
 
-    fn extract_bearer_token(header_value: &str) -> Option<&str> {
+    pub(crate) fn extract_bearer_token(header_value: &str) -> Option<&str> {
         header_value.strip_prefix("Bearer ").map(|s| s.trim())
     }
 
-    fn is_jwt(token: &str) -> bool {
+    pub(crate) fn is_jwt(token: &str) -> bool {
         let parts: Vec<&str> = token.split('.').collect();
         parts.len() == 3 && parts[0..2].iter().all(|p| !p.is_empty())
     }
 
-    fn truncate_token(token: &str) -> String {
+    /// Compact JWE: protected header, encrypted key (empty for `dir`), IV, ciphertext, tag
+    pub(crate) fn is_jwe(token: &str) -> bool {
+        let parts: Vec<&str> = token.split('.').collect();
+        parts.len() == 5
+            && parts[0].starts_with("eyJ")
+            && !parts[3].is_empty()
+            && parts.iter().all(|p| {
+                p.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+    }
+
+    pub(crate) fn truncate_token(token: &str) -> String {
         if token.len() > 50 {
             format!("{}...{}", &token[..25], &token[token.len() - 25..])
         } else {
@@ -431,4 +863,133 @@ mod tests {
         assert_eq!(header.alg, Some("HS256".to_string()));
         assert_eq!(header.typ, Some("JWT".to_string()));
     }
+
+    fn hs256_token(secret: &str) -> JwtToken {
+        use base64::engine::Engine;
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"HS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1"}"#);
+        let signing_input = format!("{}.{}", header, claims);
+        let key = aws_lc_rs::hmac::Key::new(aws_lc_rs::hmac::HMAC_SHA256, secret.as_bytes());
+        let signature = aws_lc_rs::hmac::sign(&key, signing_input.as_bytes());
+        let encoded = format!(
+            "{}.{}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.as_ref())
+        );
+        JwtAnalyzer::parse_jwt(&encoded, "2024-01-01T00:00:00Z").unwrap()
+    }
+
+    #[test]
+    fn test_find_weak_secrets_common_list() {
+        let token = hs256_token("your-256-bit-secret");
+        let issues = JwtAnalyzer::find_weak_secrets(&[token], &[]);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].issue_type, JwtIssueType::WeakSecret);
+        assert_eq!(
+            issues[0].severity,
+            super::super::security::Severity::Critical
+        );
+        assert!(issues[0].message.contains("y*****************t"));
+        assert!(!issues[0].message.contains("your-256-bit-secret"));
+    }
+
+    #[test]
+    fn test_find_weak_secrets_wordlist() {
+        let token = hs256_token("corp-signing-2019");
+        assert!(JwtAnalyzer::find_weak_secrets(std::slice::from_ref(&token), &[]).is_empty());
+
+        let wordlist = vec!["nope".to_string(), "corp-signing-2019".to_string()];
+        let issues = JwtAnalyzer::find_weak_secrets(&[token], &wordlist);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("wordlist"));
+    }
+
+    fn encode_unsigned(header: &str, claims: &str) -> String {
+        use base64::engine::Engine;
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+        format!(
+            "{}.{}.c2ln",
+            URL_SAFE_NO_PAD.encode(header),
+            URL_SAFE_NO_PAD.encode(claims)
+        )
+    }
+
+    #[test]
+    fn test_parse_jwt_claim_model() {
+        let token = encode_unsigned(
+            r#"{"alg":"RS256"}"#,
+            r#"{"aud":["api","userinfo"],"azp":"web","scope":"openid profile","scp":["profile","orders.read"],"roles":"admin","realm_access":{"roles":["user"]},"cnf":{"jkt":"abc"}}"#,
+        );
+        let parsed = JwtAnalyzer::parse_jwt(&token, "2024-01-01T00:00:00Z").unwrap();
+
+        assert_eq!(parsed.format, TokenFormat::Jws);
+        assert_eq!(parsed.claims.aud, vec!["api", "userinfo"]);
+        assert_eq!(parsed.claims.azp.as_deref(), Some("web"));
+        assert_eq!(
+            parsed.claims.scope,
+            vec!["openid", "profile", "orders.read"]
+        );
+        assert_eq!(parsed.claims.roles, vec!["admin", "user"]);
+        assert_eq!(parsed.claims.cnf.unwrap().jkt.as_deref(), Some("abc"));
+        assert!(!parsed.claims.other.contains_key("aud"));
+    }
+
+    #[test]
+    fn test_claims_deserialize_string_or_array_audience() {
+        let single: JwtClaims = serde_json::from_str(r#"{"aud":"api"}"#).unwrap();
+        assert_eq!(single.aud, vec!["api"]);
+
+        let many: JwtClaims = serde_json::from_str(r#"{"aud":["api","web"]}"#).unwrap();
+        assert_eq!(many.aud, vec!["api", "web"]);
+    }
+
+    #[test]
+    fn test_parse_jwe() {
+        let token = "eyJhbGciOiJkaXIiLCJlbmMiOiJBMjU2R0NNIn0..MDEyMzQ1Njc4OWFi.Y2lwaGVy.dGFn";
+        assert!(JwtAnalyzer::is_jwe(token));
+        assert!(!JwtAnalyzer::is_jwt(token));
+
+        let parsed = JwtAnalyzer::parse_jwt(token, "2024-01-01T00:00:00Z").unwrap();
+        assert_eq!(parsed.format, TokenFormat::Jwe);
+        assert_eq!(parsed.header.alg.as_deref(), Some("dir"));
+        assert_eq!(parsed.header.enc.as_deref(), Some("A256GCM"));
+        assert!(JwtAnalyzer::analyze_token_security(&parsed, false).is_empty());
+    }
+
+    #[test]
+    fn test_parse_nested_jwt() {
+        let inner = encode_unsigned(r#"{"alg":"HS256"}"#, r#"{"sub":"user-1","exp":1}"#);
+        let outer = encode_unsigned(r#"{"alg":"RS256","cty":"JWT"}"#, &inner);
+        let parsed = JwtAnalyzer::parse_jwt(&outer, "2024-01-01T00:00:00Z").unwrap();
+
+        assert_eq!(parsed.nested.len(), 1);
+        assert_eq!(parsed.nested[0].claims.sub.as_deref(), Some("user-1"));
+        assert!(parsed.nested_error.is_none());
+    }
+
+    #[test]
+    fn test_parse_malformed_nested_jwt_keeps_outer() {
+        let outer = encode_unsigned(r#"{"alg":"RS256","cty":"JWT"}"#, "not-a-token");
+        let parsed = JwtAnalyzer::parse_jwt(&outer, "2024-01-01T00:00:00Z").unwrap();
+
+        assert!(parsed.nested.is_empty());
+        assert!(parsed.nested_error.is_some());
+        let issues = JwtAnalyzer::analyze_token_security(&parsed, false);
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.issue_type == JwtIssueType::MalformedNestedToken)
+        );
+    }
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(JwtAnalyzer::mask_secret(""), "<empty>");
+        assert_eq!(JwtAnalyzer::mask_secret("key"), "***");
+        assert_eq!(JwtAnalyzer::mask_secret("secret"), "s****t");
+    }
 }
@@ -0,0 +1,758 @@
+use crate::Result;
+use aws_lc_rs::{hmac, signature};
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::jwt::{JwtAnalyzer, JwtIssueType, JwtSecurityIssue, JwtToken, TokenFormat};
+use super::security::Severity;
+use super::truncate;
+
+/// A key that JWT signatures can be checked against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationKey {
+    pub kid: Option<String>,
+    pub alg: Option<String>,
+    pub key_type: String,
+    pub source: String,
+    #[serde(skip)]
+    material: KeyMaterial,
+    #[serde(skip)]
+    pem: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+enum KeyMaterial {
+    #[default]
+    Unusable,
+    Secret(Vec<u8>),
+    RsaComponents {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+    /// DER public key from PEM (SubjectPublicKeyInfo or PKCS#1)
+    PublicDer(Vec<u8>),
+    /// Uncompressed EC point (0x04 || x || y)
+    EcPoint(Vec<u8>),
+    Ed25519(Vec<u8>),
+}
+
+/// Outcome of checking one token's signature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    Verified,
+    InvalidSignature,
+    Unsigned,
+    NoMatchingKey,
+    UnsupportedAlgorithm,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            VerificationStatus::Verified => "Verified",
+            VerificationStatus::InvalidSignature => "Invalid signature",
+            VerificationStatus::Unsigned => "Unsigned",
+            VerificationStatus::NoMatchingKey => "No matching key",
+            VerificationStatus::UnsupportedAlgorithm => "Unsupported algorithm",
+        }
+    }
+}
+
+/// Verification result for a single captured token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtVerification {
+    pub token_preview: String,
+    pub alg: Option<String>,
+    pub kid: Option<String>,
+    pub status: VerificationStatus,
+    pub key_source: Option<String>,
+    /// Entries where the token was sent and the server answered 2xx
+    pub accepted_entries: Vec<usize>,
+}
+
+/// Signature verification results for all tokens in a HAR
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JwtVerificationReport {
+    pub results: Vec<JwtVerification>,
+    /// Public keys found in captured responses (JWKS documents, PEM blocks)
+    pub exposed_keys: Vec<VerificationKey>,
+    /// `jwks_uri` values from discovery documents whose response was not captured
+    pub uncaptured_jwks_uris: Vec<String>,
+}
+
+/// Checks JWT signatures against JWKS documents, PEM public keys and shared secrets
+#[derive(Debug, Clone, Default)]
+pub struct JwtVerifier {
+    keys: Vec<VerificationKey>,
+}
+
+impl JwtVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys configured on this verifier (not including keys discovered in a HAR)
+    pub fn keys(&self) -> &[VerificationKey] {
+        &self.keys
+    }
+
+    /// Add every usable key from a JWKS document, returning how many were added
+    pub fn add_jwks(&mut self, jwks_json: &str, source: &str) -> Result<usize> {
+        let keys = Self::parse_jwks(jwks_json, source)?;
+        let count = keys.len();
+        self.keys.extend(keys);
+        Ok(count)
+    }
+
+    /// Add public keys from PEM text, returning how many were added
+    pub fn add_pem(&mut self, pem: &str, source: &str) -> Result<usize> {
+        let keys = Self::parse_pem(pem, source);
+        if keys.is_empty() {
+            return Err(crate::Error::Parse(format!(
+                "No PUBLIC KEY or RSA PUBLIC KEY block found in {}",
+                source
+            )));
+        }
+        let count = keys.len();
+        self.keys.extend(keys);
+        Ok(count)
+    }
+
+    /// Add an HMAC shared secret
+    pub fn add_secret(&mut self, secret: &[u8], source: &str) {
+        self.keys.push(VerificationKey {
+            kid: None,
+            alg: None,
+            key_type: "oct".to_string(),
+            source: source.to_string(),
+            material: KeyMaterial::Secret(secret.to_vec()),
+            pem: None,
+        });
+    }
+
+    /// Verify every token and report signature-related security issues
+    pub fn verify(
+        &self,
+        har: &Har,
+        tokens: &[JwtToken],
+    ) -> (JwtVerificationReport, Vec<JwtSecurityIssue>) {
+        let mut issues = Vec::new();
+        let (exposed_keys, uncaptured_jwks_uris) = Self::discover_keys(har);
+
+        let mut results = Vec::new();
+        for token in tokens {
+            // Encrypted tokens carry no signature that can be checked without the private key
+            if token.encoded.is_empty() || token.format == TokenFormat::Jwe {
+                continue;
+            }
+
+            let accepted_entries = Self::accepted_entries(har, token);
+            let (status, key_source) = self.verify_token(token, &exposed_keys);
+
+            match status {
+                VerificationStatus::Unsigned if !accepted_entries.is_empty() => {
+                    issues.push(JwtSecurityIssue {
+                        severity: Severity::Critical,
+                        issue_type: JwtIssueType::UnsignedTokenAccepted,
+                        message: format!(
+                            "Server accepted unsigned JWT (alg: {}) at entry {}",
+                            token.header.alg.as_deref().unwrap_or("missing"),
+                            accepted_entries[0]
+                        ),
+                        token_preview: token.raw_token.clone(),
+                        entry_index: Some(accepted_entries[0]),
+                    });
+                }
+                VerificationStatus::InvalidSignature => {
+                    let (severity, message) = if let Some(idx) = accepted_entries.first() {
+                        (
+                            Severity::Critical,
+                            format!(
+                                "JWT failed signature verification but was accepted by the server at entry {}",
+                                idx
+                            ),
+                        )
+                    } else {
+                        (
+                            Severity::Warning,
+                            "JWT failed signature verification against the supplied keys"
+                                .to_string(),
+                        )
+                    };
+                    issues.push(JwtSecurityIssue {
+                        severity,
+                        issue_type: JwtIssueType::InvalidSignature,
+                        message,
+                        token_preview: token.raw_token.clone(),
+                        entry_index: token.entry_indices.first().copied(),
+                    });
+                }
+                _ => {}
+            }
+
+            results.push(JwtVerification {
+                token_preview: token.raw_token.clone(),
+                alg: token.header.alg.clone(),
+                kid: token.header.kid.clone(),
+                status,
+                key_source,
+                accepted_entries,
+            });
+        }
+
+        issues.extend(Self::check_algorithm_confusion(
+            tokens,
+            &exposed_keys,
+            &mut results,
+        ));
+
+        (
+            JwtVerificationReport {
+                results,
+                exposed_keys,
+                uncaptured_jwks_uris,
+            },
+            issues,
+        )
+    }
+
+    fn verify_token(
+        &self,
+        token: &JwtToken,
+        exposed_keys: &[VerificationKey],
+    ) -> (VerificationStatus, Option<String>) {
+        let alg = token.header.alg.as_deref().unwrap_or("");
+        if alg.eq_ignore_ascii_case("none") || !token.signature_present {
+            return (VerificationStatus::Unsigned, None);
+        }
+        if Self::algorithm_family(alg).is_none() {
+            return (VerificationStatus::UnsupportedAlgorithm, None);
+        }
+
+        let Some((signing_input, signature_b64)) = token.encoded.rsplit_once('.') else {
+            return (VerificationStatus::InvalidSignature, None);
+        };
+        let Ok(signature) = JwtAnalyzer::base64url_decode_bytes(signature_b64) else {
+            return (VerificationStatus::InvalidSignature, None);
+        };
+
+        let candidates: Vec<&VerificationKey> = self
+            .keys
+            .iter()
+            .chain(exposed_keys.iter())
+            .filter(|key| key.can_verify(alg))
+            .filter(|key| match (&token.header.kid, &key.kid) {
+                (Some(token_kid), Some(key_kid)) => token_kid == key_kid,
+                _ => true,
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return (VerificationStatus::NoMatchingKey, None);
+        }
+
+        for key in candidates {
+            if key.verify(alg, signing_input.as_bytes(), &signature) {
+                return (VerificationStatus::Verified, Some(key.source.clone()));
+            }
+        }
+
+        (VerificationStatus::InvalidSignature, None)
+    }
+
+    /// Flag RS/HS confusion: HS tokens alongside a public key anyone can download
+    fn check_algorithm_confusion(
+        tokens: &[JwtToken],
+        exposed_keys: &[VerificationKey],
+        results: &mut [JwtVerification],
+    ) -> Vec<JwtSecurityIssue> {
+        let mut issues = Vec::new();
+        let asymmetric: Vec<&VerificationKey> = exposed_keys
+            .iter()
+            .filter(|k| !matches!(k.material, KeyMaterial::Secret(_)))
+            .collect();
+        if asymmetric.is_empty() {
+            return issues;
+        }
+
+        let hs_tokens: Vec<&JwtToken> = tokens
+            .iter()
+            .filter(|t| Self::algorithm_family(t.header.alg.as_deref().unwrap_or("")) == Some("HS"))
+            .collect();
+
+        let mut confirmed = false;
+        for token in &hs_tokens {
+            let alg = token.header.alg.as_deref().unwrap_or("");
+            let Some((signing_input, signature_b64)) = token.encoded.rsplit_once('.') else {
+                continue;
+            };
+            let Ok(signature) = JwtAnalyzer::base64url_decode_bytes(signature_b64) else {
+                continue;
+            };
+
+            // The classic exploit signs with the PEM text of the public key as the HMAC secret
+            for key in &asymmetric {
+                let Some(ref pem) = key.pem else { continue };
+                if Self::verify_hmac(alg, pem.as_bytes(), signing_input.as_bytes(), &signature) {
+                    confirmed = true;
+                    issues.push(JwtSecurityIssue {
+                        severity: Severity::Critical,
+                        issue_type: JwtIssueType::AlgorithmConfusion,
+                        message: format!(
+                            "{} token is signed with the exposed public key from {} as HMAC secret (RS/HS algorithm confusion)",
+                            alg, key.source
+                        ),
+                        token_preview: token.raw_token.clone(),
+                        entry_index: token.entry_indices.first().copied(),
+                    });
+                    if let Some(result) = results
+                        .iter_mut()
+                        .find(|r| r.token_preview == token.raw_token)
+                    {
+                        result.status = VerificationStatus::Verified;
+                        result.key_source = Some(format!("{} (as HMAC secret)", key.source));
+                    }
+                    break;
+                }
+            }
+        }
+
+        if !confirmed && let Some(token) = hs_tokens.first() {
+            issues.push(JwtSecurityIssue {
+                severity: Severity::Warning,
+                issue_type: JwtIssueType::AlgorithmConfusion,
+                message: format!(
+                    "HMAC-signed tokens in use while {} public key(s) are exposed - test whether the server accepts HS tokens signed with the public key",
+                    asymmetric.len()
+                ),
+                token_preview: token.raw_token.clone(),
+                entry_index: token.entry_indices.first().copied(),
+            });
+        }
+
+        issues
+    }
+
+    /// Find JWKS documents, OIDC discovery documents and PEM public keys in response bodies
+    fn discover_keys(har: &Har) -> (Vec<VerificationKey>, Vec<String>) {
+        let mut keys = Vec::new();
+        let mut jwks_uris = Vec::new();
+        let mut captured_urls = HashSet::new();
+
+        for (idx, entry) in har.log.entries.iter().enumerate() {
+            let Some(body) = Self::response_text(entry) else {
+                continue;
+            };
+            let source = format!("entry {} ({})", idx, truncate(&entry.request.url, 57));
+
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&body) {
+                if json.get("keys").is_some_and(|k| k.is_array()) {
+                    if let Ok(found) = Self::parse_jwks(&body, &source) {
+                        captured_urls.insert(Self::strip_query(&entry.request.url));
+                        keys.extend(found);
+                    }
+                } else if let Some(uri) = json.get("jwks_uri").and_then(|u| u.as_str()) {
+                    jwks_uris.push(uri.to_string());
+                }
+            }
+
+            if body.contains("-----BEGIN") {
+                // PEM blocks embedded in JSON strings keep their escaped newlines
+                let unescaped = body.replace("\\n", "\n");
+                keys.extend(Self::parse_pem(&unescaped, &source));
+            }
+        }
+
+        let mut uncaptured: Vec<String> = jwks_uris
+            .into_iter()
+            .filter(|uri| !captured_urls.contains(&Self::strip_query(uri)))
+            .collect();
+        uncaptured.sort();
+        uncaptured.dedup();
+
+        (keys, uncaptured)
+    }
+
+    fn parse_jwks(jwks_json: &str, source: &str) -> Result<Vec<VerificationKey>> {
+        let json: serde_json::Value = serde_json::from_str(jwks_json)
+            .map_err(|e| crate::Error::Parse(format!("Failed to parse JWKS: {}", e)))?;
+
+        // Accept both a key set and a bare JWK
+        let jwks: Vec<&serde_json::Value> = match json.get("keys").and_then(|k| k.as_array()) {
+            Some(keys) => keys.iter().collect(),
+            None if json.get("kty").is_some() => vec![&json],
+            None => {
+                return Err(crate::Error::Parse(
+                    "JWKS has no \"keys\" array".to_string(),
+                ));
+            }
+        };
+
+        Ok(jwks
+            .into_iter()
+            .filter_map(|jwk| Self::parse_jwk(jwk, source))
+            .collect())
+    }
+
+    fn parse_jwk(jwk: &serde_json::Value, source: &str) -> Option<VerificationKey> {
+        let field = |name: &str| jwk.get(name).and_then(|v| v.as_str());
+        let decode =
+            |name: &str| field(name).and_then(|v| JwtAnalyzer::base64url_decode_bytes(v).ok());
+
+        let kty = field("kty")?;
+        let material = match kty {
+            "RSA" => KeyMaterial::RsaComponents {
+                n: Self::strip_leading_zeros(decode("n")?),
+                e: Self::strip_leading_zeros(decode("e")?),
+            },
+            "EC" => {
+                let mut point = vec![0x04];
+                point.extend(decode("x")?);
+                point.extend(decode("y")?);
+                KeyMaterial::EcPoint(point)
+            }
+            "OKP" if field("crv") == Some("Ed25519") => KeyMaterial::Ed25519(decode("x")?),
+            "oct" => KeyMaterial::Secret(decode("k")?),
+            _ => return None,
+        };
+
+        Some(VerificationKey {
+            kid: field("kid").map(String::from),
+            alg: field("alg").map(String::from),
+            key_type: kty.to_string(),
+            source: source.to_string(),
+            material,
+            pem: None,
+        })
+    }
+
+    fn parse_pem(pem: &str, source: &str) -> Vec<VerificationKey> {
+        use base64::engine::Engine;
+        use base64::engine::general_purpose::STANDARD;
+
+        let mut keys = Vec::new();
+        let mut rest = pem;
+
+        while let Some(start) = rest.find("-----BEGIN ") {
+            let after = &rest[start + 11..];
+            let Some(label_end) = after.find("-----") else {
+                break;
+            };
+            let label = &after[..label_end];
+            let end_marker = format!("-----END {}-----", label);
+            let Some(end) = after.find(&end_marker) else {
+                break;
+            };
+
+            let block = &rest[start..start + 11 + end + end_marker.len()];
+            let body: String = after[label_end + 5..end]
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            rest = &after[end + end_marker.len()..];
+
+            let key_type = match label {
+                "PUBLIC KEY" => "SPKI",
+                "RSA PUBLIC KEY" => "RSA",
+                other => {
+                    tracing::debug!("Skipping unsupported PEM block '{}' in {}", other, source);
+                    continue;
+                }
+            };
+
+            if let Ok(der) = STANDARD.decode(&body) {
+                keys.push(VerificationKey {
+                    kid: None,
+                    alg: None,
+                    key_type: key_type.to_string(),
+                    source: source.to_string(),
+                    material: KeyMaterial::PublicDer(der),
+                    pem: Some(block.to_string()),
+                });
+            }
+        }
+
+        keys
+    }
+
+    fn accepted_entries(har: &Har, token: &JwtToken) -> Vec<usize> {
+        token
+            .entry_indices
+            .iter()
+            .copied()
+            .filter(|idx| {
+                har.log.entries.get(*idx).is_some_and(|entry| {
+                    (200..300).contains(&entry.response.status)
+                        && entry.request.headers.iter().any(|h| {
+                            h.name.eq_ignore_ascii_case("authorization")
+                                && JwtAnalyzer::extract_bearer_token(&h.value)
+                                    == Some(token.encoded.as_str())
+                        })
+                })
+            })
+            .collect()
+    }
+
+    fn response_text(entry: &Entry) -> Option<String> {
+        let text = entry.response.content.text.as_ref()?;
+        if entry.response.content.encoding.as_deref() == Some("base64") {
+            use base64::engine::Engine;
+            use base64::engine::general_purpose::STANDARD;
+            return STANDARD
+                .decode(text)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok());
+        }
+        Some(text.clone())
+    }
+
+    /// "HS", "RS", "PS", "ES" or "Ed" for supported JWS algorithms
+    fn algorithm_family(alg: &str) -> Option<&'static str> {
+        match alg {
+            "HS256" | "HS384" | "HS512" => Some("HS"),
+            "RS256" | "RS384" | "RS512" => Some("RS"),
+            "PS256" | "PS384" | "PS512" => Some("PS"),
+            "ES256" | "ES384" | "ES512" => Some("ES"),
+            "EdDSA" => Some("Ed"),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn verify_hmac(alg: &str, secret: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let algorithm = match alg {
+            "HS256" => hmac::HMAC_SHA256,
+            "HS384" => hmac::HMAC_SHA384,
+            "HS512" => hmac::HMAC_SHA512,
+            _ => return false,
+        };
+        let key = hmac::Key::new(algorithm, secret);
+        hmac::verify(&key, message, signature).is_ok()
+    }
+
+    fn strip_leading_zeros(mut bytes: Vec<u8>) -> Vec<u8> {
+        let zeros = bytes.iter().take_while(|b| **b == 0).count();
+        bytes.drain(..zeros.min(bytes.len().saturating_sub(1)));
+        bytes
+    }
+
+    fn strip_query(url: &str) -> String {
+        url.split(['?', '#']).next().unwrap_or(url).to_string()
+    }
+}
+
+impl VerificationKey {
+    fn can_verify(&self, alg: &str) -> bool {
+        if let Some(ref key_alg) = self.alg
+            && key_alg != alg
+        {
+            return false;
+        }
+
+        matches!(
+            (&self.material, JwtVerifier::algorithm_family(alg)),
+            (KeyMaterial::Secret(_), Some("HS"))
+                | (KeyMaterial::RsaComponents { .. }, Some("RS" | "PS"))
+                | (KeyMaterial::PublicDer(_), Some("RS" | "PS" | "ES" | "Ed"))
+                | (KeyMaterial::EcPoint(_), Some("ES"))
+                | (KeyMaterial::Ed25519(_), Some("Ed"))
+        )
+    }
+
+    fn verify(&self, alg: &str, message: &[u8], sig: &[u8]) -> bool {
+        match &self.material {
+            KeyMaterial::Secret(secret) => JwtVerifier::verify_hmac(alg, secret, message, sig),
+            KeyMaterial::RsaComponents { n, e } => {
+                Self::rsa_parameters(alg).is_some_and(|params| {
+                    signature::RsaPublicKeyComponents { n, e }
+                        .verify(params, message, sig)
+                        .is_ok()
+                })
+            }
+            KeyMaterial::PublicDer(der) | KeyMaterial::EcPoint(der) | KeyMaterial::Ed25519(der) => {
+                let algorithm: &'static dyn signature::VerificationAlgorithm = match alg {
+                    "ES256" => &signature::ECDSA_P256_SHA256_FIXED,
+                    "ES384" => &signature::ECDSA_P384_SHA384_FIXED,
+                    "ES512" => &signature::ECDSA_P521_SHA512_FIXED,
+                    "EdDSA" => &signature::ED25519,
+                    _ => match Self::rsa_parameters(alg) {
+                        Some(params) => params,
+                        None => return false,
+                    },
+                };
+                signature::UnparsedPublicKey::new(algorithm, der)
+                    .verify(message, sig)
+                    .is_ok()
+            }
+            KeyMaterial::Unusable => false,
+        }
+    }
+
+    fn rsa_parameters(alg: &str) -> Option<&'static signature::RsaParameters> {
+        match alg {
+            "RS256" => Some(&signature::RSA_PKCS1_2048_8192_SHA256),
+            "RS384" => Some(&signature::RSA_PKCS1_2048_8192_SHA384),
+            "RS512" => Some(&signature::RSA_PKCS1_2048_8192_SHA512),
+            "PS256" => Some(&signature::RSA_PSS_2048_8192_SHA256),
+            "PS384" => Some(&signature::RSA_PSS_2048_8192_SHA384),
+            "PS512" => Some(&signature::RSA_PSS_2048_8192_SHA512),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_lc_rs::rand::SystemRandom;
+    use aws_lc_rs::signature::{EcdsaKeyPair, KeyPair};
+    use base64::engine::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use harrier_core::har::*;
+
+    fn b64(bytes: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn hs256_token(secret: &[u8]) -> String {
+        let input = format!(
+            "{}.{}",
+            b64(br#"{"alg":"HS256","typ":"JWT"}"#),
+            b64(br#"{"sub":"alice","exp":4102444800}"#)
+        );
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        let tag = hmac::sign(&key, input.as_bytes());
+        format!("{}.{}", input, b64(tag.as_ref()))
+    }
+
+    fn bearer_entry(token: &str, status: i64) -> Entry {
+        let json = format!(
+            r#"{{"startedDateTime":"2024-01-01T00:00:00Z","time":1,
+            "request":{{"method":"GET","url":"https://api.example.com/me","httpVersion":"HTTP/1.1",
+              "cookies":[],"headers":[{{"name":"Authorization","value":"Bearer {}"}}],
+              "queryString":[],"headersSize":-1,"bodySize":0}},
+            "response":{{"status":{},"statusText":"","httpVersion":"HTTP/1.1","cookies":[],"headers":[],
+              "content":{{"size":0,"mimeType":"application/json"}},"redirectURL":"","headersSize":-1,"bodySize":0}},
+            "cache":{{}},"timings":{{"send":0,"wait":0,"receive":0}}}}"#,
+            token, status
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn har_with(entries: Vec<Entry>) -> Har {
+        Har {
+            log: Log {
+                version: "1.2".to_string(),
+                creator: Creator {
+                    name: "test".to_string(),
+                    version: "1.0".to_string(),
+                    comment: None,
+                },
+                browser: None,
+                pages: None,
+                entries,
+                comment: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_verify_hs256_with_secret() {
+        let token = hs256_token(b"correct-secret");
+        let har = har_with(vec![bearer_entry(&token, 200)]);
+        let (tokens, _) = JwtAnalyzer::analyze(&har).unwrap();
+
+        let mut verifier = JwtVerifier::new();
+        verifier.add_secret(b"correct-secret", "secret.txt");
+        let (report, issues) = verifier.verify(&har, &tokens);
+        assert_eq!(report.results[0].status, VerificationStatus::Verified);
+        assert!(issues.is_empty());
+
+        let mut wrong = JwtVerifier::new();
+        wrong.add_secret(b"wrong-secret", "secret.txt");
+        let (report, issues) = wrong.verify(&har, &tokens);
+        assert_eq!(
+            report.results[0].status,
+            VerificationStatus::InvalidSignature
+        );
+        assert_eq!(issues[0].issue_type, JwtIssueType::InvalidSignature);
+        assert_eq!(issues[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_verify_es256_with_jwks() {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref())
+                .unwrap();
+        let point = key_pair.public_key().as_ref();
+
+        let input = format!(
+            "{}.{}",
+            b64(br#"{"alg":"ES256","kid":"k1"}"#),
+            b64(br#"{"sub":"bob"}"#)
+        );
+        let sig = key_pair.sign(&rng, input.as_bytes()).unwrap();
+        let token = format!("{}.{}", input, b64(sig.as_ref()));
+
+        let jwks = format!(
+            r#"{{"keys":[{{"kty":"EC","crv":"P-256","kid":"k1","x":"{}","y":"{}"}}]}}"#,
+            b64(&point[1..33]),
+            b64(&point[33..])
+        );
+
+        let har = har_with(vec![bearer_entry(&token, 200)]);
+        let (tokens, _) = JwtAnalyzer::analyze(&har).unwrap();
+        let mut verifier = JwtVerifier::new();
+        assert_eq!(verifier.add_jwks(&jwks, "jwks.json").unwrap(), 1);
+
+        let (report, _) = verifier.verify(&har, &tokens);
+        assert_eq!(report.results[0].status, VerificationStatus::Verified);
+        assert_eq!(report.results[0].key_source.as_deref(), Some("jwks.json"));
+    }
+
+    #[test]
+    fn test_unsigned_token_accepted() {
+        let token = format!(
+            "{}.{}.",
+            b64(br#"{"alg":"none"}"#),
+            b64(br#"{"sub":"admin"}"#)
+        );
+        let har = har_with(vec![bearer_entry(&token, 200)]);
+        let (tokens, _) = JwtAnalyzer::analyze(&har).unwrap();
+
+        let (report, issues) = JwtVerifier::new().verify(&har, &tokens);
+        assert_eq!(report.results[0].status, VerificationStatus::Unsigned);
+        assert_eq!(report.results[0].accepted_entries, vec![0]);
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.issue_type == JwtIssueType::UnsignedTokenAccepted)
+        );
+    }
+
+    #[test]
+    fn test_parse_jwks_rejects_non_keyset() {
+        assert!(JwtVerifier::parse_jwks(r#"{"foo":1}"#, "x").is_err());
+        assert!(JwtVerifier::parse_jwks("not json", "x").is_err());
+    }
+
+    #[test]
+    fn test_parse_pem_blocks() {
+        let pem = "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+                   -----BEGIN PUBLIC KEY-----\nAAEC\n-----END PUBLIC KEY-----\n";
+        let keys = JwtVerifier::parse_pem(pem, "key.pem");
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key_type, "SPKI");
+        assert!(
+            keys[0]
+                .pem
+                .as_ref()
+                .unwrap()
+                .starts_with("-----BEGIN PUBLIC KEY-----")
+        );
+    }
+}
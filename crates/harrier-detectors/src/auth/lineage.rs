@@ -0,0 +1,295 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::flows::{AuthFlow, AuthFlowType, FlowRole};
+use super::jwt::{JwtAnalyzer, JwtToken};
+use super::truncate;
+
+/// Lifecycle of a token: the response that issued it, the requests that used it,
+/// and the token that replaced it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenLineage {
+    pub token_preview: String,
+    pub kind: IssuedTokenKind,
+    pub is_jwt: bool,
+    pub issuer: Option<String>, // `iss` claim for JWTs
+    pub subject: Option<String>,
+    pub issued_by: Option<TokenIssuance>,
+    pub lifetime_seconds: Option<i64>, // exp - iat, or `expires_in` from the token response
+    pub first_used: Option<String>,
+    pub last_used: Option<String>,
+    pub used_by: Vec<TokenUsage>,
+    pub replaces: Option<String>,
+    pub replaced_by: Option<String>,
+}
+
+/// Kind of token as returned by the token endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssuedTokenKind {
+    Access,
+    Id,
+    Refresh,
+}
+
+impl IssuedTokenKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            IssuedTokenKind::Access => "Access token",
+            IssuedTokenKind::Id => "ID token",
+            IssuedTokenKind::Refresh => "Refresh token",
+        }
+    }
+}
+
+/// Captured response that issued a token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenIssuance {
+    pub entry_index: usize,
+    pub timestamp: String,
+    pub url: String,
+    pub flow_type: AuthFlowType,
+    pub grant_type: Option<String>,
+    pub refreshed_with: Option<String>, // Preview of the refresh token presented
+}
+
+/// Endpoint that was called with a token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub endpoint: String, // "METHOD /path"
+    pub request_count: usize,
+    pub entry_indices: Vec<usize>,
+}
+
+pub struct TokenLineageTracker;
+
+impl TokenLineageTracker {
+    /// Link tokens to the token-exchange steps that issued them and the requests that used them
+    pub fn track(har: &Har, tokens: &[JwtToken], flows: &[AuthFlow]) -> Vec<TokenLineage> {
+        let entries = &har.log.entries;
+
+        // Entries that returned tokens, in capture order
+        let mut issuing: Vec<(usize, AuthFlowType)> = flows
+            .iter()
+            .flat_map(|flow| {
+                flow.steps
+                    .iter()
+                    .filter(|step| {
+                        matches!(step.role, FlowRole::TokenExchange | FlowRole::TokenResponse)
+                    })
+                    .map(|step| (step.entry_index, flow.flow_type.clone()))
+            })
+            .collect();
+        issuing.sort_by_key(|(idx, _)| *idx);
+        issuing.dedup_by_key(|(idx, _)| *idx);
+
+        let mut lineages: Vec<TokenLineage> = Vec::new();
+        let mut by_value: HashMap<String, usize> = HashMap::new();
+
+        for (idx, flow_type) in issuing {
+            let Some(entry) = entries.get(idx) else {
+                continue;
+            };
+            let Some(response) = Self::token_response(entry) else {
+                continue;
+            };
+
+            let expires_in = response.get("expires_in").and_then(|v| {
+                v.as_i64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            });
+            let refreshed_with = Self::request_param(entry, &["refresh_token", "refreshToken"]);
+            let issuance = TokenIssuance {
+                entry_index: idx,
+                timestamp: entry.started_date_time.clone(),
+                url: truncate(&entry.request.url, 77),
+                flow_type,
+                grant_type: Self::request_param(entry, &["grant_type"]),
+                refreshed_with: refreshed_with.as_deref().map(JwtAnalyzer::truncate_token),
+            };
+
+            // Tokens this refresh grant supersedes
+            let predecessors = refreshed_with
+                .as_ref()
+                .and_then(|refresh| by_value.get(refresh).copied())
+                .and_then(|pos| lineages[pos].issued_by.as_ref().map(|i| i.entry_index))
+                .map(|issued_at| {
+                    lineages
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, l)| {
+                            l.issued_by.as_ref().map(|i| i.entry_index) == Some(issued_at)
+                        })
+                        .map(|(pos, l)| (l.kind, pos))
+                        .collect::<HashMap<_, _>>()
+                })
+                .unwrap_or_default();
+
+            let fields: [(IssuedTokenKind, &[&str]); 3] = [
+                (
+                    IssuedTokenKind::Access,
+                    &["access_token", "accessToken", "token"],
+                ),
+                (IssuedTokenKind::Id, &["id_token", "idToken"]),
+                (IssuedTokenKind::Refresh, &["refresh_token", "refreshToken"]),
+            ];
+            for (kind, fields) in fields {
+                let Some(value) = fields
+                    .iter()
+                    .find_map(|field| response.get(*field).and_then(|v| v.as_str()))
+                else {
+                    continue;
+                };
+                if value.is_empty() || by_value.contains_key(value) {
+                    continue;
+                }
+
+                let mut lineage = Self::new_lineage(
+                    value,
+                    kind,
+                    &entry.started_date_time,
+                    Some(issuance.clone()),
+                );
+                if lineage.lifetime_seconds.is_none() && kind != IssuedTokenKind::Refresh {
+                    lineage.lifetime_seconds = expires_in;
+                }
+
+                if let Some(&previous) = predecessors.get(&kind) {
+                    lineage.replaces = Some(lineages[previous].token_preview.clone());
+                    lineages[previous].replaced_by = Some(lineage.token_preview.clone());
+                }
+
+                by_value.insert(value.to_string(), lineages.len());
+                lineages.push(lineage);
+            }
+        }
+
+        // Bearer JWTs whose issuing response was not captured
+        for token in tokens {
+            if token.encoded.is_empty() || by_value.contains_key(&token.encoded) {
+                continue;
+            }
+            let used_as_bearer = token.entry_indices.iter().any(|idx| {
+                entries
+                    .get(*idx)
+                    .and_then(Self::bearer_token)
+                    .is_some_and(|bearer| bearer == token.encoded)
+            });
+            if used_as_bearer {
+                by_value.insert(token.encoded.clone(), lineages.len());
+                lineages.push(Self::new_lineage(
+                    &token.encoded,
+                    IssuedTokenKind::Access,
+                    &token.first_seen,
+                    None,
+                ));
+            }
+        }
+
+        // Requests that presented each token
+        for (idx, entry) in entries.iter().enumerate() {
+            let presented = match Self::bearer_token(entry) {
+                Some(bearer) => Some(bearer.to_string()),
+                None => Self::request_param(entry, &["refresh_token", "refreshToken"]),
+            };
+            if let Some(value) = presented
+                && let Some(&pos) = by_value.get(&value)
+            {
+                Self::record_usage(&mut lineages[pos], idx, entry);
+            }
+        }
+
+        lineages
+    }
+
+    fn new_lineage(
+        value: &str,
+        kind: IssuedTokenKind,
+        timestamp: &str,
+        issued_by: Option<TokenIssuance>,
+    ) -> TokenLineage {
+        let parsed = if JwtAnalyzer::is_jwt(value) || JwtAnalyzer::is_jwe(value) {
+            JwtAnalyzer::parse_jwt(value, timestamp).ok()
+        } else {
+            None
+        };
+        let claims = parsed.as_ref().map(|t| &t.claims);
+
+        TokenLineage {
+            token_preview: JwtAnalyzer::truncate_token(value),
+            kind,
+            is_jwt: parsed.is_some(),
+            issuer: claims.and_then(|c| c.iss.clone()),
+            subject: claims.and_then(|c| c.sub.clone()),
+            issued_by,
+            lifetime_seconds: claims
+                .and_then(|c| c.exp.zip(c.iat))
+                .map(|(exp, iat)| exp - iat),
+            first_used: None,
+            last_used: None,
+            used_by: Vec::new(),
+            replaces: None,
+            replaced_by: None,
+        }
+    }
+
+    fn record_usage(lineage: &mut TokenLineage, idx: usize, entry: &Entry) {
+        if lineage.first_used.is_none() {
+            lineage.first_used = Some(entry.started_date_time.clone());
+        }
+        lineage.last_used = Some(entry.started_date_time.clone());
+
+        let endpoint = Self::endpoint(entry);
+        if let Some(usage) = lineage.used_by.iter_mut().find(|u| u.endpoint == endpoint) {
+            usage.request_count += 1;
+            usage.entry_indices.push(idx);
+        } else {
+            lineage.used_by.push(TokenUsage {
+                endpoint,
+                request_count: 1,
+                entry_indices: vec![idx],
+            });
+        }
+    }
+
+    fn token_response(entry: &Entry) -> Option<serde_json::Map<String, serde_json::Value>> {
+        let text = entry.response.content.text.as_ref()?;
+        match serde_json::from_str(text).ok()? {
+            serde_json::Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+
+    fn bearer_token(entry: &Entry) -> Option<&str> {
+        entry
+            .request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("authorization"))
+            .and_then(|h| JwtAnalyzer::extract_bearer_token(&h.value))
+    }
+
+    /// Read a parameter from a form-encoded or JSON request body
+    fn request_param(entry: &Entry, names: &[&str]) -> Option<String> {
+        let text = entry.request.post_data.as_ref()?.text.as_ref()?;
+
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_str(text) {
+            return names
+                .iter()
+                .find_map(|name| map.get(*name).and_then(|v| v.as_str()))
+                .map(String::from);
+        }
+
+        url::form_urlencoded::parse(text.as_bytes())
+            .find(|(key, _)| names.contains(&key.as_ref()))
+            .map(|(_, value)| value.into_owned())
+    }
+
+    fn endpoint(entry: &Entry) -> String {
+        let path = url::Url::parse(&entry.request.url)
+            .map(|u| u.path().to_string())
+            .unwrap_or_else(|_| entry.request.url.clone());
+        format!("{} {}", entry.request.method, path)
+    }
+}
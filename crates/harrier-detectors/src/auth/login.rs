@@ -0,0 +1,538 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::access_matrix::AccessMatrixBuilder;
+use super::events::{AuthEvent, AuthEventType};
+use super::flows::{AuthFlow, AuthFlowType, FlowRole};
+use super::methods::AuthDetector;
+
+/// Keys that hold the session token in a JSON login response, in order of preference
+const TOKEN_KEYS: &[&str] = &["access_token", "accessToken", "token", "jwt", "id_token"];
+
+/// Response headers that carry a token issued at login
+const TOKEN_HEADERS: &[&str] = &["authorization", "x-auth-token", "x-access-token", "x-token"];
+
+/// Deepest nesting searched for a token in a JSON login response
+const MAX_TOKEN_DEPTH: usize = 3;
+
+/// Login request replayed from a form or JSON login flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRequest {
+    pub format: LoginFormat,
+    pub entry_index: usize,
+    pub host: String, // Origin, e.g. https://app.example.com
+    pub login_path: String,
+    pub login_page_path: Option<String>,
+    pub username_field: Option<String>,
+    pub password_field: Option<String>,
+    pub csrf_field: Option<String>,
+    pub other_params: Vec<LoginParam>, // Submitted alongside the credentials
+    pub token_extraction: Option<TokenExtraction>,
+    pub test_path: Option<TestPath>,
+    pub logout_paths: Vec<String>,
+}
+
+/// How credentials are submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFormat {
+    Form,
+    Json,
+}
+
+impl LoginFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LoginFormat::Form => "form",
+            LoginFormat::Json => "json",
+        }
+    }
+}
+
+/// Field names whose values are one-time codes or secrets rather than constants
+const SECRET_FIELD_HINTS: &[&str] = &[
+    "pass",
+    "pwd",
+    "otp",
+    "mfa",
+    "2fa",
+    "totp",
+    "code",
+    "pin",
+    "token",
+    "secret",
+    "key",
+    "captcha",
+    "nonce",
+    "signature",
+];
+
+/// Longest captured value treated as a constant
+const MAX_STATIC_VALUE_LEN: usize = 32;
+
+/// Another field submitted with the login
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginParam {
+    pub name: String,
+    pub value: String,
+}
+
+impl LoginParam {
+    /// Whether the captured value looks like a constant (a flag, locale or button label)
+    /// that can be written into a committed config
+    ///
+    /// Secret-looking names and anything resembling a code or token are not.
+    pub fn is_static(&self) -> bool {
+        let name = self.name.to_lowercase();
+        if SECRET_FIELD_HINTS.iter().any(|hint| name.contains(hint)) {
+            return false;
+        }
+        let value = self.value.as_str();
+        let digits = value.chars().filter(char::is_ascii_digit).count();
+        value.len() <= MAX_STATIC_VALUE_LEN
+            && (digits == 0 || (digits == value.len() && digits <= 3))
+            && value
+                .chars()
+                .all(|c| c.is_alphabetic() || c.is_ascii_digit() || " -_.".contains(c))
+    }
+
+    /// Environment variable a non-static value is read from, e.g. `LOGIN_OTP_CODE`
+    pub fn env_var(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("LOGIN_{}", name.trim_matches('_'))
+    }
+
+    /// Value for HawkScan's `otherParams`: the captured value when static, otherwise an
+    /// `${ENV}` placeholder
+    pub fn config_value(&self) -> String {
+        if self.is_static() {
+            self.value.clone()
+        } else {
+            format!("${{{}}}", self.env_var())
+        }
+    }
+}
+
+/// Where the login response puts the token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenExtraction {
+    BodyPath { path: String }, // Dotted JSON path, e.g. data.accessToken
+    Header { name: String },
+}
+
+/// Request that shows whether the scanner is still logged in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestPath {
+    pub method: String,
+    pub path: String,
+    pub success: String,      // Regex matching the logged-in response
+    pub fail: Option<String>, // Regex matching the logged-out response
+    pub entry_index: usize,
+}
+
+pub struct LoginRequestExtractor;
+
+impl LoginRequestExtractor {
+    /// Describe the credential submission of each form and JSON login flow
+    pub fn extract(har: &Har, flows: &[AuthFlow], events: &[AuthEvent]) -> Vec<LoginRequest> {
+        let mut requests: Vec<LoginRequest> = Vec::new();
+
+        for flow in flows {
+            let format = match flow.flow_type {
+                AuthFlowType::FormBased => LoginFormat::Form,
+                AuthFlowType::JsonApi => LoginFormat::Json,
+                _ => continue,
+            };
+            let Some(submission) = flow
+                .steps
+                .iter()
+                .find(|s| s.role == FlowRole::CredentialsSubmission)
+            else {
+                continue;
+            };
+            if requests
+                .iter()
+                .any(|r| r.entry_index == submission.entry_index)
+            {
+                continue;
+            }
+            let Some(entry) = har.log.entries.get(submission.entry_index) else {
+                continue;
+            };
+            let Some(host) = Self::origin_of(&entry.request.url) else {
+                continue;
+            };
+
+            let mut username_field = None;
+            let mut password_field = None;
+            let mut csrf_field = None;
+            let mut other_params = Vec::new();
+            for (name, value) in Self::submitted_fields(entry, format) {
+                if password_field.is_none() && Self::is_password_field(&name) {
+                    password_field = Some(name);
+                } else if username_field.is_none() && Self::is_username_field(&name) {
+                    username_field = Some(name);
+                } else if csrf_field.is_none() && Self::is_csrf_field(&name) {
+                    csrf_field = Some(name);
+                } else {
+                    other_params.push(LoginParam { name, value });
+                }
+            }
+
+            let login_page_path = flow
+                .steps
+                .iter()
+                .find(|s| s.role == FlowRole::LoginPage)
+                .and_then(|s| har.log.entries.get(s.entry_index))
+                .map(|e| Self::path_of(&e.request.url));
+            let logout_paths = Self::logout_paths(events, &host);
+            let test_path = Self::test_path(
+                har,
+                flow,
+                submission.entry_index,
+                &host,
+                events,
+                login_page_path.as_deref(),
+                format,
+            );
+
+            requests.push(LoginRequest {
+                format,
+                entry_index: submission.entry_index,
+                host,
+                login_path: Self::path_of(&entry.request.url),
+                login_page_path,
+                username_field,
+                password_field,
+                csrf_field,
+                other_params,
+                token_extraction: Self::token_extraction(entry),
+                test_path,
+                logout_paths,
+            });
+        }
+
+        requests
+    }
+
+    /// Name/value pairs from the login body
+    fn submitted_fields(entry: &Entry, format: LoginFormat) -> Vec<(String, String)> {
+        let Some(ref post_data) = entry.request.post_data else {
+            return Vec::new();
+        };
+
+        if format == LoginFormat::Form
+            && let Some(ref params) = post_data.params
+            && !params.is_empty()
+        {
+            return params
+                .iter()
+                .map(|p| (p.name.clone(), p.value.clone().unwrap_or_default()))
+                .collect();
+        }
+
+        let Some(ref text) = post_data.text else {
+            return Vec::new();
+        };
+        match format {
+            LoginFormat::Form => url::form_urlencoded::parse(text.as_bytes())
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect(),
+            LoginFormat::Json => match serde_json::from_str::<serde_json::Value>(text) {
+                Ok(serde_json::Value::Object(object)) => object
+                    .into_iter()
+                    .filter_map(|(name, value)| match value {
+                        serde_json::Value::String(s) => Some((name, s)),
+                        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                            Some((name, value.to_string()))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    fn is_username_field(name: &str) -> bool {
+        let lower = name.to_lowercase();
+        ["user", "email", "login", "account"]
+            .iter()
+            .any(|k| lower.contains(k))
+    }
+
+    fn is_password_field(name: &str) -> bool {
+        let lower = name.to_lowercase();
+        lower.contains("pass") || lower.contains("pwd")
+    }
+
+    fn is_csrf_field(name: &str) -> bool {
+        let lower = name.to_lowercase();
+        lower.contains("csrf")
+            || lower.contains("xsrf")
+            || matches!(
+                lower.as_str(),
+                "_token" | "authenticity_token" | "__requestverificationtoken"
+            )
+    }
+
+    /// Token location in the login response, body first
+    fn token_extraction(entry: &Entry) -> Option<TokenExtraction> {
+        let body_path = entry
+            .response
+            .content
+            .text
+            .as_deref()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok())
+            .and_then(|json| Self::find_token_path(&json, 0));
+        if let Some(path) = body_path {
+            return Some(TokenExtraction::BodyPath { path });
+        }
+
+        entry
+            .response
+            .headers
+            .iter()
+            .find(|h| TOKEN_HEADERS.contains(&h.name.to_lowercase().as_str()))
+            .map(|h| TokenExtraction::Header {
+                name: h.name.clone(),
+            })
+    }
+
+    /// Dotted path to the first token key, preferring shallower keys
+    fn find_token_path(value: &serde_json::Value, depth: usize) -> Option<String> {
+        let object = value.as_object()?;
+        if let Some(key) = TOKEN_KEYS
+            .iter()
+            .find(|key| object.get(**key).is_some_and(|v| v.is_string()))
+        {
+            return Some(key.to_string());
+        }
+        if depth + 1 >= MAX_TOKEN_DEPTH {
+            return None;
+        }
+        object.iter().find_map(|(key, child)| {
+            Self::find_token_path(child, depth + 1).map(|path| format!("{}.{}", key, path))
+        })
+    }
+
+    /// A logged-in GET replayed to check the scanner's session
+    fn test_path(
+        har: &Har,
+        flow: &AuthFlow,
+        submission_index: usize,
+        host: &str,
+        events: &[AuthEvent],
+        login_page_path: Option<&str>,
+        format: LoginFormat,
+    ) -> Option<TestPath> {
+        let is_candidate = |entry: &Entry| {
+            let path = Self::path_of(&entry.request.url);
+            entry.request.method == "GET"
+                && (200..300).contains(&entry.response.status)
+                && Self::origin_of(&entry.request.url).as_deref() == Some(host)
+                && !AccessMatrixBuilder::is_static_resource(entry)
+                && !Self::is_logout_path(&path)
+                && Some(path.as_str()) != login_page_path
+        };
+
+        // Prefer the flow's first authenticated request, then any credentialed GET after login
+        let (entry_index, entry) = flow
+            .steps
+            .iter()
+            .filter(|s| s.role == FlowRole::FirstAuthenticatedRequest)
+            .filter_map(|s| {
+                har.log
+                    .entries
+                    .get(s.entry_index)
+                    .map(|e| (s.entry_index, e))
+            })
+            .chain(
+                har.log
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .skip(submission_index + 1)
+                    .filter(|(_, e)| {
+                        AuthDetector::has_credentials(&e.request) || !e.request.cookies.is_empty()
+                    }),
+            )
+            .find(|(_, e)| is_candidate(e))?;
+
+        Some(TestPath {
+            method: entry.request.method.clone(),
+            path: Self::path_of(&entry.request.url),
+            success: format!("HTTP.*{}.*", entry.response.status),
+            fail: Self::fail_criteria(har, host, events, login_page_path, format),
+            entry_index,
+        })
+    }
+
+    /// What a logged-out response looked like: the logout redirect, an expired-session
+    /// status, or a redirect to the login page
+    fn fail_criteria(
+        har: &Har,
+        host: &str,
+        events: &[AuthEvent],
+        login_page_path: Option<&str>,
+        format: LoginFormat,
+    ) -> Option<String> {
+        let on_host = |event: &&AuthEvent| Self::origin_of(&event.url).as_deref() == Some(host);
+
+        let logout_redirect = events
+            .iter()
+            .filter(|e| e.event_type == AuthEventType::Logout)
+            .filter(on_host)
+            .filter_map(|e| har.log.entries.get(e.entry_index))
+            .filter(|e| (300..400).contains(&e.response.status))
+            .find_map(|e| {
+                e.response
+                    .headers
+                    .iter()
+                    .find(|h| h.name.eq_ignore_ascii_case("location"))
+                    .map(|h| Self::path_of_location(&h.value))
+            })
+            .filter(|path| !path.is_empty() && path != "/");
+        if let Some(path) = logout_redirect {
+            return Some(format!(".*Location:.*{}.*", regex::escape(&path)));
+        }
+
+        if let Some(expired) = events
+            .iter()
+            .filter(|e| e.event_type == AuthEventType::SessionExpired)
+            .find(on_host)
+        {
+            return Some(format!("HTTP.*{}.*", expired.status));
+        }
+
+        match (login_page_path, format) {
+            (Some(path), _) => Some(format!(".*Location:.*{}.*", regex::escape(path))),
+            (None, LoginFormat::Json) => Some("HTTP.*401.*".to_string()),
+            (None, LoginFormat::Form) => None,
+        }
+    }
+
+    fn logout_paths(events: &[AuthEvent], host: &str) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+        for event in events {
+            if event.event_type == AuthEventType::Logout
+                && Self::origin_of(&event.url).as_deref() == Some(host)
+            {
+                let path = Self::path_of(&event.url);
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths
+    }
+
+    fn is_logout_path(path: &str) -> bool {
+        let lower = path.to_lowercase();
+        lower.contains("/logout") || lower.contains("/signout")
+    }
+
+    /// Origin of a URL, e.g. `https://app.example.com:8443`
+    pub fn origin_of(url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        Some(url.origin().ascii_serialization())
+    }
+
+    /// Path of a URL, without query or fragment
+    pub fn path_of(url: &str) -> String {
+        Url::parse(url)
+            .map(|u| u.path().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Path of a Location header, which may be relative
+    fn path_of_location(location: &str) -> String {
+        match Url::parse(location) {
+            Ok(url) => url.path().to_string(),
+            Err(_) => location
+                .split(['?', '#'])
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_classification() {
+        assert!(LoginRequestExtractor::is_username_field("email"));
+        assert!(LoginRequestExtractor::is_username_field("j_username"));
+        assert!(LoginRequestExtractor::is_password_field("j_password"));
+        assert!(LoginRequestExtractor::is_password_field("pwd"));
+        assert!(LoginRequestExtractor::is_csrf_field("csrfmiddlewaretoken"));
+        assert!(LoginRequestExtractor::is_csrf_field("authenticity_token"));
+        assert!(!LoginRequestExtractor::is_csrf_field("remember_me"));
+    }
+
+    #[test]
+    fn test_login_param_static_values() {
+        let param = |name: &str, value: &str| LoginParam {
+            name: name.to_string(),
+            value: value.to_string(),
+        };
+        assert!(param("remember_me", "on").is_static());
+        assert!(param("locale", "en-US").is_static());
+        assert!(param("commit", "Sign in").is_static());
+        assert!(param("version", "2").is_static());
+
+        let otp = param("otp", "123456");
+        assert!(!otp.is_static());
+        assert_eq!(otp.config_value(), "${LOGIN_OTP}");
+        assert!(!param("confirm_password", "hunter2").is_static());
+        assert!(!param("mfa_code", "42").is_static());
+        assert!(!param("remember", "a81f9c02d7e4b6").is_static());
+        assert!(!param("device", "4f3c9a1e-77b2-4c1d-9e0f-2a6b8d5c3e71").is_static());
+        assert_eq!(param("x-device.id", "").env_var(), "LOGIN_X_DEVICE_ID");
+    }
+
+    #[test]
+    fn test_find_token_path() {
+        let flat = serde_json::json!({"token": "abc", "user": {"id": 1}});
+        assert_eq!(
+            LoginRequestExtractor::find_token_path(&flat, 0).as_deref(),
+            Some("token")
+        );
+
+        let nested = serde_json::json!({"data": {"session": {"accessToken": "abc"}}});
+        assert_eq!(
+            LoginRequestExtractor::find_token_path(&nested, 0).as_deref(),
+            Some("data.session.accessToken")
+        );
+
+        let too_deep = serde_json::json!({"a": {"b": {"c": {"token": "abc"}}}});
+        assert!(LoginRequestExtractor::find_token_path(&too_deep, 0).is_none());
+    }
+
+    #[test]
+    fn test_path_of_location() {
+        assert_eq!(
+            LoginRequestExtractor::path_of_location("/login?next=%2F"),
+            "/login"
+        );
+        assert_eq!(
+            LoginRequestExtractor::path_of_location("https://app.example.com/signin#top"),
+            "/signin"
+        );
+    }
+}
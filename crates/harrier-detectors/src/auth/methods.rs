@@ -1,9 +1,12 @@
 use crate::Result;
-use harrier_core::har::Har;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use harrier_core::har::{Har, Request};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+use super::jwt::JwtAnalyzer;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AuthMethod {
@@ -13,6 +16,13 @@ pub enum AuthMethod {
     OAuth,
     Jwt,
     Cookie,
+    Digest,
+    Ntlm,
+    Negotiate, // SPNEGO (Kerberos, or NTLM fallback)
+    AwsSigV4,
+    Hmac(String), // Authorization scheme or signature header name
+    Dpop,
+    MutualTls,
     Custom(String),
 }
 
@@ -25,6 +35,13 @@ impl AuthMethod {
             AuthMethod::OAuth => "OAuth",
             AuthMethod::Jwt => "JWT",
             AuthMethod::Cookie => "Cookie-based",
+            AuthMethod::Digest => "HTTP Digest",
+            AuthMethod::Ntlm => "NTLM",
+            AuthMethod::Negotiate => "Negotiate (Kerberos)",
+            AuthMethod::AwsSigV4 => "AWS SigV4",
+            AuthMethod::Hmac(_) => "HMAC Request Signing",
+            AuthMethod::Dpop => "DPoP",
+            AuthMethod::MutualTls => "Mutual TLS",
             AuthMethod::Custom(_) => "Custom",
         }
     }
@@ -35,6 +52,15 @@ lazy_static! {
         Regex::new(r"^[A-Za-z0-9-_]+\.[A-Za-z0-9-_]+\.[A-Za-z0-9-_]+$").unwrap();
 }
 
+/// Headers a TLS-terminating proxy adds after verifying a client certificate
+const CLIENT_CERT_HEADERS: &[&str] = &[
+    "x-client-cert",
+    "x-ssl-client-cert",
+    "x-forwarded-client-cert",
+    "x-arr-clientcert",
+    "ssl-client-cert",
+];
+
 pub struct AuthDetector;
 
 impl AuthDetector {
@@ -44,55 +70,174 @@ impl AuthDetector {
         let mut auth_methods = HashSet::new();
 
         for entry in &har.log.entries {
-            // Check request headers
-            for header in &entry.request.headers {
-                let header_name = header.name.to_lowercase();
-                let header_value = &header.value;
-
-                match header_name.as_str() {
-                    "authorization" => {
-                        if header_value.starts_with("Basic ") {
-                            auth_methods.insert(AuthMethod::Basic);
-                        } else if header_value.starts_with("Bearer ") {
-                            let token = header_value.trim_start_matches("Bearer ");
-                            if JWT_PATTERN.is_match(token) {
-                                auth_methods.insert(AuthMethod::Jwt);
-                            } else {
-                                auth_methods.insert(AuthMethod::Bearer);
-                            }
-                        } else if header_value.starts_with("OAuth ") {
-                            auth_methods.insert(AuthMethod::OAuth);
-                        }
-                    }
-                    "x-api-key" | "api-key" | "apikey" => {
-                        auth_methods.insert(AuthMethod::ApiKey(header.name.clone()));
+            auth_methods.extend(Self::detect_request(&entry.request));
+        }
+
+        let methods: Vec<_> = auth_methods.into_iter().collect();
+        tracing::info!("Detected {} authentication method(s)", methods.len());
+
+        Ok(methods)
+    }
+
+    /// Authentication methods carried by a single request
+    pub fn detect_request(request: &Request) -> HashSet<AuthMethod> {
+        let mut auth_methods = HashSet::new();
+
+        // Check request headers
+        for header in &request.headers {
+            let header_name = header.name.to_lowercase();
+            let header_value = &header.value;
+
+            match header_name.as_str() {
+                "authorization" => {
+                    if let Some(method) = Self::authorization_method(header_value) {
+                        auth_methods.insert(method);
                     }
-                    "cookie" => {
-                        if Self::has_auth_cookie(header_value) {
-                            auth_methods.insert(AuthMethod::Cookie);
+                    // Certificate-bound (RFC 8705) and DPoP-bound (RFC 9449) access tokens
+                    if let Some(token) = JwtAnalyzer::extract_bearer_token(header_value)
+                        .or_else(|| header_value.strip_prefix("DPoP "))
+                        && JwtAnalyzer::is_jwt(token)
+                        && let Ok(jwt) = JwtAnalyzer::parse_jwt(token, "")
+                        && let Some(cnf) = jwt.claims.cnf
+                    {
+                        if cnf.x5t_s256.is_some() {
+                            auth_methods.insert(AuthMethod::MutualTls);
                         }
-                    }
-                    _ => {
-                        // Check for custom auth headers
-                        if header_name.contains("auth") || header_name.contains("token") {
-                            auth_methods.insert(AuthMethod::Custom(header.name.clone()));
+                        if cnf.jkt.is_some() {
+                            auth_methods.insert(AuthMethod::Dpop);
                         }
                     }
                 }
+                "dpop" => {
+                    auth_methods.insert(AuthMethod::Dpop);
+                }
+                "x-amz-date" | "x-amz-content-sha256" | "x-amz-security-token" => {
+                    // Only meaningful alongside an AWS4-HMAC-SHA256 Authorization header
+                }
+                name if CLIENT_CERT_HEADERS.contains(&name) => {
+                    auth_methods.insert(AuthMethod::MutualTls);
+                }
+                name if Self::is_signature_header(name) => {
+                    auth_methods.insert(AuthMethod::Hmac(header.name.clone()));
+                }
+                "x-api-key" | "api-key" | "apikey" => {
+                    auth_methods.insert(AuthMethod::ApiKey(header.name.clone()));
+                }
+                "cookie" => {
+                    if Self::has_auth_cookie(header_value) {
+                        auth_methods.insert(AuthMethod::Cookie);
+                    }
+                }
+                _ => {
+                    // Check for custom auth headers
+                    if header_name.contains("auth") || header_name.contains("token") {
+                        auth_methods.insert(AuthMethod::Custom(header.name.clone()));
+                    }
+                }
+            }
+        }
+
+        // Presigned SigV4 URLs carry the signature in the query string
+        if request
+            .query_string
+            .iter()
+            .any(|q| q.name == "X-Amz-Algorithm" && q.value == "AWS4-HMAC-SHA256")
+        {
+            auth_methods.insert(AuthMethod::AwsSigV4);
+        }
+
+        // Check cookies
+        for cookie in &request.cookies {
+            if Self::is_auth_cookie(&cookie.name) {
+                auth_methods.insert(AuthMethod::Cookie);
             }
+        }
+
+        auth_methods
+    }
 
-            // Check cookies
-            for cookie in &entry.request.cookies {
-                if Self::is_auth_cookie(&cookie.name) {
-                    auth_methods.insert(AuthMethod::Cookie);
+    /// Whether a request carries any credentials: an Authorization header,
+    /// an auth cookie, an API key or a custom auth/token header
+    pub fn has_credentials(request: &Request) -> bool {
+        request
+            .headers
+            .iter()
+            .any(|h| h.name.eq_ignore_ascii_case("authorization"))
+            || !Self::detect_request(request).is_empty()
+    }
+
+    /// Method named by an Authorization header's scheme
+    fn authorization_method(value: &str) -> Option<AuthMethod> {
+        let (scheme, credentials) = Self::split_scheme(value);
+        let method = match scheme.to_lowercase().as_str() {
+            "basic" => AuthMethod::Basic,
+            "bearer" => {
+                if JWT_PATTERN.is_match(credentials) {
+                    AuthMethod::Jwt
+                } else {
+                    AuthMethod::Bearer
                 }
             }
+            "oauth" => AuthMethod::OAuth,
+            "digest" => AuthMethod::Digest,
+            "ntlm" => AuthMethod::Ntlm,
+            "negotiate" => AuthMethod::Negotiate,
+            "aws4-hmac-sha256" => AuthMethod::AwsSigV4,
+            "dpop" => AuthMethod::Dpop,
+            "hawk" => AuthMethod::Hmac("Hawk".to_string()),
+            "signature" => AuthMethod::Hmac("Signature".to_string()),
+            "hmac" | "hmac-sha256" => AuthMethod::Hmac(scheme.to_string()),
+            _ => return None,
+        };
+        Some(method)
+    }
+
+    /// Split an Authorization or WWW-Authenticate value into its scheme and the rest
+    pub(crate) fn split_scheme(value: &str) -> (&str, &str) {
+        match value.trim().split_once(' ') {
+            Some((scheme, rest)) => (scheme, rest.trim()),
+            None => (value.trim(), ""),
         }
+    }
 
-        let methods: Vec<_> = auth_methods.into_iter().collect();
-        tracing::info!("Detected {} authentication method(s)", methods.len());
+    /// Parse `key=value, key="quoted, value"` auth parameters (keys lowercased)
+    pub(crate) fn auth_params(rest: &str) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        let mut remaining = rest.trim();
+        while let Some((key, after)) = remaining.split_once('=') {
+            let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+            let after = after.trim_start();
+            let (value, next) = if let Some(quoted) = after.strip_prefix('"') {
+                match quoted.find('"') {
+                    Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                    None => (quoted, ""),
+                }
+            } else {
+                match after.find(',') {
+                    Some(end) => (&after[..end], &after[end..]),
+                    None => (after, ""),
+                }
+            };
+            params.insert(key, value.trim().to_string());
+            remaining = next.trim_start().trim_start_matches(',');
+        }
+        params
+    }
 
-        Ok(methods)
+    /// NTLMSSP message type (1 negotiate, 2 challenge, 3 authenticate) of a base64 token
+    pub(crate) fn ntlm_message_type(token: &str) -> Option<u32> {
+        let bytes = STANDARD.decode(token.trim()).ok()?;
+        if bytes.len() < 12 || !bytes.starts_with(b"NTLMSSP\0") {
+            return None;
+        }
+        Some(u32::from_le_bytes([
+            bytes[8], bytes[9], bytes[10], bytes[11],
+        ]))
+    }
+
+    /// Headers carrying a request signature (`X-Signature`, `X-Hmac-Signature`, RFC 9421 `Signature`)
+    fn is_signature_header(name: &str) -> bool {
+        name == "signature" || name.ends_with("-signature") || name.contains("hmac")
     }
 
     fn has_auth_cookie(cookie_header: &str) -> bool {
@@ -111,3 +256,70 @@ impl AuthDetector {
             || lower == "sid"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization_method() {
+        assert_eq!(
+            AuthDetector::authorization_method("Digest username=\"ops\", realm=\"api\""),
+            Some(AuthMethod::Digest)
+        );
+        assert_eq!(
+            AuthDetector::authorization_method(
+                "NTLM TlRMTVNTUAABAAAAB4IIogAAAAAAAAAAAAAAAAAAAAAKAGFKAAAADw=="
+            ),
+            Some(AuthMethod::Ntlm)
+        );
+        assert_eq!(
+            AuthDetector::authorization_method(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20240115/us-east-1/s3/aws4_request"
+            ),
+            Some(AuthMethod::AwsSigV4)
+        );
+        assert_eq!(
+            AuthDetector::authorization_method("Hawk id=\"dh37fgj492je\", mac=\"6R4rV5\""),
+            Some(AuthMethod::Hmac("Hawk".to_string()))
+        );
+        assert_eq!(
+            AuthDetector::authorization_method("bearer abc123"),
+            Some(AuthMethod::Bearer)
+        );
+        assert_eq!(AuthDetector::authorization_method("Unknown xyz"), None);
+    }
+
+    #[test]
+    fn test_auth_params() {
+        let params = AuthDetector::auth_params(
+            r#"realm="api, v2", qop="auth,auth-int", nonce="dcd98b", algorithm=MD5, stale=false"#,
+        );
+        assert_eq!(params["realm"], "api, v2");
+        assert_eq!(params["qop"], "auth,auth-int");
+        assert_eq!(params["algorithm"], "MD5");
+        assert_eq!(params["stale"], "false");
+    }
+
+    #[test]
+    fn test_ntlm_message_type() {
+        assert_eq!(
+            AuthDetector::ntlm_message_type(
+                "TlRMTVNTUAABAAAAB4IIogAAAAAAAAAAAAAAAAAAAAAKAGFKAAAADw=="
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            AuthDetector::ntlm_message_type("YIIGhgYGKwYBBQUCoIIGejCC"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_signature_header() {
+        assert!(AuthDetector::is_signature_header("x-signature"));
+        assert!(AuthDetector::is_signature_header("x-hmac-auth"));
+        assert!(AuthDetector::is_signature_header("signature"));
+        assert!(!AuthDetector::is_signature_header("signature-input"));
+    }
+}
@@ -1,29 +1,97 @@
+pub mod access_matrix;
 pub mod advanced_security;
 pub mod analyzer;
+pub mod csrf;
 pub mod events;
 pub mod flows;
+pub mod identity;
+pub mod idor;
 pub mod jwt;
+pub mod jwt_verification;
+pub mod lineage;
+pub mod login;
 pub mod methods;
+pub mod oauth;
 pub mod saml;
+pub mod saml_message;
 pub mod security;
+pub mod session_lifecycle;
 pub mod sessions;
 pub mod summary;
+pub mod unauthenticated;
 
 // Re-export main types for convenience
+pub use access_matrix::{
+    AccessCell, AccessControlIssue, AccessMatrix, AccessMatrixBuilder, MatrixEndpoint,
+    MatrixIdentity,
+};
 pub use advanced_security::{
     AdvancedSecurityAnalysis, AdvancedSecurityAnalyzer, CorsIssue, CorsIssueType, CspFinding,
     CspFindingType, ExposureType, RefreshPatternType, TokenExposure, TokenRefreshPattern,
 };
 pub use analyzer::{AuthAnalysis, AuthAnalyzer};
+pub use csrf::{
+    CsrfAnalysis, CsrfAnalyzer, CsrfEndpoint, CsrfIssueType, CsrfProtection, CsrfSecurityIssue,
+    CsrfTokenObservation,
+};
 pub use events::{AuthEvent, AuthEventType, EventDetails, EventDetector};
 pub use flows::{AuthFlow, AuthFlowType, FlowDetector, FlowRole, FlowStep};
-pub use jwt::{JwtAnalyzer, JwtClaims, JwtHeader, JwtIssueType, JwtSecurityIssue, JwtToken};
+pub use identity::{IdentityKind, IdentityResolver, RequestIdentity};
+pub use idor::{IdorAnalysis, IdorAnalyzer, IdorCandidate, ObjectIdKind, ObjectIdLocation};
+pub use jwt::{
+    ConfirmationClaim, JwtAnalyzer, JwtClaims, JwtHeader, JwtIssueType, JwtSecurityIssue, JwtToken,
+    TokenFormat,
+};
+pub use jwt_verification::{
+    JwtVerification, JwtVerificationReport, JwtVerifier, VerificationKey, VerificationStatus,
+};
+pub use lineage::{IssuedTokenKind, TokenIssuance, TokenLineage, TokenLineageTracker, TokenUsage};
+pub use login::{
+    LoginFormat, LoginParam, LoginRequest, LoginRequestExtractor, TestPath, TokenExtraction,
+};
 pub use methods::{AuthDetector, AuthMethod};
+pub use oauth::{
+    AuthorizationRequest, OAuthAnalysis, OAuthAnalyzer, OAuthIssueType, OAuthSecurityIssue,
+    OidcProviderConfig,
+};
 pub use saml::{SamlDetector, SamlFlow, SamlFlowType, SamlSecurityIssue, SamlStep, SamlStepRole};
+pub use saml_message::{
+    SamlAssertion, SamlAttribute, SamlBinding, SamlMessage, SamlMessageDecoder, SamlMessageKind,
+};
 pub use security::{SecurityAnalyzer, SecurityNote, Severity};
+pub use session_lifecycle::{
+    ConcurrentSessions, InvalidationStatus, LogoutInvalidation, SessionIssueType,
+    SessionLifecycleAnalysis, SessionLifecycleAnalyzer, SessionLifecycleIssue, SessionRotation,
+};
 pub use sessions::{AuthSession, SessionAttributes, SessionTracker, SessionType};
 pub use summary::{
     AggregatedFinding, AuthMethodSummary, AuthSummaryGenerator, AuthenticationSummary,
     ConfidenceLevel, EndpointInfo, HawkScanConfig, SecurityFindingsSummary,
     SessionMechanismSummary,
 };
+pub use unauthenticated::{
+    InventoryEndpoint, UnauthenticatedAnalyzer, UnauthenticatedInventory, UnenforcedAuthIssue,
+};
+
+/// First `keep` characters of `value` followed by `...`, or all of it when no longer
+///
+/// Cuts on character boundaries, so captured URLs and values with multibyte text are safe.
+pub(crate) fn truncate(value: &str, keep: usize) -> String {
+    match value.char_indices().nth(keep) {
+        Some((end, _)) => format!("{}...", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        assert_eq!(truncate("short", 12), "short");
+        assert_eq!(truncate("0123456789abcdef", 12), "0123456789ab...");
+        assert_eq!(truncate("/caf\u{e9}/\u{1f600}/x", 5), "/caf\u{e9}...");
+        assert_eq!(truncate("\u{e9}\u{e9}\u{e9}", 3), "\u{e9}\u{e9}\u{e9}");
+    }
+}
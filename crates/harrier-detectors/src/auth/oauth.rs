@@ -0,0 +1,732 @@
+use crate::Result;
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::security::Severity;
+use super::truncate;
+
+/// OAuth 2.0 / OpenID Connect security analysis
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OAuthAnalysis {
+    pub authorization_requests: Vec<AuthorizationRequest>,
+    pub provider_configs: Vec<OidcProviderConfig>,
+    pub issues: Vec<OAuthSecurityIssue>,
+}
+
+/// Parameters of a captured authorization request and where its response went
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+    pub entry_index: usize,
+    pub client_id: Option<String>,
+    pub response_type: Option<String>,
+    pub response_mode: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Vec<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge_method: Option<String>, // None when no PKCE challenge was sent
+    pub callback_entry: Option<usize>,
+    pub token_entry: Option<usize>,
+}
+
+/// Settings published in `.well-known/openid-configuration` (or RFC 8414 metadata)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcProviderConfig {
+    pub entry_index: usize,
+    pub source_url: String,
+    pub issuer: Option<String>,
+    pub authorization_endpoint: Option<String>,
+    pub token_endpoint: Option<String>,
+    pub jwks_uri: Option<String>,
+    pub grant_types_supported: Vec<String>,
+    pub response_types_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+}
+
+/// OAuth / OIDC security issues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthSecurityIssue {
+    pub severity: Severity,
+    pub issue_type: OAuthIssueType,
+    pub message: String,
+    pub entry_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OAuthIssueType {
+    MissingState,
+    StateMismatch,
+    MissingNonce,
+    MissingPkce,
+    PlainPkce,
+    WeakPkceVerifier,
+    PkceNotEnforced,
+    ImplicitFlow,
+    HybridFlow,
+    TokenInFragment,
+    TokenInReferer,
+    RedirectUriMismatch,
+    WeakProviderConfig,
+}
+
+impl OAuthIssueType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            OAuthIssueType::MissingState => "Missing state",
+            OAuthIssueType::StateMismatch => "State mismatch",
+            OAuthIssueType::MissingNonce => "Missing nonce",
+            OAuthIssueType::MissingPkce => "Missing PKCE",
+            OAuthIssueType::PlainPkce => "Plain PKCE",
+            OAuthIssueType::WeakPkceVerifier => "Weak PKCE verifier",
+            OAuthIssueType::PkceNotEnforced => "PKCE not enforced",
+            OAuthIssueType::ImplicitFlow => "Implicit flow",
+            OAuthIssueType::HybridFlow => "Hybrid flow",
+            OAuthIssueType::TokenInFragment => "Token in fragment",
+            OAuthIssueType::TokenInReferer => "Token in Referer",
+            OAuthIssueType::RedirectUriMismatch => "Redirect URI mismatch",
+            OAuthIssueType::WeakProviderConfig => "Weak provider configuration",
+        }
+    }
+}
+
+/// How far past the authorization request to look for its redirect
+const CALLBACK_WINDOW: usize = 50;
+
+/// RFC 7636 recommends 32 random octets (256 bits) for the code verifier
+const MIN_VERIFIER_BITS: f64 = 128.0;
+
+pub struct OAuthAnalyzer;
+
+impl OAuthAnalyzer {
+    /// Check the security-relevant parameters of OAuth 2.0 / OIDC traffic
+    pub fn analyze(har: &Har) -> Result<OAuthAnalysis> {
+        let entries = &har.log.entries;
+        let mut analysis = OAuthAnalysis::default();
+
+        for (idx, entry) in entries.iter().enumerate() {
+            if let Some(request) = Self::parse_authorization_request(idx, entry) {
+                let request =
+                    Self::check_authorization_flow(entries, request, &mut analysis.issues);
+                analysis.authorization_requests.push(request);
+            }
+
+            if let Some(config) = Self::parse_provider_config(idx, entry) {
+                Self::check_provider_config(&config, &mut analysis.issues);
+                analysis.provider_configs.push(config);
+            }
+
+            Self::check_front_channel_leaks(idx, entry, &mut analysis.issues);
+        }
+
+        Ok(analysis)
+    }
+
+    fn parse_authorization_request(idx: usize, entry: &Entry) -> Option<AuthorizationRequest> {
+        let url = url::Url::parse(&entry.request.url).ok()?;
+        if !url.path().contains("authorize") && !url.path().ends_with("/auth") {
+            return None;
+        }
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        if !params.contains_key("response_type") && !params.contains_key("client_id") {
+            return None;
+        }
+
+        Some(AuthorizationRequest {
+            entry_index: idx,
+            client_id: params.get("client_id").cloned(),
+            response_type: params.get("response_type").cloned(),
+            response_mode: params.get("response_mode").cloned(),
+            redirect_uri: params.get("redirect_uri").cloned(),
+            scope: params
+                .get("scope")
+                .map(|s| s.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            state: params.get("state").cloned(),
+            nonce: params.get("nonce").cloned(),
+            code_challenge_method: params.get("code_challenge").map(|_| {
+                // RFC 7636: the method defaults to "plain" when omitted
+                params
+                    .get("code_challenge_method")
+                    .cloned()
+                    .unwrap_or_else(|| "plain".to_string())
+            }),
+            callback_entry: None,
+            token_entry: None,
+        })
+    }
+
+    fn check_authorization_flow(
+        entries: &[Entry],
+        mut request: AuthorizationRequest,
+        issues: &mut Vec<OAuthSecurityIssue>,
+    ) -> AuthorizationRequest {
+        let idx = request.entry_index;
+        let entry = &entries[idx];
+        let params: HashMap<String, String> = url::Url::parse(&entry.request.url)
+            .map(|u| u.query_pairs().into_owned().collect())
+            .unwrap_or_default();
+
+        let response_types: Vec<&str> = request
+            .response_type
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .collect();
+        let has_code = response_types.contains(&"code");
+        let has_access_token = response_types.contains(&"token");
+        let has_id_token = response_types.contains(&"id_token");
+        let response_type = request.response_type.clone().unwrap_or_default();
+
+        // Implicit and hybrid flows deliver tokens through the browser
+        if !has_code && (has_access_token || has_id_token) {
+            issues.push(OAuthSecurityIssue {
+                severity: Severity::Warning,
+                issue_type: OAuthIssueType::ImplicitFlow,
+                message: format!(
+                    "Implicit flow (response_type={}) returns tokens in the front channel; use authorization code with PKCE",
+                    response_type
+                ),
+                entry_index: idx,
+            });
+        } else if has_code && (has_access_token || has_id_token) {
+            issues.push(OAuthSecurityIssue {
+                severity: if has_access_token {
+                    Severity::Warning
+                } else {
+                    Severity::Info
+                },
+                issue_type: OAuthIssueType::HybridFlow,
+                message: format!(
+                    "Hybrid flow (response_type={}) returns tokens alongside the code",
+                    response_type
+                ),
+                entry_index: idx,
+            });
+        }
+
+        if request.state.is_none() {
+            issues.push(OAuthSecurityIssue {
+                severity: Severity::Warning,
+                issue_type: OAuthIssueType::MissingState,
+                message: "Authorization request without state parameter (CSRF on the redirect)"
+                    .to_string(),
+                entry_index: idx,
+            });
+        }
+
+        // OIDC requires a nonce whenever an ID token comes back through the browser
+        if request.scope.iter().any(|s| s == "openid") && request.nonce.is_none() {
+            let front_channel = has_id_token;
+            issues.push(OAuthSecurityIssue {
+                severity: if front_channel {
+                    Severity::Warning
+                } else {
+                    Severity::Info
+                },
+                issue_type: OAuthIssueType::MissingNonce,
+                message: if front_channel {
+                    "OIDC request returning an ID token without nonce (token replay)".to_string()
+                } else {
+                    "OIDC authorization request without nonce".to_string()
+                },
+                entry_index: idx,
+            });
+        }
+
+        if has_code {
+            match request.code_challenge_method.as_deref() {
+                None => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: OAuthIssueType::MissingPkce,
+                    message: "Authorization code flow without PKCE (code interception)".to_string(),
+                    entry_index: idx,
+                }),
+                Some("S256") => {}
+                Some(method) => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: OAuthIssueType::PlainPkce,
+                    message: format!("PKCE uses code_challenge_method={} instead of S256", method),
+                    entry_index: idx,
+                }),
+            }
+        }
+
+        // Follow the redirect back to the client
+        let Some((callback_idx, callback)) =
+            Self::find_callback(entries, idx, request.redirect_uri.as_deref())
+        else {
+            return request;
+        };
+        request.callback_entry = Some(callback_idx);
+
+        if let Some(ref sent) = request.state {
+            match callback.get("state") {
+                None if !callback.contains_key("error") => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: OAuthIssueType::StateMismatch,
+                    message: "state parameter not returned in the authorization response"
+                        .to_string(),
+                    entry_index: callback_idx,
+                }),
+                Some(returned) if returned != sent => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: OAuthIssueType::StateMismatch,
+                    message:
+                        "state returned in the authorization response differs from the request"
+                            .to_string(),
+                    entry_index: callback_idx,
+                }),
+                _ => {}
+            }
+        }
+
+        let Some(code) = callback.get("code") else {
+            return request;
+        };
+        let Some((token_idx, token_params)) = Self::find_token_request(entries, callback_idx, code)
+        else {
+            return request;
+        };
+        request.token_entry = Some(token_idx);
+        let accepted = (200..300).contains(&entries[token_idx].response.status);
+
+        // PKCE: the verifier must be strong and actually checked by the server
+        if let Some(ref method) = request.code_challenge_method {
+            let challenge = params.get("code_challenge").cloned().unwrap_or_default();
+            match token_params.get("code_verifier") {
+                None if accepted => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Critical,
+                    issue_type: OAuthIssueType::PkceNotEnforced,
+                    message: "Token endpoint issued tokens without a code_verifier".to_string(),
+                    entry_index: token_idx,
+                }),
+                None => {}
+                Some(verifier) => {
+                    if let Some(reason) = Self::weak_verifier_reason(verifier) {
+                        issues.push(OAuthSecurityIssue {
+                            severity: Severity::Warning,
+                            issue_type: OAuthIssueType::WeakPkceVerifier,
+                            message: format!("PKCE code_verifier is weak: {}", reason),
+                            entry_index: token_idx,
+                        });
+                    }
+                    if accepted && !Self::pkce_matches(method, verifier, &challenge) {
+                        issues.push(OAuthSecurityIssue {
+                            severity: Severity::Critical,
+                            issue_type: OAuthIssueType::PkceNotEnforced,
+                            message: "Token endpoint accepted a code_verifier that does not match the code_challenge".to_string(),
+                            entry_index: token_idx,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(ref authorize_uri) = request.redirect_uri {
+            match token_params.get("redirect_uri") {
+                Some(token_uri) if token_uri != authorize_uri && accepted => {
+                    issues.push(OAuthSecurityIssue {
+                        severity: Severity::Critical,
+                        issue_type: OAuthIssueType::RedirectUriMismatch,
+                        message: "Token endpoint accepted a redirect_uri that differs from the authorization request".to_string(),
+                        entry_index: token_idx,
+                    });
+                }
+                None if accepted => issues.push(OAuthSecurityIssue {
+                    severity: Severity::Warning,
+                    issue_type: OAuthIssueType::RedirectUriMismatch,
+                    message: "Token endpoint accepted a code exchange without the redirect_uri used to obtain it".to_string(),
+                    entry_index: token_idx,
+                }),
+                _ => {}
+            }
+        }
+
+        request
+    }
+
+    /// Find the authorization response: a request to the redirect URI, or a redirect pointing at it
+    fn find_callback(
+        entries: &[Entry],
+        start_idx: usize,
+        redirect_uri: Option<&str>,
+    ) -> Option<(usize, HashMap<String, String>)> {
+        let expected = redirect_uri.map(Self::strip_query);
+        let is_response = |params: &HashMap<String, String>| {
+            ["code", "access_token", "id_token", "error"]
+                .iter()
+                .any(|key| params.contains_key(*key))
+        };
+
+        let matches = |url: &str| {
+            let params = Self::response_params(url);
+            let matches_redirect = match expected {
+                Some(expected) => Self::strip_query(url) == expected,
+                None => true,
+            };
+            (matches_redirect && is_response(&params)).then_some(params)
+        };
+        let window = || {
+            entries
+                .iter()
+                .enumerate()
+                .skip(start_idx)
+                .take(CALLBACK_WINDOW)
+        };
+
+        // Prefer the request that delivered the response to the client
+        if let Some(found) = window()
+            .skip(1)
+            .find_map(|(i, entry)| matches(&entry.request.url).map(|params| (i, params)))
+        {
+            return Some(found);
+        }
+
+        // Fragment responses never reach the server, so only the redirect shows them
+        window().find_map(|(i, entry)| {
+            entry
+                .response
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("location"))
+                .and_then(|h| matches(&h.value))
+                .map(|params| (i, params))
+        })
+    }
+
+    fn find_token_request(
+        entries: &[Entry],
+        start_idx: usize,
+        code: &str,
+    ) -> Option<(usize, HashMap<String, String>)> {
+        entries
+            .iter()
+            .enumerate()
+            .skip(start_idx)
+            .take(CALLBACK_WINDOW)
+            .filter(|(_, entry)| entry.request.method == "POST")
+            .find_map(|(i, entry)| {
+                let text = entry.request.post_data.as_ref()?.text.as_ref()?;
+                let params: HashMap<String, String> = url::form_urlencoded::parse(text.as_bytes())
+                    .into_owned()
+                    .collect();
+                (params.get("grant_type").map(String::as_str) == Some("authorization_code")
+                    && params.get("code").map(String::as_str) == Some(code))
+                .then_some((i, params))
+            })
+    }
+
+    /// Query and fragment parameters of an authorization response URL
+    fn response_params(url: &str) -> HashMap<String, String> {
+        let Ok(parsed) = url::Url::parse(url) else {
+            return HashMap::new();
+        };
+        let mut params: HashMap<String, String> = parsed.query_pairs().into_owned().collect();
+        if let Some(fragment) = parsed.fragment() {
+            params.extend(url::form_urlencoded::parse(fragment.as_bytes()).into_owned());
+        }
+        params
+    }
+
+    fn pkce_matches(method: &str, verifier: &str, challenge: &str) -> bool {
+        use base64::engine::Engine;
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+        match method {
+            "S256" => {
+                let digest =
+                    aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, verifier.as_bytes());
+                URL_SAFE_NO_PAD.encode(digest.as_ref()) == challenge
+            }
+            _ => verifier == challenge,
+        }
+    }
+
+    /// RFC 7636: 43-128 unreserved characters with enough randomness to resist guessing
+    fn weak_verifier_reason(verifier: &str) -> Option<String> {
+        let len = verifier.chars().count();
+        if !(43..=128).contains(&len) {
+            return Some(format!("{} characters (RFC 7636 requires 43-128)", len));
+        }
+        if !verifier
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c))
+        {
+            return Some("contains characters outside the unreserved set".to_string());
+        }
+
+        let bits = Self::estimate_entropy_bits(verifier);
+        if bits < MIN_VERIFIER_BITS {
+            return Some(format!("~{:.0} bits of entropy", bits));
+        }
+        None
+    }
+
+    /// Shannon entropy of the character distribution times length
+    fn estimate_entropy_bits(value: &str) -> f64 {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for c in value.chars() {
+            *counts.entry(c).or_default() += 1;
+        }
+        let len = value.chars().count() as f64;
+        let per_char: f64 = counts
+            .values()
+            .map(|&n| {
+                let p = n as f64 / len;
+                -p * p.log2()
+            })
+            .sum();
+        per_char * len
+    }
+
+    fn parse_provider_config(idx: usize, entry: &Entry) -> Option<OidcProviderConfig> {
+        let path = url::Url::parse(&entry.request.url).ok()?.path().to_string();
+        if !path.ends_with("/.well-known/openid-configuration")
+            && !path.ends_with("/.well-known/oauth-authorization-server")
+        {
+            return None;
+        }
+        let text = entry.response.content.text.as_ref()?;
+        let json: serde_json::Value = serde_json::from_str(text).ok()?;
+        let obj = json.as_object()?;
+
+        let string = |key: &str| obj.get(key).and_then(|v| v.as_str()).map(String::from);
+        let list = |key: &str| -> Vec<String> {
+            obj.get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        Some(OidcProviderConfig {
+            entry_index: idx,
+            source_url: truncate(&entry.request.url, 77),
+            issuer: string("issuer"),
+            authorization_endpoint: string("authorization_endpoint"),
+            token_endpoint: string("token_endpoint"),
+            jwks_uri: string("jwks_uri"),
+            grant_types_supported: list("grant_types_supported"),
+            response_types_supported: list("response_types_supported"),
+            code_challenge_methods_supported: list("code_challenge_methods_supported"),
+            id_token_signing_alg_values_supported: list("id_token_signing_alg_values_supported"),
+            token_endpoint_auth_methods_supported: list("token_endpoint_auth_methods_supported"),
+        })
+    }
+
+    fn check_provider_config(config: &OidcProviderConfig, issues: &mut Vec<OAuthSecurityIssue>) {
+        let mut weak = |severity: Severity, message: String| {
+            issues.push(OAuthSecurityIssue {
+                severity,
+                issue_type: OAuthIssueType::WeakProviderConfig,
+                message,
+                entry_index: config.entry_index,
+            });
+        };
+
+        let front_channel: Vec<&str> = config
+            .response_types_supported
+            .iter()
+            .filter(|t| t.split_whitespace().any(|part| part == "token"))
+            .map(String::as_str)
+            .collect();
+        if !front_channel.is_empty() {
+            weak(
+                Severity::Warning,
+                format!(
+                    "Provider issues access tokens in the front channel (response types: {})",
+                    front_channel.join(", ")
+                ),
+            );
+        }
+
+        for (grant, message) in [
+            ("implicit", "Provider supports the implicit grant"),
+            (
+                "password",
+                "Provider supports the resource owner password credentials grant",
+            ),
+        ] {
+            if config.grant_types_supported.iter().any(|g| g == grant) {
+                weak(Severity::Warning, message.to_string());
+            }
+        }
+
+        let methods = &config.code_challenge_methods_supported;
+        if methods.is_empty() {
+            weak(
+                Severity::Info,
+                "Provider does not advertise PKCE support (code_challenge_methods_supported)"
+                    .to_string(),
+            );
+        } else if !methods.iter().any(|m| m == "S256") {
+            weak(
+                Severity::Warning,
+                "Provider supports only plain PKCE, not S256".to_string(),
+            );
+        } else if methods.iter().any(|m| m == "plain") {
+            weak(
+                Severity::Info,
+                "Provider accepts plain PKCE alongside S256 (downgrade)".to_string(),
+            );
+        }
+
+        let algs = &config.id_token_signing_alg_values_supported;
+        if algs.iter().any(|a| a.eq_ignore_ascii_case("none")) {
+            weak(
+                Severity::Critical,
+                "Provider allows unsigned ID tokens (alg none)".to_string(),
+            );
+        }
+        if algs.iter().any(|a| a.starts_with("HS")) {
+            weak(
+                Severity::Info,
+                "Provider allows HMAC-signed ID tokens keyed with the client secret".to_string(),
+            );
+        }
+
+        if config
+            .issuer
+            .as_deref()
+            .is_some_and(|issuer| issuer.starts_with("http://"))
+        {
+            weak(
+                Severity::Warning,
+                "Provider issuer is not served over HTTPS".to_string(),
+            );
+        }
+    }
+
+    /// Tokens and codes that leak through URL fragments and Referer headers
+    fn check_front_channel_leaks(idx: usize, entry: &Entry, issues: &mut Vec<OAuthSecurityIssue>) {
+        let location = entry
+            .response
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("location"))
+            .map(|h| h.value.as_str());
+        for url in [Some(entry.request.url.as_str()), location]
+            .into_iter()
+            .flatten()
+        {
+            if let Some((_, fragment)) = url.split_once('#') {
+                let params: HashMap<String, String> =
+                    url::form_urlencoded::parse(fragment.as_bytes())
+                        .into_owned()
+                        .collect();
+                if params.contains_key("access_token") || params.contains_key("id_token") {
+                    issues.push(OAuthSecurityIssue {
+                        severity: Severity::Warning,
+                        issue_type: OAuthIssueType::TokenInFragment,
+                        message: "Token delivered in URL fragment (exposed to browser history and scripts)".to_string(),
+                        entry_index: idx,
+                    });
+                }
+            }
+        }
+
+        let Some(referer) = entry
+            .request
+            .headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case("referer"))
+            .map(|h| h.value.as_str())
+        else {
+            return;
+        };
+        let params = Self::response_params(referer);
+        let cross_origin = Self::origin(referer) != Self::origin(&entry.request.url);
+
+        if params.contains_key("access_token") || params.contains_key("id_token") {
+            issues.push(OAuthSecurityIssue {
+                severity: if cross_origin {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                },
+                issue_type: OAuthIssueType::TokenInReferer,
+                message: if cross_origin {
+                    "OAuth token leaked to another origin via Referer".to_string()
+                } else {
+                    "OAuth token sent in Referer header".to_string()
+                },
+                entry_index: idx,
+            });
+        } else if params.contains_key("code") && params.contains_key("state") && cross_origin {
+            issues.push(OAuthSecurityIssue {
+                severity: Severity::Warning,
+                issue_type: OAuthIssueType::TokenInReferer,
+                message: "Authorization code leaked to another origin via Referer".to_string(),
+                entry_index: idx,
+            });
+        }
+    }
+
+    fn origin(url: &str) -> Option<String> {
+        url::Url::parse(url)
+            .ok()
+            .map(|u| u.origin().ascii_serialization())
+    }
+
+    fn strip_query(url: &str) -> &str {
+        url.split(['?', '#']).next().unwrap_or(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weak_verifier_reason() {
+        assert!(OAuthAnalyzer::weak_verifier_reason("short").is_some());
+        assert!(OAuthAnalyzer::weak_verifier_reason(&"a".repeat(64)).is_some());
+        assert!(
+            OAuthAnalyzer::weak_verifier_reason("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_pkce_matches() {
+        // Example from RFC 7636 Appendix B
+        assert!(OAuthAnalyzer::pkce_matches(
+            "S256",
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        ));
+        assert!(!OAuthAnalyzer::pkce_matches(
+            "S256",
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+            "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"
+        ));
+        assert!(OAuthAnalyzer::pkce_matches("plain", "abc", "abc"));
+    }
+
+    #[test]
+    fn test_response_params_reads_fragment() {
+        let params =
+            OAuthAnalyzer::response_params("https://app.example.com/cb#access_token=abc&state=xyz");
+        assert_eq!(params.get("access_token").map(String::as_str), Some("abc"));
+        assert_eq!(params.get("state").map(String::as_str), Some("xyz"));
+    }
+
+    #[test]
+    fn test_oauth_issue_type_as_str() {
+        assert_eq!(OAuthIssueType::PlainPkce.as_str(), "Plain PKCE");
+        assert_eq!(
+            OAuthIssueType::WeakProviderConfig.as_str(),
+            "Weak provider configuration"
+        );
+    }
+}
@@ -2,6 +2,11 @@ use crate::Result;
 use harrier_core::har::{Entry, Har};
 use serde::{Deserialize, Serialize};
 
+use super::saml_message::{SamlMessage, SamlMessageDecoder, SamlMessageKind};
+
+/// Longest assertion validity window (NotBefore to NotOnOrAfter) considered reasonable
+const MAX_ASSERTION_VALIDITY_SECS: i64 = 3600;
+
 /// Represents a detected SAML authentication flow
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SamlFlow {
@@ -11,6 +16,8 @@ pub struct SamlFlow {
     pub steps: Vec<SamlStep>,
     pub idp_entity_id: Option<String>,
     pub sp_entity_id: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<SamlMessage>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -99,9 +106,169 @@ impl SamlDetector {
             }
         }
 
+        // Decode SAML payloads and attach them to their flows
+        let messages: Vec<SamlMessage> = entries
+            .iter()
+            .enumerate()
+            .flat_map(|(i, entry)| SamlMessageDecoder::from_entry(i, entry))
+            .collect();
+        for flow in &mut flows {
+            flow.messages = messages
+                .iter()
+                .filter(|m| flow.steps.iter().any(|s| s.entry_index == m.entry_index))
+                .cloned()
+                .collect();
+            flow.idp_entity_id = flow
+                .messages
+                .iter()
+                .find(|m| m.kind == SamlMessageKind::Response)
+                .and_then(|m| m.issuer.clone());
+            flow.sp_entity_id = flow
+                .messages
+                .iter()
+                .find(|m| m.kind == SamlMessageKind::AuthnRequest)
+                .and_then(|m| m.issuer.clone())
+                .or_else(|| {
+                    flow.messages
+                        .iter()
+                        .flat_map(|m| &m.assertions)
+                        .find_map(|a| a.audiences.first().cloned())
+                });
+        }
+
+        Self::check_messages(entries, &flows, &messages, &mut security_issues);
+
         Ok((flows, security_issues))
     }
 
+    /// Security checks on decoded SAML responses and assertions
+    fn check_messages(
+        entries: &[Entry],
+        flows: &[SamlFlow],
+        messages: &[SamlMessage],
+        security_issues: &mut Vec<SamlSecurityIssue>,
+    ) {
+        let request_ids: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.kind == SamlMessageKind::AuthnRequest)
+            .filter_map(|m| m.id.as_deref())
+            .collect();
+
+        for message in messages
+            .iter()
+            .filter(|m| m.kind == SamlMessageKind::Response)
+        {
+            let idx = message.entry_index;
+            let mut push = |severity, message: String| {
+                security_issues.push(SamlSecurityIssue {
+                    severity,
+                    message,
+                    entry_index: idx,
+                });
+            };
+
+            // Assertions delivered over plain HTTP (the SP flow check already covers its own responses)
+            if entries[idx].request.url.starts_with("http://")
+                && !Self::is_part_of_sp_flow(flows, idx)
+            {
+                push(
+                    super::security::Severity::Critical,
+                    "SAML Response sent over unencrypted HTTP".to_string(),
+                );
+            }
+            let http_destination = message
+                .destination
+                .iter()
+                .chain(
+                    message
+                        .assertions
+                        .iter()
+                        .filter_map(|a| a.recipient.as_ref()),
+                )
+                .any(|url| url.starts_with("http://"));
+            if http_destination {
+                push(
+                    super::security::Severity::Critical,
+                    "SAML assertion addressed to an unencrypted HTTP endpoint".to_string(),
+                );
+            }
+
+            // InResponseTo must echo the AuthnRequest ID
+            match message.in_response_to.as_deref() {
+                Some(id) if !request_ids.is_empty() && !request_ids.contains(&id) => push(
+                    super::security::Severity::Warning,
+                    format!(
+                        "SAML Response InResponseTo '{}' does not match any captured AuthnRequest ID",
+                        id
+                    ),
+                ),
+                None if !request_ids.is_empty() => push(
+                    super::security::Severity::Warning,
+                    "Unsolicited SAML Response (no InResponseTo) after an AuthnRequest".to_string(),
+                ),
+                _ => {}
+            }
+
+            for assertion in &message.assertions {
+                if !message.signed && !assertion.signed {
+                    push(
+                        super::security::Severity::Critical,
+                        "SAML Response and assertion are both unsigned".to_string(),
+                    );
+                }
+
+                if assertion.audiences.is_empty() {
+                    push(
+                        super::security::Severity::Warning,
+                        "SAML assertion has no AudienceRestriction".to_string(),
+                    );
+                }
+
+                if let (Some(confirmed), Some(expected)) = (
+                    assertion.in_response_to.as_deref(),
+                    message.in_response_to.as_deref(),
+                ) && confirmed != expected
+                {
+                    push(
+                        super::security::Severity::Warning,
+                        "SAML assertion SubjectConfirmationData InResponseTo differs from the Response"
+                            .to_string(),
+                    );
+                }
+
+                match assertion.not_on_or_after.as_deref() {
+                    None => push(
+                        super::security::Severity::Warning,
+                        "SAML assertion has no NotOnOrAfter condition".to_string(),
+                    ),
+                    Some(not_on_or_after) => {
+                        let start = assertion
+                            .not_before
+                            .as_deref()
+                            .or(message.issue_instant.as_deref());
+                        if let Some(validity) = Self::validity_secs(start, not_on_or_after)
+                            && validity > MAX_ASSERTION_VALIDITY_SECS
+                        {
+                            push(
+                                super::security::Severity::Warning,
+                                format!(
+                                    "SAML assertion valid for {} minutes (NotBefore to NotOnOrAfter)",
+                                    validity / 60
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn validity_secs(start: Option<&str>, end: &str) -> Option<i64> {
+        let start = chrono::DateTime::parse_from_rfc3339(start?).ok()?;
+        let end = chrono::DateTime::parse_from_rfc3339(end).ok()?;
+        Some(end.signed_duration_since(start).num_seconds())
+    }
+
     fn build_sp_initiated_flow(
         entries: &[Entry],
         start_idx: usize,
@@ -185,6 +352,7 @@ impl SamlDetector {
                 steps,
                 idp_entity_id: None,
                 sp_entity_id: None,
+                messages: Vec::new(),
             })
         } else {
             None
@@ -223,6 +391,7 @@ impl SamlDetector {
             steps,
             idp_entity_id: None,
             sp_entity_id: None,
+            messages: Vec::new(),
         })
     }
 
@@ -260,6 +429,7 @@ impl SamlDetector {
             steps,
             idp_entity_id: None,
             sp_entity_id: None,
+            messages: Vec::new(),
         })
     }
 
@@ -435,6 +605,22 @@ mod tests {
         assert!(!SamlDetector::is_saml_response(&entry2));
     }
 
+    #[test]
+    fn test_validity_secs() {
+        assert_eq!(
+            SamlDetector::validity_secs(Some("2024-01-15T10:00:00Z"), "2024-01-15T18:00:00Z"),
+            Some(8 * 3600)
+        );
+        assert_eq!(
+            SamlDetector::validity_secs(None, "2024-01-15T18:00:00Z"),
+            None
+        );
+        assert_eq!(
+            SamlDetector::validity_secs(Some("not a date"), "2024-01-15T18:00:00Z"),
+            None
+        );
+    }
+
     fn create_test_entry(url: &str, method: &str) -> Entry {
         use harrier_core::har::*;
         Entry {
@@ -490,6 +676,7 @@ mod tests {
             server_ip_address: None,
             connection: None,
             comment: None,
+            web_socket_messages: None,
         }
     }
 }
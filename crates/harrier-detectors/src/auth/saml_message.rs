@@ -0,0 +1,356 @@
+use crate::Result;
+use harrier_core::har::Entry;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+/// A decoded SAML protocol message (AuthnRequest, Response, LogoutRequest, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlMessage {
+    pub entry_index: usize,
+    pub kind: SamlMessageKind,
+    pub binding: SamlBinding,
+    pub id: Option<String>,
+    pub in_response_to: Option<String>,
+    pub issuer: Option<String>,
+    pub destination: Option<String>,
+    pub issue_instant: Option<String>,
+    pub status_code: Option<String>,
+    pub signed: bool, // XML signature on the message, or Signature parameter for the redirect binding
+    pub assertions: Vec<SamlAssertion>,
+    pub encrypted_assertions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamlMessageKind {
+    AuthnRequest,
+    Response,
+    LogoutRequest,
+    LogoutResponse,
+    Other,
+}
+
+impl SamlMessageKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SamlMessageKind::AuthnRequest => "AuthnRequest",
+            SamlMessageKind::Response => "Response",
+            SamlMessageKind::LogoutRequest => "LogoutRequest",
+            SamlMessageKind::LogoutResponse => "LogoutResponse",
+            SamlMessageKind::Other => "Other",
+        }
+    }
+}
+
+/// How the message travelled between SP and IdP
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamlBinding {
+    /// HTTP-Redirect: DEFLATE + base64 in the query string
+    Redirect,
+    /// HTTP-POST: base64 in a form field
+    Post,
+}
+
+impl SamlBinding {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SamlBinding::Redirect => "HTTP-Redirect",
+            SamlBinding::Post => "HTTP-POST",
+        }
+    }
+}
+
+/// Contents of a (non-encrypted) SAML assertion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlAssertion {
+    pub id: Option<String>,
+    pub issuer: Option<String>,
+    pub signed: bool,
+    pub name_id: Option<String>,
+    pub name_id_format: Option<String>,
+    pub audiences: Vec<String>,
+    pub not_before: Option<String>,
+    pub not_on_or_after: Option<String>,
+    pub recipient: Option<String>,
+    pub in_response_to: Option<String>,
+    pub attributes: Vec<SamlAttribute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlAttribute {
+    pub name: String,
+    pub friendly_name: Option<String>,
+    pub values: Vec<String>,
+}
+
+/// Upper bound for inflated redirect-binding payloads
+const MAX_INFLATED_SIZE: u64 = 1024 * 1024;
+
+const XMLDSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+pub struct SamlMessageDecoder;
+
+impl SamlMessageDecoder {
+    /// Decode every SAMLRequest/SAMLResponse carried by a HAR entry
+    pub fn from_entry(entry_index: usize, entry: &Entry) -> Vec<SamlMessage> {
+        let mut messages = Vec::new();
+
+        if let Ok(url) = url::Url::parse(&entry.request.url) {
+            let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+            let signed = params.iter().any(|(k, _)| k == "Signature");
+            for (key, value) in &params {
+                if (key == "SAMLRequest" || key == "SAMLResponse")
+                    && let Ok(mut message) = Self::decode(entry_index, value, SamlBinding::Redirect)
+                {
+                    message.signed |= signed;
+                    messages.push(message);
+                }
+            }
+        }
+
+        if let Some(ref post_data) = entry.request.post_data
+            && let Some(ref text) = post_data.text
+        {
+            for (key, value) in url::form_urlencoded::parse(text.as_bytes()) {
+                if (key == "SAMLRequest" || key == "SAMLResponse")
+                    && let Ok(message) = Self::decode(entry_index, &value, SamlBinding::Post)
+                {
+                    messages.push(message);
+                }
+            }
+        }
+
+        messages
+    }
+
+    /// Decode a base64 (and, for the redirect binding, DEFLATE) encoded SAML message
+    pub fn decode(entry_index: usize, encoded: &str, binding: SamlBinding) -> Result<SamlMessage> {
+        let xml = Self::decode_xml(encoded)?;
+        Self::parse(entry_index, &xml, binding)
+    }
+
+    fn decode_xml(encoded: &str) -> Result<String> {
+        use base64::engine::Engine;
+        use base64::engine::general_purpose::STANDARD;
+
+        let compact: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = STANDARD
+            .decode(compact.as_bytes())
+            .map_err(|e| crate::Error::Parse(format!("SAML base64 decode failed: {}", e)))?;
+
+        // POST binding carries plain XML; redirect binding deflates it first
+        if bytes.trim_ascii_start().starts_with(b"<") {
+            return String::from_utf8(bytes)
+                .map_err(|e| crate::Error::Parse(format!("SAML message is not UTF-8: {}", e)));
+        }
+
+        let mut xml = String::new();
+        flate2::read::DeflateDecoder::new(bytes.as_slice())
+            .take(MAX_INFLATED_SIZE)
+            .read_to_string(&mut xml)
+            .map_err(|e| crate::Error::Parse(format!("SAML inflate failed: {}", e)))?;
+        Ok(xml)
+    }
+
+    /// Parse SAML protocol XML (DTDs are rejected, so entity expansion is not possible)
+    pub fn parse(entry_index: usize, xml: &str, binding: SamlBinding) -> Result<SamlMessage> {
+        let doc = roxmltree::Document::parse(xml)
+            .map_err(|e| crate::Error::Parse(format!("SAML XML parse failed: {}", e)))?;
+        let root = doc.root_element();
+
+        let kind = match root.tag_name().name() {
+            "AuthnRequest" => SamlMessageKind::AuthnRequest,
+            "Response" => SamlMessageKind::Response,
+            "LogoutRequest" => SamlMessageKind::LogoutRequest,
+            "LogoutResponse" => SamlMessageKind::LogoutResponse,
+            _ => SamlMessageKind::Other,
+        };
+
+        let status_code = Self::child(root, "Status")
+            .and_then(|status| Self::child(status, "StatusCode"))
+            .and_then(|code| code.attribute("Value"))
+            .map(String::from);
+
+        let assertions = root
+            .children()
+            .filter(|n| n.is_element() && n.tag_name().name() == "Assertion")
+            .map(Self::parse_assertion)
+            .collect();
+
+        Ok(SamlMessage {
+            entry_index,
+            kind,
+            binding,
+            id: root.attribute("ID").map(String::from),
+            in_response_to: root.attribute("InResponseTo").map(String::from),
+            issuer: Self::child_text(root, "Issuer"),
+            destination: root.attribute("Destination").map(String::from),
+            issue_instant: root.attribute("IssueInstant").map(String::from),
+            status_code,
+            signed: Self::is_signed(root),
+            assertions,
+            encrypted_assertions: root
+                .children()
+                .filter(|n| n.is_element() && n.tag_name().name() == "EncryptedAssertion")
+                .count(),
+        })
+    }
+
+    fn parse_assertion(node: roxmltree::Node) -> SamlAssertion {
+        let subject = Self::child(node, "Subject");
+        let name_id = subject.and_then(|s| Self::child(s, "NameID"));
+        let confirmation_data = subject
+            .and_then(|s| Self::child(s, "SubjectConfirmation"))
+            .and_then(|c| Self::child(c, "SubjectConfirmationData"));
+        let conditions = Self::child(node, "Conditions");
+
+        let audiences = conditions
+            .map(|c| {
+                c.children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "AudienceRestriction")
+                    .flat_map(|restriction| {
+                        restriction
+                            .children()
+                            .filter(|n| n.is_element() && n.tag_name().name() == "Audience")
+                            .filter_map(|a| a.text())
+                            .map(|t| t.trim().to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let attributes = Self::child(node, "AttributeStatement")
+            .map(|statement| {
+                statement
+                    .children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "Attribute")
+                    .map(|attr| SamlAttribute {
+                        name: attr.attribute("Name").unwrap_or_default().to_string(),
+                        friendly_name: attr.attribute("FriendlyName").map(String::from),
+                        values: attr
+                            .children()
+                            .filter(|n| n.is_element() && n.tag_name().name() == "AttributeValue")
+                            .map(|v| v.text().unwrap_or_default().trim().to_string())
+                            .collect(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        SamlAssertion {
+            id: node.attribute("ID").map(String::from),
+            issuer: Self::child_text(node, "Issuer"),
+            signed: Self::is_signed(node),
+            name_id: name_id.and_then(|n| n.text()).map(|t| t.trim().to_string()),
+            name_id_format: name_id
+                .and_then(|n| n.attribute("Format"))
+                .map(String::from),
+            audiences,
+            not_before: conditions
+                .and_then(|c| c.attribute("NotBefore"))
+                .map(String::from),
+            not_on_or_after: conditions
+                .and_then(|c| c.attribute("NotOnOrAfter"))
+                .map(String::from),
+            recipient: confirmation_data
+                .and_then(|d| d.attribute("Recipient"))
+                .map(String::from),
+            in_response_to: confirmation_data
+                .and_then(|d| d.attribute("InResponseTo"))
+                .map(String::from),
+            attributes,
+        }
+    }
+
+    /// An enveloped signature is a direct ds:Signature child of the signed element
+    fn is_signed(node: roxmltree::Node) -> bool {
+        node.children().any(|n| {
+            n.is_element()
+                && n.tag_name().name() == "Signature"
+                && n.tag_name().namespace() == Some(XMLDSIG_NS)
+        })
+    }
+
+    fn child<'a, 'input>(
+        node: roxmltree::Node<'a, 'input>,
+        name: &str,
+    ) -> Option<roxmltree::Node<'a, 'input>> {
+        node.children()
+            .find(|n| n.is_element() && n.tag_name().name() == name)
+    }
+
+    fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+        Self::child(node, name)
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use std::io::Write;
+
+    const RESPONSE: &str = r#"<samlp:Response xmlns:samlp="urn:oasis:names:tc:SAML:2.0:protocol" xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion" ID="_resp1" InResponseTo="_req1" Destination="https://sp.example.com/acs" IssueInstant="2024-01-15T10:30:00Z">
+  <saml:Issuer>https://idp.example.com</saml:Issuer>
+  <samlp:Status><samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Success"/></samlp:Status>
+  <saml:Assertion ID="_a1">
+    <saml:Issuer>https://idp.example.com</saml:Issuer>
+    <ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#"/>
+    <saml:Subject>
+      <saml:NameID Format="urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress">alice@example.com</saml:NameID>
+      <saml:SubjectConfirmation><saml:SubjectConfirmationData InResponseTo="_req1" Recipient="https://sp.example.com/acs"/></saml:SubjectConfirmation>
+    </saml:Subject>
+    <saml:Conditions NotBefore="2024-01-15T10:29:00Z" NotOnOrAfter="2024-01-15T10:35:00Z">
+      <saml:AudienceRestriction><saml:Audience>https://sp.example.com</saml:Audience></saml:AudienceRestriction>
+    </saml:Conditions>
+    <saml:AttributeStatement>
+      <saml:Attribute Name="groups"><saml:AttributeValue>admins</saml:AttributeValue><saml:AttributeValue>staff</saml:AttributeValue></saml:Attribute>
+    </saml:AttributeStatement>
+  </saml:Assertion>
+</samlp:Response>"#;
+
+    #[test]
+    fn test_decode_post_binding_response() {
+        let encoded = STANDARD.encode(RESPONSE);
+        let message = SamlMessageDecoder::decode(3, &encoded, SamlBinding::Post).unwrap();
+
+        assert_eq!(message.kind, SamlMessageKind::Response);
+        assert_eq!(message.in_response_to.as_deref(), Some("_req1"));
+        assert_eq!(message.issuer.as_deref(), Some("https://idp.example.com"));
+        assert!(!message.signed);
+
+        let assertion = &message.assertions[0];
+        assert!(assertion.signed);
+        assert_eq!(assertion.name_id.as_deref(), Some("alice@example.com"));
+        assert_eq!(
+            assertion.name_id_format.as_deref(),
+            Some("urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress")
+        );
+        assert_eq!(assertion.audiences, vec!["https://sp.example.com"]);
+        assert_eq!(assertion.attributes[0].values, vec!["admins", "staff"]);
+    }
+
+    #[test]
+    fn test_decode_redirect_binding_request() {
+        let xml = r#"<samlp:AuthnRequest xmlns:samlp="urn:oasis:names:tc:SAML:2.0:protocol" ID="_req1"><saml:Issuer xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion">https://sp.example.com</saml:Issuer></samlp:AuthnRequest>"#;
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(xml.as_bytes()).unwrap();
+        let encoded = STANDARD.encode(encoder.finish().unwrap());
+
+        let message = SamlMessageDecoder::decode(0, &encoded, SamlBinding::Redirect).unwrap();
+        assert_eq!(message.kind, SamlMessageKind::AuthnRequest);
+        assert_eq!(message.id.as_deref(), Some("_req1"));
+        assert_eq!(message.issuer.as_deref(), Some("https://sp.example.com"));
+    }
+
+    #[test]
+    fn test_parse_rejects_dtd() {
+        let xml = r#"<!DOCTYPE r [<!ENTITY x "y">]><samlp:Response xmlns:samlp="urn:oasis:names:tc:SAML:2.0:protocol">&x;</samlp:Response>"#;
+        assert!(SamlMessageDecoder::parse(0, xml, SamlBinding::Post).is_err());
+    }
+}
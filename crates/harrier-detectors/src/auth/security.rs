@@ -1,8 +1,9 @@
 use crate::Result;
 use harrier_core::har::Har;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::methods::AuthMethod;
+use super::methods::{AuthDetector, AuthMethod};
 use super::sessions::{AuthSession, SessionType};
 
 /// Security observation with severity level
@@ -66,6 +67,66 @@ impl SecurityAnalyzer {
                         entry_index: None,
                     });
                 }
+                AuthMethod::Digest => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Warning,
+                        category: "Authentication Method".to_string(),
+                        message: "HTTP Digest authentication detected - responses are MD5-based unless the server offers SHA-256 and can be cracked offline (use HTTPS)".to_string(),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::Ntlm => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Warning,
+                        category: "Authentication Method".to_string(),
+                        message: "NTLM authentication detected - handshakes can be relayed or cracked offline (prefer Kerberos)".to_string(),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::Negotiate => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Info,
+                        category: "Authentication Method".to_string(),
+                        message: "Negotiate (Kerberos/SPNEGO) authentication detected - scans need domain credentials".to_string(),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::AwsSigV4 => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Info,
+                        category: "Authentication Method".to_string(),
+                        message: "AWS SigV4 request signing detected - every modified request must be re-signed".to_string(),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::Hmac(name) => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Info,
+                        category: "Authentication Method".to_string(),
+                        message: format!(
+                            "HMAC request signing detected ({}) - every modified request must be re-signed",
+                            name
+                        ),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::Dpop => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Info,
+                        category: "Authentication Method".to_string(),
+                        message: "DPoP sender-constrained tokens detected - every request needs a fresh proof signed by the client key".to_string(),
+                        entry_index: None,
+                    });
+                }
+                AuthMethod::MutualTls => {
+                    notes.push(SecurityNote {
+                        severity: Severity::Info,
+                        category: "Authentication Method".to_string(),
+                        message: "Mutual TLS client certificate authentication detected"
+                            .to_string(),
+                        entry_index: None,
+                    });
+                }
                 _ => {}
             }
         }
@@ -162,8 +223,61 @@ impl SecurityAnalyzer {
     fn analyze_requests(har: &Har) -> Vec<SecurityNote> {
         let mut notes = Vec::new();
         let mut http_auth_seen = false;
+        let mut digest_without_qop_seen = false;
+        let mut ntlm_fallback_seen = false;
+        let mut dpop_proofs: HashMap<&str, usize> = HashMap::new();
 
         for (idx, entry) in har.log.entries.iter().enumerate() {
+            // Digest challenges without qop fall back to RFC 2069 (no client nonce)
+            if !digest_without_qop_seen
+                && entry.response.headers.iter().any(|h| {
+                    let (scheme, rest) = AuthDetector::split_scheme(&h.value);
+                    h.name.eq_ignore_ascii_case("www-authenticate")
+                        && scheme.eq_ignore_ascii_case("digest")
+                        && !AuthDetector::auth_params(rest).contains_key("qop")
+                })
+            {
+                notes.push(SecurityNote {
+                    severity: Severity::Warning,
+                    category: "Authentication Method".to_string(),
+                    message: "Digest challenge without qop (RFC 2069 mode has no client nonce or integrity protection)".to_string(),
+                    entry_index: Some(idx),
+                });
+                digest_without_qop_seen = true;
+            }
+
+            for header in &entry.request.headers {
+                let name = header.name.to_lowercase();
+                let (scheme, credentials) = AuthDetector::split_scheme(&header.value);
+
+                // Kerberos unavailable, so Negotiate carried an NTLM token instead
+                if name == "authorization"
+                    && scheme.eq_ignore_ascii_case("negotiate")
+                    && AuthDetector::ntlm_message_type(credentials).is_some()
+                    && !ntlm_fallback_seen
+                {
+                    notes.push(SecurityNote {
+                        severity: Severity::Warning,
+                        category: "Authentication Method".to_string(),
+                        message: "Negotiate authentication fell back to NTLM".to_string(),
+                        entry_index: Some(idx),
+                    });
+                    ntlm_fallback_seen = true;
+                }
+
+                // DPoP proofs are single-use (RFC 9449 section 11.1)
+                if name == "dpop" && *dpop_proofs.entry(header.value.as_str()).or_insert(idx) != idx
+                {
+                    notes.push(SecurityNote {
+                        severity: Severity::Warning,
+                        category: "Token Security".to_string(),
+                        message: "DPoP proof reused across requests (proofs must be single-use)"
+                            .to_string(),
+                        entry_index: Some(idx),
+                    });
+                }
+            }
+
             // Check for authentication over HTTP (not HTTPS)
             if entry.request.url.starts_with("http://") {
                 // Check if this request has authentication
@@ -0,0 +1,457 @@
+use harrier_core::har::{Entry, Har};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::events::{AuthEvent, AuthEventType};
+use super::security::Severity;
+use super::sessions::{AuthSession, SessionTracker, SessionType};
+
+/// Session identifier lifecycle around login and logout events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionLifecycleAnalysis {
+    pub login_rotations: Vec<SessionRotation>,
+    pub logout_checks: Vec<LogoutInvalidation>,
+    pub concurrent_sessions: Vec<ConcurrentSessions>,
+    pub issues: Vec<SessionLifecycleIssue>,
+}
+
+/// Whether a session cookie changed value across a successful login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRotation {
+    pub login_entry: usize,
+    pub cookie_name: String,
+    pub user: Option<String>,
+    pub pre_login: String,  // Preview of the value sent with the login request
+    pub post_login: String, // Preview of the value set by, or used after, the login
+    pub rotated: bool,
+}
+
+/// Whether a session identifier kept working after logout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogoutInvalidation {
+    pub logout_entry: usize,
+    pub identifier: String,
+    pub status: InvalidationStatus,
+    pub reused_entries: Vec<usize>, // Later requests that presented the same identifier
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidationStatus {
+    /// Reuse after logout was rejected (401/403 or redirect to login)
+    Invalidated,
+    /// Reuse after logout still succeeded
+    StillValid,
+    /// The identifier was not presented again after logout
+    NotTested,
+}
+
+impl InvalidationStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            InvalidationStatus::Invalidated => "Invalidated",
+            InvalidationStatus::StillValid => "Still valid",
+            InvalidationStatus::NotTested => "Not tested",
+        }
+    }
+}
+
+/// Several values of one session cookie in use for the same user at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrentSessions {
+    pub user: String,
+    pub cookie_name: String,
+    pub identifiers: Vec<String>,
+    pub entry_indices: Vec<usize>, // Login requests that established each session
+}
+
+/// Session lifecycle security issues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLifecycleIssue {
+    pub severity: Severity,
+    pub issue_type: SessionIssueType,
+    pub message: String,
+    pub entry_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionIssueType {
+    SessionFixation,
+    NotInvalidatedOnLogout,
+    ConcurrentSessions,
+}
+
+impl SessionIssueType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            SessionIssueType::SessionFixation => "Session fixation",
+            SessionIssueType::NotInvalidatedOnLogout => "Not invalidated on logout",
+            SessionIssueType::ConcurrentSessions => "Concurrent sessions",
+        }
+    }
+}
+
+/// Session established by a login: cookie value, owner and where it was in use
+struct EstablishedSession {
+    login_entry: usize,
+    user: String,
+    value: String,
+    first_used: usize,
+    last_used: usize,
+}
+
+pub struct SessionLifecycleAnalyzer;
+
+impl SessionLifecycleAnalyzer {
+    /// Check session identifier rotation at login, invalidation at logout and concurrent sessions
+    pub fn analyze(
+        har: &Har,
+        events: &[AuthEvent],
+        sessions: &[AuthSession],
+    ) -> SessionLifecycleAnalysis {
+        let entries = &har.log.entries;
+        let mut analysis = SessionLifecycleAnalysis::default();
+
+        let mut credentials: Vec<&SessionType> = Vec::new();
+        for session in sessions {
+            if !credentials.contains(&&session.session_type) {
+                credentials.push(&session.session_type);
+            }
+        }
+        let cookie_names: Vec<&str> = credentials
+            .iter()
+            .filter_map(|c| match c {
+                SessionType::Cookie { name } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let mut logins: Vec<usize> = events
+            .iter()
+            .filter(|e| e.event_type == AuthEventType::LoginSuccess)
+            .map(|e| e.entry_index)
+            .collect();
+        logins.sort_unstable();
+        let mut logouts: Vec<usize> = events
+            .iter()
+            .filter(|e| e.event_type == AuthEventType::Logout && e.status < 400)
+            .map(|e| e.entry_index)
+            .collect();
+        logouts.sort_unstable();
+
+        // Session fixation: the pre-login cookie value survives the login
+        let mut established: HashMap<&str, Vec<EstablishedSession>> = HashMap::new();
+        for &login_idx in &logins {
+            let Some(login) = entries.get(login_idx) else {
+                continue;
+            };
+            let user = Self::login_user(login);
+            let window_end = Self::next_boundary(&logins, &logouts, login_idx, entries.len());
+
+            for &name in &cookie_names {
+                let pre_login = Self::request_cookie(login, name);
+                let post_login = Self::response_cookie(login, name).or_else(|| {
+                    entries[login_idx + 1..window_end]
+                        .iter()
+                        .find_map(|e| Self::request_cookie(e, name))
+                });
+                let Some(post_login) = post_login else {
+                    continue;
+                };
+
+                if let Some(ref user) = user {
+                    let used: Vec<usize> = (login_idx..entries.len())
+                        .filter(|&i| {
+                            Self::request_cookie(&entries[i], name).as_deref()
+                                == Some(post_login.as_str())
+                        })
+                        .collect();
+                    established
+                        .entry(name)
+                        .or_default()
+                        .push(EstablishedSession {
+                            login_entry: login_idx,
+                            user: user.clone(),
+                            value: post_login.clone(),
+                            first_used: used.first().copied().unwrap_or(login_idx),
+                            last_used: used.last().copied().unwrap_or(login_idx),
+                        });
+                }
+
+                let Some(pre_login) = pre_login else {
+                    continue;
+                };
+                let rotated = pre_login != post_login;
+                if !rotated {
+                    analysis.issues.push(SessionLifecycleIssue {
+                        severity: Severity::Critical,
+                        issue_type: SessionIssueType::SessionFixation,
+                        message: format!(
+                            "Session cookie '{}' keeps its pre-login value after successful login",
+                            name
+                        ),
+                        entry_index: login_idx,
+                    });
+                }
+                analysis.login_rotations.push(SessionRotation {
+                    login_entry: login_idx,
+                    cookie_name: name.to_string(),
+                    user: user.clone(),
+                    pre_login: SessionTracker::truncate_value(&pre_login),
+                    post_login: SessionTracker::truncate_value(&post_login),
+                    rotated,
+                });
+            }
+        }
+
+        // Logout invalidation: the identifier presented at logout is accepted afterwards
+        for &logout_idx in &logouts {
+            let window_end = Self::next_boundary(&logins, &[], logout_idx, entries.len());
+
+            for credential in &credentials {
+                if matches!(credential, SessionType::ApiKey { .. }) {
+                    continue;
+                }
+                let Some(value) = entries[..=logout_idx]
+                    .iter()
+                    .rev()
+                    .find_map(|e| Self::presented(e, credential))
+                else {
+                    continue;
+                };
+
+                let mut accepted = Vec::new();
+                let mut rejected = false;
+                for (i, entry) in entries
+                    .iter()
+                    .enumerate()
+                    .take(window_end)
+                    .skip(logout_idx + 1)
+                {
+                    if Self::presented(entry, credential).as_deref() != Some(value.as_str()) {
+                        continue;
+                    }
+                    if (200..300).contains(&entry.response.status) {
+                        accepted.push(i);
+                    } else if Self::is_rejection(entry) {
+                        rejected = true;
+                    }
+                }
+
+                let identifier = Self::identifier(credential, &value);
+                let status = if !accepted.is_empty() {
+                    analysis.issues.push(SessionLifecycleIssue {
+                        severity: Severity::Critical,
+                        issue_type: SessionIssueType::NotInvalidatedOnLogout,
+                        message: format!(
+                            "{} still accepted after logout (no server-side invalidation)",
+                            Self::credential_label(credential)
+                        ),
+                        entry_index: accepted[0],
+                    });
+                    InvalidationStatus::StillValid
+                } else if rejected {
+                    InvalidationStatus::Invalidated
+                } else {
+                    InvalidationStatus::NotTested
+                };
+
+                analysis.logout_checks.push(LogoutInvalidation {
+                    logout_entry: logout_idx,
+                    identifier,
+                    status,
+                    reused_entries: accepted,
+                });
+            }
+        }
+
+        // Concurrent sessions: overlapping use of two values established for the same user
+        let mut names: Vec<&&str> = established.keys().collect();
+        names.sort();
+        for name in names {
+            let mut by_user: HashMap<&str, Vec<&EstablishedSession>> = HashMap::new();
+            for session in &established[*name] {
+                by_user.entry(&session.user).or_default().push(session);
+            }
+            let mut users: Vec<_> = by_user.into_iter().collect();
+            users.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (user, user_sessions) in users {
+                let concurrent: Vec<&EstablishedSession> = user_sessions
+                    .iter()
+                    .filter(|a| {
+                        user_sessions.iter().any(|b| {
+                            a.value != b.value
+                                && a.first_used <= b.last_used
+                                && b.first_used <= a.last_used
+                        })
+                    })
+                    .copied()
+                    .collect();
+                if concurrent.len() < 2 {
+                    continue;
+                }
+
+                analysis.issues.push(SessionLifecycleIssue {
+                    severity: Severity::Info,
+                    issue_type: SessionIssueType::ConcurrentSessions,
+                    message: format!(
+                        "User has {} concurrent '{}' sessions",
+                        concurrent.len(),
+                        name
+                    ),
+                    entry_index: concurrent[1].login_entry,
+                });
+                analysis.concurrent_sessions.push(ConcurrentSessions {
+                    user: user.to_string(),
+                    cookie_name: name.to_string(),
+                    identifiers: concurrent
+                        .iter()
+                        .map(|s| format!("{}={}", name, SessionTracker::truncate_value(&s.value)))
+                        .collect(),
+                    entry_indices: concurrent.iter().map(|s| s.login_entry).collect(),
+                });
+            }
+        }
+
+        analysis
+    }
+
+    /// Index of the next login or logout after `idx`, bounding the requests attributed to it
+    fn next_boundary(logins: &[usize], logouts: &[usize], idx: usize, len: usize) -> usize {
+        logins
+            .iter()
+            .chain(logouts)
+            .copied()
+            .filter(|&i| i > idx)
+            .min()
+            .unwrap_or(len)
+    }
+
+    /// Value of a session credential carried by a request
+    fn presented(entry: &Entry, credential: &SessionType) -> Option<String> {
+        match credential {
+            SessionType::Cookie { name } => Self::request_cookie(entry, name),
+            SessionType::BearerToken { .. } => entry
+                .request
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("authorization"))
+                .and_then(|h| h.value.strip_prefix("Bearer "))
+                .map(|token| token.trim().to_string()),
+            SessionType::ApiKey { header_name } => entry
+                .request
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case(header_name))
+                .map(|h| h.value.clone()),
+        }
+    }
+
+    fn request_cookie(entry: &Entry, name: &str) -> Option<String> {
+        entry
+            .request
+            .cookies
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.value.clone())
+    }
+
+    fn response_cookie(entry: &Entry, name: &str) -> Option<String> {
+        entry
+            .response
+            .cookies
+            .iter()
+            .find(|c| c.name == name && !c.value.is_empty())
+            .map(|c| c.value.clone())
+    }
+
+    /// Reuse that the server refused: 401/403 or a redirect back to the login page
+    fn is_rejection(entry: &Entry) -> bool {
+        let status = entry.response.status;
+        if status == 401 || status == 403 {
+            return true;
+        }
+        if (300..400).contains(&status) {
+            let location = entry
+                .response
+                .headers
+                .iter()
+                .find(|h| h.name.eq_ignore_ascii_case("location"))
+                .map(|h| h.value.to_lowercase())
+                .unwrap_or_default();
+            return location.contains("login") || location.contains("signin");
+        }
+        false
+    }
+
+    /// Username submitted with a login request (form or JSON body)
+    fn login_user(entry: &Entry) -> Option<String> {
+        const FIELDS: [&str; 5] = ["username", "email", "login", "user", "user_name"];
+        let text = entry.request.post_data.as_ref()?.text.as_ref()?;
+
+        if let Ok(serde_json::Value::Object(map)) = serde_json::from_str(text) {
+            return FIELDS
+                .iter()
+                .find_map(|field| map.get(*field).and_then(|v| v.as_str()))
+                .map(String::from);
+        }
+
+        url::form_urlencoded::parse(text.as_bytes())
+            .find(|(key, _)| FIELDS.contains(&key.as_ref()))
+            .map(|(_, value)| value.into_owned())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn credential_label(credential: &SessionType) -> String {
+        match credential {
+            SessionType::Cookie { name } => format!("Session cookie '{}'", name),
+            SessionType::BearerToken { .. } => "Bearer token".to_string(),
+            SessionType::ApiKey { header_name } => format!("API key '{}'", header_name),
+        }
+    }
+
+    fn identifier(credential: &SessionType, value: &str) -> String {
+        match credential {
+            SessionType::Cookie { name } => {
+                format!("{}={}", name, SessionTracker::truncate_value(value))
+            }
+            SessionType::BearerToken { .. } => {
+                format!("Bearer {}", SessionTracker::truncate_value(value))
+            }
+            SessionType::ApiKey { header_name } => format!("{}: ***", header_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_boundary() {
+        assert_eq!(
+            SessionLifecycleAnalyzer::next_boundary(&[1, 5], &[3], 1, 10),
+            3
+        );
+        assert_eq!(
+            SessionLifecycleAnalyzer::next_boundary(&[1, 5], &[3], 5, 10),
+            10
+        );
+    }
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(SessionTracker::truncate_value("short"), "short");
+        assert_eq!(
+            SessionTracker::truncate_value("0123456789abcdef"),
+            "0123456789ab..."
+        );
+        // Cut falls inside a multibyte character when counting bytes
+        assert_eq!(
+            SessionTracker::truncate_value("sess-\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}"),
+            "sess-\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}..."
+        );
+    }
+}
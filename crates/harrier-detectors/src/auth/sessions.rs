@@ -3,6 +3,8 @@ use harrier_core::har::{Cookie, Entry, Har};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::truncate;
+
 /// Represents a tracked authentication session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthSession {
@@ -222,7 +224,7 @@ impl SessionTracker {
         })
     }
 
-    fn is_auth_cookie(name: &str) -> bool {
+    pub(crate) fn is_auth_cookie(name: &str) -> bool {
         let lower = name.to_lowercase();
         lower.contains("session")
             || lower.contains("auth")
@@ -236,7 +238,7 @@ impl SessionTracker {
             || lower.starts_with("__secure-")
     }
 
-    fn is_api_key_header(name: &str) -> bool {
+    pub(crate) fn is_api_key_header(name: &str) -> bool {
         let lower = name.to_lowercase();
         lower == "x-api-key"
             || lower == "api-key"
@@ -264,12 +266,8 @@ impl SessionTracker {
         }
     }
 
-    fn truncate_value(value: &str) -> String {
-        if value.len() > 12 {
-            format!("{}...", &value[..12])
-        } else {
-            value.to_string()
-        }
+    pub(crate) fn truncate_value(value: &str) -> String {
+        truncate(value, 12)
     }
 
     fn parse_timestamp(timestamp: &str) -> f64 {
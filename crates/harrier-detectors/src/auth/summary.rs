@@ -1,4 +1,7 @@
-use super::{AuthAnalysis, AuthFlowType, AuthMethod, SessionType};
+use super::{
+    AuthAnalysis, AuthFlowType, AuthMethod, LoginFormat, LoginRequest, SessionType, TokenExtraction,
+};
+use crate::yaml::quote;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
@@ -63,6 +66,38 @@ pub struct AggregatedFinding {
     pub sample_entries: Vec<usize>,
 }
 
+/// Findings keyed by message in a map per severity, with a few sample entries each
+#[derive(Default)]
+struct FindingCounts {
+    critical: HashMap<String, (usize, Vec<usize>)>,
+    warnings: HashMap<String, (usize, Vec<usize>)>,
+    info: HashMap<String, (usize, Vec<usize>)>,
+}
+
+impl FindingCounts {
+    /// Count each `(key, severity, entry)` finding, keeping up to three distinct entries
+    fn add_all<'a>(
+        &mut self,
+        findings: impl IntoIterator<Item = (String, &'a super::Severity, Option<usize>)>,
+    ) {
+        for (key, severity, entry_index) in findings {
+            let target_map = match severity {
+                super::Severity::Critical => &mut self.critical,
+                super::Severity::Warning => &mut self.warnings,
+                super::Severity::Info => &mut self.info,
+            };
+            let (count, entries) = target_map.entry(key).or_default();
+            *count += 1;
+            if let Some(idx) = entry_index
+                && entries.len() < 3
+                && !entries.contains(&idx)
+            {
+                entries.push(idx);
+            }
+        }
+    }
+}
+
 pub struct AuthSummaryGenerator;
 
 impl AuthSummaryGenerator {
@@ -95,51 +130,100 @@ impl AuthSummaryGenerator {
 
     /// Aggregate and deduplicate security findings
     pub fn aggregate_security_findings(analysis: &AuthAnalysis) -> SecurityFindingsSummary {
-        let mut critical = HashMap::new();
-        let mut warnings = HashMap::new();
-        let mut info = HashMap::new();
+        let mut counts = FindingCounts::default();
 
         // Aggregate security notes
-        for note in &analysis.security_notes {
-            let key = format!("{} - {}", note.category, note.message);
-            let target_map = match note.severity {
-                super::Severity::Critical => &mut critical,
-                super::Severity::Warning => &mut warnings,
-                super::Severity::Info => &mut info,
-            };
-
-            target_map
-                .entry(key.clone())
-                .and_modify(|(count, entries): &mut (usize, Vec<usize>)| {
-                    *count += 1;
-                    if entries.len() < 3
-                        && let Some(entry_idx) = note.entry_index
-                    {
-                        entries.push(entry_idx);
-                    }
-                })
-                .or_insert((1, note.entry_index.map(|i| vec![i]).unwrap_or_default()));
-        }
+        counts.add_all(analysis.security_notes.iter().map(|note| {
+            (
+                format!("{} - {}", note.category, note.message),
+                &note.severity,
+                note.entry_index,
+            )
+        }));
 
         // Aggregate advanced security findings
-        for exposure in &analysis.advanced_security.token_exposures {
-            let key = format!("Token Exposure - {}", exposure.exposure_type.as_str());
-            let target_map = match exposure.severity {
-                super::Severity::Critical => &mut critical,
-                super::Severity::Warning => &mut warnings,
-                super::Severity::Info => &mut info,
-            };
+        counts.add_all(
+            analysis
+                .advanced_security
+                .token_exposures
+                .iter()
+                .map(|exposure| {
+                    (
+                        format!("Token Exposure - {}", exposure.exposure_type.as_str()),
+                        &exposure.severity,
+                        Some(exposure.entry_index),
+                    )
+                }),
+        );
 
-            target_map
-                .entry(key.clone())
-                .and_modify(|(count, entries): &mut (usize, Vec<usize>)| {
-                    *count += 1;
-                    if entries.len() < 3 {
-                        entries.push(exposure.entry_index);
-                    }
-                })
-                .or_insert((1, vec![exposure.entry_index]));
-        }
+        // Aggregate JWT issues (per-token findings share a message per issue type)
+        counts.add_all(analysis.jwt_issues.iter().map(|issue| {
+            (
+                format!("JWT {} - {}", issue.issue_type.as_str(), issue.message),
+                &issue.severity,
+                issue.entry_index,
+            )
+        }));
+
+        // Aggregate OAuth / OIDC issues
+        counts.add_all(analysis.oauth.issues.iter().map(|issue| {
+            (
+                format!("OAuth {} - {}", issue.issue_type.as_str(), issue.message),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        // Aggregate session lifecycle issues
+        counts.add_all(analysis.session_lifecycle.issues.iter().map(|issue| {
+            (
+                format!("{} - {}", issue.issue_type.as_str(), issue.message),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        // Aggregate CSRF issues
+        counts.add_all(analysis.csrf.issues.iter().map(|issue| {
+            (
+                format!("CSRF {} - {}", issue.issue_type.as_str(), issue.message),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        // Aggregate access control issues
+        counts.add_all(analysis.access_matrix.issues.iter().map(|issue| {
+            (
+                format!("Access Control - {}", issue.message),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        // Aggregate endpoints that don't enforce the credentials they were sent
+        counts.add_all(analysis.unauthenticated.issues.iter().map(|issue| {
+            (
+                format!("Missing Auth Enforcement - {}", issue.message),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        // Aggregate SAML issues
+        counts.add_all(analysis.saml_issues.iter().map(|issue| {
+            (
+                issue.message.clone(),
+                &issue.severity,
+                Some(issue.entry_index),
+            )
+        }));
+
+        let FindingCounts {
+            critical,
+            mut warnings,
+            mut info,
+        } = counts;
 
         // Aggregate CORS issues
         let mut cors_by_type: HashMap<String, (usize, Vec<usize>)> = HashMap::new();
@@ -194,7 +278,7 @@ impl AuthSummaryGenerator {
                     sample_entries: entries,
                 })
                 .collect();
-            findings.sort_by(|a, b| b.count.cmp(&a.count));
+            findings.sort_by_key(|f| std::cmp::Reverse(f.count));
             findings
         };
 
@@ -239,6 +323,54 @@ impl AuthSummaryGenerator {
             };
         }
 
+        // Check for challenge/response handshakes and request signing
+        for (method, method_type, description) in [
+            (
+                AuthMethod::Ntlm,
+                "NTLM",
+                "NTLM challenge/response handshake in Authorization header",
+            ),
+            (
+                AuthMethod::Negotiate,
+                "Negotiate (Kerberos)",
+                "SPNEGO tokens in Authorization: Negotiate header",
+            ),
+            (
+                AuthMethod::Digest,
+                "HTTP Digest Authentication",
+                "Hashed challenge response in Authorization header",
+            ),
+            (
+                AuthMethod::AwsSigV4,
+                "AWS SigV4 Request Signing",
+                "Requests signed with AWS4-HMAC-SHA256",
+            ),
+            (
+                AuthMethod::Dpop,
+                "DPoP-Bound Token",
+                "Access tokens bound to a client key with a DPoP proof per request",
+            ),
+        ] {
+            if analysis.methods.contains(&method) {
+                return AuthMethodSummary {
+                    method_type: method_type.to_string(),
+                    description: description.to_string(),
+                    confidence: ConfidenceLevel::High,
+                };
+            }
+        }
+        if let Some(AuthMethod::Hmac(name)) = analysis
+            .methods
+            .iter()
+            .find(|m| matches!(m, AuthMethod::Hmac(_)))
+        {
+            return AuthMethodSummary {
+                method_type: "HMAC Request Signing".to_string(),
+                description: format!("Requests signed with {}", name),
+                confidence: ConfidenceLevel::High,
+            };
+        }
+
         // Check for JWT tokens
         if !analysis.jwt_tokens.is_empty() {
             return AuthMethodSummary {
@@ -404,6 +536,29 @@ impl AuthSummaryGenerator {
             };
         }
 
+        // Check for schemes that authenticate the connection or every request
+        if analysis
+            .methods
+            .iter()
+            .any(|m| matches!(m, AuthMethod::Ntlm | AuthMethod::Negotiate))
+        {
+            return SessionMechanismSummary {
+                mechanism_type: "Connection-Based".to_string(),
+                details: "NTLM/Negotiate handshake authenticates the TCP connection".to_string(),
+            };
+        }
+        if analysis.methods.iter().any(|m| {
+            matches!(
+                m,
+                AuthMethod::Digest | AuthMethod::AwsSigV4 | AuthMethod::Hmac(_)
+            )
+        }) {
+            return SessionMechanismSummary {
+                mechanism_type: "Per-Request Credentials".to_string(),
+                details: "Each request carries its own digest or signature".to_string(),
+            };
+        }
+
         SessionMechanismSummary {
             mechanism_type: "Unknown".to_string(),
             details: "Could not determine session mechanism".to_string(),
@@ -468,7 +623,15 @@ impl AuthSummaryGenerator {
     ) -> HawkScanConfig {
         let mut notes = Vec::new();
 
-        let (auth_type, config_snippet) = if primary_method.method_type.contains("Cookie") {
+        let (auth_type, config_snippet) = if let Some(config) =
+            Self::challenge_or_signing_config(primary_method, analysis, &mut notes)
+        {
+            config
+        } else if let Some(login) = analysis.login_requests.first()
+            && !primary_method.method_type.contains("OAuth")
+        {
+            Self::username_password_config(login, analysis, &mut notes)
+        } else if primary_method.method_type.contains("Cookie") {
             if let Some(session) = analysis.sessions.first() {
                 if let SessionType::Cookie { name } = &session.session_type {
                     notes.push(format!(
@@ -484,7 +647,7 @@ impl AuthSummaryGenerator {
   type: cookieAuthn
   cookieName: {}
   cookieValue: "${{AUTH_COOKIE}}""#,
-                            name
+                            quote(name)
                         ),
                     )
                 } else {
@@ -573,6 +736,13 @@ impl AuthSummaryGenerator {
             )
         };
 
+        if analysis.methods.contains(&AuthMethod::MutualTls) {
+            notes.push(
+                "Client certificate required: give HawkScan the certificate and key (PKCS#12)"
+                    .to_string(),
+            );
+        }
+
         HawkScanConfig {
             auth_type,
             config_snippet,
@@ -580,6 +750,244 @@ impl AuthSummaryGenerator {
         }
     }
 
+    /// HawkScan `usernamePassword` settings replaying the captured login request
+    fn username_password_config(
+        login: &LoginRequest,
+        analysis: &AuthAnalysis,
+        notes: &mut Vec<String>,
+    ) -> (String, String) {
+        let login_type = match login.format {
+            LoginFormat::Form => "FORM",
+            LoginFormat::Json => "JSON",
+        };
+        let mut snippet = format!(
+            "authentication:\n  usernamePassword:\n    type: {}\n    loginPath: {}\n",
+            login_type,
+            quote(&login.login_path)
+        );
+        if let Some(ref path) = login.login_page_path {
+            snippet.push_str(&format!("    loginPagePath: {}\n", quote(path)));
+        }
+        snippet.push_str(&format!(
+            "    usernameField: {}\n    passwordField: {}\n",
+            quote(login.username_field.as_deref().unwrap_or("username")),
+            quote(login.password_field.as_deref().unwrap_or("password"))
+        ));
+        snippet.push_str("    scanUsername: \"${SCAN_USERNAME}\"\n");
+        snippet.push_str("    scanPassword: \"${SCAN_PASSWORD}\"\n");
+        if !login.other_params.is_empty() {
+            snippet.push_str("    otherParams:\n");
+            for param in &login.other_params {
+                snippet.push_str(&format!(
+                    "      - name: {}\n        val: {}\n",
+                    quote(&param.name),
+                    quote(&param.config_value())
+                ));
+                if !param.is_static() {
+                    notes.push(format!(
+                        "Set {} for the {} login field (its captured value is not written)",
+                        param.env_var(),
+                        param.name
+                    ));
+                }
+            }
+        }
+
+        let cookie_names: Vec<&str> = analysis
+            .sessions
+            .iter()
+            .filter_map(|s| match s.session_type {
+                SessionType::Cookie { ref name } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        match login.token_extraction {
+            Some(ref extraction) => {
+                let (kind, value) = match extraction {
+                    TokenExtraction::BodyPath { path } => ("TOKEN_PATH", path),
+                    TokenExtraction::Header { name } => ("HEADER_NAME", name),
+                };
+                snippet.push_str(&format!(
+                    "  tokenExtraction:\n    type: {}\n    value: {}\n",
+                    kind,
+                    quote(value)
+                ));
+                snippet.push_str(
+                    "  tokenAuthorization:\n    type: HEADER\n    value: Authorization\n    tokenType: Bearer\n",
+                );
+            }
+            None if !cookie_names.is_empty() => {
+                let mut cookie_names = cookie_names;
+                cookie_names.sort_unstable();
+                snippet.push_str("  cookieAuthorization:\n    cookieNames:\n");
+                for name in cookie_names {
+                    snippet.push_str(&format!("      - {}\n", quote(name)));
+                }
+            }
+            None => notes.push(
+                "No session cookie or token found: add cookieAuthorization or tokenAuthorization"
+                    .to_string(),
+            ),
+        }
+
+        if let Some(ref test_path) = login.test_path {
+            snippet.push_str(&format!(
+                "  testPath:\n    path: {}\n    success: {}\n",
+                quote(&test_path.path),
+                quote(&test_path.success)
+            ));
+            if let Some(ref fail) = test_path.fail {
+                snippet.push_str(&format!("    fail: {}\n", quote(fail)));
+            }
+            snippet.push_str(&format!("    requestMethod: {}", test_path.method));
+        }
+
+        notes.push("Set SCAN_USERNAME and SCAN_PASSWORD environment variables".to_string());
+        if let Some(ref field) = login.csrf_field {
+            notes.push(format!(
+                "Login form carries a CSRF token: set app.antiCsrfParam to {}",
+                field
+            ));
+        }
+        if !login.logout_paths.is_empty() {
+            notes.push(format!(
+                "Add {} to app.excludePaths so the scan stays logged in",
+                login.logout_paths.join(", ")
+            ));
+        }
+
+        (
+            "usernamePassword".to_string(),
+            snippet.trim_end().to_string(),
+        )
+    }
+
+    /// HawkScan settings for HTTP challenge/response schemes and signed requests
+    fn challenge_or_signing_config(
+        primary_method: &AuthMethodSummary,
+        analysis: &AuthAnalysis,
+        notes: &mut Vec<String>,
+    ) -> Option<(String, String)> {
+        // HawkScan has no built-in handler for these, and Harrier ships no scripts: the script
+        // name and every value are placeholders for the user's own authentication script
+        let (scheme, parameters, credentials) = match primary_method.method_type.as_str() {
+            "NTLM" => {
+                notes.push("Set AUTH_USERNAME as DOMAIN\\user and AUTH_PASSWORD".to_string());
+                notes.push(
+                    "NTLM authenticates the connection, so keep scan concurrency low".to_string(),
+                );
+                ("NTLM", Vec::new(), Self::username_password_credentials())
+            }
+            "Negotiate (Kerberos)" => {
+                notes.push(
+                    "Run the scan from a domain-joined host or provide a Kerberos keytab"
+                        .to_string(),
+                );
+                notes.push("Servers fall back to NTLM when Kerberos is unavailable".to_string());
+                (
+                    "Negotiate",
+                    Vec::new(),
+                    Self::username_password_credentials(),
+                )
+            }
+            "HTTP Digest Authentication" => {
+                notes.push("Set AUTH_USERNAME and AUTH_PASSWORD environment variables".to_string());
+                notes.push(
+                    "The script must answer each 401 challenge with a fresh response".to_string(),
+                );
+                ("Digest", Vec::new(), Self::username_password_credentials())
+            }
+            "AWS SigV4 Request Signing" => {
+                notes.push("Set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY".to_string());
+                notes.push(
+                    "Set AWS_REGION and AWS_SERVICE from the credential scope, e.g. us-east-1 and execute-api"
+                        .to_string(),
+                );
+                notes.push("Add AWS_SESSION_TOKEN for temporary credentials".to_string());
+                (
+                    "AWS SigV4",
+                    vec![
+                        ("region", "${AWS_REGION}".to_string()),
+                        ("service", "${AWS_SERVICE}".to_string()),
+                    ],
+                    vec![
+                        ("accessKeyId", "${AWS_ACCESS_KEY_ID}"),
+                        ("secretAccessKey", "${AWS_SECRET_ACCESS_KEY}"),
+                    ],
+                )
+            }
+            "HMAC Request Signing" => {
+                let header = analysis
+                    .methods
+                    .iter()
+                    .find_map(|m| match m {
+                        AuthMethod::Hmac(name) => Some(name.as_str()),
+                        _ => None,
+                    })
+                    .unwrap_or("Authorization");
+                notes.push("Set HMAC_SECRET environment variable".to_string());
+                notes.push(
+                    "The script must sign the scanner's modified request, including any timestamp or nonce"
+                        .to_string(),
+                );
+                (
+                    "HMAC signing",
+                    vec![("header", header.to_string())],
+                    vec![("secret", "${HMAC_SECRET}")],
+                )
+            }
+            "DPoP-Bound Token" => {
+                notes.push("Set AUTH_TOKEN and DPOP_PRIVATE_KEY environment variables".to_string());
+                notes.push(
+                    "Each request needs a new proof (htm, htu, iat, jti) signed with the key the token is bound to"
+                        .to_string(),
+                );
+                (
+                    "DPoP",
+                    Vec::new(),
+                    vec![
+                        ("tokenValue", "${AUTH_TOKEN}"),
+                        ("privateKey", "${DPOP_PRIVATE_KEY}"),
+                    ],
+                )
+            }
+            _ => return None,
+        };
+        notes.insert(
+            0,
+            format!(
+                "No {} authentication script ships with Harrier: write one and set AUTH_SCRIPT to its name",
+                scheme
+            ),
+        );
+
+        let mut snippet = String::from(
+            "authentication:\n  type: scriptAuthn\n  scriptAuthn:\n    name: \"${AUTH_SCRIPT}\"\n",
+        );
+        if !parameters.is_empty() {
+            snippet.push_str("    parameters:\n");
+            for (name, value) in &parameters {
+                snippet.push_str(&format!("      {}: {}\n", name, quote(value)));
+            }
+        }
+        snippet.push_str("    credentials:\n");
+        for (name, value) in &credentials {
+            snippet.push_str(&format!("      {}: {}\n", name, quote(value)));
+        }
+
+        Some(("scriptAuthn".to_string(), snippet.trim_end().to_string()))
+    }
+
+    /// Script credentials for schemes that authenticate with a username and password
+    fn username_password_credentials() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("username", "${AUTH_USERNAME}"),
+            ("password", "${AUTH_PASSWORD}"),
+        ]
+    }
+
     fn generate_additional_info(analysis: &AuthAnalysis) -> Vec<String> {
         let mut info = Vec::new();
 
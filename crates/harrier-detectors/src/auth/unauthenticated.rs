@@ -0,0 +1,204 @@
+use harrier_core::har::Har;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::access_matrix::AccessMatrixBuilder;
+use super::idor::IdorAnalyzer;
+use super::methods::AuthDetector;
+use super::security::Severity;
+
+/// Endpoints requested without any credentials, split by whether they answered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnauthenticatedInventory {
+    pub open_endpoints: Vec<InventoryEndpoint>, // 2xx without credentials
+    pub denied_endpoints: Vec<InventoryEndpoint>, // 401/403 without credentials
+    pub issues: Vec<UnenforcedAuthIssue>,
+}
+
+impl UnauthenticatedInventory {
+    /// Hosts with at least one open or denied endpoint, in order
+    pub fn hosts(&self) -> Vec<&str> {
+        let mut hosts: Vec<&str> = self
+            .open_endpoints
+            .iter()
+            .chain(&self.denied_endpoints)
+            .map(|e| e.host.as_str())
+            .collect();
+        hosts.sort_unstable();
+        hosts.dedup();
+        hosts
+    }
+}
+
+/// A templated endpoint and the responses it gave to requests without credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryEndpoint {
+    pub host: String,
+    pub endpoint: String,   // "METHOD /path/{id}"
+    pub statuses: Vec<i64>, // Distinct, ascending
+    pub request_count: usize,
+    pub entry_indices: Vec<usize>,
+}
+
+/// Endpoint that gave the same successful status with and without credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnenforcedAuthIssue {
+    pub severity: Severity,
+    pub host: String,
+    pub endpoint: String,
+    pub status: i64,
+    pub message: String,
+    pub entry_index: usize,         // Request without credentials
+    pub authenticated_entry: usize, // Request with credentials
+}
+
+/// One request to an endpoint
+struct Observation {
+    entry_index: usize,
+    status: i64,
+    authenticated: bool,
+}
+
+pub struct UnauthenticatedAnalyzer;
+
+impl UnauthenticatedAnalyzer {
+    /// List API endpoints reached without credentials and flag missing enforcement
+    pub fn analyze(har: &Har) -> UnauthenticatedInventory {
+        let mut by_endpoint: BTreeMap<(String, String), Vec<Observation>> = BTreeMap::new();
+
+        for (idx, entry) in har.log.entries.iter().enumerate() {
+            if AccessMatrixBuilder::is_static_resource(entry)
+                || entry
+                    .response
+                    .content
+                    .mime_type
+                    .to_lowercase()
+                    .starts_with("text/html")
+            {
+                continue;
+            }
+            let Ok(url) = url::Url::parse(&entry.request.url) else {
+                continue;
+            };
+
+            let (template, _) = IdorAnalyzer::template_path(url.path());
+            by_endpoint
+                .entry((
+                    url.host_str().unwrap_or_default().to_string(),
+                    format!("{} {}", entry.request.method, template),
+                ))
+                .or_default()
+                .push(Observation {
+                    entry_index: idx,
+                    status: entry.response.status,
+                    authenticated: AuthDetector::has_credentials(&entry.request),
+                });
+        }
+
+        let mut inventory = UnauthenticatedInventory::default();
+        for ((host, endpoint), observations) in by_endpoint {
+            let anonymous: Vec<&Observation> =
+                observations.iter().filter(|o| !o.authenticated).collect();
+
+            let open: Vec<&Observation> = anonymous
+                .iter()
+                .copied()
+                .filter(|o| (200..300).contains(&o.status))
+                .collect();
+            if let Some(listed) = Self::inventory_endpoint(&host, &endpoint, &open) {
+                inventory.open_endpoints.push(listed);
+            }
+
+            let denied: Vec<&Observation> = anonymous
+                .iter()
+                .copied()
+                .filter(|o| o.status == 401 || o.status == 403)
+                .collect();
+            if let Some(listed) = Self::inventory_endpoint(&host, &endpoint, &denied) {
+                inventory.denied_endpoints.push(listed);
+            }
+
+            // Same success with and without credentials means the credentials weren't checked
+            let matched = open.iter().find_map(|anon| {
+                observations
+                    .iter()
+                    .find(|o| o.authenticated && o.status == anon.status)
+                    .map(|auth| (anon, auth))
+            });
+            if let Some((anon, auth)) = matched {
+                inventory.issues.push(UnenforcedAuthIssue {
+                    severity: Severity::Warning,
+                    message: format!(
+                        "{} returned {} both with and without credentials",
+                        endpoint, anon.status
+                    ),
+                    host: host.clone(),
+                    endpoint: endpoint.clone(),
+                    status: anon.status,
+                    entry_index: anon.entry_index,
+                    authenticated_entry: auth.entry_index,
+                });
+            }
+        }
+
+        inventory
+    }
+
+    fn inventory_endpoint(
+        host: &str,
+        endpoint: &str,
+        observations: &[&Observation],
+    ) -> Option<InventoryEndpoint> {
+        if observations.is_empty() {
+            return None;
+        }
+        let mut statuses: Vec<i64> = observations.iter().map(|o| o.status).collect();
+        statuses.sort_unstable();
+        statuses.dedup();
+        Some(InventoryEndpoint {
+            host: host.to_string(),
+            endpoint: endpoint.to_string(),
+            statuses,
+            request_count: observations.len(),
+            entry_indices: observations.iter().map(|o| o.entry_index).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inventory_endpoint() {
+        let observations = [
+            Observation {
+                entry_index: 4,
+                status: 401,
+                authenticated: false,
+            },
+            Observation {
+                entry_index: 1,
+                status: 403,
+                authenticated: false,
+            },
+            Observation {
+                entry_index: 7,
+                status: 401,
+                authenticated: false,
+            },
+        ];
+        let refs: Vec<&Observation> = observations.iter().collect();
+
+        let listed =
+            UnauthenticatedAnalyzer::inventory_endpoint("api.example.com", "GET /x", &refs)
+                .unwrap();
+        assert_eq!(listed.statuses, vec![401, 403]);
+        assert_eq!(listed.request_count, 3);
+        assert_eq!(listed.entry_indices, vec![4, 1, 7]);
+
+        assert!(
+            UnauthenticatedAnalyzer::inventory_endpoint("api.example.com", "GET /x", &[]).is_none()
+        );
+    }
+}
@@ -1,17 +1,30 @@
 pub mod app_types;
 pub mod auth;
 pub mod error;
+pub mod yaml;
 
 pub use app_types::{AppType, AppTypeDetector};
 pub use auth::{
-    AdvancedSecurityAnalysis, AdvancedSecurityAnalyzer, AggregatedFinding, AuthAnalysis,
-    AuthAnalyzer, AuthDetector, AuthEvent, AuthEventType, AuthFlow, AuthFlowType, AuthMethod,
-    AuthMethodSummary, AuthSession, AuthSummaryGenerator, AuthenticationSummary, ConfidenceLevel,
-    CorsIssue, CorsIssueType, CspFinding, CspFindingType, EndpointInfo, EventDetails,
-    EventDetector, ExposureType, FlowDetector, FlowRole, FlowStep, HawkScanConfig, JwtAnalyzer,
-    JwtClaims, JwtHeader, JwtIssueType, JwtSecurityIssue, JwtToken, RefreshPatternType,
-    SamlDetector, SamlFlow, SamlFlowType, SamlSecurityIssue, SamlStep, SamlStepRole,
-    SecurityFindingsSummary, SecurityNote, SessionAttributes, SessionMechanismSummary,
-    SessionTracker, SessionType, Severity, TokenExposure, TokenRefreshPattern,
+    AccessCell, AccessControlIssue, AccessMatrix, AccessMatrixBuilder, AdvancedSecurityAnalysis,
+    AdvancedSecurityAnalyzer, AggregatedFinding, AuthAnalysis, AuthAnalyzer, AuthDetector,
+    AuthEvent, AuthEventType, AuthFlow, AuthFlowType, AuthMethod, AuthMethodSummary, AuthSession,
+    AuthSummaryGenerator, AuthenticationSummary, AuthorizationRequest, ConcurrentSessions,
+    ConfidenceLevel, ConfirmationClaim, CorsIssue, CorsIssueType, CspFinding, CspFindingType,
+    CsrfAnalysis, CsrfAnalyzer, CsrfEndpoint, CsrfIssueType, CsrfProtection, CsrfSecurityIssue,
+    CsrfTokenObservation, EndpointInfo, EventDetails, EventDetector, ExposureType, FlowDetector,
+    FlowRole, FlowStep, HawkScanConfig, IdentityKind, IdentityResolver, IdorAnalysis, IdorAnalyzer,
+    IdorCandidate, InvalidationStatus, InventoryEndpoint, IssuedTokenKind, JwtAnalyzer, JwtClaims,
+    JwtHeader, JwtIssueType, JwtSecurityIssue, JwtToken, JwtVerification, JwtVerificationReport,
+    JwtVerifier, LoginFormat, LoginParam, LoginRequest, LoginRequestExtractor, LogoutInvalidation,
+    MatrixEndpoint, MatrixIdentity, OAuthAnalysis, OAuthAnalyzer, OAuthIssueType,
+    OAuthSecurityIssue, ObjectIdKind, ObjectIdLocation, OidcProviderConfig, RefreshPatternType,
+    RequestIdentity, SamlAssertion, SamlAttribute, SamlBinding, SamlDetector, SamlFlow,
+    SamlFlowType, SamlMessage, SamlMessageDecoder, SamlMessageKind, SamlSecurityIssue, SamlStep,
+    SamlStepRole, SecurityFindingsSummary, SecurityNote, SessionAttributes, SessionIssueType,
+    SessionLifecycleAnalysis, SessionLifecycleAnalyzer, SessionLifecycleIssue,
+    SessionMechanismSummary, SessionRotation, SessionTracker, SessionType, Severity, TestPath,
+    TokenExposure, TokenExtraction, TokenFormat, TokenIssuance, TokenLineage, TokenLineageTracker,
+    TokenRefreshPattern, TokenUsage, UnauthenticatedAnalyzer, UnauthenticatedInventory,
+    UnenforcedAuthIssue, VerificationKey, VerificationStatus,
 };
 pub use error::{Error, Result};
@@ -0,0 +1,172 @@
+use crate::{Error, Result};
+use aws_lc_rs::constant_time;
+use base64::Engine;
+use http::{HeaderMap, HeaderValue, Response, StatusCode, header};
+use hudsucker::Body;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Realm sent in the `Proxy-Authenticate` challenge
+const REALM: &str = "Harrier";
+
+/// Basic credentials clients must send to use the proxy
+///
+/// Clients authenticate on the CONNECT request that opens a tunnel, not on the requests
+/// sent through it, so a client connection is trusted once it has presented valid
+/// credentials. That trust lives in a [`ConnectionTrust`] owned by the connection, so
+/// it ends when the connection closes.
+#[derive(Clone)]
+pub struct ProxyAuth {
+    /// Expected `user:password`
+    credentials: Arc<str>,
+}
+
+/// Whether one client connection has presented valid credentials
+#[derive(Debug, Default)]
+pub(crate) struct ConnectionTrust(AtomicBool);
+
+impl ProxyAuth {
+    /// Parse `user:password`
+    pub fn parse(credentials: &str) -> Result<Self> {
+        match credentials.split_once(':') {
+            Some((user, password)) if !user.is_empty() && !password.is_empty() => Ok(Self {
+                credentials: credentials.into(),
+            }),
+            _ => Err(Error::Proxy(
+                "Proxy credentials must be in the form user:password".to_string(),
+            )),
+        }
+    }
+
+    /// Check a request's `Proxy-Authorization` header, removing it so it is neither
+    /// recorded nor forwarded
+    ///
+    /// Requests without the header are allowed when `connection` authenticated earlier.
+    pub(crate) fn authorize(
+        &self,
+        client_addr: SocketAddr,
+        connection: &ConnectionTrust,
+        headers: &mut HeaderMap,
+    ) -> bool {
+        match headers.remove(header::PROXY_AUTHORIZATION) {
+            Some(value) if self.matches(&value) => {
+                connection.0.store(true, Ordering::Relaxed);
+                true
+            }
+            Some(_) => {
+                tracing::warn!("Rejected proxy credentials from {}", client_addr);
+                connection.0.store(false, Ordering::Relaxed);
+                false
+            }
+            None => connection.0.load(Ordering::Relaxed),
+        }
+    }
+
+    fn matches(&self, value: &HeaderValue) -> bool {
+        let Some((scheme, encoded)) = value.to_str().ok().and_then(|v| v.trim().split_once(' '))
+        else {
+            return false;
+        };
+        if !scheme.eq_ignore_ascii_case("basic") {
+            return false;
+        }
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .is_ok_and(|decoded| {
+                constant_time::verify_slices_are_equal(&decoded, self.credentials.as_bytes())
+                    .is_ok()
+            })
+    }
+
+    /// `407 Proxy Authentication Required` challenge
+    pub(crate) fn challenge() -> Response<Body> {
+        Response::builder()
+            .status(StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+            .header(
+                header::PROXY_AUTHENTICATE,
+                format!("Basic realm=\"{}\"", REALM),
+            )
+            .body(Body::empty())
+            .expect("Failed to build response")
+    }
+}
+
+impl std::fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProxyAuth").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> SocketAddr {
+        ([192, 168, 1, 20], 50000).into()
+    }
+
+    fn headers(authorization: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = authorization {
+            headers.insert(header::PROXY_AUTHORIZATION, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_requires_user_and_password() {
+        assert!(ProxyAuth::parse("alice:s3cret").is_ok());
+        assert!(ProxyAuth::parse("alice").is_err());
+        assert!(ProxyAuth::parse(":s3cret").is_err());
+    }
+
+    #[test]
+    fn test_connection_trusted_after_credentials() {
+        let auth = ProxyAuth::parse("alice:s3cret").unwrap();
+        let connection = ConnectionTrust::default();
+
+        // "alice:s3cret" in base64
+        let mut valid = headers(Some("Basic YWxpY2U6czNjcmV0"));
+        assert!(!auth.authorize(client(), &connection, &mut headers(None)));
+        assert!(auth.authorize(client(), &connection, &mut valid));
+        assert!(!valid.contains_key(header::PROXY_AUTHORIZATION));
+
+        // Later requests on the same connection, e.g. inside its tunnel
+        assert!(auth.authorize(client(), &connection, &mut headers(None)));
+
+        // A new connection starts untrusted, even from the same address
+        let reused = ConnectionTrust::default();
+        assert!(!auth.authorize(client(), &reused, &mut headers(None)));
+    }
+
+    #[test]
+    fn test_wrong_credentials_rejected() {
+        let auth = ProxyAuth::parse("alice:s3cret").unwrap();
+        let connection = ConnectionTrust::default();
+        assert!(auth.authorize(
+            client(),
+            &connection,
+            &mut headers(Some("Basic YWxpY2U6czNjcmV0"))
+        ));
+
+        // "alice:wrong" also revokes the connection's earlier trust
+        assert!(!auth.authorize(
+            client(),
+            &connection,
+            &mut headers(Some("Basic YWxpY2U6d3Jvbmc="))
+        ));
+        assert!(!auth.authorize(client(), &connection, &mut headers(None)));
+        assert!(!auth.authorize(
+            client(),
+            &connection,
+            &mut headers(Some("Bearer YWxpY2U6czNjcmV0"))
+        ));
+        // "alice:s3cre", a prefix of the real credentials
+        assert!(!auth.authorize(
+            client(),
+            &connection,
+            &mut headers(Some("Basic YWxpY2U6czNjcmU="))
+        ));
+    }
+}
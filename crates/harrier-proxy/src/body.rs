@@ -0,0 +1,285 @@
+use base64::Engine;
+use http_body_util::combinators::BoxBody;
+use hudsucker::Body;
+use hyper::body::{Body as HttpBody, Bytes, Frame, SizeHint};
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Default cap on recorded body bytes (matches the CDP capture's 15 MB HawkScan limit)
+pub const DEFAULT_MAX_BODY_SIZE: usize = 15 * 1024 * 1024;
+
+/// Body recorded from a request or response
+#[derive(Debug, Clone, Default)]
+pub struct CapturedBody {
+    /// Decoded bytes, at most `max_size`
+    pub data: Vec<u8>,
+    /// Decoded size, or the wire size when the body was truncated
+    pub size: usize,
+    /// Bytes on the wire, before decompression
+    pub wire_size: usize,
+    pub truncated: bool,
+    pub max_size: usize,
+}
+
+impl CapturedBody {
+    /// Decode recorded wire bytes using the `Content-Encoding` header value
+    pub fn decode(
+        recorded: RecordedBytes,
+        content_encoding: Option<&str>,
+        max_size: usize,
+    ) -> Self {
+        let encodings: Vec<String> = content_encoding
+            .unwrap_or_default()
+            .split(',')
+            .map(|e| e.trim().to_lowercase())
+            .filter(|e| !e.is_empty() && e != "identity")
+            .collect();
+
+        let mut data = recorded.data;
+        let mut complete = !recorded.truncated;
+        // Encodings are listed in the order they were applied
+        for encoding in encodings.iter().rev() {
+            match decode_one(&data, encoding, max_size) {
+                Some((decoded, finished)) => {
+                    data = decoded;
+                    complete &= finished;
+                }
+                None => {
+                    tracing::debug!("Recording {} body without decoding", encoding);
+                    break;
+                }
+            }
+        }
+
+        let truncated = !complete || data.len() > max_size;
+        data.truncate(max_size);
+        Self {
+            size: if truncated {
+                recorded.total
+            } else {
+                data.len()
+            },
+            data,
+            wire_size: recorded.total,
+            truncated,
+            max_size,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wire_size == 0
+    }
+
+    /// Body as HAR text: UTF-8 as is, anything else base64 encoded
+    pub fn to_text(&self) -> (String, Option<&'static str>) {
+        match std::str::from_utf8(&self.data) {
+            Ok(text) => (text.to_string(), None),
+            Err(_) => (
+                base64::engine::general_purpose::STANDARD.encode(&self.data),
+                Some("base64"),
+            ),
+        }
+    }
+
+    /// HAR comment explaining a truncated body
+    pub fn truncation_comment(&self) -> Option<String> {
+        self.truncated.then(|| {
+            format!(
+                "Body truncated at {} bytes (original size: {} bytes)",
+                self.max_size, self.wire_size
+            )
+        })
+    }
+}
+
+/// Whether every coding in a `Content-Encoding` header value can be decoded
+pub(crate) fn can_decode(content_encoding: Option<&str>) -> bool {
+    content_encoding
+        .unwrap_or_default()
+        .split(',')
+        .map(|e| e.trim().to_lowercase())
+        .all(|e| {
+            matches!(
+                e.as_str(),
+                "" | "identity" | "gzip" | "x-gzip" | "deflate" | "br" | "zstd"
+            )
+        })
+}
+
+/// Decode one content coding, returning the output and whether the input decoded fully
+fn decode_one(data: &[u8], encoding: &str, max_size: usize) -> Option<(Vec<u8>, bool)> {
+    let reader: Box<dyn Read + '_> = match encoding {
+        "gzip" | "x-gzip" => Box::new(flate2::read::MultiGzDecoder::new(data)),
+        "deflate" => {
+            // Servers send both zlib-wrapped and raw deflate under this name
+            if data.first().is_some_and(|b| b & 0x0f == 8) {
+                Box::new(flate2::read::ZlibDecoder::new(data))
+            } else {
+                Box::new(flate2::read::DeflateDecoder::new(data))
+            }
+        }
+        "br" => Box::new(brotli::Decompressor::new(data, 4096)),
+        "zstd" => Box::new(zstd::stream::read::Decoder::new(data).ok()?),
+        _ => return None,
+    };
+
+    // Read one byte past the limit so an over-long body is seen as truncated
+    let mut decoded = Vec::new();
+    let finished = reader
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decoded)
+        .is_ok();
+    Some((decoded, finished))
+}
+
+/// Bytes seen on the wire, up to the recording limit
+#[derive(Debug, Default)]
+pub struct RecordedBytes {
+    pub data: Vec<u8>,
+    pub total: usize,
+    pub truncated: bool,
+}
+
+type OnComplete = Box<dyn FnOnce(RecordedBytes) + Send + Sync>;
+
+/// Body that forwards every frame unchanged while recording up to `limit` bytes
+///
+/// Frames are passed on as soon as they arrive, so large downloads stream through the
+/// proxy. `on_complete` runs once, when the body ends, fails, or is dropped early.
+pub struct TeeBody {
+    inner: Body,
+    recorded: RecordedBytes,
+    limit: usize,
+    on_complete: Option<OnComplete>,
+}
+
+impl TeeBody {
+    pub fn new(
+        inner: Body,
+        limit: usize,
+        on_complete: impl FnOnce(RecordedBytes) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            recorded: RecordedBytes::default(),
+            limit,
+            on_complete: Some(Box::new(on_complete)),
+        }
+    }
+
+    /// Wrap as a hudsucker body
+    pub fn into_body(self) -> Body {
+        Body::from(BoxBody::new(self))
+    }
+
+    fn record(&mut self, chunk: &Bytes) {
+        self.recorded.total += chunk.len();
+        let room = self.limit.saturating_sub(self.recorded.data.len());
+        if chunk.len() > room {
+            self.recorded.truncated = true;
+        }
+        self.recorded
+            .data
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+
+    fn complete(&mut self) {
+        if let Some(on_complete) = self.on_complete.take() {
+            on_complete(std::mem::take(&mut self.recorded));
+        }
+    }
+}
+
+impl HttpBody for TeeBody {
+    type Data = Bytes;
+    type Error = hudsucker::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        match polled {
+            Poll::Ready(Some(Ok(ref frame))) => {
+                if let Some(chunk) = frame.data_ref() {
+                    let chunk = chunk.clone();
+                    self.record(&chunk);
+                }
+            }
+            Poll::Ready(Some(Err(_))) | Poll::Ready(None) => self.complete(),
+            Poll::Pending => {}
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for TeeBody {
+    fn drop(&mut self) {
+        self.complete();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn recorded(data: &[u8]) -> RecordedBytes {
+        RecordedBytes {
+            data: data.to_vec(),
+            total: data.len(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"{\"id\": 42}").unwrap();
+        let wire = encoder.finish().unwrap();
+
+        let body = CapturedBody::decode(recorded(&wire), Some("gzip"), 1024);
+        assert_eq!(body.data, b"{\"id\": 42}");
+        assert_eq!(body.size, 10);
+        assert_eq!(body.wire_size, wire.len());
+        assert!(!body.truncated);
+        assert_eq!(body.to_text(), ("{\"id\": 42}".to_string(), None));
+    }
+
+    #[test]
+    fn test_decode_truncates_at_limit() {
+        let body = CapturedBody::decode(recorded(b"abcdefgh"), None, 4);
+        assert_eq!(body.data, b"abcd");
+        assert!(body.truncated);
+        assert_eq!(
+            body.truncation_comment().unwrap(),
+            "Body truncated at 4 bytes (original size: 8 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_encoding_kept_raw() {
+        let body = CapturedBody::decode(recorded(&[0xff, 0x00]), Some("compress"), 16);
+        assert_eq!(body.data, vec![0xff, 0x00]);
+        assert_eq!(body.to_text(), ("/wA=".to_string(), Some("base64")));
+    }
+
+    #[test]
+    fn test_tee_records_up_to_limit() {
+        let mut tee = TeeBody::new(Body::empty(), 5, |_| {});
+        tee.record(&Bytes::from_static(b"abc"));
+        tee.record(&Bytes::from_static(b"defg"));
+        assert_eq!(tee.recorded.data, b"abcde");
+        assert_eq!(tee.recorded.total, 7);
+        assert!(tee.recorded.truncated);
+    }
+}
@@ -1,136 +1,1219 @@
-use http::{Request, Response};
-use hudsucker::{Body, HttpContext, HttpHandler, RequestOrResponse};
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use crate::auth::{ConnectionTrust, ProxyAuth};
+use crate::body::{CapturedBody, DEFAULT_MAX_BODY_SIZE, TeeBody};
+use crate::passthrough::{Counted, OpenTunnels, Reason, TlsPassthrough, TunnelBytes};
+use crate::rewrite::RewriteRules;
+use crate::upstream::{UpstreamConnector, UpstreamProxy};
+use crate::websocket::{Direction, WebSocketSessions};
+use harrier_core::filter::{CaptureScope, ScopeDecision};
+use harrier_core::har::{HarJournal, WebSocketMessage};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Version, header};
+use hudsucker::futures::{Sink, SinkExt, Stream, StreamExt};
+use hudsucker::hyper::ext::ReasonPhrase;
+use hudsucker::hyper_util::client::legacy::Error as ClientError;
+use hudsucker::hyper_util::client::legacy::connect::HttpInfo;
+use hudsucker::hyper_util::rt::TokioIo;
+use hudsucker::tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use hudsucker::tokio_tungstenite::tungstenite::protocol::Role;
+use hudsucker::tokio_tungstenite::tungstenite::{self, Message};
+use hudsucker::tokio_tungstenite::{
+    self as tokio_tungstenite, Connector, MaybeTlsStream, WebSocketStream,
+};
+use hudsucker::{
+    Body, HttpContext, HttpHandler, RequestOrResponse, WebSocketContext, WebSocketHandler,
+};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::net::TcpStream;
+use tower::Service;
+
+/// Request body filled in once the client finishes sending it
+type BodySlot = Arc<Mutex<Option<CapturedBody>>>;
+
+/// WebSocket to a server, reached through an upstream proxy tunnel
+type ServerWebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
 /// HTTP handler that captures requests and responses for HAR generation
-#[derive(Clone)]
+///
+/// hudsucker clones the handler for every request and calls `handle_request` and then
+/// `handle_response` (or `handle_error`) on that same clone, so each clone carries its
+/// own in-flight request and responses can never be paired with another connection's
+/// request.
 pub struct HarCaptureHandler {
-    /// Shared buffer for captured entries (will convert to HAR entries later)
-    entries: Arc<Mutex<Vec<CapturedEntry>>>,
-    /// Temporary storage for pending requests (URL -> entry index)
-    pending: Arc<Mutex<Vec<PendingRequest>>>,
+    /// Destination for completed entries
+    sink: EntrySink,
+    /// Most body bytes recorded per request or response
+    max_body_size: usize,
+    /// Hosts to record, record headers-only, or forward unrecorded
+    scope: Arc<CaptureScope>,
+    /// Upstream proxy that plain HTTP requests may need credentials for
+    upstream: Option<UpstreamProxy>,
+    /// Credentials clients must present to use the proxy
+    auth: Option<ProxyAuth>,
+    /// State of the client connection this clone serves
+    connection: Option<Arc<Connection>>,
+    /// Match-and-replace rules applied before traffic is recorded
+    rules: Arc<RewriteRules>,
+    /// Hosts whose TLS is tunnelled without decryption
+    passthrough: Arc<TlsPassthrough>,
+    /// WebSocket upgrades whose frames are being recorded
+    websockets: WebSocketSessions,
+    /// Passthrough tunnels being relayed
+    tunnels: OpenTunnels,
+    /// TLS for WebSockets relayed through the upstream proxy
+    websocket_connector: Option<Connector>,
+    /// Request handled by this clone that has not been answered yet
+    in_flight: Option<InFlight>,
+    /// This clone's request as sent on, and the rules that changed it so far
+    rewritten: Option<Rewritten>,
 }
 
-/// Temporary storage for a request waiting for its response
-#[derive(Debug, Clone)]
+/// Request as rewritten, which response rules match against
+struct Rewritten {
+    method: Method,
+    uri: http::Uri,
+    /// Rules applied, e.g. "Inject auth (request)"
+    applied: Vec<String>,
+}
+
+/// Identifies a request by the client connection it arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId {
+    pub client_addr: SocketAddr,
+    /// Position of the request on its connection, starting at 0
+    pub sequence: u64,
+}
+
+/// State shared by the requests of one client connection, dropped with it
+#[derive(Debug, Default)]
+struct Connection {
+    /// Whether the client presented valid proxy credentials
+    trust: ConnectionTrust,
+    /// Sequence number of the next request
+    next_sequence: AtomicU64,
+}
+
+/// Request waiting for its response
+#[derive(Debug)]
 struct PendingRequest {
+    id: RequestId,
     url: String,
     method: String,
+    version: Version,
     request_headers: Vec<(String, String)>,
+    request_body: BodySlot,
+    /// Out of scope: record headers but leave bodies alone
+    headers_only: bool,
+    /// Rewrite rules applied to the request
+    rewrites: Vec<String>,
+    /// Mock that answered instead of the origin
+    mocked_by: Option<String>,
     started_at: std::time::SystemTime,
 }
 
+impl PendingRequest {
+    /// Entry for this request with no response recorded yet
+    fn into_entry(self, comment: Option<String>) -> CapturedEntry {
+        let request_body = self
+            .request_body
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        CapturedEntry {
+            id: self.id,
+            method: self.method,
+            url: self.url,
+            request_version: self.version,
+            request_headers: self.request_headers,
+            request_body,
+            response_status: 0,
+            status_text: String::new(),
+            response_version: None,
+            response_headers: Vec::new(),
+            response_body: None,
+            server_addr: None,
+            started_at: self.started_at,
+            responded_at: None,
+            completed_at: std::time::SystemTime::now(),
+            comment,
+            rewrites: self.rewrites,
+            mocked_by: self.mocked_by,
+            tunnel: None,
+            websocket_messages: None,
+        }
+    }
+}
+
+/// Pending request that records itself as unanswered if dropped before a response
+///
+/// hudsucker drops the request future, and with it this handler clone, when the client
+/// disconnects before the response arrives.
+struct InFlight {
+    request: Option<PendingRequest>,
+    sink: EntrySink,
+}
+
+impl InFlight {
+    fn take(mut self) -> Option<PendingRequest> {
+        self.request.take()
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(request) = self.request.take() {
+            tracing::debug!("No response for {} {}", request.method, request.url);
+            let comment = "No response received (request aborted)".to_string();
+            self.sink.push(request.into_entry(Some(comment)));
+        }
+    }
+}
+
 /// Captured HTTP transaction data
 #[derive(Debug, Clone)]
 pub struct CapturedEntry {
+    pub id: RequestId,
     pub method: String,
     pub url: String,
+    /// HTTP version the client spoke to the proxy
+    pub request_version: Version,
     pub request_headers: Vec<(String, String)>,
+    pub request_body: Option<CapturedBody>,
+    /// Response status, or 0 when no response was received
     pub response_status: u16,
+    /// Reason phrase sent by the server, or the canonical one for the status
+    pub status_text: String,
+    /// HTTP version negotiated with the upstream server
+    pub response_version: Option<Version>,
     pub response_headers: Vec<(String, String)>,
+    pub response_body: Option<CapturedBody>,
+    /// Upstream address the response came from
+    pub server_addr: Option<SocketAddr>,
     pub started_at: std::time::SystemTime,
+    /// When the response headers arrived
+    pub responded_at: Option<std::time::SystemTime>,
     pub completed_at: std::time::SystemTime,
+    /// Why the request got no response, or why its bodies were not recorded
+    pub comment: Option<String>,
+    /// Rewrite rules applied to the request or response, e.g. "Inject auth (request)"
+    pub rewrites: Vec<String>,
+    /// Mock that answered the request without contacting the origin
+    pub mocked_by: Option<String>,
+    /// Bytes relayed when this is a TLS passthrough tunnel
+    pub tunnel: Option<TunnelBytes>,
+    /// Frames of the WebSocket this request opened
+    pub websocket_messages: Option<Vec<WebSocketMessage>>,
 }
 
 impl HarCaptureHandler {
     pub fn new() -> Self {
         Self {
-            entries: Arc::new(Mutex::new(Vec::new())),
-            pending: Arc::new(Mutex::new(Vec::new())),
+            sink: EntrySink::default(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            scope: Arc::new(CaptureScope::new()),
+            upstream: None,
+            auth: None,
+            connection: None,
+            rules: Arc::new(RewriteRules::default()),
+            passthrough: Arc::new(TlsPassthrough::default()),
+            websockets: WebSocketSessions::default(),
+            tunnels: OpenTunnels::default(),
+            websocket_connector: None,
+            in_flight: None,
+            rewritten: None,
+        }
+    }
+
+    /// Record at most `bytes` of each request and response body
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Only record traffic within `scope`
+    pub fn with_scope(mut self, scope: CaptureScope) -> Self {
+        self.scope = Arc::new(scope);
+        self
+    }
+
+    /// Authenticate plain HTTP requests forwarded through `upstream`
+    pub fn with_upstream(mut self, upstream: UpstreamProxy) -> Self {
+        self.upstream = Some(upstream);
+        self
+    }
+
+    /// Require clients to authenticate with `auth`
+    pub fn with_auth(mut self, auth: ProxyAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Rewrite matching traffic with `rules` before it is recorded
+    pub fn with_rules(mut self, rules: RewriteRules) -> Self {
+        self.rules = Arc::new(rules);
+        self
+    }
+
+    /// Tunnel TLS to these hosts without decrypting it
+    pub fn with_passthrough(mut self, passthrough: TlsPassthrough) -> Self {
+        self.passthrough = Arc::new(passthrough);
+        self
+    }
+
+    /// Handler for a proxy to clone per client connection, not tied to any connection
+    pub(crate) fn for_listener(&self) -> Self {
+        Self {
+            connection: None,
+            ..self.clone()
         }
     }
 
+    /// Connect `wss://` WebSockets relayed through the upstream proxy with `connector`
+    pub(crate) fn with_websocket_connector(mut self, connector: Connector) -> Self {
+        self.websocket_connector = Some(connector);
+        self
+    }
+
+    /// Hosts tunnelled without decryption, shared with the certificate authority
+    pub(crate) fn passthrough(&self) -> Arc<TlsPassthrough> {
+        Arc::clone(&self.passthrough)
+    }
+
+    /// Append completed entries to `journal` instead of keeping them in memory
+    pub fn with_journal(mut self, journal: HarJournal) -> Self {
+        self.sink.journal = Some(journal);
+        self
+    }
+
+    /// Entries held in memory (those not written to a journal)
     pub fn entries(&self) -> Arc<Mutex<Vec<CapturedEntry>>> {
-        Arc::clone(&self.entries)
+        Arc::clone(&self.sink.entries)
     }
-}
 
-impl Default for HarCaptureHandler {
-    fn default() -> Self {
-        Self::new()
+    fn next_id(&mut self, client_addr: SocketAddr) -> RequestId {
+        let connection = self.connection.get_or_insert_default();
+        RequestId {
+            client_addr,
+            sequence: connection.next_sequence.fetch_add(1, Ordering::Relaxed),
+        }
     }
-}
 
-impl HttpHandler for HarCaptureHandler {
-    fn handle_request(
-        &mut self,
-        _ctx: &HttpContext,
-        req: Request<Body>,
-    ) -> impl std::future::Future<Output = RequestOrResponse> + Send {
+    /// Record a request and return it with its body wrapped for capture
+    fn capture_request(&mut self, client_addr: SocketAddr, req: Request<Body>) -> Request<Body> {
+        // CONNECT opens a tunnel whose requests are handled separately
+        if req.method() == Method::CONNECT {
+            return req;
+        }
+        // WebSocket upgrades are answered by the proxy itself and recorded with their frames
+        if is_websocket_upgrade(req.headers()) {
+            self.capture_websocket(client_addr, &req);
+            return req;
+        }
+
+        let id = self.next_id(client_addr);
         let method = req.method().to_string();
         let url = req.uri().to_string();
+
+        // Out-of-scope and ignored traffic is forwarded before anything is kept
+        let headers_only = match self.scope.decide(&url) {
+            ScopeDecision::Record => false,
+            ScopeDecision::HeadersOnly => true,
+            ScopeDecision::Ignore => {
+                tracing::trace!("Not recording out-of-scope request: {} {}", method, url);
+                return req;
+            }
+        };
         let started_at = std::time::SystemTime::now();
 
         // Capture request headers
-        let request_headers: Vec<(String, String)> = req
-            .headers()
-            .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-            .collect();
+        let request_headers = header_pairs(req.headers());
 
-        tracing::debug!("Intercepted request: {} {}", method, url);
+        tracing::debug!(
+            "Intercepted request #{} from {}: {} {}",
+            id.sequence,
+            id.client_addr,
+            method,
+            url
+        );
 
-        // Store pending request
-        let pending = self.pending.clone();
-        let pending_req = PendingRequest {
-            url: url.clone(),
-            method: method.clone(),
-            request_headers,
-            started_at,
+        // Record the body as it streams upstream; upgrades keep theirs untouched
+        let request_body = BodySlot::default();
+        let req = if headers_only || req.headers().contains_key(header::UPGRADE) {
+            req
+        } else {
+            let (parts, body) = req.into_parts();
+            let slot = request_body.clone();
+            let encoding = content_encoding(&parts.headers);
+            let max_body_size = self.max_body_size;
+            let body = TeeBody::new(body, max_body_size, move |recorded| {
+                let body = CapturedBody::decode(recorded, encoding.as_deref(), max_body_size);
+                *slot.lock().unwrap_or_else(PoisonError::into_inner) = Some(body);
+            });
+            Request::from_parts(parts, body.into_body())
         };
 
-        async move {
-            let mut pending_guard = pending.lock().await;
-            pending_guard.push(pending_req);
-            drop(pending_guard);
+        self.in_flight = Some(InFlight {
+            request: Some(PendingRequest {
+                id,
+                url,
+                method,
+                version: req.version(),
+                request_headers,
+                request_body,
+                headers_only,
+                rewrites: self.applied_rewrites(),
+                mocked_by: None,
+                started_at,
+            }),
+            sink: self.sink.clone(),
+        });
+
+        req
+    }
+
+    /// Apply request rules, remembering the request so response rules can match it
+    async fn rewrite_request(&mut self, req: Request<Body>) -> Request<Body> {
+        if self.rules.is_empty() || req.method() == Method::CONNECT {
+            return req;
+        }
+
+        let rules = Arc::clone(&self.rules);
+        let (mut parts, body) = req.into_parts();
+        let mut rewrite = rules.rewrite_request(&parts.method, &mut parts.uri, &mut parts.headers);
+        let body = if rewrite.has_body_rules() && !is_websocket_upgrade(&parts.headers) {
+            rewrite
+                .rewrite_body(body, &mut parts.headers, self.max_body_size)
+                .await
+        } else {
+            body
+        };
 
-            RequestOrResponse::Request(req)
+        for name in &rewrite.applied {
+            tracing::debug!("Rule '{}' rewrote {} {}", name, parts.method, parts.uri);
         }
+        self.rewritten = Some(Rewritten {
+            method: parts.method.clone(),
+            uri: parts.uri.clone(),
+            applied: rewrite
+                .applied
+                .into_iter()
+                .map(|name| format!("{} (request)", name))
+                .collect(),
+        });
+        Request::from_parts(parts, body)
     }
 
-    fn handle_response(
+    /// Apply response rules to the response for this clone's request
+    async fn rewrite_response(&mut self, res: Response<Body>) -> Response<Body> {
+        let max_body_size = self.max_body_size;
+        let Some(rewritten) = self.rewritten.as_mut() else {
+            return res;
+        };
+
+        let rules = Arc::clone(&self.rules);
+        let (mut parts, body) = res.into_parts();
+        let mut rewrite =
+            rules.rewrite_response(&rewritten.method, &rewritten.uri, &mut parts.headers);
+        let body = if rewrite.has_body_rules() {
+            rewrite
+                .rewrite_body(body, &mut parts.headers, max_body_size)
+                .await
+        } else {
+            body
+        };
+
+        for name in rewrite.applied {
+            tracing::debug!("Rule '{}' rewrote the response to {}", name, rewritten.uri);
+            rewritten.applied.push(format!("{} (response)", name));
+        }
+        Response::from_parts(parts, body)
+    }
+
+    /// Answer a request from a matching mock, recording it as if the origin had
+    ///
+    /// Returns the request unchanged when no mock matches.
+    async fn answer_from_mock(
         &mut self,
-        _ctx: &HttpContext,
-        res: Response<Body>,
-    ) -> impl std::future::Future<Output = Response<Body>> + Send {
+        client_addr: SocketAddr,
+        req: Request<Body>,
+    ) -> RequestOrResponse {
+        if self.rules.mock_count() == 0
+            || req.method() == Method::CONNECT
+            || is_websocket_upgrade(req.headers())
+        {
+            return RequestOrResponse::Request(req);
+        }
+        let rules = Arc::clone(&self.rules);
+        let Some(mock) = rules.find_mock(req.method(), req.uri(), req.headers()) else {
+            return RequestOrResponse::Request(req);
+        };
+        tracing::debug!(
+            "Mock '{}' answered {} {}",
+            mock.name,
+            req.method(),
+            req.uri()
+        );
+
+        // Read the request body through the capture, since nothing else will
+        let req = self.capture_request(client_addr, req);
+        let (parts, body) = req.into_parts();
+        if let Err(e) = http_body_util::BodyExt::collect(body).await {
+            tracing::warn!("Failed to read mocked request body: {}", e);
+        }
+        if let Some(request) = self.in_flight.as_mut().and_then(|f| f.request.as_mut()) {
+            request.mocked_by = Some(mock.name.clone());
+        }
+
+        let res = mock.respond(&parts.method, &parts.uri).await;
+        let res = self.rewrite_response(res).await;
+        RequestOrResponse::Response(self.capture_response(res))
+    }
+
+    /// Rules applied to this clone's request and response so far
+    fn applied_rewrites(&self) -> Vec<String> {
+        self.rewritten
+            .as_ref()
+            .map(|rewritten| rewritten.applied.clone())
+            .unwrap_or_default()
+    }
+
+    /// Record a WebSocket upgrade; its frames are added as they are forwarded
+    fn capture_websocket(&mut self, client_addr: SocketAddr, req: &Request<Body>) {
+        let url = websocket_url(req.uri());
+        let headers_only = match self.scope.decide(&url) {
+            ScopeDecision::Record => false,
+            ScopeDecision::HeadersOnly => true,
+            ScopeDecision::Ignore => {
+                tracing::trace!("Not recording out-of-scope WebSocket: {}", url);
+                return;
+            }
+        };
+        let id = self.next_id(client_addr);
+        tracing::debug!(
+            "Intercepted WebSocket #{} from {}: {}",
+            id.sequence,
+            client_addr,
+            url
+        );
+
+        let request = PendingRequest {
+            id,
+            url,
+            method: req.method().to_string(),
+            version: req.version(),
+            request_headers: header_pairs(req.headers()),
+            request_body: BodySlot::default(),
+            headers_only,
+            rewrites: self.applied_rewrites(),
+            mocked_by: None,
+            started_at: std::time::SystemTime::now(),
+        };
+        let comment = headers_only.then(|| "Out of scope: frames not recorded".to_string());
+        let mut entry = request.into_entry(comment);
+
+        // The handshake response the proxy sends the client
+        let mut response_headers = vec![
+            ("connection".to_string(), "upgrade".to_string()),
+            ("upgrade".to_string(), "websocket".to_string()),
+        ];
+        if let Some(key) = req.headers().get(header::SEC_WEBSOCKET_KEY) {
+            let accept = derive_accept_key(key.as_bytes());
+            response_headers.push(("sec-websocket-accept".to_string(), accept));
+        }
+        entry.response_status = StatusCode::SWITCHING_PROTOCOLS.as_u16();
+        entry.status_text = "Switching Protocols".to_string();
+        entry.response_version = Some(req.version());
+        entry.response_headers = response_headers;
+        entry.responded_at = Some(std::time::SystemTime::now());
+        entry.websocket_messages = (!headers_only).then(Vec::new);
+
+        if let Some(stale) = self.websockets.open(entry, self.max_body_size) {
+            self.sink.push(stale);
+        }
+    }
+
+    /// Record every WebSocket and tunnel still open, e.g. when the proxy shuts down
+    pub(crate) fn close_open_connections(&self) {
+        for entry in self.websockets.close_all() {
+            self.sink.push(entry);
+        }
+        for entry in self.tunnels.close_all() {
+            self.sink.push(entry);
+        }
+    }
+
+    /// Relay a CONNECT tunnel to its server without decrypting it
+    ///
+    /// The entry records the tunnel's host, port, bytes and duration once it closes.
+    async fn tunnel(
+        &mut self,
+        client_addr: SocketAddr,
+        mut req: Request<Body>,
+        reason: Reason,
+    ) -> Response<Body> {
+        let Some(target) = req
+            .uri()
+            .authority()
+            .and_then(|authority| format!("https://{}", authority).parse::<http::Uri>().ok())
+        else {
+            return Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::empty())
+                .expect("Failed to build response");
+        };
+        let url = target.to_string();
+        tracing::debug!(
+            "Tunnelling {} without decryption ({})",
+            url,
+            reason.describe()
+        );
+
+        let entry = match self.scope.decide(&url) {
+            ScopeDecision::Ignore => None,
+            ScopeDecision::Record | ScopeDecision::HeadersOnly => {
+                let request = PendingRequest {
+                    id: self.next_id(client_addr),
+                    url: url.clone(),
+                    method: req.method().to_string(),
+                    version: req.version(),
+                    request_headers: header_pairs(req.headers()),
+                    request_body: BodySlot::default(),
+                    headers_only: true,
+                    rewrites: Vec::new(),
+                    mocked_by: None,
+                    started_at: std::time::SystemTime::now(),
+                };
+                Some(request.into_entry(None))
+            }
+        };
+
+        // Connect before answering, so the client learns of an unreachable server
+        let mut connector = UpstreamConnector::new(self.upstream.clone());
+        let server = match connector.call(target).await {
+            Ok(server) => server,
+            Err(e) => {
+                tracing::warn!("Failed to connect to {}: {}", url, e);
+                if let Some(mut entry) = entry {
+                    entry.comment = Some(format!(
+                        "No response received (tunnel to server failed: {})",
+                        e
+                    ));
+                    self.sink.push(entry);
+                }
+                return Response::builder()
+                    .status(StatusCode::BAD_GATEWAY)
+                    .body(Body::empty())
+                    .expect("Failed to build response");
+            }
+        };
+
+        let (id, counter) = match entry {
+            Some(mut entry) => {
+                entry.response_status = StatusCode::OK.as_u16();
+                entry.status_text = "Connection Established".to_string();
+                entry.response_version = Some(req.version());
+                entry.responded_at = Some(std::time::SystemTime::now());
+                let id = entry.id;
+                (Some(id), self.tunnels.open(entry, reason))
+            }
+            None => (None, Arc::default()),
+        };
+        let tunnels = self.tunnels.clone();
+        let sink = self.sink.clone();
+        tokio::spawn(async move {
+            match hyper::upgrade::on(&mut req).await {
+                Ok(upgraded) => {
+                    let mut client = Counted::new(TokioIo::new(upgraded), counter);
+                    let mut server = TokioIo::new(server);
+                    if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut server).await {
+                        tracing::debug!("Tunnel to {} closed: {}", url, e);
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to open tunnel to {}: {}", url, e),
+            }
+            if let Some(entry) = id.and_then(|id| tunnels.close(id)) {
+                sink.push(entry);
+            }
+        });
+
+        Response::new(Body::empty())
+    }
+
+    /// Relay a WebSocket through a CONNECT tunnel on the upstream proxy
+    ///
+    /// hudsucker connects WebSockets to their server directly, so upgrades the upstream
+    /// proxy applies to are handled here, forwarding frames the same way.
+    async fn upgrade_through(
+        &self,
+        client_addr: SocketAddr,
+        upstream: &UpstreamProxy,
+        req: Request<Body>,
+    ) -> Response<Body> {
+        let url = websocket_url(req.uri());
+        let (server, handshake) = match self.connect_websocket(upstream, &req, &url).await {
+            Ok(connected) => connected,
+            Err(e) => return self.websocket_failed(client_addr, &url, &e),
+        };
+
+        // The handshake response the proxy sends the client
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+        let headers = res.headers_mut();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        if let Some(key) = req.headers().get(header::SEC_WEBSOCKET_KEY)
+            && let Ok(accept) = HeaderValue::from_str(&derive_accept_key(key.as_bytes()))
+        {
+            headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+        }
+        // The subprotocol the server chose, which the client expects to see
+        if let Some(protocol) = handshake.headers().get(header::SEC_WEBSOCKET_PROTOCOL) {
+            headers.insert(header::SEC_WEBSOCKET_PROTOCOL, protocol.clone());
+        }
+
+        let handler = self.clone();
+        tokio::spawn(async move {
+            let client = match hyper::upgrade::on(req).await {
+                Ok(upgraded) => {
+                    WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None)
+                        .await
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to upgrade to WebSocket: {}", e);
+                    if let Some(entry) = handler.websockets.fail(client_addr, &e.to_string()) {
+                        handler.sink.push(entry);
+                    }
+                    return;
+                }
+            };
+            let (server_sink, server_stream) = server.split();
+            let (client_sink, client_stream) = client.split();
+            tokio::join!(
+                handler.forward_websocket(
+                    client_addr,
+                    Direction::Receive,
+                    server_stream,
+                    client_sink
+                ),
+                handler.forward_websocket(client_addr, Direction::Send, client_stream, server_sink),
+            );
+        });
+
+        res
+    }
+
+    /// Open the server side of a relayed WebSocket, sending on the client's handshake headers
+    ///
+    /// Returns the WebSocket and the server's handshake response.
+    async fn connect_websocket(
+        &self,
+        upstream: &UpstreamProxy,
+        req: &Request<Body>,
+        url: &str,
+    ) -> Result<(ServerWebSocket, tungstenite::handshake::client::Response), BoxError> {
+        let stream = upstream.tunnel(req.uri()).await?;
+        let mut request = Request::new(());
+        *request.uri_mut() = url.parse()?;
+        *request.headers_mut() = req.headers().clone();
+        let connector = self.websocket_connector.clone();
+        let connected =
+            tokio_tungstenite::client_async_tls_with_config(request, stream, None, connector)
+                .await?;
+        Ok(connected)
+    }
+
+    /// Forward frames as hudsucker does, recording each one on the upgrade entry
+    async fn forward_websocket(
+        &self,
+        client_addr: SocketAddr,
+        direction: Direction,
+        mut stream: impl Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+        mut sink: impl Sink<Message, Error = tungstenite::Error> + Unpin,
+    ) {
+        self.websockets.attach(client_addr);
+        let _direction = WebSocketDirection {
+            client_addr,
+            websockets: self.websockets.clone(),
+            sink: self.sink.clone(),
+        };
+
+        while let Some(message) = stream.next().await {
+            match message {
+                Ok(message) => {
+                    self.websockets.record(client_addr, direction, &message);
+                    match sink.send(message).await {
+                        Ok(()) | Err(tungstenite::Error::ConnectionClosed) => (),
+                        Err(e) => tracing::warn!("WebSocket send error: {}", e),
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("WebSocket message error: {}", e);
+                    let _ = sink.send(Message::Close(None)).await;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Record a WebSocket whose server could not be reached and answer `502`
+    fn websocket_failed(
+        &self,
+        client_addr: SocketAddr,
+        url: &str,
+        error: &dyn std::fmt::Display,
+    ) -> Response<Body> {
+        tracing::warn!("Failed to connect WebSocket to {}: {}", url, error);
+        if let Some(entry) = self.websockets.fail(client_addr, &error.to_string()) {
+            self.sink.push(entry);
+        }
+        Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .body(Body::empty())
+            .expect("Failed to build response")
+    }
+
+    /// Pair a response with this clone's request; the entry is stored once the body has streamed
+    fn capture_response(&mut self, res: Response<Body>) -> Response<Body> {
+        let Some(mut req) = self.in_flight.take().and_then(InFlight::take) else {
+            return res;
+        };
+        // Now including any response rules
+        req.rewrites = self.applied_rewrites();
+
+        let responded_at = std::time::SystemTime::now();
         let status = res.status().as_u16();
+        let status_text = match res.extensions().get::<ReasonPhrase>() {
+            Some(reason) => String::from_utf8_lossy(reason.as_bytes()).into_owned(),
+            None => res.status().canonical_reason().unwrap_or("").to_string(),
+        };
+        let response_version = res.version();
+        let server_addr = res
+            .extensions()
+            .get::<HttpInfo>()
+            .map(HttpInfo::remote_addr);
 
         // Capture response headers
-        let response_headers: Vec<(String, String)> = res
-            .headers()
-            .iter()
-            .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-            .collect();
-
-        tracing::debug!("Intercepted response: {}", status);
-
-        // Match response with the most recent pending request
-        // This is a simple FIFO approach for MVP - proper implementation would use request ID
-        let pending = self.pending.clone();
-        let entries = self.entries.clone();
-
-        async move {
-            let mut pending_guard = pending.lock().await;
-            if let Some(req) = pending_guard.pop() {
-                let entry = CapturedEntry {
-                    method: req.method,
-                    url: req.url,
-                    request_headers: req.request_headers,
-                    response_status: status,
-                    response_headers,
-                    started_at: req.started_at,
-                    completed_at: std::time::SystemTime::now(),
-                };
+        let response_headers = header_pairs(res.headers());
 
-                let mut entries_guard = entries.lock().await;
-                entries_guard.push(entry);
-                tracing::trace!("Captured entry (total: {})", entries_guard.len());
-                drop(entries_guard);
+        tracing::debug!(
+            "Intercepted response #{} to {}: {}",
+            req.id.sequence,
+            req.id.client_addr,
+            status
+        );
+
+        let answered = move |req: PendingRequest, comment: Option<String>| {
+            let mut entry = req.into_entry(comment);
+            entry.response_status = status;
+            entry.status_text = status_text;
+            entry.response_version = Some(response_version);
+            entry.response_headers = response_headers;
+            entry.server_addr = server_addr;
+            entry.responded_at = Some(responded_at);
+            entry
+        };
+
+        // Out-of-scope entries are complete as soon as the headers are in
+        if req.headers_only {
+            let comment = "Out of scope: bodies not recorded".to_string();
+            self.sink.push(answered(req, Some(comment)));
+            return res;
+        }
+
+        // The entry is complete once the response body has streamed to the client
+        let sink = self.sink.clone();
+        let max_body_size = self.max_body_size;
+        let (parts, body) = res.into_parts();
+        let encoding = content_encoding(&parts.headers);
+        let body = TeeBody::new(body, max_body_size, move |recorded| {
+            let mut entry = answered(req, None);
+            entry.response_body = Some(CapturedBody::decode(
+                recorded,
+                encoding.as_deref(),
+                max_body_size,
+            ));
+            sink.push(entry);
+        });
+
+        Response::from_parts(parts, body.into_body())
+    }
+
+    /// Record this clone's request as failed upstream
+    fn capture_error(&mut self, err: &ClientError) {
+        if let Some(req) = self.in_flight.take().and_then(InFlight::take) {
+            let comment = format!(
+                "No response received (upstream error: {})",
+                error_chain(err)
+            );
+            self.sink.push(req.into_entry(Some(comment)));
+        }
+    }
+}
+
+impl Default for HarCaptureHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for HarCaptureHandler {
+    /// Clones share captured entries; the in-flight request stays with the original
+    ///
+    /// The proxy clones its handler once per client connection, then per request, so
+    /// a clone of a handler not yet serving a connection starts a new connection state
+    /// that the request clones share and drop along with the connection.
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+            max_body_size: self.max_body_size,
+            scope: Arc::clone(&self.scope),
+            upstream: self.upstream.clone(),
+            auth: self.auth.clone(),
+            connection: Some(self.connection.clone().unwrap_or_default()),
+            rules: Arc::clone(&self.rules),
+            passthrough: Arc::clone(&self.passthrough),
+            websockets: self.websockets.clone(),
+            tunnels: self.tunnels.clone(),
+            websocket_connector: self.websocket_connector.clone(),
+            in_flight: None,
+            rewritten: None,
+        }
+    }
+}
+
+/// Where completed entries go: the journal when there is one, memory otherwise
+///
+/// Entries are added when the response body finishes streaming, possibly from a
+/// body's `Drop`, so the lock is never held across an await.
+#[derive(Clone, Default)]
+struct EntrySink {
+    entries: Arc<Mutex<Vec<CapturedEntry>>>,
+    journal: Option<HarJournal>,
+}
+
+impl EntrySink {
+    fn push(&self, entry: CapturedEntry) {
+        if let Some(journal) = &self.journal {
+            match journal.append(&entry.to_har_entry()) {
+                Ok(()) => return,
+                Err(e) => tracing::warn!("Failed to journal entry, keeping it in memory: {}", e),
             }
-            drop(pending_guard);
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.push(entry);
+        tracing::trace!("Captured entry (total: {})", entries.len());
+    }
+}
+
+/// Header names and values, with non-text values left empty
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+/// `Content-Encoding` header value, if any
+fn content_encoding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+}
+
+/// `ws://` or `wss://` URL of an upgrade request, as browsers record it
+fn websocket_url(uri: &http::Uri) -> String {
+    let url = uri.to_string();
+    if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        url
+    }
+}
 
-            res
+/// Finishes one forwarding direction of a WebSocket, even if its task is dropped
+struct WebSocketDirection {
+    client_addr: SocketAddr,
+    websockets: WebSocketSessions,
+    sink: EntrySink,
+}
+
+impl Drop for WebSocketDirection {
+    fn drop(&mut self) {
+        if let Some(entry) = self.websockets.detach(self.client_addr) {
+            tracing::debug!("WebSocket closed: {}", entry.url);
+            self.sink.push(entry);
+        }
+    }
+}
+
+/// Error message followed by its sources, e.g. "client error (Connect): tcp connect error"
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
+
+impl HttpHandler for HarCaptureHandler {
+    async fn handle_request(
+        &mut self,
+        ctx: &HttpContext,
+        mut req: Request<Body>,
+    ) -> RequestOrResponse {
+        // Unauthenticated clients are challenged before anything is recorded
+        if let Some(auth) = &self.auth
+            && !auth.authorize(
+                ctx.client_addr,
+                &self.connection.get_or_insert_default().trust,
+                req.headers_mut(),
+            )
+        {
+            return RequestOrResponse::Response(ProxyAuth::challenge());
+        }
+
+        // Passthrough hosts are relayed as opaque bytes, never decrypted
+        if req.method() == Method::CONNECT
+            && let Some(reason) = req.uri().host().and_then(|h| self.passthrough.reason(h))
+        {
+            let res = self.tunnel(ctx.client_addr, req, reason).await;
+            return RequestOrResponse::Response(res);
+        }
+        // A decrypted request shows the client accepted the proxy's certificate
+        if req.uri().scheme() == Some(&http::uri::Scheme::HTTPS)
+            && let Some(host) = req.uri().host()
+        {
+            self.passthrough.handshake_succeeded(host);
+        }
+
+        // Traffic is recorded as it is sent on, after rewriting
+        let req = self.rewrite_request(req).await;
+        let req = match self.answer_from_mock(ctx.client_addr, req).await {
+            RequestOrResponse::Request(req) => req,
+            mocked => return mocked,
+        };
+        let mut req = self.capture_request(ctx.client_addr, req);
+
+        if is_websocket_upgrade(req.headers())
+            && let Some(upstream) = self.upstream.as_ref().filter(|u| u.applies_to(req.uri()))
+        {
+            let res = self.upgrade_through(ctx.client_addr, upstream, req).await;
+            return RequestOrResponse::Response(res);
+        }
+
+        // Added after capture so upstream credentials never reach the HAR
+        if let Some(auth) = self
+            .upstream
+            .as_ref()
+            .and_then(|upstream| upstream.proxy_authorization(req.uri()))
+        {
+            req.headers_mut().insert(header::PROXY_AUTHORIZATION, auth);
         }
+
+        RequestOrResponse::Request(req)
+    }
+
+    async fn handle_response(&mut self, _ctx: &HttpContext, res: Response<Body>) -> Response<Body> {
+        let res = self.rewrite_response(res).await;
+        self.capture_response(res)
+    }
+
+    async fn handle_error(&mut self, _ctx: &HttpContext, err: ClientError) -> Response<Body> {
+        tracing::warn!("Failed to forward request: {}", err);
+        self.capture_error(&err);
+        Response::builder()
+            .status(StatusCode::BAD_GATEWAY)
+            .body(Body::empty())
+            .expect("Failed to build response")
+    }
+}
+
+impl WebSocketHandler for HarCaptureHandler {
+    async fn handle_websocket(
+        self,
+        ctx: WebSocketContext,
+        stream: impl Stream<Item = Result<Message, tungstenite::Error>> + Unpin + Send + 'static,
+        sink: impl Sink<Message, Error = tungstenite::Error> + Unpin + Send + 'static,
+    ) {
+        let (client_addr, direction) = match ctx {
+            WebSocketContext::ClientToServer { src, .. } => (src, Direction::Send),
+            WebSocketContext::ServerToClient { dst, .. } => (dst, Direction::Receive),
+        };
+        self.forward_websocket(client_addr, direction, stream, sink)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    fn client() -> SocketAddr {
+        ([127, 0, 0, 1], 50000).into()
+    }
+
+    #[tokio::test]
+    async fn test_captures_request_and_response_bodies() {
+        let mut handler = HarCaptureHandler::new();
+
+        let req = Request::builder()
+            .method("POST")
+            .uri("http://api.example.com/login")
+            .body(Body::from("{\"user\":\"alice\"}"))
+            .unwrap();
+        let req = handler.capture_request(client(), req);
+        let forwarded = req.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&forwarded[..], b"{\"user\":\"alice\"}");
+
+        let res = Response::builder()
+            .status(200)
+            .body(Body::from("{\"token\":\"abc\"}"))
+            .unwrap();
+        let res = handler.capture_response(res);
+        assert!(handler.entries().lock().unwrap().is_empty());
+
+        let delivered = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&delivered[..], b"{\"token\":\"abc\"}");
+
+        let entries = handler.entries().lock().unwrap().clone();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].request_body.as_ref().unwrap().data,
+            b"{\"user\":\"alice\"}"
+        );
+        assert_eq!(
+            entries[0].response_body.as_ref().unwrap().data,
+            b"{\"token\":\"abc\"}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_large_body_truncated_but_forwarded_whole() {
+        let mut handler = HarCaptureHandler::new().with_max_body_size(4);
+
+        let req = Request::builder()
+            .uri("http://cdn.example.com/video.mp4")
+            .body(Body::empty())
+            .unwrap();
+        let req = handler.capture_request(client(), req);
+        req.into_body().collect().await.unwrap();
+
+        let res = Response::builder().body(Body::from("0123456789")).unwrap();
+        let res = handler.capture_response(res);
+        let delivered = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(delivered.len(), 10);
+
+        let entries = handler.entries().lock().unwrap().clone();
+        let body = entries[0].response_body.as_ref().unwrap();
+        assert_eq!(body.data, b"0123");
+        assert!(body.truncated);
+        assert_eq!(body.wire_size, 10);
+    }
+
+    #[test]
+    fn test_sequence_per_connection() {
+        let handler = HarCaptureHandler::new();
+        let other: SocketAddr = ([127, 0, 0, 1], 50001).into();
+
+        // Request clones share their connection's sequence
+        let mut connection = handler.clone();
+        assert_eq!(connection.next_id(client()).sequence, 0);
+        assert_eq!(connection.clone().next_id(client()).sequence, 1);
+        assert_eq!(handler.clone().next_id(other).sequence, 0);
+
+        // A new connection from a reused address starts over
+        drop(connection);
+        assert_eq!(handler.clone().next_id(client()).sequence, 0);
+    }
+
+    #[test]
+    fn test_dropped_request_recorded_as_aborted() {
+        let handler = HarCaptureHandler::new();
+
+        let mut clone = handler.clone();
+        let req = Request::builder()
+            .uri("http://api.example.com/slow")
+            .body(Body::empty())
+            .unwrap();
+        drop(clone.capture_request(client(), req));
+        drop(clone);
+
+        let entries = handler.entries().lock().unwrap().clone();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].response_status, 0);
+        assert_eq!(
+            entries[0].comment.as_deref(),
+            Some("No response received (request aborted)")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_out_of_scope_traffic() {
+        let scope = CaptureScope::new()
+            .with_hosts(vec!["api.example.com".to_string()])
+            .unwrap()
+            .with_ignored_hosts(vec!["telemetry.example.com".to_string()])
+            .unwrap()
+            .with_out_of_scope(harrier_core::filter::OutOfScope::HeadersOnly);
+        let handler = HarCaptureHandler::new().with_scope(scope);
+
+        for url in [
+            "http://telemetry.example.com/collect",
+            "http://cdn.example.com/app.js",
+        ] {
+            let mut clone = handler.clone();
+            let req = Request::builder()
+                .uri(url)
+                .body(Body::from("payload"))
+                .unwrap();
+            let req = clone.capture_request(client(), req);
+            req.into_body().collect().await.unwrap();
+            let res = Response::builder()
+                .body(Body::from("console.log(1)"))
+                .unwrap();
+            let res = clone.capture_response(res);
+            res.into_body().collect().await.unwrap();
+        }
+
+        // Ignored hosts leave nothing behind; out-of-scope ones keep headers only
+        let entries = handler.entries().lock().unwrap().clone();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, "http://cdn.example.com/app.js");
+        assert_eq!(entries[0].response_status, 200);
+        assert!(entries[0].request_body.is_none());
+        assert!(entries[0].response_body.is_none());
+        assert_eq!(
+            entries[0].comment.as_deref(),
+            Some("Out of scope: bodies not recorded")
+        );
+    }
+
+    #[test]
+    fn test_connect_not_tracked() {
+        let mut handler = HarCaptureHandler::new();
+        let req = Request::builder()
+            .method("CONNECT")
+            .uri("api.example.com:443")
+            .body(Body::empty())
+            .unwrap();
+        handler.capture_request(client(), req);
+        drop(handler.clone());
+        assert!(handler.in_flight.is_none());
     }
 }
@@ -0,0 +1,520 @@
+use crate::body::CapturedBody;
+use crate::handler::{CapturedEntry, HarCaptureHandler};
+use chrono::{DateTime, Utc};
+use harrier_core::har::{
+    Cache, Content, Cookie, Creator, Entry, Har, Header, Log, Param, PostData, QueryParam, Request,
+    Response, Timings,
+};
+use http::Version;
+use std::sync::PoisonError;
+use std::time::SystemTime;
+
+impl HarCaptureHandler {
+    /// Convert captured transactions to HAR format, in request order
+    pub fn to_har(&self) -> Har {
+        let mut captured = self
+            .entries()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        // Entries are recorded as responses finish, so restore request order
+        captured.sort_by_key(|entry| entry.started_at);
+
+        Har {
+            log: Log {
+                version: "1.2".to_string(),
+                creator: Creator {
+                    name: "Harrier".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    comment: None,
+                },
+                browser: None,
+                pages: None,
+                entries: captured.iter().map(CapturedEntry::to_har_entry).collect(),
+                comment: None,
+            },
+        }
+    }
+}
+
+impl CapturedEntry {
+    /// Convert to a HAR entry
+    pub fn to_har_entry(&self) -> Entry {
+        let wait = self.responded_at.unwrap_or(self.completed_at);
+
+        Entry {
+            page_ref: None,
+            started_date_time: iso8601(self.started_at),
+            time: millis_between(self.started_at, self.completed_at),
+            request: self.har_request(),
+            response: self.har_response(),
+            cache: Cache {
+                before_request: None,
+                after_request: None,
+                comment: None,
+            },
+            timings: Timings {
+                blocked: None,
+                dns: None,
+                connect: None,
+                send: 0.0,
+                wait: millis_between(self.started_at, wait),
+                receive: millis_between(wait, self.completed_at),
+                ssl: None,
+                comment: None,
+            },
+            server_ip_address: self.server_addr.map(|addr| addr.ip().to_string()),
+            connection: Some(self.id.client_addr.port().to_string()),
+            comment: self.har_comment(),
+            web_socket_messages: self.websocket_messages.clone(),
+        }
+    }
+
+    /// Entry comment, also naming any mock that answered and rewrite rules that changed
+    /// the traffic
+    fn har_comment(&self) -> Option<String> {
+        let mocked = self
+            .mocked_by
+            .as_ref()
+            .map(|name| format!("Mocked by '{}' (origin not contacted)", name));
+        let rewrites = (!self.rewrites.is_empty())
+            .then(|| format!("Rewritten by rules: {}", self.rewrites.join(", ")));
+        let notes: Vec<String> = [self.comment.clone(), mocked, rewrites]
+            .into_iter()
+            .flatten()
+            .collect();
+        (!notes.is_empty()).then(|| notes.join("; "))
+    }
+
+    fn har_request(&self) -> Request {
+        let url = url::Url::parse(&self.url).ok();
+        // A CONNECT names its tunnel's host and port
+        let target = match self.url.strip_prefix("https://") {
+            Some(authority) if self.method == "CONNECT" => authority.to_string(),
+            _ => url.as_ref().map_or_else(
+                || self.url.clone(),
+                |url| match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                },
+            ),
+        };
+        let query_string = url
+            .as_ref()
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| QueryParam {
+                        name: name.into_owned(),
+                        value: value.into_owned(),
+                        comment: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Request {
+            method: self.method.clone(),
+            url: self.url.clone(),
+            http_version: http_version(self.request_version),
+            cookies: request_cookies(&self.request_headers),
+            headers: har_headers(&self.request_headers),
+            query_string,
+            post_data: self
+                .request_body
+                .as_ref()
+                .filter(|body| !body.is_empty())
+                .map(|body| post_data(body, header_value(&self.request_headers, "content-type"))),
+            headers_size: headers_size(
+                self.request_version,
+                &format!("{} {}", self.method, target),
+                &self.request_headers,
+            ),
+            body_size: match self.tunnel {
+                Some(tunnel) => tunnel.sent as i64,
+                None => self
+                    .request_body
+                    .as_ref()
+                    .map_or(0, |body| body.wire_size as i64),
+            },
+            comment: None,
+        }
+    }
+
+    fn har_response(&self) -> Response {
+        let Some(version) = self.response_version else {
+            // No response: status 0, as browsers record aborted requests
+            return Response {
+                status: 0,
+                status_text: String::new(),
+                http_version: String::new(),
+                cookies: vec![],
+                headers: vec![],
+                content: Content {
+                    size: 0,
+                    compression: None,
+                    mime_type: "x-unknown".to_string(),
+                    text: None,
+                    encoding: None,
+                    comment: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+                comment: None,
+            };
+        };
+
+        let mime_type = header_value(&self.response_headers, "content-type")
+            .unwrap_or("x-unknown")
+            .to_string();
+
+        Response {
+            status: self.response_status as i64,
+            status_text: self.status_text.clone(),
+            http_version: http_version(version),
+            cookies: response_cookies(&self.response_headers),
+            headers: har_headers(&self.response_headers),
+            content: content(self.response_body.as_ref(), mime_type),
+            redirect_url: header_value(&self.response_headers, "location")
+                .unwrap_or_default()
+                .to_string(),
+            headers_size: headers_size(
+                version,
+                &format!("{} {}", self.response_status, self.status_text),
+                &self.response_headers,
+            ),
+            body_size: match self.tunnel {
+                Some(tunnel) => tunnel.received as i64,
+                None => self
+                    .response_body
+                    .as_ref()
+                    .map_or(-1, |body| body.wire_size as i64),
+            },
+            comment: None,
+        }
+    }
+}
+
+fn iso8601(time: SystemTime) -> String {
+    let datetime: DateTime<Utc> = time.into();
+    datetime.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn millis_between(start: SystemTime, end: SystemTime) -> f64 {
+    end.duration_since(start).unwrap_or_default().as_secs_f64() * 1000.0
+}
+
+/// HAR version string, e.g. "HTTP/1.1" or "HTTP/2.0"
+fn http_version(version: Version) -> String {
+    format!("{:?}", version)
+}
+
+/// Size of an HTTP/1.x header block, from the start line through the blank line
+///
+/// HTTP/2 and HTTP/3 compress headers, so their size on the wire is unknown (-1).
+fn headers_size(version: Version, start_line: &str, headers: &[(String, String)]) -> i64 {
+    if version > Version::HTTP_11 {
+        return -1;
+    }
+    // "<start line> HTTP/1.1\r\n", each "name: value\r\n", then "\r\n"
+    let start = start_line.len() + 1 + http_version(version).len() + 2;
+    let fields: usize = headers
+        .iter()
+        .map(|(name, value)| name.len() + 2 + value.len() + 2)
+        .sum();
+    (start + fields + 2) as i64
+}
+
+fn har_headers(headers: &[(String, String)]) -> Vec<Header> {
+    headers
+        .iter()
+        .map(|(name, value)| Header {
+            name: name.clone(),
+            value: value.clone(),
+            comment: None,
+        })
+        .collect()
+}
+
+/// First value of a header, matched case-insensitively
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+fn cookie(name: &str, value: &str) -> Cookie {
+    Cookie {
+        name: name.trim().to_string(),
+        value: value.trim().to_string(),
+        path: None,
+        domain: None,
+        expires: None,
+        http_only: None,
+        secure: None,
+        comment: None,
+    }
+}
+
+/// Cookies sent in `Cookie` headers
+fn request_cookies(headers: &[(String, String)]) -> Vec<Cookie> {
+    headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, v)| v.split(';'))
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| cookie(name, value))
+        .collect()
+}
+
+/// Cookies set by `Set-Cookie` headers, with their attributes
+fn response_cookies(headers: &[(String, String)]) -> Vec<Cookie> {
+    headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("set-cookie"))
+        .filter_map(|(_, v)| {
+            let mut parts = v.split(';');
+            let (name, value) = parts.next()?.split_once('=')?;
+            let mut cookie = cookie(name, value);
+            cookie.http_only = Some(false);
+            cookie.secure = Some(false);
+
+            for attribute in parts {
+                let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+                let value = value.trim();
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "path" => cookie.path = Some(value.to_string()),
+                    "domain" => cookie.domain = Some(value.to_string()),
+                    "expires" => {
+                        cookie.expires = Some(
+                            DateTime::parse_from_rfc2822(value)
+                                .map(|date| {
+                                    date.with_timezone(&Utc)
+                                        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                                })
+                                .unwrap_or_else(|_| value.to_string()),
+                        )
+                    }
+                    "httponly" => cookie.http_only = Some(true),
+                    "secure" => cookie.secure = Some(true),
+                    _ => {}
+                }
+            }
+            Some(cookie)
+        })
+        .collect()
+}
+
+/// HAR `postData` for a captured request body
+fn post_data(body: &CapturedBody, mime_type: Option<&str>) -> PostData {
+    let mime_type = mime_type.unwrap_or("application/octet-stream");
+    let (text, encoding) = body.to_text();
+
+    // Form bodies are also listed field by field
+    let params = (encoding.is_none() && mime_type.starts_with("application/x-www-form-urlencoded"))
+        .then(|| {
+            url::form_urlencoded::parse(text.as_bytes())
+                .map(|(name, value)| Param {
+                    name: name.into_owned(),
+                    value: Some(value.into_owned()),
+                    file_name: None,
+                    content_type: None,
+                    comment: None,
+                })
+                .collect()
+        });
+
+    // postData has no `encoding` field, so binary bodies are flagged in the comment
+    let comment = [
+        encoding.map(|_| "Body is base64 encoded".to_string()),
+        body.truncation_comment(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("; ");
+
+    PostData {
+        mime_type: mime_type.to_string(),
+        params,
+        text: Some(text),
+        comment: (!comment.is_empty()).then_some(comment),
+    }
+}
+
+/// HAR `content` for a captured response body
+fn content(body: Option<&CapturedBody>, mime_type: String) -> Content {
+    let Some(body) = body else {
+        return Content {
+            size: 0,
+            compression: None,
+            mime_type,
+            text: None,
+            encoding: None,
+            comment: None,
+        };
+    };
+
+    let (text, encoding) = if body.is_empty() {
+        (None, None)
+    } else {
+        let (text, encoding) = body.to_text();
+        (Some(text), encoding.map(str::to_string))
+    };
+
+    Content {
+        size: body.size as i64,
+        compression: (!body.truncated && body.size != body.wire_size)
+            .then(|| body.size as i64 - body.wire_size as i64),
+        mime_type,
+        text,
+        encoding,
+        comment: body.truncation_comment(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::RequestId;
+    use harrier_core::har::HarReader;
+
+    fn entry() -> CapturedEntry {
+        let started_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        CapturedEntry {
+            id: RequestId {
+                client_addr: ([127, 0, 0, 1], 50000).into(),
+                sequence: 0,
+            },
+            method: "POST".to_string(),
+            url: "https://api.example.com/login?next=%2Fhome&lang=en".to_string(),
+            request_version: Version::HTTP_11,
+            request_headers: vec![
+                ("host".to_string(), "api.example.com".to_string()),
+                ("cookie".to_string(), "theme=dark; sid=abc".to_string()),
+                (
+                    "content-type".to_string(),
+                    "application/x-www-form-urlencoded".to_string(),
+                ),
+            ],
+            request_body: Some(CapturedBody {
+                data: b"user=alice&pass=s%26cret".to_vec(),
+                size: 24,
+                wire_size: 24,
+                truncated: false,
+                max_size: 1024,
+            }),
+            response_status: 302,
+            status_text: "Found".to_string(),
+            response_version: Some(Version::HTTP_2),
+            response_headers: vec![
+                ("location".to_string(), "/home".to_string()),
+                (
+                    "set-cookie".to_string(),
+                    "sid=xyz; Path=/; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly; Secure"
+                        .to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+            ],
+            response_body: Some(CapturedBody::default()),
+            server_addr: Some(([93, 184, 216, 34], 443).into()),
+            started_at,
+            responded_at: Some(started_at + std::time::Duration::from_millis(120)),
+            completed_at: started_at + std::time::Duration::from_millis(150),
+            comment: None,
+            rewrites: Vec::new(),
+            mocked_by: None,
+            tunnel: None,
+            websocket_messages: None,
+        }
+    }
+
+    #[test]
+    fn test_entry_fields() {
+        let har = entry().to_har_entry();
+
+        assert_eq!(har.started_date_time, "2023-11-14T22:13:20.000Z");
+        assert_eq!(har.time, 150.0);
+        assert_eq!((har.timings.wait, har.timings.receive), (120.0, 30.0));
+        assert_eq!(har.server_ip_address.as_deref(), Some("93.184.216.34"));
+        assert_eq!(har.connection.as_deref(), Some("50000"));
+
+        let request = &har.request;
+        assert_eq!(request.http_version, "HTTP/1.1");
+        assert_eq!(request.query_string.len(), 2);
+        assert_eq!(request.query_string[0].value, "/home");
+        assert_eq!(request.cookies.len(), 2);
+        assert_eq!(request.cookies[1].name, "sid");
+        let params = request.post_data.as_ref().unwrap().params.as_ref().unwrap();
+        assert_eq!(params[1].value.as_deref(), Some("s&cret"));
+        assert_eq!(request.body_size, 24);
+        assert!(request.headers_size > 0);
+
+        let response = &har.response;
+        assert_eq!(response.status_text, "Found");
+        assert_eq!(response.http_version, "HTTP/2.0");
+        assert_eq!(response.redirect_url, "/home");
+        assert_eq!(response.headers_size, -1);
+        assert_eq!(response.content.mime_type, "text/html");
+        let cookie = &response.cookies[0];
+        assert_eq!(cookie.path.as_deref(), Some("/"));
+        assert_eq!(cookie.expires.as_deref(), Some("2026-10-21T07:28:00.000Z"));
+        assert_eq!((cookie.http_only, cookie.secure), (Some(true), Some(true)));
+    }
+
+    #[test]
+    fn test_http1_headers_size() {
+        let headers = vec![("host".to_string(), "a.io".to_string())];
+        // "GET / HTTP/1.1\r\n" + "host: a.io\r\n" + "\r\n"
+        assert_eq!(
+            headers_size(Version::HTTP_11, "GET /", &headers),
+            16 + 12 + 2
+        );
+    }
+
+    #[test]
+    fn test_unanswered_entry() {
+        let mut entry = entry();
+        entry.response_status = 0;
+        entry.response_version = None;
+        entry.comment = Some("No response received (request aborted)".to_string());
+
+        let har = entry.to_har_entry();
+        assert_eq!(har.response.status, 0);
+        assert_eq!(har.response.http_version, "");
+        assert_eq!(har.comment, entry.comment);
+    }
+
+    #[test]
+    fn test_rewrites_noted_in_comment() {
+        let mut entry = entry();
+        entry.rewrites = vec![
+            "Inject auth (request)".to_string(),
+            "No caching (response)".to_string(),
+        ];
+        assert_eq!(
+            entry.to_har_entry().comment.as_deref(),
+            Some("Rewritten by rules: Inject auth (request), No caching (response)")
+        );
+
+        entry.comment = Some("Out of scope: bodies not recorded".to_string());
+        assert_eq!(
+            entry.to_har_entry().comment.as_deref(),
+            Some(
+                "Out of scope: bodies not recorded; Rewritten by rules: Inject auth (request), No caching (response)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_har_round_trips_and_validates() {
+        let handler = HarCaptureHandler::new();
+        handler.entries().lock().unwrap().push(entry());
+
+        let json = serde_json::to_string(&handler.to_har()).unwrap();
+        let har = HarReader::parse(&json).unwrap();
+        HarReader::validate(&har).unwrap();
+        assert_eq!(har.log.entries.len(), 1);
+    }
+}
@@ -1,11 +1,24 @@
 // Proxy functionality for HAR capture
 
+pub mod auth;
+pub mod body;
 pub mod cert;
 pub mod error;
 pub mod handler;
+pub mod har;
+mod mock;
+pub mod passthrough;
+pub mod rewrite;
 pub mod server;
+pub mod upstream;
+mod websocket;
 
+pub use auth::ProxyAuth;
+pub use body::{CapturedBody, DEFAULT_MAX_BODY_SIZE};
 pub use cert::CertificateAuthority;
 pub use error::{Error, Result};
-pub use handler::{CapturedEntry, HarCaptureHandler};
+pub use handler::{CapturedEntry, HarCaptureHandler, RequestId};
+pub use passthrough::{TlsPassthrough, TunnelBytes};
+pub use rewrite::RewriteRules;
 pub use server::ProxyServer;
+pub use upstream::UpstreamProxy;
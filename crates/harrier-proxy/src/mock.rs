@@ -0,0 +1,254 @@
+use crate::rewrite::{HeaderMatchConfig, Matcher};
+use crate::{Error, Result};
+use http::header::{self, HeaderName};
+use http::{HeaderMap, HeaderValue, Method, Response, StatusCode, Uri};
+use http_body_util::Full;
+use hudsucker::Body;
+use hyper::body::Bytes;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Mock as written in the rules file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MockConfig {
+    name: Option<String>,
+    host: Option<String>,
+    path: Option<String>,
+    method: Option<String>,
+    header: Option<HeaderMatchConfig>,
+    #[serde(default = "default_status")]
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    /// Inline body template
+    body: Option<String>,
+    /// File served as the body, relative to the rules file
+    file: Option<PathBuf>,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+/// Response the proxy sends itself for matching requests, without contacting the origin
+#[derive(Debug, Clone)]
+pub(crate) struct Mock {
+    pub(crate) name: String,
+    pub(crate) matcher: Matcher,
+    status: StatusCode,
+    /// Header values may contain placeholders, filled in per request
+    headers: Vec<(HeaderName, String)>,
+    body: MockBody,
+}
+
+#[derive(Debug, Clone)]
+enum MockBody {
+    Template(String),
+    /// Read for every request, so edits show up without restarting the proxy
+    File(PathBuf),
+}
+
+impl Mock {
+    /// Compile a mock; `base` is the directory `file` paths are relative to
+    pub(crate) fn compile(config: MockConfig, position: usize, base: &Path) -> Result<Self> {
+        let name = config.name.unwrap_or_else(|| format!("mock {}", position));
+        let invalid = |what: &str, e: &dyn std::fmt::Display| {
+            Error::Proxy(format!("Mock '{}': invalid {}: {}", name, what, e))
+        };
+
+        let matcher = Matcher::compile(
+            config.host.as_deref(),
+            config.path.as_deref(),
+            config.method.as_deref(),
+            config.header.as_ref(),
+            &invalid,
+        )?;
+        let status = StatusCode::from_u16(config.status).map_err(|e| invalid("status", &e))?;
+
+        let mut headers = Vec::new();
+        for (name, value) in config.headers {
+            let name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid("header name", &e))?;
+            HeaderValue::from_str(&value).map_err(|e| invalid("header value", &e))?;
+            headers.push((name, value));
+        }
+
+        let body = match (config.body, config.file) {
+            (Some(_), Some(_)) => {
+                return Err(Error::Proxy(format!(
+                    "Mock '{}': set either body or file, not both",
+                    name
+                )));
+            }
+            (_, Some(file)) => {
+                let file = base.join(file);
+                if !file.is_file() {
+                    return Err(Error::Proxy(format!(
+                        "Mock '{}': file {} not found",
+                        name,
+                        file.display()
+                    )));
+                }
+                MockBody::File(file)
+            }
+            (body, None) => MockBody::Template(body.unwrap_or_default()),
+        };
+
+        Ok(Self {
+            name,
+            matcher,
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Build the response for a request
+    ///
+    /// A file that can no longer be read is answered with a 500 naming it.
+    pub(crate) async fn respond(&self, method: &Method, uri: &Uri) -> Response<Body> {
+        let (status, body) = match &self.body {
+            MockBody::Template(template) => (self.status, fill(template, method, uri).into()),
+            MockBody::File(path) => match tokio::fs::read(path).await {
+                Ok(data) => (self.status, Bytes::from(data)),
+                Err(e) => {
+                    tracing::warn!(
+                        "Mock '{}' could not read {}: {}",
+                        self.name,
+                        path.display(),
+                        e
+                    );
+                    let message = format!("Mock file unavailable: {}", path.display());
+                    (StatusCode::INTERNAL_SERVER_ERROR, message.into())
+                }
+            },
+        };
+
+        let mut headers = HeaderMap::new();
+        for (name, template) in &self.headers {
+            match HeaderValue::from_str(&fill(template, method, uri)) {
+                Ok(value) => {
+                    headers.append(name.clone(), value);
+                }
+                Err(e) => {
+                    tracing::warn!("Mock '{}' produced an invalid {}: {}", self.name, name, e)
+                }
+            }
+        }
+        if !headers.contains_key(header::CONTENT_TYPE)
+            && let MockBody::File(path) = &self.body
+            && let Some(mime_type) = mime_type(path)
+        {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(mime_type));
+        }
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+
+        let mut response = Response::new(Body::from(Full::new(body)));
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+    }
+}
+
+/// Fill in `{{method}}`, `{{url}}`, `{{host}}`, `{{path}}` and `{{query}}`
+fn fill(template: &str, method: &Method, uri: &Uri) -> String {
+    if !template.contains("{{") {
+        return template.to_string();
+    }
+    template
+        .replace("{{method}}", method.as_str())
+        .replace("{{url}}", &uri.to_string())
+        .replace("{{host}}", uri.host().unwrap_or(""))
+        .replace("{{path}}", uri.path())
+        .replace("{{query}}", uri.query().unwrap_or(""))
+}
+
+/// Content type for a file served by a mock, guessed from its extension
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mime_type = match extension.as_str() {
+        "json" => "application/json",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    };
+    Some(mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    fn mock(toml: &str, base: &Path) -> Result<Mock> {
+        let config: MockConfig = toml::from_str(toml).unwrap();
+        Mock::compile(config, 1, base)
+    }
+
+    #[tokio::test]
+    async fn test_inline_template() {
+        let mock = mock(
+            r#"
+status = 401
+headers = { Content-Type = "application/json", X-Mocked-Path = "{{path}}" }
+body = '{"error":"unauthorized","method":"{{method}}","query":"{{query}}"}'
+"#,
+            Path::new("."),
+        )
+        .unwrap();
+        assert_eq!(mock.name, "mock 1");
+
+        let uri: Uri = "https://api.example.com/v1/me?full=1".parse().unwrap();
+        let response = mock.respond(&Method::GET, &uri).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()["x-mocked-path"], "/v1/me");
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            body.as_ref(),
+            br#"{"error":"unauthorized","method":"GET","query":"full=1"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_body() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("users.json"), "[]").unwrap();
+        let mock = mock("file = \"users.json\"\n", dir.path()).unwrap();
+
+        let uri: Uri = "http://localhost/users".parse().unwrap();
+        let response = mock.respond(&Method::GET, &uri).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert_eq!(response.headers()["content-length"], "2");
+
+        // Files removed after loading are answered with a 500
+        std::fs::remove_file(dir.path().join("users.json")).unwrap();
+        let response = mock.respond(&Method::GET, &uri).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_invalid_mocks_rejected() {
+        let err = mock("status = 1000\n", Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("Mock 'mock 1': invalid status"));
+
+        let err = mock("file = \"missing.json\"\n", Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("missing.json not found"));
+
+        let err = mock("body = \"\"\nfile = \"a.json\"\n", Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("set either body or file"));
+    }
+}
@@ -0,0 +1,423 @@
+use crate::handler::{CapturedEntry, RequestId};
+use crate::{Error, Result};
+use harrier_core::filter::HostPattern;
+use http::uri::Authority;
+use hudsucker::certificate_authority::CertificateAuthority;
+use hudsucker::rustls::ServerConfig;
+use hudsucker::rustls::server::StoresServerSessions;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Failed handshakes in a row after which a host is tunnelled instead of intercepted
+const FAILURES_BEFORE_PASSTHROUGH: u32 = 3;
+
+/// Hosts whose TLS is tunnelled to the server without decryption
+///
+/// Hosts are listed up front, or detected when clients keep rejecting the proxy's
+/// certificate for them, as certificate-pinning clients do.
+#[derive(Debug)]
+pub struct TlsPassthrough {
+    hosts: Vec<HostPattern>,
+    detect: bool,
+    /// Failed handshakes in a row per host
+    failures: Mutex<HashMap<String, u32>>,
+    /// Hosts tunnelled after repeated handshake failures
+    detected: Mutex<HashSet<String>>,
+    /// Success flags of the handshakes watched on open connections, per host
+    watching: Mutex<HashMap<String, Vec<Arc<AtomicBool>>>>,
+}
+
+/// Why a CONNECT is tunnelled rather than intercepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    Listed,
+    Detected,
+}
+
+impl Reason {
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            Reason::Listed => "host excluded from interception",
+            Reason::Detected => "client rejected the proxy's certificate",
+        }
+    }
+}
+
+impl TlsPassthrough {
+    /// Tunnel hosts matching `hosts` (exact or glob), detecting others as they fail
+    pub fn new(hosts: &[String]) -> Result<Self> {
+        let hosts = hosts
+            .iter()
+            .map(|host| {
+                HostPattern::parse(host).map_err(|e| {
+                    Error::Proxy(format!("Invalid passthrough host '{}': {}", host, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            hosts,
+            ..Self::default()
+        })
+    }
+
+    /// Only tunnel the listed hosts
+    pub fn without_detection(mut self) -> Self {
+        self.detect = false;
+        self
+    }
+
+    /// Whether a CONNECT to `host` should be tunnelled, and why
+    pub(crate) fn reason(&self, host: &str) -> Option<Reason> {
+        if self.hosts.iter().any(|pattern| pattern.matches(host)) {
+            return Some(Reason::Listed);
+        }
+        lock(&self.detected)
+            .contains(&host.to_ascii_lowercase())
+            .then_some(Reason::Detected)
+    }
+
+    /// Note that traffic for `host` was decrypted, so its handshakes work
+    ///
+    /// Requests don't say which connection carried them, so every open connection to
+    /// `host` counts as succeeded.
+    pub(crate) fn handshake_succeeded(&self, host: &str) {
+        if !self.detect {
+            return;
+        }
+        let host = host.to_ascii_lowercase();
+        if let Some(flags) = lock(&self.watching).get(&host) {
+            for succeeded in flags {
+                succeeded.store(true, Ordering::Relaxed);
+            }
+        }
+        lock(&self.failures).remove(&host);
+    }
+
+    /// Start watching a handshake with `host`, returning its success flag
+    fn watch(&self, host: &str) -> Arc<AtomicBool> {
+        let succeeded = Arc::new(AtomicBool::new(false));
+        lock(&self.watching)
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .push(Arc::clone(&succeeded));
+        succeeded
+    }
+
+    /// Stop watching a handshake, reporting whether it succeeded
+    fn unwatch(&self, host: &str, succeeded: &Arc<AtomicBool>) -> bool {
+        let host = host.to_ascii_lowercase();
+        let mut watching = lock(&self.watching);
+        if let Some(flags) = watching.get_mut(&host) {
+            flags.retain(|flag| !Arc::ptr_eq(flag, succeeded));
+            if flags.is_empty() {
+                watching.remove(&host);
+            }
+        }
+        succeeded.load(Ordering::Relaxed)
+    }
+
+    fn handshake_failed(&self, host: &str) {
+        let host = host.to_ascii_lowercase();
+        let mut failures = lock(&self.failures);
+        let count = failures.entry(host.clone()).or_default();
+        *count += 1;
+        if *count < FAILURES_BEFORE_PASSTHROUGH {
+            return;
+        }
+        failures.remove(&host);
+        drop(failures);
+
+        if lock(&self.detected).insert(host.clone()) {
+            tracing::warn!(
+                "Clients rejected the proxy's certificate for {} {} times in a row; tunnelling it without decryption",
+                host,
+                FAILURES_BEFORE_PASSTHROUGH
+            );
+        }
+    }
+}
+
+impl Default for TlsPassthrough {
+    fn default() -> Self {
+        Self {
+            hosts: Vec::new(),
+            detect: true,
+            failures: Mutex::new(HashMap::new()),
+            detected: Mutex::new(HashSet::new()),
+            watching: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Certificate authority that watches each client handshake for failure
+///
+/// Every intercepted connection gets its own copy of the server config whose session
+/// store is a [`HandshakeWatch`]. rustls only stores a session once it has the client's
+/// `Finished` message, which a client sends after accepting the certificate.
+pub(crate) struct WatchedAuthority<CA> {
+    inner: CA,
+    passthrough: Arc<TlsPassthrough>,
+}
+
+impl<CA> WatchedAuthority<CA> {
+    pub(crate) fn new(inner: CA, passthrough: Arc<TlsPassthrough>) -> Self {
+        Self { inner, passthrough }
+    }
+}
+
+impl<CA: CertificateAuthority> CertificateAuthority for WatchedAuthority<CA> {
+    async fn gen_server_config(&self, authority: &Authority) -> Arc<ServerConfig> {
+        let config = self.inner.gen_server_config(authority).await;
+        if !self.passthrough.detect {
+            return config;
+        }
+
+        let mut config = ServerConfig::clone(&config);
+        config.session_storage = Arc::new(HandshakeWatch {
+            host: authority.host().to_string(),
+            passthrough: Arc::clone(&self.passthrough),
+            succeeded: self.passthrough.watch(authority.host()),
+        });
+        Arc::new(config)
+    }
+}
+
+/// Session store for one connection that reports a failed handshake when dropped
+///
+/// Sessions are not kept, so every handshake is a full one that reaches `put`. Clients
+/// that take no session tickets never reach it; a decrypted request sets the shared
+/// success flag instead.
+#[derive(Debug)]
+struct HandshakeWatch {
+    host: String,
+    passthrough: Arc<TlsPassthrough>,
+    succeeded: Arc<AtomicBool>,
+}
+
+impl StoresServerSessions for HandshakeWatch {
+    fn put(&self, _key: Vec<u8>, _value: Vec<u8>) -> bool {
+        if !self.succeeded.swap(true, Ordering::Relaxed) {
+            lock(&self.passthrough.failures).remove(&self.host.to_ascii_lowercase());
+        }
+        false
+    }
+
+    fn get(&self, _key: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn take(&self, _key: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn can_cache(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for HandshakeWatch {
+    fn drop(&mut self) {
+        if !self.passthrough.unwatch(&self.host, &self.succeeded) {
+            tracing::debug!("TLS handshake for {} did not complete", self.host);
+            self.passthrough.handshake_failed(&self.host);
+        }
+    }
+}
+
+/// Bytes relayed through a TLS passthrough tunnel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TunnelBytes {
+    /// Client to server
+    pub sent: u64,
+    /// Server to client
+    pub received: u64,
+}
+
+/// Running byte counts of a tunnel
+#[derive(Debug, Default)]
+pub(crate) struct TunnelCounter {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl TunnelCounter {
+    fn bytes(&self) -> TunnelBytes {
+        TunnelBytes {
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Tunnels being relayed, recorded when they close or when capture stops
+#[derive(Clone, Default)]
+pub(crate) struct OpenTunnels {
+    tunnels: Arc<Mutex<HashMap<RequestId, OpenTunnel>>>,
+}
+
+struct OpenTunnel {
+    entry: CapturedEntry,
+    reason: Reason,
+    counter: Arc<TunnelCounter>,
+}
+
+impl OpenTunnels {
+    /// Start recording a tunnel, returning the counter for its bytes
+    pub(crate) fn open(&self, entry: CapturedEntry, reason: Reason) -> Arc<TunnelCounter> {
+        let counter = Arc::new(TunnelCounter::default());
+        let tunnel = OpenTunnel {
+            entry,
+            reason,
+            counter: Arc::clone(&counter),
+        };
+        lock(&self.tunnels).insert(tunnel.entry.id, tunnel);
+        counter
+    }
+
+    /// Entry for a tunnel that closed
+    pub(crate) fn close(&self, id: RequestId) -> Option<CapturedEntry> {
+        lock(&self.tunnels)
+            .remove(&id)
+            .map(|tunnel| tunnel.finish(false))
+    }
+
+    /// Entries for every tunnel still open
+    pub(crate) fn close_all(&self) -> Vec<CapturedEntry> {
+        lock(&self.tunnels)
+            .drain()
+            .map(|(_, tunnel)| tunnel.finish(true))
+            .collect()
+    }
+}
+
+impl OpenTunnel {
+    fn finish(mut self, still_open: bool) -> CapturedEntry {
+        let mut comment = format!(
+            "TLS passthrough, not decrypted ({})",
+            self.reason.describe()
+        );
+        if still_open {
+            comment.push_str("; still open when capture stopped");
+        }
+        self.entry.comment = Some(comment);
+        self.entry.tunnel = Some(self.counter.bytes());
+        self.entry.completed_at = SystemTime::now();
+        self.entry
+    }
+}
+
+/// Client side of a tunnel, counting the bytes relayed each way
+///
+/// Counts survive the tunnel ending in an error, unlike `copy_bidirectional`'s result.
+pub(crate) struct Counted<T> {
+    inner: T,
+    counter: Arc<TunnelCounter>,
+}
+
+impl<T> Counted<T> {
+    pub(crate) fn new(inner: T, counter: Arc<TunnelCounter>) -> Self {
+        Self { inner, counter }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Counted<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = (buf.filled().len() - before) as u64;
+        self.counter.sent.fetch_add(read, Ordering::Relaxed);
+        result
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Counted<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            self.counter
+                .received
+                .fetch_add(written as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(passthrough: &Arc<TlsPassthrough>, host: &str) -> HandshakeWatch {
+        HandshakeWatch {
+            host: host.to_string(),
+            passthrough: Arc::clone(passthrough),
+            succeeded: passthrough.watch(host),
+        }
+    }
+
+    #[test]
+    fn test_listed_hosts() {
+        let passthrough =
+            TlsPassthrough::new(&["*.apple.com".to_string(), "bank.example".to_string()]).unwrap();
+        assert_eq!(passthrough.reason("swscan.apple.com"), Some(Reason::Listed));
+        assert_eq!(passthrough.reason("bank.example"), Some(Reason::Listed));
+        assert_eq!(passthrough.reason("example.com"), None);
+    }
+
+    #[test]
+    fn test_repeated_handshake_failures_detected() {
+        let passthrough = Arc::new(TlsPassthrough::default());
+
+        // A success in between resets the count
+        drop(watch(&passthrough, "pinned.example"));
+        drop(watch(&passthrough, "pinned.example"));
+        let ok = watch(&passthrough, "pinned.example");
+        ok.put(Vec::new(), Vec::new());
+        drop(ok);
+        drop(watch(&passthrough, "pinned.example"));
+        assert_eq!(passthrough.reason("pinned.example"), None);
+
+        drop(watch(&passthrough, "pinned.example"));
+        drop(watch(&passthrough, "pinned.example"));
+        assert_eq!(passthrough.reason("pinned.example"), Some(Reason::Detected));
+        assert_eq!(passthrough.reason("other.example"), None);
+    }
+
+    #[test]
+    fn test_decrypted_request_marks_open_connections_succeeded() {
+        let passthrough = Arc::new(TlsPassthrough::default());
+
+        // Connections that never store a session, each carrying a decrypted request
+        for _ in 0..5 {
+            let conn = watch(&passthrough, "api.example");
+            passthrough.handshake_succeeded("API.example");
+            drop(conn);
+        }
+        assert_eq!(passthrough.reason("api.example"), None);
+        assert!(lock(&passthrough.watching).is_empty());
+    }
+}
@@ -0,0 +1,687 @@
+//! Match-and-replace rules applied to traffic as it passes through the proxy.
+//!
+//! Rules are read from a TOML file. Each rule matches on host, path, method and a
+//! header, and rewrites headers, the URL or the body of requests, responses or both.
+//! Traffic is recorded as it was sent on, and the HAR entry's `comment` names every rule
+//! that changed it.
+//!
+//! The same file may hold mocks: responses the proxy sends itself for matching requests,
+//! without contacting the origin. A mock's body is an inline template or a file relative
+//! to the rules file; `{{method}}`, `{{url}}`, `{{host}}`, `{{path}}` and `{{query}}` in
+//! templates and header values are filled in from the request. The first matching mock
+//! answers, after request rules have run.
+//!
+//! # Examples
+//!
+//! ```toml
+//! [[rule]]
+//! name = "Inject auth"
+//! host = "api.example.com"
+//! set_headers = { Authorization = "Bearer test-token" }
+//! remove_headers = ["If-None-Match"]
+//!
+//! [[rule]]
+//! name = "No caching"
+//! on = "response"
+//! path = "^/api/"
+//! set_headers = { Cache-Control = "no-cache" }
+//!
+//! [[rule]]
+//! name = "Beta flag"
+//! header = { name = "Cookie", value = "beta=" }
+//! replace_headers = [{ name = "Cookie", pattern = "beta=off", replacement = "beta=on" }]
+//!
+//! [[rule]]
+//! name = "Staging"
+//! rewrite_url = { pattern = "^https://www\\.example\\.com", replacement = "https://staging.example.com" }
+//!
+//! [[rule]]
+//! name = "Premium user"
+//! on = "response"
+//! method = "GET"
+//! path = "^/api/me$"
+//! replace_body = [{ pattern = '"plan":"free"', replacement = '"plan":"premium"' }]
+//!
+//! [[mock]]
+//! name = "Checkout down"
+//! method = "POST"
+//! path = "^/api/checkout$"
+//! status = 500
+//! headers = { Content-Type = "application/json" }
+//! body = '{"error":"internal","path":"{{path}}"}'
+//!
+//! [[mock]]
+//! name = "New API"
+//! host = "api.example.com"
+//! path = "^/v2/users$"
+//! file = "mocks/users.json"
+//! ```
+
+use crate::body::{self, CapturedBody, RecordedBytes};
+use crate::mock::{Mock, MockConfig};
+use crate::{Error, Result};
+use harrier_core::filter::HostPattern;
+use http::header::{self, HeaderName};
+use http::{HeaderMap, HeaderValue, Method, Uri};
+use http_body_util::{BodyExt, Full};
+use hudsucker::Body;
+use hyper::body::Bytes;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Which traffic a rule rewrites
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Requests on their way to the server
+    #[default]
+    Request,
+    /// Responses on their way to the client
+    Response,
+    /// Both
+    Both,
+}
+
+impl Phase {
+    fn includes(self, phase: Phase) -> bool {
+        self == Phase::Both || self == phase
+    }
+}
+
+/// Rule as written in the rules file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    name: Option<String>,
+    #[serde(default)]
+    on: Phase,
+    host: Option<String>,
+    path: Option<String>,
+    method: Option<String>,
+    header: Option<HeaderMatchConfig>,
+    #[serde(default)]
+    set_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    remove_headers: Vec<String>,
+    #[serde(default)]
+    replace_headers: Vec<HeaderReplaceConfig>,
+    rewrite_url: Option<ReplaceConfig>,
+    #[serde(default)]
+    replace_body: Vec<ReplaceConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct HeaderMatchConfig {
+    name: String,
+    /// Regex the value must match; any value when absent
+    value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HeaderReplaceConfig {
+    name: String,
+    pattern: String,
+    replacement: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplaceConfig {
+    pattern: String,
+    replacement: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleConfig>,
+    #[serde(default, rename = "mock")]
+    mocks: Vec<MockConfig>,
+}
+
+/// Rewrite rules and mocks, applied in file order
+#[derive(Debug, Clone, Default)]
+pub struct RewriteRules {
+    rules: Vec<Rule>,
+    mocks: Vec<Mock>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    name: String,
+    on: Phase,
+    matcher: Matcher,
+    set_headers: Vec<(HeaderName, HeaderValue)>,
+    remove_headers: Vec<HeaderName>,
+    replace_headers: Vec<(HeaderName, Regex, String)>,
+    rewrite_url: Option<(Regex, String)>,
+    replace_body: Vec<(regex::bytes::Regex, String)>,
+}
+
+/// Result of applying rules to a request's or response's head
+#[derive(Default)]
+pub(crate) struct Rewrite<'a> {
+    /// Names of the rules that changed the URL or headers
+    pub(crate) applied: Vec<String>,
+    /// Matching rules with body replacements still to apply
+    body_rules: Vec<&'a Rule>,
+}
+
+/// Conditions on a request's method, URL and headers; all must hold
+#[derive(Debug, Clone)]
+pub(crate) struct Matcher {
+    host: Option<HostPattern>,
+    path: Option<Regex>,
+    method: Option<Method>,
+    header: Option<(HeaderName, Option<Regex>)>,
+}
+
+impl Matcher {
+    /// Compile the matching fields of a rule, reporting problems through `invalid`
+    pub(crate) fn compile(
+        host: Option<&str>,
+        path: Option<&str>,
+        method: Option<&str>,
+        header: Option<&HeaderMatchConfig>,
+        invalid: &dyn Fn(&str, &dyn std::fmt::Display) -> Error,
+    ) -> Result<Self> {
+        let host = host
+            .map(HostPattern::parse)
+            .transpose()
+            .map_err(|e| invalid("host", &e))?;
+        let path = path
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| invalid("path", &e))?;
+        let method = method
+            .map(|m| Method::from_bytes(m.trim().to_ascii_uppercase().as_bytes()))
+            .transpose()
+            .map_err(|e| invalid("method", &e))?;
+        let header = match header {
+            Some(matcher) => Some((
+                HeaderName::from_bytes(matcher.name.as_bytes())
+                    .map_err(|e| invalid("header name", &e))?,
+                matcher
+                    .value
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| invalid("header value", &e))?,
+            )),
+            None => None,
+        };
+        Ok(Self {
+            host,
+            path,
+            method,
+            header,
+        })
+    }
+
+    pub(crate) fn matches(&self, method: &Method, uri: &Uri, headers: &HeaderMap) -> bool {
+        if self.method.as_ref().is_some_and(|m| m != method) {
+            return false;
+        }
+        if let Some(host) = &self.host
+            && !uri.host().is_some_and(|h| host.matches(h))
+        {
+            return false;
+        }
+        if let Some(path) = &self.path
+            && !path.is_match(uri.path())
+        {
+            return false;
+        }
+        match &self.header {
+            Some((name, value)) => headers.get_all(name).iter().any(|v| {
+                value
+                    .as_ref()
+                    .is_none_or(|pattern| v.to_str().is_ok_and(|v| pattern.is_match(v)))
+            }),
+            None => true,
+        }
+    }
+}
+
+impl RewriteRules {
+    /// Load rules from a TOML file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::Proxy(format!(
+                "Failed to read rewrite rules {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        Self::parse_in(&content, base)
+            .map_err(|e| Error::Proxy(format!("Invalid rewrite rules {}: {}", path.display(), e)))
+    }
+
+    /// Parse rules from TOML; mock files are relative to the current directory
+    pub fn parse(content: &str) -> Result<Self> {
+        Self::parse_in(content, Path::new("."))
+    }
+
+    fn parse_in(content: &str, base: &Path) -> Result<Self> {
+        let file: RulesFile = toml::from_str(content).map_err(|e| Error::Proxy(e.to_string()))?;
+        let rules = file
+            .rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, config)| Rule::compile(config, i + 1))
+            .collect::<Result<_>>()?;
+        let mocks = file
+            .mocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, config)| Mock::compile(config, i + 1, base))
+            .collect::<Result<_>>()?;
+        Ok(Self { rules, mocks })
+    }
+
+    /// Number of rewrite rules, not counting mocks
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn mock_count(&self) -> usize {
+        self.mocks.len()
+    }
+
+    /// Whether there are neither rules nor mocks
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.mocks.is_empty()
+    }
+
+    /// First mock matching a request
+    pub(crate) fn find_mock(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Option<&Mock> {
+        self.mocks
+            .iter()
+            .find(|mock| mock.matcher.matches(method, uri, headers))
+    }
+
+    /// Rewrite a request's URL and headers
+    pub(crate) fn rewrite_request(
+        &self,
+        method: &Method,
+        uri: &mut Uri,
+        headers: &mut HeaderMap,
+    ) -> Rewrite<'_> {
+        let mut rewrite = Rewrite::default();
+        for rule in self.rules_for(Phase::Request) {
+            if rule.matcher.matches(method, uri, headers) {
+                let changed = rule.apply_headers(headers) | rule.apply_url(uri, headers);
+                rewrite.add(rule, changed);
+            }
+        }
+        rewrite
+    }
+
+    /// Rewrite a response's headers; rules match on the request's method and URL
+    pub(crate) fn rewrite_response(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &mut HeaderMap,
+    ) -> Rewrite<'_> {
+        let mut rewrite = Rewrite::default();
+        for rule in self.rules_for(Phase::Response) {
+            if rule.matcher.matches(method, uri, headers) {
+                let changed = rule.apply_headers(headers);
+                rewrite.add(rule, changed);
+            }
+        }
+        rewrite
+    }
+
+    fn rules_for(&self, phase: Phase) -> impl Iterator<Item = &Rule> {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.on.includes(phase))
+    }
+}
+
+impl<'a> Rewrite<'a> {
+    fn add(&mut self, rule: &'a Rule, changed: bool) {
+        if changed {
+            self.applied.push(rule.name.clone());
+        }
+        if !rule.replace_body.is_empty() {
+            self.body_rules.push(rule);
+        }
+    }
+
+    /// Whether the body needs buffering for replacements
+    pub(crate) fn has_body_rules(&self) -> bool {
+        !self.body_rules.is_empty()
+    }
+
+    /// Buffer a body and apply the matching rules' replacements to it
+    ///
+    /// Compressed bodies are decoded first and sent on uncompressed. Bodies larger than
+    /// `max_size` once decoded are sent on unchanged. Rules that changed the body are
+    /// added to `applied`.
+    pub(crate) async fn rewrite_body(
+        &mut self,
+        body: Body,
+        headers: &mut HeaderMap,
+        max_size: usize,
+    ) -> Body {
+        let wire = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(e) => {
+                tracing::warn!("Failed to read body for rewriting: {}", e);
+                return Body::empty();
+            }
+        };
+
+        let encoding = headers
+            .get(header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        if !body::can_decode(encoding.as_deref()) {
+            tracing::debug!("Cannot decode {:?} body, not rewriting it", encoding);
+            return Body::from(Full::new(wire));
+        }
+        let decoded = CapturedBody::decode(
+            RecordedBytes {
+                data: wire.to_vec(),
+                total: wire.len(),
+                truncated: false,
+            },
+            encoding.as_deref(),
+            max_size,
+        );
+        if decoded.truncated {
+            tracing::debug!("Body over {} bytes, not rewriting it", max_size);
+            return Body::from(Full::new(wire));
+        }
+
+        let mut data = decoded.data;
+        let mut changed = false;
+        for rule in &self.body_rules {
+            let before = data.clone();
+            for (pattern, replacement) in &rule.replace_body {
+                data = pattern
+                    .replace_all(&data, replacement.as_bytes())
+                    .into_owned();
+            }
+            if data != before {
+                changed = true;
+                if !self.applied.contains(&rule.name) {
+                    self.applied.push(rule.name.clone());
+                }
+            }
+        }
+        if !changed {
+            return Body::from(Full::new(wire));
+        }
+
+        headers.remove(header::CONTENT_ENCODING);
+        headers.remove(header::TRANSFER_ENCODING);
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(data.len()));
+        Body::from(Full::new(Bytes::from(data)))
+    }
+}
+
+impl Rule {
+    fn compile(config: RuleConfig, position: usize) -> Result<Self> {
+        let name = config.name.unwrap_or_else(|| format!("rule {}", position));
+        let invalid = |what: &str, e: &dyn std::fmt::Display| {
+            Error::Proxy(format!("Rule '{}': invalid {}: {}", name, what, e))
+        };
+        let header_name = |value: &str| {
+            HeaderName::from_bytes(value.as_bytes()).map_err(|e| invalid("header name", &e))
+        };
+
+        let matcher = Matcher::compile(
+            config.host.as_deref(),
+            config.path.as_deref(),
+            config.method.as_deref(),
+            config.header.as_ref(),
+            &invalid,
+        )?;
+
+        let mut set_headers = Vec::new();
+        for (name, value) in &config.set_headers {
+            let value = HeaderValue::from_str(value).map_err(|e| invalid("header value", &e))?;
+            set_headers.push((header_name(name)?, value));
+        }
+        let remove_headers = config
+            .remove_headers
+            .iter()
+            .map(|name| header_name(name))
+            .collect::<Result<_>>()?;
+        let mut replace_headers = Vec::new();
+        for replace in config.replace_headers {
+            let pattern = Regex::new(&replace.pattern).map_err(|e| invalid("pattern", &e))?;
+            replace_headers.push((header_name(&replace.name)?, pattern, replace.replacement));
+        }
+        let rewrite_url = match config.rewrite_url {
+            Some(_) if config.on == Phase::Response => {
+                return Err(Error::Proxy(format!(
+                    "Rule '{}': rewrite_url only applies to requests",
+                    name
+                )));
+            }
+            Some(replace) => Some((
+                Regex::new(&replace.pattern).map_err(|e| invalid("pattern", &e))?,
+                replace.replacement,
+            )),
+            None => None,
+        };
+        let mut replace_body = Vec::new();
+        for replace in config.replace_body {
+            let pattern =
+                regex::bytes::Regex::new(&replace.pattern).map_err(|e| invalid("pattern", &e))?;
+            replace_body.push((pattern, replace.replacement));
+        }
+
+        Ok(Self {
+            name,
+            on: config.on,
+            matcher,
+            set_headers,
+            remove_headers,
+            replace_headers,
+            rewrite_url,
+            replace_body,
+        })
+    }
+
+    /// Apply header actions, returning whether anything changed
+    fn apply_headers(&self, headers: &mut HeaderMap) -> bool {
+        let mut changed = false;
+
+        for name in &self.remove_headers {
+            changed |= headers.remove(name).is_some();
+        }
+        for (name, pattern, replacement) in &self.replace_headers {
+            let values: Vec<HeaderValue> = headers.get_all(name).iter().cloned().collect();
+            let mut rewritten = Vec::new();
+            for value in &values {
+                let Ok(text) = value.to_str() else {
+                    rewritten.push(value.clone());
+                    continue;
+                };
+                let replaced = pattern.replace_all(text, replacement.as_str());
+                match HeaderValue::from_str(&replaced) {
+                    Ok(new) => rewritten.push(new),
+                    Err(_) => rewritten.push(value.clone()),
+                }
+            }
+            if rewritten != values {
+                headers.remove(name);
+                for value in rewritten {
+                    headers.append(name.clone(), value);
+                }
+                changed = true;
+            }
+        }
+        for (name, value) in &self.set_headers {
+            if headers.get(name) != Some(value) || headers.get_all(name).iter().count() > 1 {
+                headers.insert(name.clone(), value.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Apply the URL rewrite, returning whether the URL changed
+    fn apply_url(&self, uri: &mut Uri, headers: &mut HeaderMap) -> bool {
+        let Some((pattern, replacement)) = &self.rewrite_url else {
+            return false;
+        };
+        let current = uri.to_string();
+        let replaced = pattern.replace_all(&current, replacement.as_str());
+        if replaced == current {
+            return false;
+        }
+
+        match replaced.parse::<Uri>() {
+            Ok(new) => {
+                if let Some(authority) = new.authority()
+                    && headers.contains_key(header::HOST)
+                    && let Ok(host) = HeaderValue::from_str(authority.as_str())
+                {
+                    headers.insert(header::HOST, host);
+                }
+                *uri = new;
+                true
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Rule '{}' produced an invalid URL '{}': {}",
+                    self.name,
+                    replaced,
+                    e
+                );
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+[[rule]]
+name = "Inject auth"
+host = "*.example.com"
+set_headers = { Authorization = "Bearer test-token" }
+remove_headers = ["If-None-Match"]
+
+[[rule]]
+name = "Beta flag"
+header = { name = "Cookie", value = "beta=" }
+replace_headers = [{ name = "Cookie", pattern = "beta=off", replacement = "beta=on" }]
+
+[[rule]]
+name = "Staging"
+method = "post"
+rewrite_url = { pattern = "^https://www\\.example\\.com", replacement = "https://staging.example.com" }
+
+[[rule]]
+on = "response"
+path = "^/api/"
+set_headers = { Cache-Control = "no-cache" }
+replace_body = [{ pattern = '"plan":"free"', replacement = '"plan":"premium"' }]
+"#;
+
+    fn request(method: &str, uri: &str, headers: &[(&str, &str)]) -> (Method, Uri, HeaderMap) {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        (method.parse().unwrap(), uri.parse().unwrap(), map)
+    }
+
+    #[test]
+    fn test_request_rules() {
+        let rules = RewriteRules::parse(RULES).unwrap();
+        assert_eq!(rules.len(), 4);
+
+        let (method, mut uri, mut headers) = request(
+            "POST",
+            "https://www.example.com/login",
+            &[
+                ("host", "www.example.com"),
+                ("if-none-match", "\"abc\""),
+                ("cookie", "sid=1; beta=off"),
+            ],
+        );
+        let rewrite = rules.rewrite_request(&method, &mut uri, &mut headers);
+
+        assert_eq!(rewrite.applied, ["Inject auth", "Beta flag", "Staging"]);
+        assert!(!rewrite.has_body_rules());
+        assert_eq!(headers["authorization"], "Bearer test-token");
+        assert!(!headers.contains_key("if-none-match"));
+        assert_eq!(headers["cookie"], "sid=1; beta=on");
+        assert_eq!(uri.to_string(), "https://staging.example.com/login");
+        assert_eq!(headers["host"], "staging.example.com");
+    }
+
+    #[test]
+    fn test_unmatched_rules_leave_request_alone() {
+        let rules = RewriteRules::parse(RULES).unwrap();
+        let (method, mut uri, mut headers) = request("GET", "https://other.net/", &[]);
+        let rewrite = rules.rewrite_request(&method, &mut uri, &mut headers);
+        assert!(rewrite.applied.is_empty());
+        assert!(headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_response_rules_rewrite_body() {
+        let rules = RewriteRules::parse(RULES).unwrap();
+        let uri: Uri = "https://api.example.com/api/me".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(15));
+
+        let mut rewrite = rules.rewrite_response(&Method::GET, &uri, &mut headers);
+        assert_eq!(rewrite.applied, ["rule 4"]);
+        assert_eq!(headers["cache-control"], "no-cache");
+
+        let body = rewrite
+            .rewrite_body(Body::from(r#"{"plan":"free"}"#), &mut headers, 1024)
+            .await;
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(bytes.as_ref(), br#"{"plan":"premium"}"#);
+        assert_eq!(headers["content-length"], "18");
+
+        // Already recorded for its header change
+        assert_eq!(rewrite.applied, ["rule 4"]);
+    }
+
+    #[test]
+    fn test_invalid_rules_rejected() {
+        let err = RewriteRules::parse("[[rule]]\npath = \"(unclosed\"\n").unwrap_err();
+        assert!(err.to_string().contains("Rule 'rule 1': invalid path"));
+
+        let err = RewriteRules::parse(
+            "[[rule]]\non = \"response\"\nrewrite_url = { pattern = \"a\", replacement = \"b\" }\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("rewrite_url only applies to requests")
+        );
+
+        assert!(RewriteRules::parse("[[rule]]\nsett_headers = {}\n").is_err());
+    }
+}
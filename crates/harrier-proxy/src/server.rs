@@ -1,34 +1,106 @@
+use crate::auth::ProxyAuth;
 use crate::handler::HarCaptureHandler;
+use crate::passthrough::{TlsPassthrough, WatchedAuthority};
+use crate::rewrite::RewriteRules;
+use crate::upstream::{UpstreamConnector, UpstreamProxy};
 use crate::{CertificateAuthority, Error, Result};
+use harrier_core::filter::CaptureScope;
+use harrier_core::har::HarJournal;
 use hudsucker::Proxy;
+use hudsucker::tokio_tungstenite::Connector;
+use hyper_rustls::ConfigBuilderExt;
 use rustls::crypto::aws_lc_rs::default_provider;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
 
 /// MITM proxy server for capturing HTTP/HTTPS traffic
 pub struct ProxyServer {
-    port: u16,
+    /// Addresses to listen on
+    listen: Vec<SocketAddr>,
     ca: CertificateAuthority,
     handler: HarCaptureHandler,
+    upstream: Option<UpstreamProxy>,
+    /// Whether clients must authenticate
+    requires_auth: bool,
 }
 
 impl ProxyServer {
-    /// Create a new proxy server
+    /// Create a new proxy server listening on `127.0.0.1:port`
     pub fn new(port: u16, ca: CertificateAuthority) -> Self {
         Self {
-            port,
+            listen: vec![([127, 0, 0, 1], port).into()],
             ca,
             handler: HarCaptureHandler::new(),
+            upstream: None,
+            requires_auth: false,
         }
     }
 
-    /// Start the proxy server and return captured entries when it shuts down
-    pub async fn start(self) -> Result<HarCaptureHandler> {
-        let addr: SocketAddr = ([127, 0, 0, 1], self.port).into();
+    /// Listen on these addresses instead of `127.0.0.1`
+    pub fn with_listen_addrs(mut self, addrs: Vec<SocketAddr>) -> Self {
+        if !addrs.is_empty() {
+            self.listen = addrs;
+        }
+        self
+    }
 
-        tracing::info!("Starting proxy server on {}", addr);
+    /// Require clients to send these credentials in `Proxy-Authorization`
+    pub fn with_auth(mut self, auth: ProxyAuth) -> Self {
+        self.handler = self.handler.with_auth(auth);
+        self.requires_auth = true;
+        self
+    }
+
+    /// Rewrite matching traffic with `rules`
+    pub fn with_rules(mut self, rules: RewriteRules) -> Self {
+        self.handler = self.handler.with_rules(rules);
+        self
+    }
+
+    /// Record at most `bytes` of each request and response body
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.handler = self.handler.with_max_body_size(bytes);
+        self
+    }
+
+    /// Only record traffic within `scope`; everything else is still forwarded
+    pub fn with_scope(mut self, scope: CaptureScope) -> Self {
+        self.handler = self.handler.with_scope(scope);
+        self
+    }
+
+    /// Connect to origin servers through an upstream proxy
+    pub fn with_upstream(mut self, upstream: UpstreamProxy) -> Self {
+        self.handler = self.handler.with_upstream(upstream.clone());
+        self.upstream = Some(upstream);
+        self
+    }
+
+    /// Tunnel TLS to listed hosts, and hosts whose clients reject the proxy's
+    /// certificate, without decrypting it
+    pub fn with_passthrough(mut self, passthrough: TlsPassthrough) -> Self {
+        self.handler = self.handler.with_passthrough(passthrough);
+        self
+    }
+
+    /// Append completed entries to `journal` as they finish
+    pub fn with_journal(mut self, journal: HarJournal) -> Self {
+        self.handler = self.handler.with_journal(journal);
+        self
+    }
 
-        // Create certificate authority for MITM
-        let ca = self.create_hudsucker_ca()?;
+    /// Start the proxy server and return captured entries when it shuts down
+    pub async fn start(self) -> Result<HarCaptureHandler> {
+        let mut listeners = Vec::new();
+        for addr in &self.listen {
+            tracing::info!("Starting proxy server on {}", addr);
+            let listener = TcpListener::bind(addr)
+                .await
+                .map_err(|e| Error::Proxy(format!("Failed to bind {}: {}", addr, e)))?;
+            listeners.push(listener);
+        }
 
         // Set up shutdown signal
         let shutdown_signal = async {
@@ -40,40 +112,110 @@ impl ProxyServer {
             println!("🛑 Shutting down proxy...");
         };
 
-        // Create proxy with our handler and shutdown signal
-        let proxy = Proxy::builder()
-            .with_addr(addr)
-            .with_ca(ca)
-            .with_rustls_connector(default_provider())
-            .with_http_handler(self.handler.clone())
-            .with_graceful_shutdown(shutdown_signal)
-            .build()
-            .map_err(|e| Error::Proxy(format!("Failed to build proxy: {}", e)))?;
-
-        tracing::info!("✓ Proxy server listening on http://{}", addr);
-        println!("✓ Proxy server listening on http://{}", addr);
+        let mut bound = Vec::new();
+        for listener in &listeners {
+            let addr = listener.local_addr()?;
+            tracing::info!("✓ Proxy server listening on http://{}", addr);
+            println!("✓ Proxy server listening on http://{}", addr);
+            bound.push(addr);
+        }
+        if !self.requires_auth && bound.iter().any(|addr| !addr.ip().is_loopback()) {
+            println!(
+                "⚠️  Listening beyond this machine without --proxy-auth: anyone who can reach it can use the proxy"
+            );
+        }
+
+        let proxy_addr = match bound[0] {
+            addr if addr.ip().is_unspecified() => format!("<this machine's IP>:{}", addr.port()),
+            addr => addr.to_string(),
+        };
         println!();
         println!("Configure your browser or application to use this proxy:");
-        println!("  HTTP Proxy:  127.0.0.1:{}", self.port);
-        println!("  HTTPS Proxy: 127.0.0.1:{}", self.port);
+        println!("  HTTP Proxy:  {}", proxy_addr);
+        println!("  HTTPS Proxy: {}", proxy_addr);
         println!();
         println!("Press Ctrl+C to stop capturing and generate HAR file...");
         println!();
 
-        // Clone the handler before moving proxy
-        let handler = self.handler.clone();
-
-        // Start the proxy server (this will run until shutdown)
-        proxy
-            .start()
-            .await
-            .map_err(|e| Error::Proxy(format!("Proxy failed: {}", e)))?;
+        let handler = self.serve_all(listeners, shutdown_signal).await?;
 
         println!("✅ Proxy stopped gracefully");
 
         Ok(handler)
     }
 
+    /// Serve connections from `listener` until `shutdown` completes
+    pub async fn serve(
+        self,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<HarCaptureHandler> {
+        self.serve_all(vec![listener], shutdown).await
+    }
+
+    /// Serve connections from every listener until `shutdown` completes
+    ///
+    /// All listeners share one handler, so their entries end up in the same capture.
+    pub async fn serve_all(
+        mut self,
+        listeners: Vec<TcpListener>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<HarCaptureHandler> {
+        // TLS to origin servers, over a direct connection or an upstream proxy tunnel
+        let tls_config = rustls::ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| Error::Tls(format!("Failed to configure TLS client: {}", e)))?
+            .with_webpki_roots()
+            .with_no_client_auth();
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(tls_config.clone())
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(UpstreamConnector::new(self.upstream.clone()));
+        let websocket_connector = Connector::Rustls(Arc::new(tls_config));
+        self.handler = self
+            .handler
+            .with_websocket_connector(websocket_connector.clone());
+
+        // One hudsucker proxy per listener, all stopped by the same signal
+        let (stop, stopped) = watch::channel(());
+        let mut proxies = Vec::new();
+        for listener in listeners {
+            // Create certificate authority for MITM
+            let ca = WatchedAuthority::new(self.create_hudsucker_ca()?, self.handler.passthrough());
+            let mut stopped = stopped.clone();
+            let proxy = Proxy::builder()
+                .with_listener(listener)
+                .with_ca(ca)
+                .with_http_connector(connector.clone())
+                .with_websocket_connector(websocket_connector.clone())
+                .with_http_handler(self.handler.for_listener())
+                .with_websocket_handler(self.handler.for_listener())
+                .with_graceful_shutdown(async move {
+                    let _ = stopped.changed().await;
+                })
+                .build()
+                .map_err(|e| Error::Proxy(format!("Failed to build proxy: {}", e)))?;
+            proxies.push(proxy.start());
+        }
+
+        // Start the proxies (this will run until shutdown)
+        let mut running = std::pin::pin!(futures::future::try_join_all(proxies));
+        let result = tokio::select! {
+            result = &mut running => result,
+            () = shutdown => {
+                let _ = stop.send(());
+                running.await
+            }
+        };
+        result.map_err(|e| Error::Proxy(format!("Proxy failed: {}", e)))?;
+
+        // WebSockets and tunnels can outlive the proxy's connections; keep what they recorded
+        self.handler.close_open_connections();
+        Ok(self.handler)
+    }
+
     /// Create hudsucker CA from our certificate
     fn create_hudsucker_ca(&self) -> Result<hudsucker::certificate_authority::RcgenAuthority> {
         tracing::debug!("Creating hudsucker certificate authority");
@@ -0,0 +1,258 @@
+use crate::handler::CapturedEntry;
+use base64::Engine;
+use harrier_core::har::WebSocketMessage;
+use hudsucker::tokio_tungstenite::tungstenite::Message;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Direction a WebSocket frame travelled in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+    /// Client to server
+    Send,
+    /// Server to client
+    Receive,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Send => "send",
+            Direction::Receive => "receive",
+        }
+    }
+}
+
+/// WebSocket connections being recorded, keyed by the client connection that upgraded
+///
+/// An upgrade takes over its client connection, so the client address identifies the
+/// WebSocket in both forwarding directions, including inside a CONNECT tunnel.
+#[derive(Clone, Default)]
+pub(crate) struct WebSocketSessions {
+    sessions: Arc<Mutex<HashMap<SocketAddr, Session>>>,
+}
+
+/// Upgrade entry collecting the frames of its WebSocket
+struct Session {
+    entry: CapturedEntry,
+    /// Whether the proxy connected to the server
+    connected: bool,
+    /// Forwarding directions that have finished; the WebSocket is closed at 2
+    closed: u8,
+    /// Frames larger than this many bytes are cut off
+    max_frame_size: usize,
+    truncated: bool,
+}
+
+impl WebSocketSessions {
+    /// Start recording frames for `entry`, the upgrade request
+    ///
+    /// Returns a previous upgrade from the same address, whose server connection never
+    /// opened.
+    pub(crate) fn open(
+        &self,
+        entry: CapturedEntry,
+        max_frame_size: usize,
+    ) -> Option<CapturedEntry> {
+        let client_addr = entry.id.client_addr;
+        let session = Session {
+            entry,
+            connected: false,
+            closed: 0,
+            max_frame_size,
+            truncated: false,
+        };
+        self.lock()
+            .insert(client_addr, session)
+            .map(|stale| stale.finish(SystemTime::now()))
+    }
+
+    /// Note that a forwarding direction started for the WebSocket of `client_addr`
+    pub(crate) fn attach(&self, client_addr: SocketAddr) {
+        if let Some(session) = self.lock().get_mut(&client_addr) {
+            session.connected = true;
+        }
+    }
+
+    /// Record a frame, unless the WebSocket is not being recorded or only its headers are
+    pub(crate) fn record(&self, client_addr: SocketAddr, direction: Direction, message: &Message) {
+        let mut sessions = self.lock();
+        let Some(session) = sessions.get_mut(&client_addr) else {
+            return;
+        };
+        let max_frame_size = session.max_frame_size;
+        let Some(messages) = session.entry.websocket_messages.as_mut() else {
+            return;
+        };
+        if let Some((frame, truncated)) = frame(direction, message, max_frame_size) {
+            messages.push(frame);
+            session.truncated |= truncated;
+        }
+    }
+
+    /// Note that a forwarding direction finished, returning the entry once both have
+    pub(crate) fn detach(&self, client_addr: SocketAddr) -> Option<CapturedEntry> {
+        let mut sessions = self.lock();
+        let session = sessions.get_mut(&client_addr)?;
+        session.closed += 1;
+        if session.closed < 2 {
+            return None;
+        }
+        sessions
+            .remove(&client_addr)
+            .map(|session| session.finish(SystemTime::now()))
+    }
+
+    /// Entry for a WebSocket whose server connection could not be opened
+    pub(crate) fn fail(&self, client_addr: SocketAddr, error: &str) -> Option<CapturedEntry> {
+        let mut session = self.lock().remove(&client_addr)?;
+        session.entry.comment = Some(format!(
+            "WebSocket connection to the server failed ({})",
+            error
+        ));
+        Some(session.finish(SystemTime::now()))
+    }
+
+    /// Entries for every WebSocket still being recorded
+    pub(crate) fn close_all(&self) -> Vec<CapturedEntry> {
+        let now = SystemTime::now();
+        self.lock()
+            .drain()
+            .map(|(_, mut session)| {
+                if session.connected && session.entry.comment.is_none() {
+                    session.entry.comment =
+                        Some("WebSocket still open when capture stopped".to_string());
+                }
+                session.finish(now)
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, Session>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Session {
+    fn finish(mut self, completed_at: SystemTime) -> CapturedEntry {
+        if !self.connected && self.entry.comment.is_none() {
+            self.entry.comment = Some("WebSocket connection to the server failed".to_string());
+        } else if self.truncated && self.entry.comment.is_none() {
+            self.entry.comment = Some(format!(
+                "WebSocket frames larger than {} bytes were truncated",
+                self.max_frame_size
+            ));
+        }
+        self.entry.completed_at = completed_at;
+        self.entry
+    }
+}
+
+/// HAR record of a frame, and whether its payload was cut off
+///
+/// Text frames keep their text; other frames are base64 encoded.
+fn frame(
+    direction: Direction,
+    message: &Message,
+    max_size: usize,
+) -> Option<(WebSocketMessage, bool)> {
+    let (opcode, payload): (u8, &[u8]) = match message {
+        Message::Text(text) => (1, text.as_bytes()),
+        Message::Binary(data) => (2, data),
+        Message::Ping(data) => (9, data),
+        Message::Pong(data) => (10, data),
+        Message::Close(close) => {
+            let payload = close
+                .as_ref()
+                .map(|close| {
+                    let mut payload = u16::from(close.code).to_be_bytes().to_vec();
+                    payload.extend_from_slice(close.reason.as_bytes());
+                    payload
+                })
+                .unwrap_or_default();
+            return Some((encode(direction, 8, base64_payload(&payload)), false));
+        }
+        // Raw frames only appear when sending
+        Message::Frame(_) => return None,
+    };
+
+    let truncated = payload.len() > max_size;
+    let payload = &payload[..payload.len().min(max_size)];
+    let data = if opcode == 1 {
+        String::from_utf8_lossy(payload).into_owned()
+    } else {
+        base64_payload(payload)
+    };
+    Some((encode(direction, opcode, data), truncated))
+}
+
+fn encode(direction: Direction, opcode: u8, data: String) -> WebSocketMessage {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_secs_f64());
+    WebSocketMessage {
+        message_type: direction.as_str().to_string(),
+        time,
+        opcode,
+        data,
+    }
+}
+
+fn base64_payload(payload: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hudsucker::tokio_tungstenite::tungstenite::protocol::CloseFrame;
+    use hudsucker::tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+    #[test]
+    fn test_text_and_binary_frames() {
+        let (text, truncated) = frame(
+            Direction::Send,
+            &Message::text("{\"op\":\"subscribe\"}"),
+            1024,
+        )
+        .unwrap();
+        assert_eq!(text.message_type, "send");
+        assert_eq!(text.opcode, 1);
+        assert_eq!(text.data, "{\"op\":\"subscribe\"}");
+        assert!(!truncated);
+
+        let (binary, _) = frame(
+            Direction::Receive,
+            &Message::binary(vec![0u8, 159, 146, 150]),
+            1024,
+        )
+        .unwrap();
+        assert_eq!(binary.message_type, "receive");
+        assert_eq!(binary.opcode, 2);
+        assert_eq!(binary.data, "AJ+Slg==");
+    }
+
+    #[test]
+    fn test_control_frames() {
+        let (ping, _) =
+            frame(Direction::Send, &Message::Ping(b"hi".to_vec().into()), 1024).unwrap();
+        assert_eq!((ping.opcode, ping.data.as_str()), (9, "aGk="));
+
+        let close = Message::Close(Some(CloseFrame {
+            code: CloseCode::Normal,
+            reason: "bye".into(),
+        }));
+        let (close, _) = frame(Direction::Receive, &close, 1024).unwrap();
+        // 1000 followed by "bye"
+        assert_eq!((close.opcode, close.data.as_str()), (8, "A+hieWU="));
+    }
+
+    #[test]
+    fn test_large_frames_truncated() {
+        let (text, truncated) = frame(Direction::Send, &Message::text("abcdef"), 4).unwrap();
+        assert_eq!(text.data, "abcd");
+        assert!(truncated);
+    }
+}
@@ -0,0 +1,279 @@
+// Request/response correlation through a running proxy and a local upstream server
+
+use harrier_proxy::{CapturedEntry, CertificateAuthority, ProxyServer};
+use http_body_util::{BodyExt, Empty};
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Upstream answering `/delay/{ms}/{name}` after `ms` milliseconds with the path as body
+async fn start_upstream() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let service = hyper::service::service_fn(|req: Request<Incoming>| async move {
+                    let path = req.uri().path().to_string();
+                    let delay = path
+                        .split('/')
+                        .nth(2)
+                        .and_then(|ms| ms.parse().ok())
+                        .unwrap_or(0);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    Ok::<_, hyper::Error>(Response::new(http_body_util::Full::new(Bytes::from(
+                        path,
+                    ))))
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+
+    addr
+}
+
+struct RunningProxy {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<harrier_proxy::Result<harrier_proxy::HarCaptureHandler>>,
+}
+
+impl RunningProxy {
+    async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown, signal) = oneshot::channel::<()>();
+
+        let server = ProxyServer::new(addr.port(), CertificateAuthority::generate().unwrap());
+        let task = tokio::spawn(server.serve(listener, async {
+            let _ = signal.await;
+        }));
+
+        Self {
+            addr,
+            shutdown,
+            task,
+        }
+    }
+
+    /// Stop the proxy and return its entries in request order
+    async fn finish(self) -> Vec<CapturedEntry> {
+        self.shutdown.send(()).unwrap();
+        let handler = tokio::time::timeout(Duration::from_secs(10), self.task)
+            .await
+            .expect("proxy did not shut down")
+            .unwrap()
+            .unwrap();
+        let mut entries = handler.entries().lock().unwrap().clone();
+        entries.sort_by_key(|e| e.started_at);
+        entries
+    }
+}
+
+fn get(url: &str) -> Request<Empty<Bytes>> {
+    let uri: hyper::Uri = url.parse().unwrap();
+    Request::builder()
+        .uri(&uri)
+        .header("host", uri.authority().unwrap().as_str())
+        .body(Empty::new())
+        .unwrap()
+}
+
+/// Send one request over its own HTTP/1.1 connection to the proxy
+async fn fetch(proxy: SocketAddr, url: String) -> (u16, String) {
+    let stream = TcpStream::connect(proxy).await.unwrap();
+    let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .unwrap();
+    tokio::spawn(conn);
+
+    let res = sender.send_request(get(&url)).await.unwrap();
+    let status = res.status().as_u16();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Response body each entry was paired with
+fn response_text(entry: &CapturedEntry) -> String {
+    String::from_utf8_lossy(&entry.response_body.as_ref().unwrap().data).into_owned()
+}
+
+fn assert_paired(entries: &[CapturedEntry]) {
+    for entry in entries {
+        let path = entry.url.split_once("//").unwrap().1;
+        let path = &path[path.find('/').unwrap()..];
+        assert_eq!(
+            response_text(entry),
+            path,
+            "response paired with wrong request"
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_connections_out_of_order() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+
+    // Earlier requests answer later, so responses arrive in reverse order
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            let url = format!("http://{}/delay/{}/req{}", upstream, (8 - i) * 40, i);
+            tokio::spawn(fetch(proxy.addr, url))
+        })
+        .collect();
+    for task in tasks {
+        let (status, _) = task.await.unwrap();
+        assert_eq!(status, 200);
+    }
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 8);
+    assert_paired(&entries);
+    assert!(entries.iter().all(|e| e.response_status == 200));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_http2_multiplexed_streams() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+
+    let stream = TcpStream::connect(proxy.addr).await.unwrap();
+    let (sender, conn) =
+        hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+            .await
+            .unwrap();
+    tokio::spawn(conn);
+
+    let tasks: Vec<_> = (0..6)
+        .map(|i| {
+            let mut sender = sender.clone();
+            let url = format!("http://{}/delay/{}/stream{}", upstream, (6 - i) * 40, i);
+            tokio::spawn(async move {
+                let res = sender.send_request(get(&url)).await.unwrap();
+                res.into_body().collect().await.unwrap();
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    drop(sender);
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 6);
+    assert_paired(&entries);
+
+    // All streams share one client connection, numbered in arrival order
+    let client = entries[0].id.client_addr;
+    assert!(entries.iter().all(|e| e.id.client_addr == client));
+    let mut sequences: Vec<u64> = entries.iter().map(|e| e.id.sequence).collect();
+    sequences.sort();
+    assert_eq!(sequences, (0..6).collect::<Vec<_>>());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_pipelined_requests() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+
+    let mut stream = TcpStream::connect(proxy.addr).await.unwrap();
+    let pipelined = format!(
+        "GET http://{up}/delay/150/first HTTP/1.1\r\nHost: {up}\r\n\r\n\
+         GET http://{up}/delay/0/second HTTP/1.1\r\nHost: {up}\r\nConnection: close\r\n\r\n",
+        up = upstream
+    );
+    stream.write_all(pipelined.as_bytes()).await.unwrap();
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).await.unwrap();
+    assert!(raw.find("/delay/150/first").unwrap() < raw.find("/delay/0/second").unwrap());
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 2);
+    assert_paired(&entries);
+    assert_eq!(entries[0].id.sequence, 0);
+    assert_eq!(entries[1].id.sequence, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_upstream_error_recorded_with_status_zero() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+
+    // Nothing listens on this port once the listener is dropped
+    let closed = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let (status, _) = fetch(proxy.addr, format!("http://{}/missing", closed)).await;
+    assert_eq!(status, 502);
+    fetch(proxy.addr, format!("http://{}/delay/0/ok", upstream)).await;
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].response_status, 0);
+    assert!(entries[0].response_body.is_none());
+    let comment = entries[0].comment.as_deref().unwrap();
+    assert!(comment.starts_with("No response received (upstream error:"));
+    assert_eq!(entries[1].response_status, 200);
+    assert_eq!(response_text(&entries[1]), "/delay/0/ok");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_aborted_request_recorded_with_status_zero() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+
+    // Give up on a slow response by closing the connection
+    let url = format!("http://{}/delay/5000/slow", upstream);
+    let abandoned = tokio::time::timeout(Duration::from_millis(200), fetch(proxy.addr, url)).await;
+    assert!(abandoned.is_err());
+
+    let (status, body) = fetch(proxy.addr, format!("http://{}/delay/0/fast", upstream)).await;
+    assert_eq!((status, body.as_str()), (200, "/delay/0/fast"));
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].url.ends_with("/delay/5000/slow"));
+    assert_eq!(entries[0].response_status, 0);
+    assert_eq!(
+        entries[0].comment.as_deref(),
+        Some("No response received (request aborted)")
+    );
+    assert_eq!(response_text(&entries[1]), "/delay/0/fast");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_har_from_live_traffic() {
+    let upstream = start_upstream().await;
+    let proxy = RunningProxy::start().await;
+    fetch(proxy.addr, format!("http://{}/delay/0/page?q=1", upstream)).await;
+
+    let entries = proxy.finish().await;
+    assert_eq!(entries.len(), 1);
+    let entry = entries[0].to_har_entry();
+    assert_eq!(entry.server_ip_address.as_deref(), Some("127.0.0.1"));
+    assert_eq!(entry.response.status_text, "OK");
+    assert_eq!(entry.response.http_version, "HTTP/1.1");
+    assert_eq!(entry.request.query_string[0].name, "q");
+    assert!(entry.response.headers_size > 0);
+    assert_eq!(
+        entry.response.content.text.as_deref(),
+        Some("/delay/0/page")
+    );
+}
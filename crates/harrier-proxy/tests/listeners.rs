@@ -0,0 +1,113 @@
+// Several listeners sharing one capture, and proxy authentication
+
+use harrier_proxy::{CertificateAuthority, ProxyAuth, ProxyServer};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Origin answering every request with "hello"
+async fn start_origin() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+                    )
+                    .await;
+            });
+        }
+    });
+    addr
+}
+
+/// Send one raw request to `proxy` and return the raw response
+async fn send(proxy: SocketAddr, request: String) -> String {
+    let mut stream = TcpStream::connect(proxy).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).await.unwrap();
+    raw
+}
+
+fn get(origin: SocketAddr, path: &str, authorization: Option<&str>) -> String {
+    let auth = authorization
+        .map(|value| format!("Proxy-Authorization: {}\r\n", value))
+        .unwrap_or_default();
+    format!(
+        "GET http://{origin}{path} HTTP/1.1\r\nHost: {origin}\r\n{auth}Connection: close\r\n\r\n"
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_listeners_share_capture_and_require_auth() {
+    let origin = start_origin().await;
+    let first = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let second = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addrs = [first.local_addr().unwrap(), second.local_addr().unwrap()];
+
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(0, CertificateAuthority::generate().unwrap())
+        .with_auth(ProxyAuth::parse("alice:s3cret").unwrap());
+    let task = tokio::spawn(server.serve_all(vec![first, second], async {
+        let _ = signal.await;
+    }));
+
+    // "alice:s3cret" in base64
+    let valid = Some("Basic YWxpY2U6czNjcmV0");
+    let rejected = send(addrs[0], get(origin, "/anonymous", None)).await;
+    assert!(rejected.starts_with("HTTP/1.1 407"));
+    assert!(
+        rejected
+            .to_lowercase()
+            .contains("proxy-authenticate: basic realm=\"harrier\"")
+    );
+
+    let wrong = send(
+        addrs[1],
+        get(origin, "/wrong", Some("Basic YWxpY2U6d3Jvbmc=")),
+    )
+    .await;
+    assert!(wrong.starts_with("HTTP/1.1 407"));
+
+    assert!(
+        send(addrs[0], get(origin, "/first", valid))
+            .await
+            .ends_with("hello")
+    );
+    assert!(
+        send(addrs[1], get(origin, "/second", valid))
+            .await
+            .ends_with("hello")
+    );
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    // Rejected requests are not recorded, and neither are the proxy credentials
+    let mut entries = handler.entries().lock().unwrap().clone();
+    entries.sort_by_key(|e| e.started_at);
+    let paths: Vec<&str> = entries
+        .iter()
+        .map(|e| e.url.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(paths, ["first", "second"]);
+    assert!(entries.iter().all(|e| {
+        e.request_headers
+            .iter()
+            .all(|(name, _)| !name.eq_ignore_ascii_case("proxy-authorization"))
+    }));
+}
@@ -0,0 +1,121 @@
+// Mocks answering requests without contacting the origin
+
+use harrier_proxy::{CertificateAuthority, ProxyServer, RewriteRules};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+const RULES: &str = r#"
+[[mock]]
+name = "Checkout down"
+method = "POST"
+path = "^/api/checkout$"
+status = 500
+headers = { Content-Type = "application/json" }
+body = '{"error":"internal","path":"{{path}}"}'
+"#;
+
+/// Origin answering every request with "hello", counting the connections it accepts
+async fn start_origin() -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&connections);
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+                    )
+                    .await;
+            });
+        }
+    });
+    (addr, connections)
+}
+
+/// Send one raw request to `proxy` and return the raw response
+async fn send(proxy: SocketAddr, request: String) -> String {
+    let mut stream = TcpStream::connect(proxy).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).await.unwrap();
+    raw
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_mock_answers_without_origin() {
+    let (origin, connections) = start_origin().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap())
+        .with_rules(RewriteRules::parse(RULES).unwrap());
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    let mocked = send(
+        proxy,
+        format!(
+            "POST http://{origin}/api/checkout HTTP/1.1\r\nHost: {origin}\r\nContent-Type: application/json\r\nContent-Length: 11\r\nConnection: close\r\n\r\n{{\"cart\":42}}"
+        ),
+    )
+    .await;
+    assert!(mocked.starts_with("HTTP/1.1 500"), "{}", mocked);
+    assert!(mocked.ends_with("{\"error\":\"internal\",\"path\":\"/api/checkout\"}"));
+    assert_eq!(connections.load(Ordering::SeqCst), 0);
+
+    // Requests no mock matches still reach the origin
+    let passed = send(
+        proxy,
+        format!(
+            "GET http://{origin}/api/cart HTTP/1.1\r\nHost: {origin}\r\nConnection: close\r\n\r\n"
+        ),
+    )
+    .await;
+    assert!(passed.ends_with("hello"));
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let har = handler.to_har();
+    let mut entries: Vec<_> = har.log.entries.iter().collect();
+    entries.sort_by_key(|e| e.request.url.clone());
+    let (cart, checkout) = (entries[0], entries[1]);
+
+    assert_eq!(checkout.response.status, 500);
+    assert_eq!(
+        checkout
+            .request
+            .post_data
+            .as_ref()
+            .and_then(|p| p.text.as_deref()),
+        Some("{\"cart\":42}")
+    );
+    assert_eq!(
+        checkout.response.content.text.as_deref(),
+        Some("{\"error\":\"internal\",\"path\":\"/api/checkout\"}")
+    );
+    assert_eq!(
+        checkout.comment.as_deref(),
+        Some("Mocked by 'Checkout down' (origin not contacted)")
+    );
+    assert_eq!(cart.comment, None);
+}
@@ -0,0 +1,245 @@
+// CONNECT tunnels relayed without decryption and recorded as metadata
+
+use harrier_proxy::{CapturedEntry, CertificateAuthority, ProxyServer, TlsPassthrough};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Server echoing every byte it receives
+async fn start_echo_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let (mut reader, mut writer) = stream.split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+    addr
+}
+
+/// Open a CONNECT tunnel to `origin`, send "ping" through it and return the echo
+async fn ping_through(proxy: SocketAddr, origin: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(proxy).await.unwrap();
+    let connect = format!("CONNECT {origin} HTTP/1.1\r\nHost: {origin}\r\n\r\n");
+    stream.write_all(connect.as_bytes()).await.unwrap();
+
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(stream.read_u8().await.unwrap());
+    }
+    assert!(head.starts_with(b"HTTP/1.1 200"));
+
+    stream.write_all(b"ping").await.unwrap();
+    let mut echo = [0u8; 4];
+    stream.read_exact(&mut echo).await.unwrap();
+    String::from_utf8(echo.to_vec()).unwrap()
+}
+
+/// Open a CONNECT tunnel and start a TLS handshake that rejects the proxy's certificate
+fn rejecting_handshake(proxy: SocketAddr, origin: SocketAddr) {
+    let mut stream = std::net::TcpStream::connect(proxy).unwrap();
+    let connect = format!("CONNECT {origin} HTTP/1.1\r\nHost: {origin}\r\n\r\n");
+    stream.write_all(connect.as_bytes()).unwrap();
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+
+    // No trusted roots, so the client rejects any certificate, as a pinned client would
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(rustls::RootCertStore::empty())
+    .with_no_client_auth();
+    let name = ServerName::try_from("pinned.example").unwrap();
+    let mut client = rustls::ClientConnection::new(Arc::new(config), name).unwrap();
+    let result = client.complete_io(&mut stream);
+    assert!(result.is_err(), "handshake should fail");
+}
+
+/// Open a CONNECT tunnel, complete a TLS handshake trusting the proxy's CA and send one
+/// request through it
+fn decrypted_request(proxy: SocketAddr, origin: SocketAddr, ca_pem: &str) {
+    // Named by host, as the proxy's certificates carry DNS names
+    let origin = format!("localhost:{}", origin.port());
+    let mut stream = std::net::TcpStream::connect(proxy).unwrap();
+    let connect = format!("CONNECT {origin} HTTP/1.1\r\nHost: {origin}\r\n\r\n");
+    stream.write_all(connect.as_bytes()).unwrap();
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots
+        .add(CertificateDer::from_pem_slice(ca_pem.as_bytes()).unwrap())
+        .unwrap();
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let name = ServerName::try_from("localhost").unwrap();
+    let mut client = rustls::ClientConnection::new(Arc::new(config), name).unwrap();
+    let mut tls = rustls::Stream::new(&mut client, &mut stream);
+    let request = format!("GET / HTTP/1.1\r\nHost: {origin}\r\nConnection: close\r\n\r\n");
+    tls.write_all(request.as_bytes())
+        .expect("handshake with the proxy's certificate should succeed");
+
+    // The origin speaks no TLS, so the proxy answers with an error; any response will do
+    let mut status = [0u8; 12];
+    tls.read_exact(&mut status).unwrap();
+    assert!(status.starts_with(b"HTTP/1.1 "));
+}
+
+async fn wait_for_entries(entries: &Arc<Mutex<Vec<CapturedEntry>>>, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while entries.lock().unwrap().len() < count {
+        assert!(Instant::now() < deadline, "entries not recorded");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_listed_host_tunnelled_and_recorded() {
+    let origin = start_echo_server().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap())
+        .with_passthrough(TlsPassthrough::new(&["127.0.0.1".to_string()]).unwrap());
+    let entries = server.handler().entries();
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    assert_eq!(ping_through(proxy, origin).await, "ping");
+    wait_for_entries(&entries, 1).await;
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let har = handler.to_har();
+    let entry = &har.log.entries[0];
+    assert_eq!(entry.request.method, "CONNECT");
+    assert_eq!(entry.request.url, format!("https://{}/", origin));
+    assert_eq!(entry.response.status, 200);
+    assert_eq!((entry.request.body_size, entry.response.body_size), (4, 4));
+    assert_eq!(
+        entry.comment.as_deref(),
+        Some("TLS passthrough, not decrypted (host excluded from interception)")
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_rejected_certificates_switch_host_to_passthrough() {
+    let origin = start_echo_server().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap());
+    let entries = server.handler().entries();
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    for _ in 0..3 {
+        tokio::task::spawn_blocking(move || rejecting_handshake(proxy, origin))
+            .await
+            .unwrap();
+    }
+
+    // Failures are counted as the proxy drops each connection; keep trying until the
+    // host is tunnelled and recorded
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while entries.lock().unwrap().is_empty() {
+        assert!(
+            Instant::now() < deadline,
+            "host never switched to passthrough"
+        );
+        assert_eq!(ping_through(proxy, origin).await, "ping");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let har = handler.to_har();
+    assert!(
+        har.log.entries[0]
+            .comment
+            .as_deref()
+            .is_some_and(|comment| {
+                comment.starts_with(
+                    "TLS passthrough, not decrypted (client rejected the proxy's certificate)",
+                )
+            })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_decrypted_connections_stay_intercepted() {
+    let origin = start_echo_server().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let ca = CertificateAuthority::generate().unwrap();
+    let ca_pem = Arc::new(ca.cert_pem().to_string());
+    let server = ProxyServer::new(proxy.port(), ca);
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    // Each connection is closed before the next, so a miscounted failure would have
+    // switched the host to passthrough and broken the later handshakes
+    for _ in 0..5 {
+        let ca_pem = Arc::clone(&ca_pem);
+        tokio::task::spawn_blocking(move || decrypted_request(proxy, origin, &ca_pem))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+    let har = handler.to_har();
+    assert_eq!(har.log.entries.len(), 5);
+    assert!(
+        har.log
+            .entries
+            .iter()
+            .all(|entry| entry.request.method == "GET")
+    );
+}
@@ -0,0 +1,111 @@
+// Rewrite rules applied to live traffic and noted in the HAR
+
+use harrier_proxy::{CertificateAuthority, ProxyServer, RewriteRules};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+const RULES: &str = r#"
+[[rule]]
+name = "Inject auth"
+set_headers = { Authorization = "Bearer test-token" }
+remove_headers = ["If-None-Match"]
+
+[[rule]]
+name = "Premium user"
+on = "response"
+path = "^/api/me$"
+set_headers = { Cache-Control = "no-cache" }
+replace_body = [{ pattern = '"plan":"free"', replacement = '"plan":"premium"' }]
+"#;
+
+/// Origin answering with a JSON body, keeping the request heads it received
+async fn start_origin() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let log = Arc::clone(&log);
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                log.lock().unwrap().push(head.to_lowercase());
+                let _ = reader
+                    .into_inner()
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 15\r\nconnection: close\r\n\r\n{\"plan\":\"free\"}",
+                    )
+                    .await;
+            });
+        }
+    });
+    (addr, seen)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_rules_rewrite_traffic_and_note_it() {
+    let (origin, seen) = start_origin().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap())
+        .with_rules(RewriteRules::parse(RULES).unwrap());
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    let mut stream = TcpStream::connect(proxy).await.unwrap();
+    let request = format!(
+        "GET http://{origin}/api/me HTTP/1.1\r\nHost: {origin}\r\nIf-None-Match: \"v1\"\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.ends_with("{\"plan\":\"premium\"}"));
+    assert!(response.to_lowercase().contains("cache-control: no-cache"));
+    let seen = seen.lock().unwrap()[0].clone();
+    assert!(seen.contains("authorization: bearer test-token"));
+    assert!(!seen.contains("if-none-match"));
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    // The HAR holds the traffic as sent on, and says which rules changed it
+    let har = handler.to_har();
+    let entry = &har.log.entries[0];
+    assert!(
+        entry
+            .request
+            .headers
+            .iter()
+            .any(|h| h.name == "authorization" && h.value == "Bearer test-token")
+    );
+    assert_eq!(
+        entry.response.content.text.as_deref(),
+        Some("{\"plan\":\"premium\"}")
+    );
+    assert_eq!(
+        entry.comment.as_deref(),
+        Some("Rewritten by rules: Inject auth (request), Premium user (response)")
+    );
+}
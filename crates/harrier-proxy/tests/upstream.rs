@@ -0,0 +1,183 @@
+// Chaining through an upstream proxy, with a local stand-in for the corporate proxy
+
+use harrier_proxy::{CapturedEntry, CertificateAuthority, ProxyServer, UpstreamProxy};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Request heads received by the stand-in proxy
+type Seen = Arc<Mutex<Vec<String>>>;
+
+/// Stand-in corporate proxy
+///
+/// Plain requests are answered with a body naming the proxy. CONNECT requests are
+/// accepted and the tunnel closed at once, so the TLS handshake through it fails.
+async fn start_corporate_proxy() -> (SocketAddr, Seen) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let seen = Seen::default();
+
+    let log = Arc::clone(&seen);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let log = Arc::clone(&log);
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                let is_connect = head.starts_with("CONNECT ");
+                log.lock().unwrap().push(head);
+
+                let mut stream = reader.into_inner();
+                let reply: &[u8] = if is_connect {
+                    b"HTTP/1.1 200 Connection established\r\n\r\n"
+                } else {
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 13\r\nconnection: close\r\n\r\nvia corporate"
+                };
+                let _ = stream.write_all(reply).await;
+            });
+        }
+    });
+
+    (addr, seen)
+}
+
+/// Origin answering every request with "direct"
+async fn start_origin() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\ncontent-length: 6\r\nconnection: close\r\n\r\ndirect",
+                    )
+                    .await;
+            });
+        }
+    });
+    addr
+}
+
+/// Run Harrier chained to `upstream`, send each raw request on its own connection,
+/// and return the responses and the captured entries
+async fn capture_through(
+    upstream: UpstreamProxy,
+    requests: &[String],
+) -> (Vec<String>, Vec<CapturedEntry>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(addr.port(), CertificateAuthority::generate().unwrap())
+        .with_upstream(upstream);
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    let mut responses = Vec::new();
+    for request in requests {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).await.unwrap();
+        responses.push(raw);
+    }
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+    let entries = handler.entries().lock().unwrap().clone();
+    (responses, entries)
+}
+
+fn get(url: &str, host: &str) -> String {
+    format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        url, host
+    )
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_plain_http_forwarded_with_credentials() {
+    let (corporate, seen) = start_corporate_proxy().await;
+    let upstream = UpstreamProxy::new(&format!("http://alice:s3cret@{}", corporate), "").unwrap();
+
+    let (responses, entries) = capture_through(
+        upstream,
+        &[get("http://intranet.test/page", "intranet.test")],
+    )
+    .await;
+
+    assert!(responses[0].ends_with("via corporate"));
+    let seen = seen.lock().unwrap().clone();
+    assert!(seen[0].starts_with("GET http://intranet.test/page HTTP/1.1"));
+    assert!(
+        seen[0]
+            .to_lowercase()
+            .contains("proxy-authorization: basic ywxpy2u6cznjcmv0")
+    );
+
+    // Credentials for the upstream proxy are not recorded
+    assert_eq!(entries.len(), 1);
+    assert!(
+        entries[0]
+            .request_headers
+            .iter()
+            .all(|(name, _)| !name.eq_ignore_ascii_case("proxy-authorization"))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_https_tunnelled_with_connect() {
+    let (corporate, seen) = start_corporate_proxy().await;
+    let upstream = UpstreamProxy::new(&format!("http://alice:s3cret@{}", corporate), "").unwrap();
+
+    let (_, entries) =
+        capture_through(upstream, &[get("https://secure.test/api", "secure.test")]).await;
+
+    let seen = seen.lock().unwrap().clone();
+    assert!(seen[0].starts_with("CONNECT secure.test:443 HTTP/1.1"));
+    assert!(
+        seen[0]
+            .to_lowercase()
+            .contains("proxy-authorization: basic ywxpy2u6cznjcmv0")
+    );
+
+    // The stand-in closes the tunnel, so the request is recorded as failed
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].response_status, 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_bypassed_hosts_connect_directly() {
+    let (corporate, seen) = start_corporate_proxy().await;
+    let origin = start_origin().await;
+    let upstream = UpstreamProxy::new(&corporate.to_string(), "127.0.0.1").unwrap();
+
+    let url = format!("http://{}/", origin);
+    let (responses, _) = capture_through(upstream, &[get(&url, &origin.to_string())]).await;
+
+    assert!(responses[0].ends_with("direct"));
+    assert!(seen.lock().unwrap().is_empty());
+}
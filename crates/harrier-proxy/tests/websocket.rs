@@ -0,0 +1,214 @@
+// WebSocket frames recorded on the upgrade entry
+
+use harrier_proxy::{CertificateAuthority, ProxyServer, UpstreamProxy};
+use hudsucker::futures::{SinkExt, StreamExt};
+use hudsucker::tokio_tungstenite::tungstenite::Message;
+use hudsucker::tokio_tungstenite::tungstenite::protocol::Role;
+use hudsucker::tokio_tungstenite::{WebSocketStream, accept_async};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// WebSocket server echoing every text and binary message
+async fn start_echo_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            tokio::spawn(async move {
+                let Ok(mut ws) = accept_async(stream).await else {
+                    return;
+                };
+                while let Some(Ok(message)) = ws.next().await {
+                    if message.is_text() || message.is_binary() {
+                        let _ = ws.send(message).await;
+                    }
+                }
+            });
+        }
+    });
+    addr
+}
+
+/// Stand-in corporate proxy relaying CONNECT tunnels, recording their targets
+async fn start_tunnelling_proxy() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let targets = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&targets);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let log = Arc::clone(&log);
+            tokio::spawn(async move {
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                }
+                let target = request_line.split(' ').nth(1).unwrap().to_string();
+                log.lock().unwrap().push(request_line);
+
+                let mut client = reader.into_inner();
+                let mut server = TcpStream::connect(target).await.unwrap();
+                client
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await
+                    .unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+            });
+        }
+    });
+    (addr, targets)
+}
+
+/// Open a WebSocket to `origin` through the proxy at `proxy`
+async fn connect_through(proxy: SocketAddr, origin: SocketAddr) -> WebSocketStream<TcpStream> {
+    let mut stream = TcpStream::connect(proxy).await.unwrap();
+    let handshake = format!(
+        "GET http://{origin}/chat HTTP/1.1\r\nHost: {origin}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+    );
+    stream.write_all(handshake.as_bytes()).await.unwrap();
+
+    // Read the handshake response a byte at a time so no frame is consumed
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        head.push(stream.read_u8().await.unwrap());
+    }
+    let head = String::from_utf8(head).unwrap();
+    assert!(
+        head.starts_with("HTTP/1.1 101"),
+        "unexpected handshake: {}",
+        head
+    );
+
+    WebSocketStream::from_raw_socket(stream, Role::Client, None).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_frames_recorded_on_upgrade_entry() {
+    let origin = start_echo_server().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap());
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    let mut ws = connect_through(proxy, origin).await;
+    ws.send(Message::text("{\"type\":\"subscribe\"}"))
+        .await
+        .unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::text("{\"type\":\"subscribe\"}")
+    );
+    ws.send(Message::binary(vec![1u8, 2, 3])).await.unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::binary(vec![1u8, 2, 3])
+    );
+    ws.close(None).await.unwrap();
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let entries = handler.entries().lock().unwrap().clone();
+    assert_eq!(entries.len(), 1);
+    let har = entries[0].to_har_entry();
+    assert_eq!(har.request.url, format!("ws://{}/chat", origin));
+    assert_eq!(har.response.status, 101);
+    assert!(
+        har.response
+            .headers
+            .iter()
+            .any(|h| h.name == "sec-websocket-accept" && h.value == "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+    );
+
+    // Data frames in the order they crossed the proxy
+    let frames: Vec<(String, u8, String)> = har
+        .web_socket_messages
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.opcode == 1 || m.opcode == 2)
+        .map(|m| (m.message_type, m.opcode, m.data))
+        .collect();
+    let frame = |t: &str, opcode: u8, data: &str| (t.to_string(), opcode, data.to_string());
+    assert_eq!(
+        frames,
+        [
+            frame("send", 1, "{\"type\":\"subscribe\"}"),
+            frame("receive", 1, "{\"type\":\"subscribe\"}"),
+            frame("send", 2, "AQID"),
+            frame("receive", 2, "AQID"),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_websocket_tunnelled_through_upstream_proxy() {
+    let origin = start_echo_server().await;
+    let (corporate, targets) = start_tunnelling_proxy().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = listener.local_addr().unwrap();
+    let (shutdown, signal) = oneshot::channel::<()>();
+    let server = ProxyServer::new(proxy.port(), CertificateAuthority::generate().unwrap())
+        .with_upstream(UpstreamProxy::new(&corporate.to_string(), "").unwrap());
+    let task = tokio::spawn(server.serve(listener, async {
+        let _ = signal.await;
+    }));
+
+    let mut ws = connect_through(proxy, origin).await;
+    ws.send(Message::text("hello")).await.unwrap();
+    assert_eq!(ws.next().await.unwrap().unwrap(), Message::text("hello"));
+    ws.close(None).await.unwrap();
+
+    // The WebSocket went through the corporate proxy rather than straight to the origin
+    assert_eq!(
+        targets.lock().unwrap().as_slice(),
+        [format!("CONNECT {} HTTP/1.1\r\n", origin)]
+    );
+
+    shutdown.send(()).unwrap();
+    let handler = tokio::time::timeout(Duration::from_secs(10), task)
+        .await
+        .expect("proxy did not shut down")
+        .unwrap()
+        .unwrap();
+
+    let entries = handler.entries().lock().unwrap().clone();
+    assert_eq!(entries.len(), 1);
+    let har = entries[0].to_har_entry();
+    assert_eq!(har.response.status, 101);
+    let frames: Vec<(String, String)> = har
+        .web_socket_messages
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.opcode == 1)
+        .map(|m| (m.message_type, m.data))
+        .collect();
+    assert_eq!(
+        frames,
+        [
+            ("send".to_string(), "hello".to_string()),
+            ("receive".to_string(), "hello".to_string()),
+        ]
+    );
+}